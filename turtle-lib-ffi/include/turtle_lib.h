@@ -0,0 +1,74 @@
+/*
+ * C interface of turtle-lib, see turtle-lib-ffi/src/lib.rs for details.
+ *
+ * Link against the turtle_lib_ffi library built by
+ *     cargo build --release -p turtle-lib-ffi
+ */
+#ifndef TURTLE_LIB_H
+#define TURTLE_LIB_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define TURTLE_FFI_VERSION 1
+
+typedef enum TurtleStatus {
+    TURTLE_OK = 0,
+    TURTLE_NULL_POINTER = 1,
+    TURTLE_INVALID_ARGUMENT = 2,
+    TURTLE_EXPORT_FAILED = 3,
+    TURTLE_PANICKED = 4,
+} TurtleStatus;
+
+/* Commands for turtle_plan_push_command(), with the meaning of `value` */
+typedef enum TurtleCommandKind {
+    TURTLE_FORWARD = 0,       /* pixels */
+    TURTLE_BACKWARD = 1,      /* pixels */
+    TURTLE_LEFT = 2,          /* degrees */
+    TURTLE_RIGHT = 3,         /* degrees */
+    TURTLE_PEN_UP = 4,
+    TURTLE_PEN_DOWN = 5,
+    TURTLE_SET_PEN_WIDTH = 6, /* pixels */
+    TURTLE_SET_HEADING = 7,   /* degrees counter-clockwise from east */
+    TURTLE_BEGIN_FILL = 8,
+    TURTLE_END_FILL = 9,
+    TURTLE_SHOW = 10,
+    TURTLE_HIDE = 11,
+} TurtleCommandKind;
+
+typedef struct TurtlePlan TurtlePlan;
+typedef struct TurtleWorld TurtleWorld;
+
+uint32_t turtle_ffi_version(void);
+/* Message of the last failure on this thread, NULL if there was none */
+const char *turtle_last_error(void);
+
+TurtlePlan *turtle_plan_new(void);
+void turtle_plan_free(TurtlePlan *plan);
+/* `kind` is a TurtleCommandKind, unknown values are an invalid argument */
+TurtleStatus turtle_plan_push_command(TurtlePlan *plan, uint32_t kind, float value);
+TurtleStatus turtle_plan_go_to(TurtlePlan *plan, float x, float y);
+/* Negative degrees turn right */
+TurtleStatus turtle_plan_circle(TurtlePlan *plan, float radius, float degrees, uint32_t steps);
+/* Channels from 0.0 to 1.0, `fill` selects the fill color */
+TurtleStatus turtle_plan_set_color(TurtlePlan *plan, float red, float green, float blue,
+                                   float alpha, bool fill);
+/* Script syntax of turtle_lib::script, e.g. "repeat 4 [ forward 100 right 90 ]" */
+TurtleStatus turtle_plan_push_script(TurtlePlan *plan, const char *source);
+
+TurtleWorld *turtle_world_new(void);
+void turtle_world_free(TurtleWorld *world);
+TurtleStatus turtle_world_run(TurtleWorld *world, uint32_t turtle_id, TurtlePlan *plan);
+TurtleStatus turtle_world_export_svg(TurtleWorld *world, const char *filename);
+TurtleStatus turtle_world_export_png(TurtleWorld *world, const char *filename, uint16_t width,
+                                     uint16_t height);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TURTLE_LIB_H */
@@ -0,0 +1,457 @@
+//! C interface for embedding `turtle-lib`
+//!
+//! Plans are built command by command (or from script text, see
+//! `turtle_lib::script`), run on a headless world and exported to SVG or
+//! PNG. No window is opened. The declarations for C are in
+//! `include/turtle_lib.h`:
+//!
+//! ```c
+//! TurtlePlan *plan = turtle_plan_new();
+//! for (int i = 0; i < 4; i++) {
+//!     turtle_plan_push_command(plan, TURTLE_FORWARD, 100.0f);
+//!     turtle_plan_push_command(plan, TURTLE_RIGHT, 90.0f);
+//! }
+//! TurtleWorld *world = turtle_world_new();
+//! turtle_world_run(world, 0, plan);
+//! if (turtle_world_export_svg(world, "square.svg") != TURTLE_OK) {
+//!     fprintf(stderr, "%s\n", turtle_last_error());
+//! }
+//! turtle_world_free(world);
+//! turtle_plan_free(plan);
+//! ```
+//!
+//! Every function returns a `TurtleStatus` (or a null pointer) instead of
+//! panicking across the boundary. The message of the last failure on the
+//! calling thread is available from `turtle_last_error()`.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use turtle_lib::{
+    headless, parse_script, vec2, Color, CurvedMovement, DirectionalMovement, Turnable, TurtlePlan,
+    TurtleWorld, WithCommands,
+};
+
+/// Version of the C interface, increased on every incompatible change
+pub const TURTLE_FFI_VERSION: u32 = 1;
+
+/// Result of every fallible function
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TurtleStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidArgument = 2,
+    ExportFailed = 3,
+    Panicked = 4,
+}
+
+/// Commands for `turtle_plan_push_command()`, with the meaning of `value`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TurtleCommandKind {
+    /// Pixels
+    Forward = 0,
+    /// Pixels
+    Backward = 1,
+    /// Degrees
+    Left = 2,
+    /// Degrees
+    Right = 3,
+    PenUp = 4,
+    PenDown = 5,
+    /// Pixels
+    SetPenWidth = 6,
+    /// Degrees counter-clockwise from east
+    SetHeading = 7,
+    BeginFill = 8,
+    EndFill = 9,
+    Show = 10,
+    Hide = 11,
+}
+
+impl TryFrom<u32> for TurtleCommandKind {
+    type Error = u32;
+
+    fn try_from(kind: u32) -> Result<Self, u32> {
+        Ok(match kind {
+            0 => Self::Forward,
+            1 => Self::Backward,
+            2 => Self::Left,
+            3 => Self::Right,
+            4 => Self::PenUp,
+            5 => Self::PenDown,
+            6 => Self::SetPenWidth,
+            7 => Self::SetHeading,
+            8 => Self::BeginFill,
+            9 => Self::EndFill,
+            10 => Self::Show,
+            11 => Self::Hide,
+            _ => return Err(kind),
+        })
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn fail(status: TurtleStatus, message: impl Into<String>) -> TurtleStatus {
+    // Interior NUL bytes would cut the message short anyway
+    let message = message.into().replace('\0', " ");
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).ok());
+    status
+}
+
+/// Run `f`, turning panics into `TurtleStatus::Panicked`
+fn guard(f: impl FnOnce() -> TurtleStatus) -> TurtleStatus {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| fail(TurtleStatus::Panicked, "panicked"))
+}
+
+/// # Safety
+///
+/// `pointer` must be null or come from the matching `*_new()` function and
+/// not be freed yet.
+unsafe fn borrow<'a, T>(pointer: *mut T) -> Result<&'a mut T, TurtleStatus> {
+    // SAFETY: guaranteed by the caller
+    unsafe { pointer.as_mut() }.ok_or_else(|| fail(TurtleStatus::NullPointer, "null pointer"))
+}
+
+/// # Safety
+///
+/// `text` must be null or a NUL-terminated string.
+unsafe fn text<'a>(text: *const c_char) -> Result<&'a str, TurtleStatus> {
+    if text.is_null() {
+        return Err(fail(TurtleStatus::NullPointer, "null string"));
+    }
+    // SAFETY: guaranteed by the caller
+    unsafe { CStr::from_ptr(text) }
+        .to_str()
+        .map_err(|_| fail(TurtleStatus::InvalidArgument, "string is not UTF-8"))
+}
+
+/// Version of the interface the library was built with, compare with
+/// `TURTLE_FFI_VERSION` from the header
+#[no_mangle]
+pub extern "C" fn turtle_ffi_version() -> u32 {
+    TURTLE_FFI_VERSION
+}
+
+/// Message of the last failure on this thread, null if there was none
+///
+/// The string stays valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn turtle_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |m| m.as_ptr())
+    })
+}
+
+/// New empty plan, free it with `turtle_plan_free()`
+#[no_mangle]
+pub extern "C" fn turtle_plan_new() -> *mut TurtlePlan {
+    Box::into_raw(Box::new(TurtlePlan::new()))
+}
+
+/// # Safety
+///
+/// `plan` must be null or come from `turtle_plan_new()` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn turtle_plan_free(plan: *mut TurtlePlan) {
+    if !plan.is_null() {
+        // SAFETY: guaranteed by the caller
+        drop(unsafe { Box::from_raw(plan) });
+    }
+}
+
+/// Append a command, `value` is ignored by commands without a parameter
+///
+/// `kind` is a `TurtleCommandKind`, passed as integer because C callers
+/// can pass any value.
+///
+/// # Safety
+///
+/// `plan` must come from `turtle_plan_new()` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn turtle_plan_push_command(
+    plan: *mut TurtlePlan,
+    kind: u32,
+    value: f32,
+) -> TurtleStatus {
+    guard(|| {
+        // SAFETY: guaranteed by the caller
+        let plan = match unsafe { borrow(plan) } {
+            Ok(plan) => plan,
+            Err(status) => return status,
+        };
+        let Ok(kind) = TurtleCommandKind::try_from(kind) else {
+            return fail(
+                TurtleStatus::InvalidArgument,
+                format!("unknown command kind {kind}"),
+            );
+        };
+        if !value.is_finite() {
+            return fail(TurtleStatus::InvalidArgument, "value is not finite");
+        }
+        match kind {
+            TurtleCommandKind::Forward => plan.forward(value),
+            TurtleCommandKind::Backward => plan.backward(value),
+            TurtleCommandKind::Left => plan.left(value),
+            TurtleCommandKind::Right => plan.right(value),
+            TurtleCommandKind::PenUp => plan.pen_up(),
+            TurtleCommandKind::PenDown => plan.pen_down(),
+            TurtleCommandKind::SetPenWidth => plan.set_pen_width(value),
+            TurtleCommandKind::SetHeading => plan.set_heading(value),
+            TurtleCommandKind::BeginFill => plan.begin_fill(),
+            TurtleCommandKind::EndFill => plan.end_fill(),
+            TurtleCommandKind::Show => plan.show(),
+            TurtleCommandKind::Hide => plan.hide(),
+        };
+        TurtleStatus::Ok
+    })
+}
+
+/// Append a move to `(x, y)`
+///
+/// # Safety
+///
+/// `plan` must come from `turtle_plan_new()` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn turtle_plan_go_to(plan: *mut TurtlePlan, x: f32, y: f32) -> TurtleStatus {
+    guard(|| {
+        // SAFETY: guaranteed by the caller
+        match unsafe { borrow(plan) } {
+            Ok(plan) => {
+                plan.go_to(vec2(x, y));
+                TurtleStatus::Ok
+            }
+            Err(status) => status,
+        }
+    })
+}
+
+/// Append an arc of `degrees` around a center `radius` pixels to the left
+/// (negative degrees turn right)
+///
+/// # Safety
+///
+/// `plan` must come from `turtle_plan_new()` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn turtle_plan_circle(
+    plan: *mut TurtlePlan,
+    radius: f32,
+    degrees: f32,
+    steps: u32,
+) -> TurtleStatus {
+    guard(|| {
+        // SAFETY: guaranteed by the caller
+        let plan = match unsafe { borrow(plan) } {
+            Ok(plan) => plan,
+            Err(status) => return status,
+        };
+        let steps = steps.max(1) as usize;
+        if degrees < 0.0 {
+            plan.circle_right(radius, -degrees, steps);
+        } else {
+            plan.circle_left(radius, degrees, steps);
+        }
+        TurtleStatus::Ok
+    })
+}
+
+/// Pen color from 0.0 to 1.0 per channel, `fill` selects the fill color
+///
+/// # Safety
+///
+/// `plan` must come from `turtle_plan_new()` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn turtle_plan_set_color(
+    plan: *mut TurtlePlan,
+    red: f32,
+    green: f32,
+    blue: f32,
+    alpha: f32,
+    fill: bool,
+) -> TurtleStatus {
+    guard(|| {
+        // SAFETY: guaranteed by the caller
+        let plan = match unsafe { borrow(plan) } {
+            Ok(plan) => plan,
+            Err(status) => return status,
+        };
+        let color = Color::new(red, green, blue, alpha);
+        if fill {
+            plan.set_fill_color(color);
+        } else {
+            plan.set_pen_color(color);
+        }
+        TurtleStatus::Ok
+    })
+}
+
+/// Append the commands of a script, see `turtle_lib::script` for the syntax
+///
+/// # Safety
+///
+/// `plan` must come from `turtle_plan_new()` and not be freed yet, `source`
+/// must be a NUL-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn turtle_plan_push_script(
+    plan: *mut TurtlePlan,
+    source: *const c_char,
+) -> TurtleStatus {
+    guard(|| {
+        // SAFETY: guaranteed by the caller
+        let (plan, source) = match unsafe { (borrow(plan), text(source)) } {
+            (Ok(plan), Ok(source)) => (plan, source),
+            (Err(status), _) | (_, Err(status)) => return status,
+        };
+        match parse_script(source) {
+            Ok(script) => {
+                plan.get_commands_mut().append(script.build());
+                TurtleStatus::Ok
+            }
+            Err(error) => fail(TurtleStatus::InvalidArgument, error.to_string()),
+        }
+    })
+}
+
+/// New empty world without a window, free it with `turtle_world_free()`
+#[no_mangle]
+pub extern "C" fn turtle_world_new() -> *mut TurtleWorld {
+    Box::into_raw(Box::new(TurtleWorld::headless()))
+}
+
+/// # Safety
+///
+/// `world` must be null or come from `turtle_world_new()` and not be freed
+/// yet.
+#[no_mangle]
+pub unsafe extern "C" fn turtle_world_free(world: *mut TurtleWorld) {
+    if !world.is_null() {
+        // SAFETY: guaranteed by the caller
+        drop(unsafe { Box::from_raw(world) });
+    }
+}
+
+/// Run all commands of `plan` instantly on turtle `turtle_id`, creating the
+/// turtle if needed; the plan can be run again or extended
+///
+/// # Safety
+///
+/// `world` and `plan` must come from their `*_new()` functions and not be
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn turtle_world_run(
+    world: *mut TurtleWorld,
+    turtle_id: u32,
+    plan: *mut TurtlePlan,
+) -> TurtleStatus {
+    guard(|| {
+        // SAFETY: guaranteed by the caller
+        let (world, plan) = match unsafe { (borrow(world), borrow(plan)) } {
+            (Ok(world), Ok(plan)) => (world, plan),
+            (Err(status), _) | (_, Err(status)) => return status,
+        };
+        headless::execute(world, turtle_id as usize, &plan.clone().build());
+        TurtleStatus::Ok
+    })
+}
+
+/// Write the drawing as SVG
+///
+/// # Safety
+///
+/// `world` must come from `turtle_world_new()` and not be freed yet,
+/// `filename` must be a NUL-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn turtle_world_export_svg(
+    world: *mut TurtleWorld,
+    filename: *const c_char,
+) -> TurtleStatus {
+    guard(|| {
+        // SAFETY: guaranteed by the caller
+        let (world, filename) = match unsafe { (borrow(world), text(filename)) } {
+            (Ok(world), Ok(filename)) => (world, filename),
+            (Err(status), _) | (_, Err(status)) => return status,
+        };
+        match headless::export_svg(world, filename) {
+            Ok(()) => TurtleStatus::Ok,
+            Err(error) => fail(TurtleStatus::ExportFailed, format!("{error:?}")),
+        }
+    })
+}
+
+/// Write the drawing as a `width` x `height` PNG, centered on the origin
+///
+/// # Safety
+///
+/// `world` must come from `turtle_world_new()` and not be freed yet,
+/// `filename` must be a NUL-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn turtle_world_export_png(
+    world: *mut TurtleWorld,
+    filename: *const c_char,
+    width: u16,
+    height: u16,
+) -> TurtleStatus {
+    guard(|| {
+        // SAFETY: guaranteed by the caller
+        let (world, filename) = match unsafe { (borrow(world), text(filename)) } {
+            (Ok(world), Ok(filename)) => (world, filename),
+            (Err(status), _) | (_, Err(status)) => return status,
+        };
+        if width == 0 || height == 0 {
+            return fail(TurtleStatus::InvalidArgument, "image size is zero");
+        }
+        match headless::export_png(world, filename, width, height) {
+            Ok(()) => TurtleStatus::Ok,
+            Err(error) => fail(
+                TurtleStatus::ExportFailed,
+                format!("cannot write {filename}: {error:?}"),
+            ),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans_run_through_the_c_interface() {
+        let script = CString::new("forward 50 right 90 forward 50").unwrap();
+        unsafe {
+            let plan = turtle_plan_new();
+            assert_eq!(
+                turtle_plan_push_command(plan, TurtleCommandKind::PenUp as u32, 0.0),
+                TurtleStatus::Ok
+            );
+            assert_eq!(
+                turtle_plan_push_script(plan, script.as_ptr()),
+                TurtleStatus::Ok
+            );
+            assert_eq!(
+                turtle_plan_push_command(plan, TurtleCommandKind::Forward as u32, f32::NAN),
+                TurtleStatus::InvalidArgument
+            );
+            assert_eq!(
+                turtle_plan_push_command(plan, 12, 0.0),
+                TurtleStatus::InvalidArgument
+            );
+            assert!(!turtle_last_error().is_null());
+
+            let world = turtle_world_new();
+            assert_eq!(turtle_world_run(world, 1, plan), TurtleStatus::Ok);
+            assert_eq!((*world).turtles.len(), 2);
+            assert_eq!(
+                turtle_world_run(std::ptr::null_mut(), 0, plan),
+                TurtleStatus::NullPointer
+            );
+            turtle_world_free(world);
+            turtle_plan_free(plan);
+        }
+    }
+}
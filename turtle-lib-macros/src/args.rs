@@ -0,0 +1,239 @@
+//! Parsing of the `turtle_main` attribute arguments
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, LitStr, Meta, Token};
+
+/// Arguments of `#[turtle_main(...)]`
+///
+/// Accepts a plain title (`#[turtle_main("My Drawing")]`) as well as named
+/// options (`#[turtle_main(title = "X", width = 1280, fullscreen)]`). A plain
+/// title may be followed by further options.
+#[derive(Default)]
+pub(crate) struct MacroArgs {
+    pub title: Option<LitStr>,
+    pub width: Option<Expr>,
+    pub height: Option<Expr>,
+    pub background: Option<Expr>,
+    pub fullscreen: Option<Expr>,
+    pub update: Option<Expr>,
+    pub export: Option<Expr>,
+    pub screenshot: Option<Expr>,
+    pub quit_keys: Option<Expr>,
+    pub playback_keys: Option<Expr>,
+    pub overlay: Option<Expr>,
+    pub screenshot_key: Option<Expr>,
+    pub auto_fit: Option<Expr>,
+}
+
+impl Parse for MacroArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = MacroArgs::default();
+
+        while !input.is_empty() {
+            if input.peek(LitStr) {
+                let title: LitStr = input.parse()?;
+                args.set_title(title)?;
+            } else {
+                args.apply(input.parse()?)?;
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(args)
+    }
+}
+
+impl MacroArgs {
+    fn set_title(&mut self, title: LitStr) -> syn::Result<()> {
+        if self.title.is_some() {
+            return Err(syn::Error::new(title.span(), "duplicate window title"));
+        }
+        self.title = Some(title);
+        Ok(())
+    }
+
+    fn apply(&mut self, meta: Meta) -> syn::Result<()> {
+        let Some(name) = meta.path().get_ident().map(ToString::to_string) else {
+            return Err(syn::Error::new_spanned(
+                meta.path(),
+                "expected an option name",
+            ));
+        };
+
+        // Flags without a value
+        if let Meta::Path(path) = &meta {
+            return match name.as_str() {
+                "fullscreen" => set_once(&mut self.fullscreen, path, syn::parse_quote!(true)),
+                "auto_fit" => set_once(&mut self.auto_fit, path, syn::parse_quote!(true)),
+                _ => Err(unknown_option(path)),
+            };
+        }
+
+        let Meta::NameValue(pair) = meta else {
+            return Err(syn::Error::new_spanned(meta, "expected `name = value`"));
+        };
+        let value = pair.value;
+        match name.as_str() {
+            "title" => match value {
+                Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(title),
+                    ..
+                }) => self.set_title(title),
+                other => Err(syn::Error::new_spanned(other, "expected a string literal")),
+            },
+            "width" => set_once(&mut self.width, &pair.path, value),
+            "height" => set_once(&mut self.height, &pair.path, value),
+            "background" => set_once(&mut self.background, &pair.path, value),
+            "fullscreen" => set_once(&mut self.fullscreen, &pair.path, value),
+            "update" => set_once(&mut self.update, &pair.path, value),
+            "export" => set_once(&mut self.export, &pair.path, value),
+            "screenshot" => set_once(&mut self.screenshot, &pair.path, value),
+            "quit_keys" => set_once(&mut self.quit_keys, &pair.path, value),
+            "playback_keys" => set_once(&mut self.playback_keys, &pair.path, value),
+            "overlay" => set_once(&mut self.overlay, &pair.path, value),
+            "screenshot_key" => set_once(&mut self.screenshot_key, &pair.path, value),
+            "auto_fit" => set_once(&mut self.auto_fit, &pair.path, value),
+            _ => Err(unknown_option(&pair.path)),
+        }
+    }
+
+    /// The `macroquad` window configuration function for these arguments
+    pub fn window_conf(&self, fn_name: &syn::Ident) -> TokenStream2 {
+        let title = match &self.title {
+            Some(title) => quote! { #title },
+            None => quote! { "Turtle Graphics" },
+        };
+        let width = self
+            .width
+            .as_ref()
+            .map(|width| quote! { window_width: #width, });
+        let height = self
+            .height
+            .as_ref()
+            .map(|height| quote! { window_height: #height, });
+        let fullscreen = self
+            .fullscreen
+            .as_ref()
+            .map(|fullscreen| quote! { fullscreen: #fullscreen, });
+
+        quote! {
+            fn #fn_name() -> macroquad::window::Conf {
+                macroquad::window::Conf {
+                    window_title: ::std::string::String::from(#title),
+                    #width
+                    #height
+                    #fullscreen
+                    ..::std::default::Default::default()
+                }
+            }
+        }
+    }
+
+    /// Configuration of the quit keys, playback keys, screenshot key,
+    /// overlay text and auto-fit
+    pub fn app_setup(&self) -> TokenStream2 {
+        let quit_keys = self
+            .quit_keys
+            .as_ref()
+            .map(|keys| quote! { app.set_quit_keys(&#keys); });
+        let playback_keys = self
+            .playback_keys
+            .as_ref()
+            .map(|enabled| quote! { app.set_playback_keys(#enabled); });
+        let screenshot_key = self
+            .screenshot_key
+            .as_ref()
+            .map(|key| quote! { app.set_screenshot_key(::std::option::Option::Some(#key)); });
+        let auto_fit = self
+            .auto_fit
+            .as_ref()
+            .map(|enabled| quote! { app.set_auto_fit(#enabled); });
+        let overlay = self.overlay.as_ref().map(|overlay| match overlay {
+            // `overlay = false` hides the help line, `overlay = true` keeps it
+            Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Bool(enabled),
+                ..
+            }) => {
+                if enabled.value {
+                    quote! {}
+                } else {
+                    quote! { app.set_overlay_text(::std::option::Option::<&str>::None); }
+                }
+            }
+            text => quote! { app.set_overlay_text(::std::option::Option::Some(#text)); },
+        });
+        quote! {
+            #quit_keys
+            #playback_keys
+            #screenshot_key
+            #overlay
+            #auto_fit
+        }
+    }
+
+    /// `RunConfig` hook calling the `update` function every frame, if
+    /// configured
+    pub fn update_config(&self) -> TokenStream2 {
+        let Some(update) = &self.update else {
+            return quote! {};
+        };
+        quote! {
+            .on_frame({
+                // Unbounded: the update function sends on the render thread,
+                // which can't drain a full buffer until the frame ends
+                let turtle_sender = app.unbounded_turtle_channel(0);
+                move |app: &mut turtle_lib::TurtleApp| {
+                    let input = app.frame_input();
+                    #update(&input, &turtle_sender);
+                }
+            })
+        }
+    }
+
+    /// `RunConfig` setting for the `export` and `screenshot` options (empty
+    /// if neither is set)
+    pub fn export_config(&self) -> TokenStream2 {
+        if self.export.is_none() && self.screenshot.is_none() {
+            return quote! {};
+        }
+        let optional = |value: &Option<Expr>| match value {
+            Some(value) => quote! { ::std::option::Option::Some(#value) },
+            None => quote! { ::std::option::Option::None },
+        };
+        let svg = optional(&self.export);
+        let screenshot = optional(&self.screenshot);
+        quote! {
+            .with_auto_export(turtle_lib::export::AutoExport::new(#svg, #screenshot))
+        }
+    }
+
+    /// The background color expression (white unless configured)
+    pub fn background(&self) -> TokenStream2 {
+        match &self.background {
+            Some(background) => quote! { #background },
+            None => quote! { macroquad::prelude::WHITE },
+        }
+    }
+}
+
+fn set_once(slot: &mut Option<Expr>, path: &syn::Path, value: Expr) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(syn::Error::new_spanned(path, "duplicate option"));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+fn unknown_option(path: &syn::Path) -> syn::Error {
+    syn::Error::new_spanned(
+        path,
+        "unknown option, expected one of: title, width, height, background, fullscreen, \
+         update, export, screenshot, quit_keys, playback_keys, overlay, screenshot_key, auto_fit",
+    )
+}
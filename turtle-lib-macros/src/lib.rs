@@ -4,9 +4,12 @@
 //! creating turtle graphics programs by automatically setting up the
 //! macroquad window, turtle initialization, and the main rendering loop.
 
+mod args;
+
+use args::MacroArgs;
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, ItemFn};
+use syn::{parse_macro_input, FnArg, ItemFn, Type};
 
 /// A convenience macro that wraps your turtle drawing code with the necessary
 /// boilerplate for running a turtle graphics program.
@@ -15,9 +18,54 @@ use syn::{parse_macro_input, ItemFn};
 /// - Wraps your code with `#[macroquad::main]`
 /// - Creates a turtle instance (`turtle`)
 /// - Sets up the `TurtleApp` with your drawing commands
-/// - Provides a main loop with rendering and quit handling (ESC or Q)
+/// - Provides a main loop with rendering and quit handling (ESC or Q, configurable)
 /// - Adds command-line parameter support for SVG export (when `svg` feature is enabled)
 ///
+/// # Signatures
+///
+/// * `fn draw(turtle: &mut TurtlePlan)` - A single turtle
+/// * `fn draw()` - The same, with the body inlined and `turtle` in scope
+/// * `fn draw(t1: &mut TurtlePlan, t2: &mut TurtlePlan, ...)` - One turtle
+///   per parameter, with IDs in parameter order
+/// * `fn draw(app: &mut TurtleApp)` - Full control over the app, e.g. to add
+///   turtles or enable collisions
+///
+/// ```ignore
+/// #[turtle_main("Race")]
+/// fn race(red: &mut TurtlePlan, blue: &mut TurtlePlan) {
+///     red.set_pen_color(RED).forward(200.0);
+///     blue.set_pen_color(BLUE).right(90.0).forward(200.0);
+/// }
+/// ```
+///
+/// # Arguments
+///
+/// The attribute takes either just a window title or named options:
+///
+/// * `title = "..."` - Window title (default `"Turtle Graphics"`)
+/// * `width = 1280`, `height = 720` - Window size in pixels
+/// * `background = BLACK` - Background color, any `Color` expression
+/// * `fullscreen` (or `fullscreen = true`) - Start in fullscreen mode
+/// * `update = function` - Call `function(&FrameInput, &TurtleCommandSender)`
+///   every frame, see below
+/// * `export = "drawing.svg"` - Save the drawing as SVG once it is complete
+///   (or when the window closes); needs the `svg` feature
+/// * `screenshot = "drawing.png"` - Save a PNG of the window at the same time
+/// * `quit_keys = [KeyCode::Escape]` - Keys that close the window (`[]` for none)
+/// * `playback_keys = false` - Disable the SPACE/S/N/UP/DOWN playback keys
+/// * `overlay = "text"` - Replace the help line, `overlay = false` hides it
+/// * `screenshot_key = KeyCode::F12` - Save a numbered PNG screenshot when
+///   the key is pressed
+/// * `auto_fit` (or `auto_fit = true`) - Zoom out whenever the drawing grows
+///   beyond the window
+///
+/// ```ignore
+/// #[turtle_main(title = "Night Sky", width = 1280, height = 720, background = BLACK)]
+/// fn night_sky(turtle: &mut TurtlePlan) {
+///     turtle.set_pen_color(YELLOW).forward(100.0);
+/// }
+/// ```
+///
 /// # Command-Line Parameters
 ///
 /// When the `svg` feature is enabled, the following command-line parameter is available:
@@ -53,6 +101,36 @@ use syn::{parse_macro_input, ItemFn};
 /// }
 /// ```
 ///
+/// # Interactive Programs
+///
+/// With `update = function` the annotated function only sets things up and
+/// `function` runs once per frame. It receives the keyboard and mouse state
+/// and a sender for the first turtle, so it can react to input by sending
+/// new commands:
+///
+/// ```ignore
+/// use turtle_lib::*;
+///
+/// #[turtle_main(title = "Etch A Sketch", update = steer)]
+/// fn setup(turtle: &mut TurtlePlan) {
+///     turtle.set_speed(500.0);
+/// }
+///
+/// fn steer(input: &FrameInput, turtle: &TurtleCommandSender) {
+///     let mut plan = create_turtle_plan();
+///     if input.is_key_down(KeyCode::Left) {
+///         plan.left(5.0);
+///     }
+///     if input.is_key_down(KeyCode::Right) {
+///         plan.right(5.0);
+///     }
+///     if input.is_key_down(KeyCode::Up) {
+///         plan.forward(5.0);
+///     }
+///     turtle.try_send(plan.build()).ok();
+/// }
+/// ```
+///
 /// # SVG Export Example
 ///
 /// ```bash
@@ -66,153 +144,174 @@ use syn::{parse_macro_input, ItemFn};
 /// use macroquad::prelude::*;
 /// use turtle_lib::*;
 ///
-/// #[macroquad::main("My Turtle Drawing")]
+/// fn turtle_window_conf() -> macroquad::window::Conf {
+///     macroquad::window::Conf {
+///         window_title: String::from("My Turtle Drawing"),
+///         ..Default::default()
+///     }
+/// }
+///
+/// #[macroquad::main(turtle_window_conf)]
 /// async fn main() {
-///     // Parse CLI args for --export-svg flag
-///     let args: Vec<String> = std::env::args().collect();
-///     // ... (argument parsing logic)
-///     
-///     let mut turtle = create_turtle_plan();
-///     
-///     // Your drawing code here
-///     turtle.set_pen_color(RED);
-///     turtle.forward(100.0);
-///     turtle.right(90.0);
-///     turtle.forward(100.0);
+///     let mut build_app = |app: &mut TurtleApp| {
+///         let mut turtle = create_turtle_plan();
+///         my_drawing(&mut turtle);
+///         app.append_to_queue(0, turtle);
+///     };
 ///
-///     let mut app = TurtleApp::new().with_commands(turtle.build());
+///     // If the --export-svg flag is present, export and exit
+///     turtle_lib::export::handle_svg_export_app(&mut build_app);
 ///
-///     // If --export-svg flag is present, export and exit
 ///     // Otherwise, enter normal rendering loop
-///     loop {
-///         clear_background(WHITE);
-///         app.update();
-///         app.render();
-///         draw_text("Press ESC or Q to quit", 10.0, 40.0, 16.0, DARKGRAY);
-///         
-///         if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Q) {
-///             break;
-///         }
-///         
-///         next_frame().await;
-///     }
+///     let mut app = TurtleApp::new();
+///     build_app(&mut app);
+///
+///     // Clear, update, render, overlay ("ESC/Q: quit, SPACE: pause, ...")
+///     // and playback/quit keys every frame
+///     app.run(RunConfig::new().with_background(WHITE)).await;
 /// }
 /// ```
 #[proc_macro_attribute]
 pub fn turtle_main(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as MacroArgs);
     let input_fn = parse_macro_input!(input as ItemFn);
 
-    // Parse the window title from args (default to "Turtle Graphics")
-    let window_title = if args.is_empty() {
-        quote! { "Turtle Graphics" }
-    } else {
-        let args_str = args.to_string();
-        // Remove quotes if present
-        let title = args_str.trim().trim_matches('"');
-        quote! { #title }
-    };
-
     let fn_name = &input_fn.sig.ident;
     let fn_block = &input_fn.block;
 
-    // Check if the function has the expected signature
-    let has_turtle_param = input_fn.sig.inputs.len() == 1;
-
-    // Note: The following code has some duplication between the two branches
-    // (with/without turtle parameter). This is intentional in proc macros as
-    // we're generating different code paths, and extracting the common parts
-    // into helper functions would make the macro more complex without significant benefit.
-
-    let expanded = if has_turtle_param {
-        // Function takes a turtle parameter
-        quote! {
-            #[macroquad::main(#window_title)]
-            async fn main() {
-                // Build function reused for both export and normal rendering
-                let mut build_commands = |turtle: &mut turtle_lib::TurtlePlan| {
-                    #fn_name(turtle);
-                };
-
-                // Handle optional SVG export internally in turtle-lib
-                turtle_lib::export::handle_svg_export(&mut build_commands);
-
-                // Normal rendering mode (with window)
-                let mut turtle = turtle_lib::create_turtle_plan();
-
-                // Call the user's function with the turtle
-                build_commands(&mut turtle);
+    let window_conf = args.window_conf(&quote::format_ident!("turtle_window_conf"));
+    let background = args.background();
+    let update_config = args.update_config();
+    let export_config = args.export_config();
+    let app_setup = args.app_setup();
 
-                let mut app = turtle_lib::TurtleApp::new()
-                    .with_commands(turtle.build());
-
-                loop {
-                    macroquad::prelude::clear_background(macroquad::prelude::WHITE);
-                    app.update();
-                    app.render();
-                    macroquad::prelude::draw_text(
-                        "Press ESC or Q to quit",
-                        10.0,
-                        40.0,
-                        16.0,
-                        macroquad::prelude::DARKGRAY
-                    );
-
-                    if macroquad::prelude::is_key_pressed(macroquad::prelude::KeyCode::Escape)
-                        || macroquad::prelude::is_key_pressed(macroquad::prelude::KeyCode::Q)
+    // The build function differs between the supported signatures, the
+    // window setup and main loop are shared.
+    let app_params = input_fn
+        .sig
+        .inputs
+        .iter()
+        .filter(|arg| is_app_param(arg))
+        .count();
+    let (build_app, user_fn) = match (input_fn.sig.inputs.len(), app_params) {
+        // Function takes no parameters - inline the code
+        (0, _) => (
+            quote! {
+                |app: &mut turtle_lib::TurtleApp| {
+                    let mut turtle = turtle_lib::create_turtle_plan();
                     {
-                        break;
+                        let turtle = &mut turtle;
+                        #fn_block
                     }
-
-                    macroquad::prelude::next_frame().await;
+                    app.append_to_queue(0, turtle);
                 }
-            }
-
-            fn #fn_name(turtle: &mut turtle_lib::TurtlePlan) #fn_block
+            },
+            quote! {},
+        ),
+        // `fn draw(app: &mut TurtleApp)` sets up everything itself
+        (1, 1) => (
+            quote! {
+                |app: &mut turtle_lib::TurtleApp| {
+                    #fn_name(app);
+                }
+            },
+            qualified_fn(&input_fn),
+        ),
+        // One turtle per `&mut TurtlePlan` parameter, in order
+        (count, 0) => {
+            let plans: Vec<_> = (0..count)
+                .map(|i| quote::format_ident!("turtle_{}", i))
+                .collect();
+            let ids = 0..count;
+            (
+                quote! {
+                    |app: &mut turtle_lib::TurtleApp| {
+                        #(let mut #plans = turtle_lib::create_turtle_plan();)*
+                        #fn_name(#(&mut #plans),*);
+                        #(app.append_to_queue(#ids, #plans);)*
+                    }
+                },
+                qualified_fn(&input_fn),
+            )
         }
-    } else {
-        // Function takes no parameters - inline the code
-        quote! {
-            #[macroquad::main(#window_title)]
-            async fn main() {
-                // Build function reused for both export and normal rendering
-                let mut build_commands = |turtle: &mut turtle_lib::TurtlePlan| {
-                    let turtle = turtle;
-                    #fn_block
-                };
-
-                // Handle optional SVG export internally in turtle-lib
-                turtle_lib::export::handle_svg_export(&mut build_commands);
+        _ => {
+            return syn::Error::new_spanned(
+                &input_fn.sig.inputs,
+                "expected no parameters, one `&mut TurtleApp` or only `&mut TurtlePlan` parameters",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
 
-                // Normal rendering mode (with window)
-                let mut turtle = turtle_lib::create_turtle_plan();
-                build_commands(&mut turtle);
+    let expanded = quote! {
+        #window_conf
 
-                let mut app = turtle_lib::TurtleApp::new()
-                    .with_commands(turtle.build());
+        #[macroquad::main(turtle_window_conf)]
+        async fn main() {
+            // Build function reused for both export and normal rendering
+            let mut build_app = #build_app;
 
-                loop {
-                    macroquad::prelude::clear_background(macroquad::prelude::WHITE);
-                    app.update();
-                    app.render();
-                    macroquad::prelude::draw_text(
-                        "Press ESC or Q to quit",
-                        10.0,
-                        40.0,
-                        16.0,
-                        macroquad::prelude::DARKGRAY
-                    );
+            // Handle optional SVG export internally in turtle-lib
+            turtle_lib::export::handle_svg_export_app(&mut build_app);
 
-                    if macroquad::prelude::is_key_pressed(macroquad::prelude::KeyCode::Escape)
-                        || macroquad::prelude::is_key_pressed(macroquad::prelude::KeyCode::Q)
-                    {
-                        break;
-                    }
+            // Normal rendering mode (with window)
+            let mut app = turtle_lib::TurtleApp::new();
+            #app_setup
+            build_app(&mut app);
 
-                    macroquad::prelude::next_frame().await;
-                }
-            }
+            let config = turtle_lib::RunConfig::new()
+                .with_background(#background)
+                #update_config
+                #export_config;
+            app.run(config).await;
         }
+
+        #user_fn
     };
 
     TokenStream::from(expanded)
 }
+
+/// The user's function with unqualified `TurtlePlan` and `TurtleApp`
+/// parameter types replaced by their `turtle_lib` paths, so only
+/// `turtle_main` has to be imported
+fn qualified_fn(input_fn: &ItemFn) -> proc_macro2::TokenStream {
+    let mut input_fn = input_fn.clone();
+    for arg in &mut input_fn.sig.inputs {
+        let FnArg::Typed(arg) = arg else {
+            continue;
+        };
+        let Type::Reference(reference) = arg.ty.as_mut() else {
+            continue;
+        };
+        let Type::Path(path) = reference.elem.as_ref() else {
+            continue;
+        };
+        if path.qself.is_some() || path.path.segments.len() != 1 {
+            continue;
+        }
+        let ident = &path.path.segments[0].ident;
+        if ident == "TurtlePlan" || ident == "TurtleApp" {
+            *reference.elem = syn::parse_quote!(turtle_lib::#ident);
+        }
+    }
+    quote! { #input_fn }
+}
+
+/// Whether a parameter has the type `&mut TurtleApp`
+fn is_app_param(arg: &FnArg) -> bool {
+    let FnArg::Typed(arg) = arg else {
+        return false;
+    };
+    let Type::Reference(reference) = arg.ty.as_ref() else {
+        return false;
+    };
+    let Type::Path(path) = reference.elem.as_ref() else {
+        return false;
+    };
+    path.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "TurtleApp")
+}
@@ -0,0 +1,252 @@
+//! Python bindings for `turtle-lib`
+//!
+//! Exposes plan building and headless rendering to Python as the `turtlers`
+//! module, see the README. Everything runs without a window: plans are
+//! executed instantly with `turtle_lib::headless` and exported to SVG or PNG.
+
+// The code generated by `#[pymethods]` converts `PyErr` into itself
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use turtle_lib::{
+    headless, parse_color, vec2, Color, CurvedMovement, DirectionalMovement, Turnable, TurtlePlan,
+    TurtleWorld,
+};
+
+/// Size of the image Jupyter shows for a `World`
+const REPR_SIZE: (u16, u16) = (400, 300);
+
+fn parse(color: &str) -> PyResult<Color> {
+    parse_color(color).ok_or_else(|| PyValueError::new_err(format!("unknown color '{color}'")))
+}
+
+/// Commands for one turtle, methods return the plan so calls can be chained
+#[pyclass(name = "TurtlePlan", unsendable)]
+#[derive(Clone, Default)]
+struct PyTurtlePlan {
+    plan: TurtlePlan,
+}
+
+#[pymethods]
+impl PyTurtlePlan {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn forward(mut slf: PyRefMut<'_, Self>, distance: f32) -> PyRefMut<'_, Self> {
+        slf.plan.forward(distance);
+        slf
+    }
+
+    fn backward(mut slf: PyRefMut<'_, Self>, distance: f32) -> PyRefMut<'_, Self> {
+        slf.plan.backward(distance);
+        slf
+    }
+
+    fn left(mut slf: PyRefMut<'_, Self>, degrees: f32) -> PyRefMut<'_, Self> {
+        slf.plan.left(degrees);
+        slf
+    }
+
+    fn right(mut slf: PyRefMut<'_, Self>, degrees: f32) -> PyRefMut<'_, Self> {
+        slf.plan.right(degrees);
+        slf
+    }
+
+    #[pyo3(signature = (radius, degrees = 360.0, steps = 36))]
+    fn circle_left(
+        mut slf: PyRefMut<'_, Self>,
+        radius: f32,
+        degrees: f32,
+        steps: usize,
+    ) -> PyRefMut<'_, Self> {
+        slf.plan.circle_left(radius, degrees, steps);
+        slf
+    }
+
+    #[pyo3(signature = (radius, degrees = 360.0, steps = 36))]
+    fn circle_right(
+        mut slf: PyRefMut<'_, Self>,
+        radius: f32,
+        degrees: f32,
+        steps: usize,
+    ) -> PyRefMut<'_, Self> {
+        slf.plan.circle_right(radius, degrees, steps);
+        slf
+    }
+
+    fn go_to(mut slf: PyRefMut<'_, Self>, x: f32, y: f32) -> PyRefMut<'_, Self> {
+        slf.plan.go_to(vec2(x, y));
+        slf
+    }
+
+    /// Degrees counter-clockwise from east
+    fn set_heading(mut slf: PyRefMut<'_, Self>, degrees: f32) -> PyRefMut<'_, Self> {
+        slf.plan.set_heading(degrees);
+        slf
+    }
+
+    fn pen_up(mut slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf.plan.pen_up();
+        slf
+    }
+
+    fn pen_down(mut slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf.plan.pen_down();
+        slf
+    }
+
+    fn set_pen_color<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        color: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.plan.set_pen_color(parse(color)?);
+        Ok(slf)
+    }
+
+    fn set_fill_color<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        color: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.plan.set_fill_color(parse(color)?);
+        Ok(slf)
+    }
+
+    fn set_pen_width(mut slf: PyRefMut<'_, Self>, width: f32) -> PyRefMut<'_, Self> {
+        slf.plan.set_pen_width(width);
+        slf
+    }
+
+    fn begin_fill(mut slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf.plan.begin_fill();
+        slf
+    }
+
+    fn end_fill(mut slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf.plan.end_fill();
+        slf
+    }
+
+    fn hide(mut slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf.plan.hide();
+        slf
+    }
+
+    fn show(mut slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf.plan.show();
+        slf
+    }
+
+    /// Where the plan ends up, without drawing anything
+    fn analyze(&self) -> PyAnalysis {
+        let analysis = self.plan.analyze(turtle_lib::AnimationSpeed::default());
+        PyAnalysis {
+            position: (analysis.position.x, analysis.position.y),
+            heading: analysis.heading.to_degrees().value(),
+            bounds: (
+                (analysis.bounds.min.x, analysis.bounds.min.y),
+                (analysis.bounds.max.x, analysis.bounds.max.y),
+            ),
+            duration: analysis.duration,
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.plan.clone().build().len()
+    }
+}
+
+/// Result of `TurtlePlan.analyze()`
+#[pyclass(name = "Analysis", get_all, frozen)]
+struct PyAnalysis {
+    /// Final position `(x, y)`
+    position: (f32, f32),
+    /// Final heading in degrees
+    heading: f32,
+    /// Corners `((min_x, min_y), (max_x, max_y))` of everything passed
+    bounds: ((f32, f32), (f32, f32)),
+    /// Seconds the animation would take in a window
+    duration: f64,
+}
+
+#[pymethods]
+impl PyAnalysis {
+    fn __repr__(&self) -> String {
+        format!(
+            "Analysis(position={:?}, heading={}, bounds={:?}, duration={})",
+            self.position, self.heading, self.bounds, self.duration
+        )
+    }
+}
+
+/// Drawings of any number of turtles, rendered without a window
+#[pyclass(name = "World", unsendable)]
+struct PyWorld {
+    world: TurtleWorld,
+}
+
+#[pymethods]
+impl PyWorld {
+    #[new]
+    fn new() -> Self {
+        Self {
+            world: TurtleWorld::headless(),
+        }
+    }
+
+    /// Run `plan` instantly on turtle `turtle_id`, creating it if needed
+    #[pyo3(signature = (plan, turtle_id = 0))]
+    fn execute(&mut self, plan: &PyTurtlePlan, turtle_id: usize) {
+        headless::execute(&mut self.world, turtle_id, &plan.plan.clone().build());
+    }
+
+    fn set_background(&mut self, color: &str) -> PyResult<()> {
+        self.world.background_color = parse(color)?;
+        Ok(())
+    }
+
+    fn export_svg(&self, filename: &str) -> PyResult<()> {
+        headless::export_svg(&self.world, filename)
+            .map_err(|error| PyIOError::new_err(format!("{error:?}")))
+    }
+
+    #[pyo3(signature = (filename, width = 800, height = 600))]
+    fn export_png(&self, filename: &str, width: u16, height: u16) -> PyResult<()> {
+        headless::export_png(&self.world, filename, width, height)
+            .map_err(|error| PyIOError::new_err(format!("{error:?}")))
+    }
+
+    /// PNG file contents, e.g. for `IPython.display.Image`
+    #[pyo3(signature = (width = 800, height = 600))]
+    fn png<'py>(&self, py: Python<'py>, width: u16, height: u16) -> PyResult<Bound<'py, PyBytes>> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        // PNGs are only encoded into files
+        let path = std::env::temp_dir().join(format!(
+            "turtlers-{}-{}.png",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        headless::export_png(&self.world, &path.to_string_lossy(), width, height)
+            .map_err(|error| PyIOError::new_err(format!("{error:?}")))?;
+        let bytes = std::fs::read(&path).map_err(|error| PyIOError::new_err(error.to_string()));
+        std::fs::remove_file(&path).ok();
+        Ok(PyBytes::new_bound(py, &bytes?))
+    }
+
+    /// Shown by Jupyter when a `World` is the last expression of a cell
+    fn _repr_png_<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        self.png(py, REPR_SIZE.0, REPR_SIZE.1)
+    }
+}
+
+#[pymodule]
+fn turtlers(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyTurtlePlan>()?;
+    module.add_class::<PyAnalysis>()?;
+    module.add_class::<PyWorld>()?;
+    Ok(())
+}
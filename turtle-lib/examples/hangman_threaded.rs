@@ -96,7 +96,7 @@ fn run_game_logic(
     const MAX_WRONG: usize = 8; // 8 body parts after base
 
     // Draw current state of lines
-    draw_lines_state(&lines_tx, &secret, &all_guesses);
+    draw_lines_state(&lines_tx, secret, &all_guesses);
     // Main game loop
     loop {
         // Check if won
@@ -120,7 +120,7 @@ fn run_game_logic(
         if secret.contains(&guess_lower) {
             println!("✓ Correct! '{}' is in the word", guess_lower);
             // Draw current state of lines
-            draw_lines_state(&lines_tx, &secret, &all_guesses);
+            draw_lines_state(&lines_tx, secret, &all_guesses);
         } else {
             println!("✗ Wrong! '{}' is NOT in the word", guess_lower);
             wrong_guesses += 1;
@@ -272,7 +272,7 @@ fn draw_lines_state(tx: &TurtleCommandSender, secret: &str, all_guesses: &str) {
         if all_guesses.contains(letter) {
             // Draw green circle for revealed letter
             plan.set_pen_color(GREEN)
-                .write_text(&letter.to_uppercase().to_string(), 32u16)
+                .write_text(letter.to_uppercase().to_string(), 32u16)
                 .forward(15.0);
         } else {
             // Draw black underscore
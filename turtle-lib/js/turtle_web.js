@@ -0,0 +1,27 @@
+// Browser side of turtle-lib's `web` module, load it after mq_js_bundle.js:
+//
+//     <script src="mq_js_bundle.js"></script>
+//     <script src="turtle_web.js"></script>
+//     <script>load("my_turtle_program.wasm");</script>
+"use strict";
+
+miniquad_add_plugin({
+    name: "turtle_web",
+    version: 1,
+    register_plugin: function (importObject) {
+        // Offer `data_len` bytes at `data_ptr` as a file download
+        importObject.env.turtle_download = function (name_ptr, name_len, mime_ptr, mime_len, data_ptr, data_len) {
+            const text = (ptr, len) => new TextDecoder().decode(new Uint8Array(wasm_memory.buffer, ptr, len));
+            // Copy, the wasm memory may grow and detach the view
+            const data = new Uint8Array(wasm_memory.buffer, data_ptr, data_len).slice();
+            const blob = new Blob([data], { type: text(mime_ptr, mime_len) });
+            const link = document.createElement("a");
+            link.href = URL.createObjectURL(blob);
+            link.download = text(name_ptr, name_len);
+            document.body.appendChild(link);
+            link.click();
+            link.remove();
+            URL.revokeObjectURL(link.href);
+        };
+    },
+});
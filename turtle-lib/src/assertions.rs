@@ -0,0 +1,136 @@
+//! Checks for auto-grading turtle programs
+//!
+//! Each check runs the plan with `simulate()`, without a window, and panics
+//! with a message meant for students if the drawing doesn't meet the
+//! expectation, so they work like `assert!` in regular tests:
+//!
+//! ```
+//! use turtle_lib::*;
+//! use turtle_lib::assertions::*;
+//!
+//! // The student's solution
+//! fn square(turtle: &mut TurtlePlan) {
+//!     for _ in 0..4 {
+//!         turtle.forward(100.0).left(90.0);
+//!     }
+//! }
+//!
+//! let mut plan = create_turtle_plan();
+//! square(&mut plan);
+//! expect_position(&plan, (0.0, 0.0), 0.01);
+//! expect_visits_point(&plan, (100.0, 100.0), 0.01);
+//! expect_total_distance_at_least(&plan, 400.0);
+//! ```
+//!
+//! Points are given in the default `CoordinateMode::MathYUp`, with the
+//! turtle starting at the origin.
+
+use crate::builders::TurtlePlan;
+use crate::general::{Coordinate, CoordinateMode};
+use crate::headless::{simulate, SimulationResult};
+
+fn run(plan: &TurtlePlan) -> SimulationResult {
+    simulate(&plan.clone().build())
+}
+
+fn to_screen(point: impl Into<Coordinate>) -> Coordinate {
+    CoordinateMode::default().to_screen(point.into())
+}
+
+fn from_screen(point: Coordinate) -> Coordinate {
+    CoordinateMode::default().from_screen(point)
+}
+
+/// Distance from `point` to the line segment from `start` to `end`
+fn distance_to_segment(point: Coordinate, start: Coordinate, end: Coordinate) -> f32 {
+    let along = end - start;
+    let length_squared = along.length_squared();
+    if length_squared == 0.0 {
+        return point.distance(start);
+    }
+    let t = ((point - start).dot(along) / length_squared).clamp(0.0, 1.0);
+    point.distance(start + along * t)
+}
+
+/// Check that the turtle ends within `tolerance` pixels of `expected`
+///
+/// # Panics
+///
+/// Panics if it ends further away
+#[track_caller]
+pub fn expect_position(plan: &TurtlePlan, expected: impl Into<Coordinate>, tolerance: f32) {
+    let expected = expected.into();
+    let actual = from_screen(run(plan).params.position);
+    assert!(
+        actual.distance(expected) <= tolerance,
+        "the turtle ends at ({:.2}, {:.2}), expected ({:.2}, {:.2}) ± {tolerance}",
+        actual.x,
+        actual.y,
+        expected.x,
+        expected.y,
+    );
+}
+
+/// Check that the turtle passes within `tolerance` pixels of `point`, with
+/// the pen up or down
+///
+/// # Panics
+///
+/// Panics if it never gets that close
+#[track_caller]
+pub fn expect_visits_point(plan: &TurtlePlan, point: impl Into<Coordinate>, tolerance: f32) {
+    let expected = point.into();
+    let point = to_screen(expected);
+    let trace = run(plan).trace;
+    let closest = match trace.as_slice() {
+        [only] => only.distance(point),
+        _ => trace
+            .windows(2)
+            .map(|segment| distance_to_segment(point, segment[0], segment[1]))
+            .fold(f32::INFINITY, f32::min),
+    };
+    assert!(
+        closest <= tolerance,
+        "the turtle never visits ({:.2}, {:.2}) ± {tolerance}, it gets {closest:.2} pixels close",
+        expected.x,
+        expected.y,
+    );
+}
+
+/// Check that the turtle moves at least `distance` pixels in total, with the
+/// pen up or down
+///
+/// # Panics
+///
+/// Panics if it moves less (with a tolerance of 0.01% for sampled arcs)
+#[track_caller]
+pub fn expect_total_distance_at_least(plan: &TurtlePlan, distance: f32) {
+    let trace = run(plan).trace;
+    let travelled: f32 = trace
+        .windows(2)
+        .map(|segment| segment[0].distance(segment[1]))
+        .sum();
+    assert!(
+        travelled >= distance * 0.9999,
+        "the turtle moves {travelled:.2} pixels, expected at least {distance}",
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::{CurvedMovement, DirectionalMovement, Turnable};
+
+    #[test]
+    #[should_panic(expected = "never visits (0.00, 50.00)")]
+    fn reports_missed_points() {
+        let mut plan = TurtlePlan::new();
+        plan.pen_up().circle_left(50.0, 360.0, 36);
+        expect_visits_point(&plan, (0.0, 100.0), 0.5);
+        expect_total_distance_at_least(&plan, 100.0 * std::f32::consts::PI);
+
+        plan.left(90.0).forward(20.0);
+        expect_position(&plan, (0.0, 20.0), 0.01);
+        expect_visits_point(&plan, (0.0, 50.0), 5.0);
+    }
+}
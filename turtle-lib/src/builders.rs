@@ -1,8 +1,13 @@
 //! Builder pattern traits for creating turtle command sequences
 
-use crate::commands::{CommandQueue, TurtleCommand};
-use crate::general::{AnimationSpeed, Color, Coordinate, FontSize, Precision};
-use crate::shapes::{ShapeType, TurtleShape};
+use crate::commands::{CommandQueue, StampId, TurtleCommand};
+use crate::error::TurtleError;
+use crate::general::{Angle, AnimationSpeed, Color, Coordinate, FontSize, Length, Precision};
+use crate::palettes::Palette;
+use crate::shapes::{ShapeSize, ShapeType, TurtleShape};
+use crate::state::BlendMode;
+use crate::stencil::{Stencil, StencilTransform};
+use crate::tweening::Easing;
 
 /// Trait for adding commands to a queue
 pub trait WithCommands {
@@ -15,7 +20,8 @@ pub trait DirectionalMovement: WithCommands {
     /// Moves the turtle forward by the specified distance.
     ///
     /// The turtle moves in the direction of its current heading.
-    /// If the pen is down, a line is drawn.
+    /// If the pen is down, a line is drawn. The distance is in pixels, or a
+    /// `Length` in other units like `Length::cm(2.0)`.
     ///
     /// # Examples
     ///
@@ -33,9 +39,9 @@ pub trait DirectionalMovement: WithCommands {
     /// ```
     fn forward<T>(&mut self, distance: T) -> &mut Self
     where
-        T: Into<Precision>,
+        T: Into<Length>,
     {
-        let dist: Precision = distance.into();
+        let dist = distance.into().0;
         self.get_commands_mut().push(TurtleCommand::Move(dist));
         self
     }
@@ -61,9 +67,9 @@ pub trait DirectionalMovement: WithCommands {
     /// ```
     fn backward<T>(&mut self, distance: T) -> &mut Self
     where
-        T: Into<Precision>,
+        T: Into<Length>,
     {
-        let dist: Precision = distance.into();
+        let dist = distance.into().0;
         self.get_commands_mut().push(TurtleCommand::Move(-dist));
         self
     }
@@ -74,7 +80,8 @@ pub trait Turnable: WithCommands {
     /// Turns the turtle left (counter-clockwise) by the specified angle in degrees.
     ///
     /// Changes the turtle's heading without moving its position.
-    /// Does not draw anything.
+    /// Does not draw anything. Pass an `Angle` like `Angle::radians(PI)` to
+    /// turn by an angle in radians.
     ///
     /// # Examples
     ///
@@ -87,13 +94,16 @@ pub trait Turnable: WithCommands {
     ///     for _ in 0..4 {
     ///         turtle.forward(100.0).left(90.0);
     ///     }
+    ///
+    ///     // Units are explicit with `Angle` and `Length`
+    ///     turtle.left(Angle::radians(std::f32::consts::FRAC_PI_2)).forward(Length::cm(2.0));
     /// }
     /// ```
     fn left<T>(&mut self, angle: T) -> &mut Self
     where
-        T: Into<Precision>,
+        T: Into<Angle>,
     {
-        let degrees: Precision = angle.into();
+        let degrees = angle.into().to_degrees().value();
         self.get_commands_mut().push(TurtleCommand::Turn(-degrees));
         self
     }
@@ -118,9 +128,9 @@ pub trait Turnable: WithCommands {
     /// ```
     fn right<T>(&mut self, angle: T) -> &mut Self
     where
-        T: Into<Precision>,
+        T: Into<Angle>,
     {
-        let degrees: Precision = angle.into();
+        let degrees = angle.into().to_degrees().value();
         self.get_commands_mut().push(TurtleCommand::Turn(degrees));
         self
     }
@@ -159,11 +169,11 @@ pub trait CurvedMovement: WithCommands {
     /// ```
     fn circle_left<R, A>(&mut self, radius: R, angle: A, steps: usize) -> &mut Self
     where
-        R: Into<Precision>,
-        A: Into<Precision>,
+        R: Into<Length>,
+        A: Into<Angle>,
     {
-        let r: Precision = radius.into();
-        let a: Precision = angle.into();
+        let r = radius.into().0;
+        let a = angle.into().to_degrees().value();
         self.get_commands_mut().push(TurtleCommand::Circle {
             radius: r,
             angle: a,
@@ -206,11 +216,11 @@ pub trait CurvedMovement: WithCommands {
     /// ```
     fn circle_right<R, A>(&mut self, radius: R, angle: A, steps: usize) -> &mut Self
     where
-        R: Into<Precision>,
-        A: Into<Precision>,
+        R: Into<Length>,
+        A: Into<Angle>,
     {
-        let r: Precision = radius.into();
-        let a: Precision = angle.into();
+        let r = radius.into().0;
+        let a = angle.into().to_degrees().value();
         self.get_commands_mut().push(TurtleCommand::Circle {
             radius: r,
             angle: a,
@@ -223,8 +233,11 @@ pub trait CurvedMovement: WithCommands {
 
 /// Builder for creating turtle command sequences
 #[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TurtlePlan {
     queue: CommandQueue,
+    // Stencils begun but not ended yet, innermost last
+    open_stencils: Vec<Stencil>,
 }
 
 impl TurtlePlan {
@@ -261,6 +274,7 @@ impl TurtlePlan {
     pub fn new() -> Self {
         Self {
             queue: CommandQueue::new(),
+            open_stencils: Vec::new(),
         }
     }
 
@@ -294,6 +308,88 @@ impl TurtlePlan {
         self
     }
 
+    /// Sets the easing curve for subsequent animated commands.
+    ///
+    /// The default is `Easing::CubicInOut`, which starts and ends each
+    /// movement slowly. Has no visible effect in instant mode.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Easing Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     // Constant speed movement
+    ///     turtle.set_easing(Easing::Linear)
+    ///           .forward(100.0);
+    ///
+    ///     // Overshoot and settle
+    ///     turtle.set_easing(Easing::ElasticOut)
+    ///           .right(90.0);
+    /// }
+    /// ```
+    pub fn set_easing(&mut self, easing: Easing) -> &mut Self {
+        self.queue.push(TurtleCommand::SetEasing(easing));
+        self
+    }
+
+    /// Sets how fast the turtle rotates, in degrees per second.
+    ///
+    /// Applies to `left()`, `right()` and `set_heading()` while animating, so
+    /// the turtle can turn slowly and still move fast (or the other way round).
+    /// Pass `None` to derive the rotation speed from `set_speed()` again.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Turn Speed Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     // Fast lines, slow and visible turns
+    ///     turtle.set_speed(400.0).set_turn_speed(90.0);
+    ///     for _ in 0..4 {
+    ///         turtle.forward(100.0).right(90.0);
+    ///     }
+    /// }
+    /// ```
+    pub fn set_turn_speed(
+        &mut self,
+        degrees_per_second: impl Into<Option<Precision>>,
+    ) -> &mut Self {
+        self.queue
+            .push(TurtleCommand::SetTurnSpeed(degrees_per_second.into()));
+        self
+    }
+
+    /// Sets how many seconds pen color and pen width changes take.
+    ///
+    /// With a transition, `set_pen_color()` blends smoothly to the new color
+    /// (in a perceptual color space) and `set_pen_width()` grows or shrinks
+    /// gradually instead of switching at once. Combined with a movement in
+    /// `together()`, a single stroke shifts from one style to the next over at
+    /// least this time. `0.0` (the default) switches immediately.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Style Transition Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.set_style_transition(0.5);
+    ///     // The line shifts from black to red while it is drawn
+    ///     turtle.together(|t| {
+    ///         t.forward(200.0).set_pen_color(RED).set_pen_width(8.0);
+    ///     });
+    /// }
+    /// ```
+    pub fn set_style_transition(&mut self, seconds: Precision) -> &mut Self {
+        self.queue.push(TurtleCommand::SetStyleTransition(seconds));
+        self
+    }
+
     /// Sets the pen color for drawing lines.
     ///
     /// The pen color affects all subsequent drawing operations (forward, backward, circles)
@@ -319,6 +415,73 @@ impl TurtlePlan {
         self
     }
 
+    /// Sets the pen color from 0–255 channels.
+    ///
+    /// Like Python turtle's `pencolor(255, 128, 0)` after `colormode(255)`,
+    /// which makes porting such programs easier.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("RGB Color Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.set_pen_color_rgb(255, 128, 0).forward(100.0);
+    /// }
+    /// ```
+    pub fn set_pen_color_rgb(&mut self, red: u8, green: u8, blue: u8) -> &mut Self {
+        self.set_pen_color(Color::from_rgba(red, green, blue, 255))
+    }
+
+    /// Sets how the following strokes combine with the drawing below them.
+    ///
+    /// `BlendMode::Additive` makes overlapping lines glow on dark
+    /// backgrounds, `BlendMode::Multiply` darkens them like layered ink. See
+    /// `BlendMode` for the limitations.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Blend Mode Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.set_blend_mode(BlendMode::Additive).set_pen_width(8.0);
+    ///     for _ in 0..36 {
+    ///         turtle.set_pen_color(Color::new(0.2, 0.1, 0.4, 1.0)).forward(150.0).right(170.0);
+    ///     }
+    /// }
+    /// ```
+    pub fn set_blend_mode(&mut self, mode: BlendMode) -> &mut Self {
+        self.queue.push(TurtleCommand::SetBlendMode(mode));
+        self
+    }
+
+    /// Draws every following stroke in the next color of `palette`.
+    ///
+    /// Each line, arc or `together()` group drawn with the pen down advances
+    /// the palette by one color, starting over after the last. Setting a pen
+    /// color with `set_pen_color()` stops using the palette.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Palette Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.set_pen_palette(Palette::classroom()).set_pen_width(4.0);
+    ///     for _ in 0..8 {
+    ///         turtle.forward(120.0).right(135.0);
+    ///     }
+    /// }
+    /// ```
+    pub fn set_pen_palette(&mut self, palette: Palette) -> &mut Self {
+        self.queue.push(TurtleCommand::SetPenPalette(Some(palette)));
+        self
+    }
+
     /// Sets the pen width (thickness) for drawing lines.
     ///
     /// The width is measured in pixels. Default is typically 2.0.
@@ -369,7 +532,7 @@ impl TurtlePlan {
     /// ```
     pub fn set_heading(&mut self, heading: Precision) -> &mut Self {
         self.queue
-            .push(TurtleCommand::SetHeading(-heading.to_radians()));
+            .push(TurtleCommand::SetHeading(heading.to_radians()));
         self
     }
 
@@ -518,6 +681,123 @@ impl TurtlePlan {
         self.set_shape(shape_type.to_shape())
     }
 
+    /// Scales the turtle cursor, like Python turtle's `turtlesize()`.
+    ///
+    /// `stretch_wid` scales the shape perpendicular to the heading,
+    /// `stretch_len` along it, and `outline` sets the width of the cursor's
+    /// outline in pixels. Only the displayed cursor changes (and collision
+    /// detection, which uses the cursor), not the drawing. The change animates
+    /// over the time set with `set_style_transition()`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Shape Size Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     // A long, thin turtle with a thick outline
+    ///     turtle.set_style_transition(1.0).set_shape_size(0.5, 3.0, 3.0);
+    /// }
+    /// ```
+    pub fn set_shape_size(
+        &mut self,
+        stretch_wid: Precision,
+        stretch_len: Precision,
+        outline: Precision,
+    ) -> &mut Self {
+        self.queue.push(TurtleCommand::SetShapeSize(ShapeSize::new(
+            stretch_wid,
+            stretch_len,
+            outline,
+        )));
+        self
+    }
+
+    /// Sets the fill and outline color of the turtle cursor.
+    ///
+    /// Only the cursor changes, the pen color stays. The outline width is set
+    /// with `set_shape_size()`. The change animates over the time set with
+    /// `set_style_transition()`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Turtle Color Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.set_turtle_color(GREEN, DARKGRAY).set_shape_size(1.5, 1.5, 2.0);
+    /// }
+    /// ```
+    pub fn set_turtle_color(&mut self, fill: Color, outline: Color) -> &mut Self {
+        self.queue
+            .push(TurtleCommand::SetTurtleColor { fill, outline });
+        self
+    }
+
+    /// Rotates the turtle cursor by `angle` degrees without changing the
+    /// heading.
+    ///
+    /// Positive angles rotate clockwise like `right()`. Only the displayed
+    /// shape turns, `forward()` still moves along the heading. The tilt stays
+    /// relative to the heading, so the cursor keeps its tilt while turning.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Tilt Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     // A crab walking sideways
+    ///     turtle.shape(ShapeType::Arrow).tilt(90.0).forward(100.0);
+    /// }
+    /// ```
+    pub fn tilt(&mut self, angle: Precision) -> &mut Self {
+        self.queue.push(TurtleCommand::Tilt(angle));
+        self
+    }
+
+    /// Sets the rotation of the turtle cursor relative to the heading, in
+    /// degrees (clockwise, `0.0` points the shape along the heading).
+    ///
+    /// See `tilt()`.
+    pub fn set_tilt_angle(&mut self, angle: Precision) -> &mut Self {
+        self.queue
+            .push(TurtleCommand::SetTiltAngle(angle.to_radians()));
+        self
+    }
+
+    /// Sets the turtle's appearance to a shape registered by name.
+    ///
+    /// Shapes are registered on the world with `TurtleApp::register_shape()`
+    /// and can be used by all turtles. If no shape with that name is
+    /// registered when the command executes, a warning is logged and the
+    /// shape stays unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Shape Example")]
+    /// fn draw(app: &mut TurtleApp) {
+    ///     app.register_shape(
+    ///         "house",
+    ///         vec![vec2(10.0, 0.0), vec2(0.0, -10.0), vec2(-10.0, -10.0),
+    ///              vec2(-10.0, 10.0), vec2(0.0, 10.0)],
+    ///     );
+    ///     let mut turtle = create_turtle_plan();
+    ///     turtle.shape_named("house").forward(100.0);
+    ///     app.append_to_queue(0, turtle);
+    /// }
+    /// ```
+    pub fn shape_named(&mut self, name: impl Into<String>) -> &mut Self {
+        self.queue.push(TurtleCommand::SetShapeNamed(name.into()));
+        self
+    }
+
     /// Starts recording a shape to be filled.
     ///
     /// All turtle movements between `begin_fill()` and `end_fill()` define
@@ -607,6 +887,11 @@ impl TurtlePlan {
         self
     }
 
+    /// Sets the fill color from 0–255 channels, see `set_pen_color_rgb()`.
+    pub fn set_fill_color_rgb(&mut self, red: u8, green: u8, blue: u8) -> &mut Self {
+        self.set_fill_color(Color::from_rgba(red, green, blue, 255))
+    }
+
     /// Moves the turtle to an absolute position.
     ///
     /// The turtle moves in a straight line to the specified coordinates.
@@ -636,6 +921,40 @@ impl TurtlePlan {
         self
     }
 
+    /// Remembers the turtle's current position under a name.
+    ///
+    /// The position is recorded when the command executes and is stored in the
+    /// world, so any turtle can return to it with `go_to_mark()`. Marking the
+    /// same name again overwrites the old position.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Mark Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.forward(100.0).mark("roof_corner");
+    ///     turtle.left(90.0).forward(80.0);
+    ///
+    ///     // Back to the corner without tracking the position manually
+    ///     turtle.go_to_mark("roof_corner");
+    /// }
+    /// ```
+    pub fn mark(&mut self, name: impl Into<String>) -> &mut Self {
+        self.queue.push(TurtleCommand::Mark(name.into()));
+        self
+    }
+
+    /// Moves the turtle to a position recorded with `mark()`.
+    ///
+    /// Behaves like `go_to()`. If no mark with that name exists when the
+    /// command executes, a warning is logged and the command is skipped.
+    pub fn go_to_mark(&mut self, name: impl Into<String>) -> &mut Self {
+        self.queue.push(TurtleCommand::GoToMark(name.into()));
+        self
+    }
+
     /// Writes text at the turtle's current position, oriented along its heading direction.
     ///
     /// The text is rendered with its baseline positioned slightly above the turtle's current position,
@@ -677,6 +996,30 @@ impl TurtlePlan {
         self
     }
 
+    /// Labels the next stroke.
+    ///
+    /// The label is drawn next to the middle of the next line, arc or
+    /// `together()` group drawn with the pen down, and exported to SVG as the
+    /// stroke's `<title>` and a `<text>`. Strokes drawn with the pen up don't
+    /// use up the label.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Annotation Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.annotate("a").forward(120.0).left(90.0);
+    ///     turtle.annotate("b").forward(90.0);
+    ///     turtle.annotate("hypotenuse").go_to(vec2(0.0, 0.0));
+    /// }
+    /// ```
+    pub fn annotate(&mut self, label: impl Into<String>) -> &mut Self {
+        self.queue.push(TurtleCommand::Annotate(label.into()));
+        self
+    }
+
     /// Resets the turtle to its default state.
     ///
     /// This clears all drawings, clears the animation queue, and resets all turtle parameters:
@@ -712,6 +1055,443 @@ impl TurtlePlan {
         self
     }
 
+    /// Clears the drawings of all turtles, like Python turtle's `clearscreen`.
+    ///
+    /// Unlike `reset()`, this affects every turtle in the world but keeps the
+    /// pen, colors and other settings. With `rehome` set to `true` all turtles
+    /// also return to the origin facing right.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Clear Screen Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.forward(100.0);
+    ///
+    ///     // Wipe everything and start over from the center
+    ///     turtle.clear_screen(true);
+    ///     turtle.circle_left(50.0, 360.0, 36);
+    /// }
+    /// ```
+    pub fn clear_screen(&mut self, rehome: bool) -> &mut Self {
+        self.queue.push(TurtleCommand::ClearScreen { rehome });
+        self
+    }
+
+    /// Runs a group of commands at the same time.
+    ///
+    /// All commands added inside the closure start from the same state and
+    /// finish together, taking as long as the slowest of them. Movements,
+    /// turns and pen width changes add up, so the turtle can move while
+    /// turning or while its line gets thicker. Pen colors blend along the
+    /// way, other settings switch halfway through the group.
+    ///
+    /// Commands that only make sense in sequence (fills, text, markers,
+    /// speed and similar) are ignored with a warning. Nested groups are merged.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Together Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     // A line that grows thicker while it is drawn
+    ///     turtle.together(|group| {
+    ///         group.forward(200.0).set_pen_width(12.0);
+    ///     });
+    ///
+    ///     // Move and turn at once for a curved path
+    ///     turtle.together(|group| {
+    ///         group.forward(150.0).left(90.0);
+    ///     });
+    /// }
+    /// ```
+    pub fn together(&mut self, build: impl FnOnce(&mut TurtlePlan)) -> &mut Self {
+        let mut group = TurtlePlan::new();
+        build(&mut group);
+
+        let mut members = Vec::new();
+        collect_group_members(&mut members, group.build());
+        self.queue.push(TurtleCommand::Together(members));
+        self
+    }
+
+    /// Leaves a copy of the turtle cursor on the canvas, like Python turtle's
+    /// `stamp`.
+    ///
+    /// Returns an ID to remove the stamp later with `clear_stamp()`. Unlike
+    /// most plan methods this does not chain.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Stamp Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.pen_up();
+    ///     // A trail of three stamps that cleans up after itself
+    ///     let mut trail = std::collections::VecDeque::new();
+    ///     for _ in 0..12 {
+    ///         trail.push_back(turtle.stamp());
+    ///         if trail.len() > 3 {
+    ///             turtle.clear_stamp(trail.pop_front().unwrap());
+    ///         }
+    ///         turtle.forward(30.0).right(30.0);
+    ///     }
+    /// }
+    /// ```
+    pub fn stamp(&mut self) -> StampId {
+        let id = StampId::next();
+        self.queue.push(TurtleCommand::Stamp(id));
+        id
+    }
+
+    /// Removes a stamp created by this turtle with `stamp()`.
+    pub fn clear_stamp(&mut self, id: StampId) -> &mut Self {
+        self.queue.push(TurtleCommand::ClearStamp(id));
+        self
+    }
+
+    /// Removes the first `n` of this turtle's stamps, or the last `-n` if `n`
+    /// is negative, like Python turtle's `clearstamps`.
+    pub fn clear_stamps(&mut self, n: i32) -> &mut Self {
+        self.queue.push(TurtleCommand::ClearStamps(Some(n)));
+        self
+    }
+
+    /// Removes all of this turtle's stamps, keeping its other drawings.
+    pub fn clear_all_stamps(&mut self) -> &mut Self {
+        self.queue.push(TurtleCommand::ClearStamps(None));
+        self
+    }
+
+    /// Saves the position, heading, pen width and pen color.
+    ///
+    /// `pop_state()` returns to them, so a branching drawing can go back to
+    /// the fork without retracing its steps. Saved states form a stack, so
+    /// pushes can be nested.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// fn tree(turtle: &mut TurtlePlan, length: f32, depth: u32) {
+    ///     if depth == 0 {
+    ///         return;
+    ///     }
+    ///     turtle.set_pen_width(depth as f32).forward(length);
+    ///     for angle in [-25.0_f32, 25.0] {
+    ///         turtle.push_state().right(angle);
+    ///         tree(turtle, length * 0.7, depth - 1);
+    ///         turtle.pop_state();
+    ///     }
+    /// }
+    ///
+    /// #[turtle_main("Tree Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.set_heading(90.0);
+    ///     tree(turtle, 80.0, 8);
+    /// }
+    /// ```
+    pub fn push_state(&mut self) -> &mut Self {
+        self.queue.push(TurtleCommand::PushState);
+        self
+    }
+
+    /// Restores what the matching `push_state()` saved.
+    ///
+    /// The turtle jumps back without drawing. Popping more states than were
+    /// pushed is skipped and reports `TurtleError::PopWithoutPush`.
+    pub fn pop_state(&mut self) -> &mut Self {
+        self.queue.push(TurtleCommand::PopState);
+        self
+    }
+
+    /// Runs `build` between `push_state()` and `pop_state()`.
+    ///
+    /// The turtle draws a branch and ends up where it started.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Branch Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     // A star of twelve rays around the center
+    ///     for _ in 0..12 {
+    ///         turtle.branch(|ray| {
+    ///             ray.forward(100.0);
+    ///         });
+    ///         turtle.right(30.0);
+    ///     }
+    /// }
+    /// ```
+    pub fn branch(&mut self, build: impl FnOnce(&mut TurtlePlan)) -> &mut Self {
+        self.push_state();
+        build(self);
+        self.pop_state()
+    }
+
+    /// Waits until every turtle that has a `wait_for_group(tag)` with the
+    /// same tag ahead reached it, then all of them continue together.
+    ///
+    /// Turtles that never wait for `tag`, or have already passed it, aren't
+    /// waited for. Barriers are coordinated by `TurtleApp::update()`, turtles
+    /// without it (e.g. headless rendering) pass them right away.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[macroquad::main("Barrier Example")]
+    /// async fn main() {
+    ///     let mut app = TurtleApp::new();
+    ///     let dancers = TurtleGroup::spawn(&mut app, 2);
+    ///     dancers.send(&mut app, |index, plan| {
+    ///         // The second turtle takes a detour but both turn around together
+    ///         plan.forward(100.0 + index as f32 * 200.0)
+    ///             .wait_for_group("turn")
+    ///             .right(180.0)
+    ///             .forward(100.0);
+    ///     });
+    ///     app.run(RunConfig::new()).await;
+    /// }
+    /// ```
+    pub fn wait_for_group(&mut self, tag: impl Into<String>) -> &mut Self {
+        self.queue.push(TurtleCommand::Barrier(tag.into()));
+        self
+    }
+
+    /// Leaves `payload` in the mailbox of turtle `to`.
+    ///
+    /// The message is delivered when the command runs, so it tells the
+    /// receiver how far the sender got. Mailboxes are read with
+    /// `TurtleApp::read_messages()` or `TurtleCommandSender::read_messages()`
+    /// on another thread.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// # #[macroquad::main("Message Example")]
+    /// # async fn main() {
+    /// let mut app = TurtleApp::new();
+    /// let mut plan = create_turtle_plan();
+    /// plan.forward(100.0).send_message(1, "done");
+    /// app.append_to_queue(0, plan);
+    /// // Later, e.g. in a frame hook
+    /// for message in app.read_messages(1) {
+    ///     println!("turtle {} says {}", message.from, message.payload);
+    /// }
+    /// # }
+    /// ```
+    pub fn send_message(&mut self, to: usize, payload: impl Into<String>) -> &mut Self {
+        self.queue.push(TurtleCommand::SendMessage {
+            to,
+            payload: payload.into(),
+        });
+        self
+    }
+
+    /// Starts a new turtle that runs the plan made by `build` on its own,
+    /// in parallel to this one.
+    ///
+    /// The new turtle starts with this turtle's pose, pen and speed, moved
+    /// `offset.x` pixels forward and `offset.y` pixels to the left. Spawned
+    /// turtles can spawn again, so the branches of a recursive drawing grow
+    /// at the same time. They get the next free IDs and start with the next
+    /// frame of `TurtleApp::update()`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// fn tree(turtle: &mut TurtlePlan, length: f32, depth: u32) {
+    ///     turtle.forward(length);
+    ///     if depth == 0 {
+    ///         return;
+    ///     }
+    ///     for angle in [-25.0_f32, 25.0] {
+    ///         turtle.spawn(vec2(0.0, 0.0), |branch| {
+    ///             branch.right(angle);
+    ///             tree(branch, length * 0.7, depth - 1);
+    ///         });
+    ///     }
+    /// }
+    ///
+    /// #[turtle_main("Spawn Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.set_heading(90.0).set_speed(100.0);
+    ///     tree(turtle, 80.0, 6);
+    /// }
+    /// ```
+    pub fn spawn(
+        &mut self,
+        offset: impl Into<Coordinate>,
+        build: impl FnOnce(&mut TurtlePlan),
+    ) -> &mut Self {
+        let mut child = TurtlePlan::new();
+        build(&mut child);
+        self.queue.push(TurtleCommand::Spawn {
+            plan: child.build().commands().to_vec(),
+            offset: offset.into(),
+        });
+        self
+    }
+
+    /// Removes the turtle once it gets here, commands after it never run.
+    ///
+    /// With `keep_drawing` its drawings stay on the canvas, otherwise they
+    /// vanish with it. The IDs of other turtles don't change. Meant for
+    /// helper turtles started with `spawn()` that clean up after themselves.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Die Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     for angle in [0.0_f32, 120.0, 240.0] {
+    ///         turtle.spawn(vec2(0.0, 0.0), |helper| {
+    ///             // Draws a ray and disappears, the ray stays
+    ///             helper.right(angle).forward(100.0).die(true);
+    ///         });
+    ///     }
+    /// }
+    /// ```
+    pub fn die(&mut self, keep_drawing: bool) -> &mut Self {
+        self.queue.push(TurtleCommand::Die { keep_drawing });
+        self
+    }
+
+    /// Draws a parallel outline of the path drawn last, `distance` pixels
+    /// beside it.
+    ///
+    /// The path is everything drawn in one connected stroke up to now, lines
+    /// and arcs alike. Open paths are offset to the left for positive
+    /// distances, closed paths grow. The outline uses the current pen color
+    /// and width, and the turtle doesn't move. See `path_offset` for details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Offset Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     // A square with a thin border around it
+    ///     for _ in 0..4 {
+    ///         turtle.forward(100.0).left(90.0);
+    ///     }
+    ///     turtle.set_pen_width(1.0).offset_last_path(8.0);
+    /// }
+    /// ```
+    pub fn offset_last_path(&mut self, distance: Precision) -> &mut Self {
+        self.queue.push(TurtleCommand::OffsetLastPath(distance));
+        self
+    }
+
+    /// Starts recording a stencil.
+    ///
+    /// Everything the turtle does until the matching `end_stencil()` is
+    /// recorded while it runs, relative to the turtle's position and heading
+    /// at this point. Stencils can be nested.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Stencil Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.begin_stencil();
+    ///     turtle.forward(60.0).circle_left(20.0, 180.0, 12).forward(60.0);
+    ///     let hairpin = turtle.end_stencil();
+    ///
+    ///     turtle.right(90.0).forward(40.0).right(90.0);
+    ///     turtle.stamp_stencil(&hairpin, StencilTransform::new().mirrored());
+    /// }
+    /// ```
+    pub fn begin_stencil(&mut self) -> &mut Self {
+        let stencil = Stencil::next();
+        self.open_stencils.push(stencil);
+        self.queue.push(TurtleCommand::BeginStencil(stencil));
+        self
+    }
+
+    /// Stops recording the stencil begun last, see `begin_stencil()`.
+    ///
+    /// Returns the stencil to replay with `stamp_stencil()`. Unlike most plan
+    /// methods this does not chain. Without a `begin_stencil()` the returned
+    /// stencil stays empty and stamping it reports an error.
+    pub fn end_stencil(&mut self) -> Stencil {
+        let Some(stencil) = self.open_stencils.pop() else {
+            tracing::warn!("end_stencil() without begin_stencil()");
+            return Stencil::next();
+        };
+        self.queue.push(TurtleCommand::EndStencil(stencil));
+        stencil
+    }
+
+    /// Replays a recorded stencil from the turtle's current position and
+    /// heading.
+    ///
+    /// `transform` scales the stencil and can mirror it along the heading.
+    /// Any turtle can stamp a stencil once it has been recorded.
+    pub fn stamp_stencil(&mut self, stencil: &Stencil, transform: StencilTransform) -> &mut Self {
+        self.queue.push(TurtleCommand::StampStencil {
+            stencil: *stencil,
+            transform,
+        });
+        self
+    }
+
+    /// Removes this turtle's drawings, like Python turtle's `clear`.
+    ///
+    /// The turtle keeps its position, heading and pen settings. Other turtles
+    /// are not affected, see `clear_screen()` for that.
+    pub fn clear(&mut self) -> &mut Self {
+        self.queue.push(TurtleCommand::Clear);
+        self
+    }
+
+    /// Repeats the whole plan forever.
+    ///
+    /// After the last command the turtle starts over with the first one,
+    /// continuing from wherever it ended up. With `clear_drawings` set, the
+    /// turtle's drawings are removed before every repetition, which is handy
+    /// for screensaver-style animations.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// #
+    /// #[turtle_main("Loop Example")]
+    /// fn draw(turtle: &mut TurtlePlan) {
+    ///     turtle.set_speed(200.0);
+    ///     for _ in 0..5 {
+    ///         turtle.forward(120.0).right(144.0);
+    ///     }
+    ///     turtle.loop_forever(true);
+    /// }
+    /// ```
+    pub fn loop_forever(&mut self, clear_drawings: bool) -> &mut Self {
+        self.queue.set_looping(true);
+        self.queue.set_clear_between_loops(clear_drawings);
+        self
+    }
+
     /// Consumes the `TurtlePlan` and returns the command queue.
     ///
     /// Use this to finalize the turtle commands and pass them to `TurtleApp`.
@@ -733,6 +1513,83 @@ impl TurtlePlan {
     pub fn build(self) -> CommandQueue {
         self.queue
     }
+
+    /// Like `build()`, but checks the parameters of every command first
+    ///
+    /// `TurtleApp` drops invalid commands when they are queued; use this to
+    /// find mistakes like `forward(f32::NAN)` or `circle_left(10.0, 90.0, 0)`
+    /// early.
+    ///
+    /// ```
+    /// # use turtle_lib::*;
+    /// let mut turtle = TurtlePlan::new();
+    /// turtle.forward(100.0).set_pen_width(-2.0);
+    /// let error = turtle.try_build().unwrap_err();
+    /// assert!(matches!(error, TurtleError::InvalidCommand { index: 1, .. }));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the first invalid command, see `TurtleCommand::validate()`
+    pub fn try_build(self) -> Result<CommandQueue, TurtleError> {
+        self.queue.validate()?;
+        Ok(self.queue)
+    }
+
+    /// Predicts the final position and heading, the bounding box and the
+    /// animation time of the plan without drawing anything.
+    ///
+    /// The turtle starts at the origin with `speed`; `set_speed()` commands
+    /// in the plan are taken into account. Useful to size the window or
+    /// center the camera before the drawing starts. Results use the default
+    /// `CoordinateMode::MathYUp`.
+    ///
+    /// ```
+    /// # use turtle_lib::*;
+    /// let mut turtle = TurtlePlan::new();
+    /// for _ in 0..4 {
+    ///     turtle.forward(100.0).right(90.0);
+    /// }
+    /// let analysis = turtle.analyze(100.0);
+    /// assert!(analysis.position.length() < 0.01);
+    /// assert!((analysis.bounds.min.y + 100.0).abs() < 0.01);
+    /// // 1 s per side and 0.5 s per turn
+    /// assert!((analysis.duration - 6.0).abs() < 0.01);
+    /// ```
+    #[must_use]
+    pub fn analyze(&self, speed: impl Into<AnimationSpeed>) -> crate::headless::PlanAnalysis {
+        crate::headless::analyze(&self.queue, speed.into())
+    }
+}
+
+/// Add the commands of a `together()` group, merging nested groups
+fn collect_group_members(
+    members: &mut Vec<TurtleCommand>,
+    commands: impl IntoIterator<Item = TurtleCommand>,
+) {
+    for command in commands {
+        match command {
+            TurtleCommand::Together(inner) => collect_group_members(members, inner),
+            TurtleCommand::Move(_)
+            | TurtleCommand::Turn(_)
+            | TurtleCommand::Circle { .. }
+            | TurtleCommand::Goto(_)
+            | TurtleCommand::GoToMark(_)
+            | TurtleCommand::SetShapeNamed(_)
+            | TurtleCommand::SetHeading(_)
+            | TurtleCommand::SetPenWidth(_)
+            | TurtleCommand::SetColor(_)
+            | TurtleCommand::SetFillColor(_)
+            | TurtleCommand::SetShape(_)
+            | TurtleCommand::PenUp
+            | TurtleCommand::PenDown
+            | TurtleCommand::ShowTurtle
+            | TurtleCommand::HideTurtle => members.push(command),
+            other => {
+                tracing::warn!(command = ?other, "Command not supported in together(), ignoring");
+            }
+        }
+    }
 }
 
 impl WithCommands for TurtlePlan {
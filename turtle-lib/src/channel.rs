@@ -0,0 +1,193 @@
+//! Channels between the render loop and game logic
+//!
+//! Native targets use crossbeam. wasm32 has no threads to block on, so the
+//! channels there are plain shared queues with the same non-blocking API: a
+//! `send()` into a full channel fails instead of waiting.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use crossbeam::channel::{bounded, unbounded, Receiver, Sender};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use local::{bounded, unbounded, Receiver, Sender};
+
+#[cfg(any(target_arch = "wasm32", test))]
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+mod local {
+    use std::collections::VecDeque;
+    use std::fmt;
+    use std::sync::{Arc, Mutex, PoisonError};
+
+    #[derive(Debug)]
+    struct Shared<T> {
+        queue: VecDeque<T>,
+        capacity: usize,
+        senders: usize,
+        receivers: usize,
+    }
+
+    type SharedQueue<T> = Arc<Mutex<Shared<T>>>;
+
+    fn lock<T>(shared: &SharedQueue<T>) -> std::sync::MutexGuard<'_, Shared<T>> {
+        shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Why a value couldn't be sent, the value is dropped
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub(crate) enum TrySendError {
+        Full,
+        Disconnected,
+    }
+
+    impl fmt::Display for TrySendError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Full => write!(f, "sending on a full channel"),
+                Self::Disconnected => write!(f, "sending on a disconnected channel"),
+            }
+        }
+    }
+
+    /// Why nothing was received
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub(crate) enum TryRecvError {
+        Empty,
+        Disconnected,
+    }
+
+    impl TryRecvError {
+        pub(crate) fn is_disconnected(self) -> bool {
+            self == Self::Disconnected
+        }
+    }
+
+    #[derive(Debug)]
+    pub(crate) struct Sender<T> {
+        shared: SharedQueue<T>,
+    }
+
+    #[derive(Debug)]
+    pub(crate) struct Receiver<T> {
+        shared: SharedQueue<T>,
+    }
+
+    pub(crate) fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+        let shared = Arc::new(Mutex::new(Shared {
+            queue: VecDeque::new(),
+            capacity,
+            senders: 1,
+            receivers: 1,
+        }));
+        (
+            Sender {
+                shared: Arc::clone(&shared),
+            },
+            Receiver { shared },
+        )
+    }
+
+    pub(crate) fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
+        bounded(usize::MAX)
+    }
+
+    impl<T> Sender<T> {
+        /// Same as `try_send()`, waiting is impossible without threads
+        pub(crate) fn send(&self, value: T) -> Result<(), TrySendError> {
+            self.try_send(value)
+        }
+
+        pub(crate) fn try_send(&self, value: T) -> Result<(), TrySendError> {
+            let mut shared = lock(&self.shared);
+            if shared.receivers == 0 {
+                return Err(TrySendError::Disconnected);
+            }
+            if shared.queue.len() >= shared.capacity {
+                return Err(TrySendError::Full);
+            }
+            shared.queue.push_back(value);
+            Ok(())
+        }
+    }
+
+    impl<T> Receiver<T> {
+        pub(crate) fn try_recv(&self) -> Result<T, TryRecvError> {
+            let mut shared = lock(&self.shared);
+            match shared.queue.pop_front() {
+                Some(value) => Ok(value),
+                None if shared.senders == 0 => Err(TryRecvError::Disconnected),
+                None => Err(TryRecvError::Empty),
+            }
+        }
+
+        pub(crate) fn try_iter(&self) -> impl Iterator<Item = T> + '_ {
+            std::iter::from_fn(|| self.try_recv().ok())
+        }
+
+        pub(crate) fn len(&self) -> usize {
+            lock(&self.shared).queue.len()
+        }
+
+        pub(crate) fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+    }
+
+    impl<T> Clone for Sender<T> {
+        fn clone(&self) -> Self {
+            lock(&self.shared).senders += 1;
+            Self {
+                shared: Arc::clone(&self.shared),
+            }
+        }
+    }
+
+    impl<T> Clone for Receiver<T> {
+        fn clone(&self) -> Self {
+            lock(&self.shared).receivers += 1;
+            Self {
+                shared: Arc::clone(&self.shared),
+            }
+        }
+    }
+
+    impl<T> Drop for Sender<T> {
+        fn drop(&mut self) {
+            lock(&self.shared).senders -= 1;
+        }
+    }
+
+    impl<T> Drop for Receiver<T> {
+        fn drop(&mut self) {
+            let mut shared = lock(&self.shared);
+            shared.receivers -= 1;
+            // Nobody can read them anymore
+            if shared.receivers == 0 {
+                shared.queue.clear();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::local::{bounded, TryRecvError, TrySendError};
+
+    #[test]
+    fn local_channels_behave_like_crossbeam_without_blocking() {
+        let (tx, rx) = bounded(2);
+        let second = tx.clone();
+        tx.send(1).unwrap();
+        second.try_send(2).unwrap();
+        assert_eq!(tx.send(3), Err(TrySendError::Full));
+        assert_eq!(rx.len(), 2);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![1, 2]);
+
+        // Disconnected once every sender is gone and the queue is drained
+        second.send(4).unwrap();
+        drop((tx, second));
+        assert_eq!(rx.try_recv(), Ok(4));
+        assert!(rx.try_recv().is_err_and(TryRecvError::is_disconnected));
+
+        let (tx, rx) = bounded::<u8>(1);
+        drop(rx);
+        assert_eq!(tx.try_send(1), Err(TrySendError::Disconnected));
+    }
+}
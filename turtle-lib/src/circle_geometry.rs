@@ -4,6 +4,7 @@ use macroquad::prelude::*;
 
 /// Direction of circular motion (in screen coordinates with Y-down)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CircleDirection {
     Left,  // Counter-clockwise visually, heading decreases
     Right, // Clockwise visually, heading increases
@@ -0,0 +1,84 @@
+//! Copy drawings to the system clipboard (desktop only)
+//!
+//! `TurtleApp::copy_to_clipboard()` places the current drawing on the
+//! clipboard, ready to be pasted into documents or image editors without
+//! exporting a file first:
+//!
+//! ```no_run
+//! use turtle_lib::clipboard::Format;
+//! use turtle_lib::*;
+//!
+//! #[macroquad::main("Clipboard")]
+//! async fn main() {
+//!     let mut plan = create_turtle_plan();
+//!     plan.circle_left(80.0, 360.0, 36);
+//!     let mut app = TurtleApp::new().with_commands(plan.build());
+//!     app.finish_all();
+//!     app.copy_to_clipboard(Format::Png).expect("clipboard unavailable");
+//! }
+//! ```
+//!
+//! Images are rasterized like `headless::render_image()`, so cursors and
+//! text are left out. SVG is copied as text and needs the `svg` feature.
+
+use crate::export::ExportError;
+use crate::headless;
+use crate::state::TurtleWorld;
+use std::borrow::Cow;
+use std::cell::RefCell;
+
+thread_local! {
+    // On X11 and Wayland the copied data is served by whoever owns the
+    // clipboard, so it has to stay alive after copying
+    static CLIPBOARD: RefCell<Option<arboard::Clipboard>> = const { RefCell::new(None) };
+}
+
+/// What `TurtleApp::copy_to_clipboard()` places on the clipboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The SVG document as text
+    #[cfg(feature = "svg")]
+    Svg,
+    /// An image, pasted as PNG by most applications
+    Png,
+}
+
+/// Copy the drawings of `world` to the clipboard, images are `width` x
+/// `height` pixels
+///
+/// # Errors
+///
+/// Returns `ExportError::Clipboard` if there is no clipboard or it refused
+/// the data
+pub fn copy(
+    world: &TurtleWorld,
+    format: Format,
+    width: u16,
+    height: u16,
+) -> Result<(), ExportError> {
+    CLIPBOARD.with_borrow_mut(|kept| {
+        let clipboard = match kept.take() {
+            Some(clipboard) => clipboard,
+            None => arboard::Clipboard::new().map_err(clipboard_error)?,
+        };
+        let clipboard = kept.insert(clipboard);
+        match format {
+            #[cfg(feature = "svg")]
+            Format::Svg => clipboard
+                .set_text(crate::export_svg::svg_export::SvgExporter::document(world).to_string()),
+            Format::Png => {
+                let image = headless::render_image(world, width, height);
+                clipboard.set_image(arboard::ImageData {
+                    width: usize::from(image.width),
+                    height: usize::from(image.height),
+                    bytes: Cow::Owned(image.bytes),
+                })
+            }
+        }
+        .map_err(clipboard_error)
+    })
+}
+
+fn clipboard_error(error: arboard::Error) -> ExportError {
+    ExportError::Clipboard(error.to_string())
+}
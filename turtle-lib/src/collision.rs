@@ -0,0 +1,395 @@
+//! Turtle-to-turtle collision detection
+//!
+//! Two turtles collide when their cursor shapes overlap or when their positions
+//! come within a configurable distance. Optionally a turtle also collides with
+//! the lines drawn by other turtles. `TurtleApp` runs a `CollisionDetector`
+//! every `update()` once collisions are enabled.
+//!
+//! Game logic on other threads subscribes to the events instead of polling:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//!
+//! # #[macroquad::main("Tag")]
+//! # async fn main() {
+//! let mut app = TurtleApp::new();
+//! app.enable_collisions(5.0);
+//! let events = app.subscribe_collisions();
+//! std::thread::spawn(move || {
+//!     // Blocks until the next contact, ends when the app is dropped
+//!     while let Some(event) = events.recv() {
+//!         println!("turtle {} tagged turtle {}", event.a, event.b);
+//!     }
+//! });
+//! app.run(RunConfig::new()).await;
+//! # }
+//! ```
+
+use crate::channel::{unbounded, Receiver, Sender};
+use crate::general::Coordinate;
+use crate::state::{DrawCommand, TurtleParams, TurtleWorld};
+use std::collections::HashSet;
+
+/// Events kept for `CollisionDetector::take_events()`, older ones are dropped
+pub const MAX_PENDING_EVENTS: usize = 1024;
+
+/// What a turtle ran into
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CollisionKind {
+    /// Another turtle's cursor
+    Turtles,
+    /// A line drawn by another turtle
+    Drawing,
+}
+
+/// Two turtles started touching, or a turtle touched another one's drawing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollisionEvent {
+    /// The turtle with the lower ID, for `CollisionKind::Drawing` the turtle
+    /// that ran into the line
+    pub a: usize,
+    /// The turtle with the higher ID, for `CollisionKind::Drawing` the turtle
+    /// that drew the line
+    pub b: usize,
+    /// Point where the contact happened, in the world's `CoordinateMode`:
+    /// between both turtles, or the position of the turtle `a` for drawings
+    pub point: Coordinate,
+    pub kind: CollisionKind,
+}
+
+/// Receives events from another thread, created by
+/// `TurtleApp::subscribe_collisions()`
+///
+/// Every receiver gets every event. A dropped receiver is unsubscribed.
+#[derive(Clone, Debug)]
+pub struct TurtleEventReceiver {
+    rx: Receiver<CollisionEvent>,
+}
+
+impl TurtleEventReceiver {
+    /// Wait for the next event, `None` once the app is gone
+    ///
+    /// Not available on wasm32, which has no threads to wait on.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn recv(&self) -> Option<CollisionEvent> {
+        self.rx.recv().ok()
+    }
+
+    /// The next event if there is one (non-blocking)
+    #[must_use]
+    pub fn try_recv(&self) -> Option<CollisionEvent> {
+        self.rx.try_recv().ok()
+    }
+
+    /// All pending events (non-blocking)
+    #[must_use]
+    pub fn recv_all(&self) -> Vec<CollisionEvent> {
+        self.rx.try_iter().collect()
+    }
+}
+
+/// Tracks colliding turtle pairs between frames and emits events for new contacts
+#[derive(Clone, Debug, Default)]
+pub struct CollisionDetector {
+    proximity: f32,
+    drawings: bool,
+    active: HashSet<(usize, usize, CollisionKind)>,
+    events: Vec<CollisionEvent>,
+    subscribers: Vec<Sender<CollisionEvent>>,
+}
+
+impl CollisionDetector {
+    /// Create a detector; turtles closer than `proximity` count as colliding
+    /// even if their shapes don't overlap (use `0.0` for shape overlap only)
+    #[must_use]
+    pub fn new(proximity: f32) -> Self {
+        Self {
+            proximity,
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn proximity(&self) -> f32 {
+        self.proximity
+    }
+
+    pub fn set_proximity(&mut self, proximity: f32) {
+        self.proximity = proximity;
+    }
+
+    /// Also let turtles collide with the lines drawn by other turtles
+    pub fn set_drawings(&mut self, enabled: bool) {
+        self.drawings = enabled;
+    }
+
+    /// A receiver that gets every event from now on
+    pub fn subscribe(&mut self) -> TurtleEventReceiver {
+        let (tx, rx) = unbounded();
+        self.subscribers.push(tx);
+        TurtleEventReceiver { rx }
+    }
+
+    /// Check all turtle pairs and queue events for pairs that started colliding
+    pub fn update(&mut self, world: &TurtleWorld) {
+        let mut collisions = find_collisions(world, self.proximity);
+        if self.drawings {
+            collisions.extend(find_drawing_collisions(world));
+        }
+        let mut active = HashSet::with_capacity(collisions.len());
+        for event in collisions {
+            let key = (event.a, event.b, event.kind);
+            if !self.active.contains(&key) {
+                self.subscribers
+                    .retain(|subscriber| subscriber.send(event).is_ok());
+                self.events.push(event);
+            }
+            active.insert(key);
+        }
+        self.active = active;
+        // Programs that only subscribe never take the events
+        let excess = self.events.len().saturating_sub(MAX_PENDING_EVENTS);
+        self.events.drain(..excess);
+    }
+
+    /// Pairs of turtle IDs colliding as of the last update
+    #[must_use]
+    pub fn colliding_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs: Vec<(usize, usize)> = self
+            .active
+            .iter()
+            .filter(|(_, _, kind)| *kind == CollisionKind::Turtles)
+            .map(|&(a, b, _)| (a, b))
+            .collect();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// Remove and return all events collected since the last call
+    ///
+    /// Only the latest `MAX_PENDING_EVENTS` are kept, subscribers get every
+    /// event.
+    pub fn take_events(&mut self) -> Vec<CollisionEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+/// Find all pairs of visible turtles that currently collide
+#[must_use]
+pub fn find_collisions(world: &TurtleWorld, proximity: f32) -> Vec<CollisionEvent> {
+    let visible: Vec<_> = world
+        .turtles
+        .iter()
+        .filter(|turtle| turtle.params.visible)
+        .collect();
+
+    let mode = world.coordinate_mode();
+    let mut events = Vec::new();
+    for (i, first) in visible.iter().enumerate() {
+        for second in &visible[i + 1..] {
+            if turtles_collide(&first.params, &second.params, proximity) {
+                let (a, b) = if first.turtle_id < second.turtle_id {
+                    (first.turtle_id, second.turtle_id)
+                } else {
+                    (second.turtle_id, first.turtle_id)
+                };
+                events.push(CollisionEvent {
+                    a,
+                    b,
+                    point: mode.from_screen((first.params.position + second.params.position) * 0.5),
+                    kind: CollisionKind::Turtles,
+                });
+            }
+        }
+    }
+    events
+}
+
+/// Find visible turtles whose cursor touches a line drawn by another turtle
+#[must_use]
+pub fn find_drawing_collisions(world: &TurtleWorld) -> Vec<CollisionEvent> {
+    let mode = world.coordinate_mode();
+    let mut events = Vec::new();
+    for turtle in world.turtles.iter().filter(|turtle| turtle.params.visible) {
+        let cursor = turtle.params.cursor_vertices();
+        for other in &world.turtles {
+            if other.turtle_id == turtle.turtle_id {
+                continue;
+            }
+            let hit = other.commands.iter().any(|command| {
+                let DrawCommand::Mesh { source, .. } = command else {
+                    return false;
+                };
+                crate::path_offset::stroke_points(source).is_some_and(|points| {
+                    points
+                        .windows(2)
+                        .any(|line| segment_touches_polygon(line[0], line[1], &cursor))
+                })
+            });
+            if hit {
+                events.push(CollisionEvent {
+                    a: turtle.turtle_id,
+                    b: other.turtle_id,
+                    point: mode.from_screen(turtle.params.position),
+                    kind: CollisionKind::Drawing,
+                });
+            }
+        }
+    }
+    events
+}
+
+/// Check whether the segment `from-to` crosses or lies inside a closed polygon
+fn segment_touches_polygon(from: Coordinate, to: Coordinate, polygon: &[Coordinate]) -> bool {
+    point_in_polygon(from, polygon)
+        || point_in_polygon(to, polygon)
+        || polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .any(|(&p1, &p2)| segments_intersect(from, to, p1, p2))
+}
+
+/// Check whether two turtles are within `proximity` or their cursor shapes overlap
+#[must_use]
+pub fn turtles_collide(a: &TurtleParams, b: &TurtleParams, proximity: f32) -> bool {
+    a.position.distance(b.position) <= proximity
+        || polygons_overlap(&a.cursor_vertices(), &b.cursor_vertices())
+}
+
+/// Check whether two closed polygons intersect or one contains the other
+#[must_use]
+pub fn polygons_overlap(a: &[Coordinate], b: &[Coordinate]) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+
+    for (i, &a1) in a.iter().enumerate() {
+        let a2 = a[(i + 1) % a.len()];
+        for (j, &b1) in b.iter().enumerate() {
+            if segments_intersect(a1, a2, b1, b[(j + 1) % b.len()]) {
+                return true;
+            }
+        }
+    }
+
+    point_in_polygon(a[0], b) || point_in_polygon(b[0], a)
+}
+
+/// Ray casting test whether `point` lies inside a closed polygon
+#[must_use]
+pub fn point_in_polygon(point: Coordinate, polygon: &[Coordinate]) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+
+    let mut inside = false;
+    let mut j = polygon.len() - 1;
+    for (i, &pi) in polygon.iter().enumerate() {
+        let pj = polygon[j];
+        if (pi.y > point.y) != (pj.y > point.y)
+            && point.x < (pj.x - pi.x) * (point.y - pi.y) / (pj.y - pi.y) + pi.x
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Check whether the segments `p1-p2` and `q1-q2` properly cross each other
+fn segments_intersect(p1: Coordinate, p2: Coordinate, q1: Coordinate, q2: Coordinate) -> bool {
+    let d1 = (q2 - q1).perp_dot(p1 - q1);
+    let d2 = (q2 - q1).perp_dot(p2 - q1);
+    let d3 = (p2 - p1).perp_dot(q1 - p1);
+    let d4 = (p2 - p1).perp_dot(q2 - p1);
+    ((d1 > 0.0) != (d2 > 0.0)) && ((d3 > 0.0) != (d4 > 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::prelude::vec2;
+
+    #[test]
+    fn overlapping_and_separate_squares() {
+        let square = |x: f32| {
+            vec![
+                vec2(x, 0.0),
+                vec2(x + 10.0, 0.0),
+                vec2(x + 10.0, 10.0),
+                vec2(x, 10.0),
+            ]
+        };
+        assert!(polygons_overlap(&square(0.0), &square(5.0)));
+        assert!(!polygons_overlap(&square(0.0), &square(20.0)));
+    }
+
+    #[test]
+    fn contained_polygon_overlaps() {
+        let outer = vec![
+            vec2(0.0, 0.0),
+            vec2(100.0, 0.0),
+            vec2(100.0, 100.0),
+            vec2(0.0, 100.0),
+        ];
+        let inner = vec![vec2(40.0, 40.0), vec2(60.0, 40.0), vec2(50.0, 60.0)];
+        assert!(polygons_overlap(&outer, &inner));
+        assert!(point_in_polygon(vec2(50.0, 50.0), &outer));
+        assert!(!point_in_polygon(vec2(150.0, 50.0), &outer));
+    }
+
+    #[test]
+    fn subscribers_get_new_drawing_contacts() {
+        use crate::commands::TurtleCommand;
+        use crate::execution::execute_command;
+
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        world.add_turtle();
+        // Turtle 1 draws a wall across the path of turtle 0
+        for command in [
+            TurtleCommand::PenUp,
+            TurtleCommand::Move(50.0),
+            TurtleCommand::Turn(90.0),
+            TurtleCommand::Move(-50.0),
+            TurtleCommand::PenDown,
+            TurtleCommand::Move(100.0),
+        ] {
+            execute_command(&command, &mut world.turtles[1]);
+        }
+        world.turtles[0].params.pen_down = false;
+
+        let mut detector = CollisionDetector::new(0.0);
+        detector.set_drawings(true);
+        let events = detector.subscribe();
+        for _ in 0..10 {
+            execute_command(&TurtleCommand::Move(10.0), &mut world.turtles[0]);
+            detector.update(&world);
+        }
+
+        let received = events.recv_all();
+        assert_eq!(received.len(), 1);
+        assert_eq!((received[0].a, received[0].b), (0, 1));
+        assert_eq!(received[0].kind, CollisionKind::Drawing);
+        assert_eq!(detector.take_events(), received);
+        assert!(detector.colliding_pairs().is_empty());
+    }
+
+    #[test]
+    fn pending_events_are_bounded() {
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        world.add_turtle();
+        let mut detector = CollisionDetector::new(5.0);
+        let events = detector.subscribe();
+        for _ in 0..MAX_PENDING_EVENTS + 10 {
+            world.turtles[1].params.position = vec2(0.0, 0.0);
+            detector.update(&world);
+            world.turtles[1].params.position = vec2(500.0, 0.0);
+            detector.update(&world);
+        }
+        assert_eq!(events.recv_all().len(), MAX_PENDING_EVENTS + 10);
+        assert_eq!(detector.take_events().len(), MAX_PENDING_EVENTS);
+    }
+}
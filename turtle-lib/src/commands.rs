@@ -1,10 +1,18 @@
 //! Turtle commands and command queue
 
+use crate::error::{CommandProblem, TurtleError};
 use crate::general::{AnimationSpeed, Color, Coordinate, FontSize, Precision};
-use crate::shapes::TurtleShape;
+use crate::palettes::Palette;
+use crate::shapes::{ShapeSize, TurtleShape};
+use crate::state::BlendMode;
+use crate::stencil::{Stencil, StencilTransform};
+use crate::tweening::Easing;
+use std::ops::{Bound, RangeBounds};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Individual turtle commands
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TurtleCommand {
     // Movement (positive = forward, negative = backward)
     Move(Precision),
@@ -25,16 +33,42 @@ pub enum TurtleCommand {
     PenDown,
 
     // Appearance
-    SetColor(Color),
-    SetFillColor(Option<Color>),
+    SetColor(#[cfg_attr(feature = "serde", serde(with = "crate::wire::color"))] Color),
+    SetFillColor(
+        #[cfg_attr(feature = "serde", serde(with = "crate::wire::option_color"))] Option<Color>,
+    ),
     SetPenWidth(Precision),
     SetSpeed(AnimationSpeed),
     SetShape(TurtleShape),
+    SetEasing(Easing),
+    SetTurnSpeed(Option<Precision>), // degrees per second, `None` follows the speed
+    SetStyleTransition(Precision),   // seconds for animated color/pen width changes
+    SetShapeSize(ShapeSize),
+    Tilt(Precision),         // degrees, relative to the current tilt
+    SetTiltAngle(Precision), // radians, relative to the heading
+    SetTurtleColor {
+        #[cfg_attr(feature = "serde", serde(with = "crate::wire::color"))]
+        fill: Color,
+        #[cfg_attr(feature = "serde", serde(with = "crate::wire::color"))]
+        outline: Color,
+    },
+    SetPenPalette(Option<Palette>), // pen color cycles through it per stroke
+    SetBlendMode(BlendMode),
 
     // Position
-    Goto(Coordinate),
+    // Absolute values are in the world's `CoordinateMode` until
+    // `Turtle::resolve_command()` converts them to screen space
+    Goto(#[cfg_attr(feature = "serde", serde(with = "crate::wire::vec2"))] Coordinate),
     SetHeading(Precision), // radians
 
+    // Members start from the same state and animate simultaneously
+    Together(Vec<TurtleCommand>),
+
+    // Named markers (stored in the world, resolved at execution time)
+    Mark(String),
+    GoToMark(String),
+    SetShapeNamed(String), // shape from the world's `ShapeRegistry`
+
     // Visibility
     ShowTurtle,
     HideTurtle,
@@ -48,16 +82,242 @@ pub enum TurtleCommand {
         text: String,
         font_size: FontSize,
     },
+    Annotate(String), // label for the next stroke, see `TurtlePlan::annotate()`
+
+    // Stamps: copies of the cursor left on the canvas, see `TurtlePlan::stamp()`
+    Stamp(StampId),
+    ClearStamp(StampId),
+    ClearStamps(Option<i32>), // first n (positive), last n (negative) or all
+
+    // Stencils: recorded commands replayed elsewhere, see `TurtlePlan::begin_stencil()`
+    BeginStencil(Stencil),
+    EndStencil(Stencil),
+    StampStencil {
+        stencil: Stencil,
+        transform: StencilTransform,
+    },
+
+    // Save and restore pose, pen width and color, see `TurtlePlan::push_state()`
+    PushState,
+    PopState,
+
+    // Parallel outline of the last drawn path, see `TurtlePlan::offset_last_path()`
+    OffsetLastPath(Precision),
+
+    // Wait until all turtles with this barrier ahead reached it
+    Barrier(String),
+
+    // Leave a message in another turtle's mailbox, see `TurtlePlan::send_message()`
+    SendMessage {
+        to: usize,
+        payload: String,
+    },
+
+    // Remove the turtle, its drawings move to `TurtleWorld::layer` or vanish
+    Die {
+        keep_drawing: bool,
+    },
+
+    // Start a new turtle with its own queue, `offset` pixels forward and to
+    // the left, see `TurtlePlan::spawn()`
+    Spawn {
+        plan: Vec<TurtleCommand>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::wire::vec2"))]
+        offset: Coordinate,
+    },
 
     // Reset
     Reset,
+
+    // Remove this turtle's drawings, keeping its position and settings
+    Clear,
+
+    // Clear the drawings of all turtles (optionally moving them back home)
+    ClearScreen {
+        rehome: bool,
+    },
+}
+
+/// Widest pen accepted by `TurtleCommand::validate()`, in pixels
+pub const MAX_PEN_WIDTH: Precision = 1000.0;
+
+impl TurtleCommand {
+    /// Name of the variant, e.g. `"Move"` for `TurtleCommand::Move(10.0)`
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            TurtleCommand::Move(_) => "Move",
+            TurtleCommand::Turn(_) => "Turn",
+            TurtleCommand::Circle { .. } => "Circle",
+            TurtleCommand::PenUp => "PenUp",
+            TurtleCommand::PenDown => "PenDown",
+            TurtleCommand::SetColor(_) => "SetColor",
+            TurtleCommand::SetFillColor(_) => "SetFillColor",
+            TurtleCommand::SetPenWidth(_) => "SetPenWidth",
+            TurtleCommand::SetSpeed(_) => "SetSpeed",
+            TurtleCommand::SetShape(_) => "SetShape",
+            TurtleCommand::SetEasing(_) => "SetEasing",
+            TurtleCommand::SetTurnSpeed(_) => "SetTurnSpeed",
+            TurtleCommand::SetStyleTransition(_) => "SetStyleTransition",
+            TurtleCommand::SetShapeSize(_) => "SetShapeSize",
+            TurtleCommand::Tilt(_) => "Tilt",
+            TurtleCommand::SetTiltAngle(_) => "SetTiltAngle",
+            TurtleCommand::SetTurtleColor { .. } => "SetTurtleColor",
+            TurtleCommand::SetPenPalette(_) => "SetPenPalette",
+            TurtleCommand::SetBlendMode(_) => "SetBlendMode",
+            TurtleCommand::Goto(_) => "Goto",
+            TurtleCommand::SetHeading(_) => "SetHeading",
+            TurtleCommand::Together(_) => "Together",
+            TurtleCommand::Mark(_) => "Mark",
+            TurtleCommand::GoToMark(_) => "GoToMark",
+            TurtleCommand::SetShapeNamed(_) => "SetShapeNamed",
+            TurtleCommand::ShowTurtle => "ShowTurtle",
+            TurtleCommand::HideTurtle => "HideTurtle",
+            TurtleCommand::BeginFill => "BeginFill",
+            TurtleCommand::EndFill => "EndFill",
+            TurtleCommand::WriteText { .. } => "WriteText",
+            TurtleCommand::Annotate(_) => "Annotate",
+            TurtleCommand::Stamp(_) => "Stamp",
+            TurtleCommand::ClearStamp(_) => "ClearStamp",
+            TurtleCommand::ClearStamps(_) => "ClearStamps",
+            TurtleCommand::BeginStencil(_) => "BeginStencil",
+            TurtleCommand::EndStencil(_) => "EndStencil",
+            TurtleCommand::StampStencil { .. } => "StampStencil",
+            TurtleCommand::PushState => "PushState",
+            TurtleCommand::PopState => "PopState",
+            TurtleCommand::OffsetLastPath(_) => "OffsetLastPath",
+            TurtleCommand::Barrier(_) => "Barrier",
+            TurtleCommand::SendMessage { .. } => "SendMessage",
+            TurtleCommand::Spawn { .. } => "Spawn",
+            TurtleCommand::Die { .. } => "Die",
+            TurtleCommand::Reset => "Reset",
+            TurtleCommand::Clear => "Clear",
+            TurtleCommand::ClearScreen { .. } => "ClearScreen",
+        }
+    }
+
+    /// Check the parameters of the command, including the members of a
+    /// `Together` group
+    ///
+    /// # Errors
+    ///
+    /// Returns the first parameter that can't be drawn: NaN or infinite
+    /// numbers, negative radii or durations, circles without steps and pen
+    /// widths outside of 0 to `MAX_PEN_WIDTH` and negative stencil scales
+    pub fn validate(&self) -> Result<(), CommandProblem> {
+        let finite = |value: Precision, parameter: &'static str| {
+            if value.is_finite() {
+                Ok(())
+            } else {
+                Err(CommandProblem::NotFinite(parameter))
+            }
+        };
+        match self {
+            TurtleCommand::Move(distance) => finite(*distance, "distance"),
+            TurtleCommand::Turn(angle) | TurtleCommand::Tilt(angle) => finite(*angle, "angle"),
+            TurtleCommand::SetHeading(angle) | TurtleCommand::SetTiltAngle(angle) => {
+                finite(*angle, "angle")
+            }
+            TurtleCommand::Circle {
+                radius,
+                angle,
+                steps,
+                ..
+            } => {
+                finite(*radius, "radius")?;
+                finite(*angle, "angle")?;
+                if *radius < 0.0 {
+                    Err(CommandProblem::NegativeRadius(*radius))
+                } else if *steps == 0 {
+                    Err(CommandProblem::ZeroSteps)
+                } else {
+                    Ok(())
+                }
+            }
+            TurtleCommand::SetPenWidth(width) => {
+                if (0.0..=MAX_PEN_WIDTH).contains(width) {
+                    Ok(())
+                } else {
+                    Err(CommandProblem::PenWidth(*width))
+                }
+            }
+            TurtleCommand::Goto(position) => {
+                finite(position.x, "x")?;
+                finite(position.y, "y")
+            }
+            TurtleCommand::SetTurnSpeed(Some(speed)) => {
+                finite(*speed, "turn speed")?;
+                if *speed < 0.0 {
+                    Err(CommandProblem::Negative("turn speed"))
+                } else {
+                    Ok(())
+                }
+            }
+            TurtleCommand::SetStyleTransition(seconds) => {
+                finite(*seconds, "transition")?;
+                if *seconds < 0.0 {
+                    Err(CommandProblem::Negative("transition"))
+                } else {
+                    Ok(())
+                }
+            }
+            TurtleCommand::SetShapeSize(size) => {
+                finite(size.stretch_wid, "stretch_wid")?;
+                finite(size.stretch_len, "stretch_len")?;
+                finite(size.outline, "outline")
+            }
+            TurtleCommand::Together(members) => members.iter().try_for_each(Self::validate),
+            TurtleCommand::StampStencil { transform, .. } => {
+                finite(transform.scale, "scale")?;
+                if transform.scale < 0.0 {
+                    Err(CommandProblem::Negative("scale"))
+                } else {
+                    Ok(())
+                }
+            }
+            TurtleCommand::OffsetLastPath(distance) => finite(*distance, "distance"),
+            TurtleCommand::Spawn { plan, offset } => {
+                finite(offset.x, "x")?;
+                finite(offset.y, "y")?;
+                plan.iter().try_for_each(Self::validate)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Identifies a stamp, returned by `TurtlePlan::stamp()`
+///
+/// IDs are unique within the program. A stamp in a looping queue reuses its
+/// ID on every repetition, so `clear_stamp()` removes all of its copies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StampId(u64);
+
+impl StampId {
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
 }
 
 /// Queue of turtle commands with execution state
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CommandQueue {
     commands: Vec<TurtleCommand>,
     current_index: usize,
+    looping: Option<LoopSettings>,
+}
+
+/// How a looping `CommandQueue` restarts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct LoopSettings {
+    // Index the queue jumps back to after the last command
+    start: usize,
+    // Emit `TurtleCommand::Clear` before every repetition
+    clear_drawings: bool,
 }
 
 impl CommandQueue {
@@ -66,6 +326,7 @@ impl CommandQueue {
         Self {
             commands: Vec::new(),
             current_index: 0,
+            looping: None,
         }
     }
     #[must_use]
@@ -73,6 +334,7 @@ impl CommandQueue {
         Self {
             commands: Vec::with_capacity(capacity),
             current_index: 0,
+            looping: None,
         }
     }
 
@@ -83,9 +345,56 @@ impl CommandQueue {
     pub fn extend(&mut self, commands: impl IntoIterator<Item = TurtleCommand>) {
         self.commands.extend(commands);
     }
+    /// Append the remaining commands of another queue, keeping its loop
+    ///
+    /// If `other` loops, this queue loops over the appended commands from then
+    /// on. Commands appended to a looping queue become part of the loop.
+    pub fn append(&mut self, other: CommandQueue) {
+        let offset = self.commands.len();
+        let skipped = other.current_index.min(other.commands.len());
+        self.commands.extend_from_slice(&other.commands[skipped..]);
+        if let Some(looping) = other.looping {
+            self.looping = Some(LoopSettings {
+                start: offset + looping.start.saturating_sub(skipped),
+                ..looping
+            });
+        }
+    }
+
+    /// All commands of the queue, regardless of the current position
+    #[must_use]
+    pub fn commands(&self) -> &[TurtleCommand] {
+        &self.commands
+    }
+
+    /// Restart from the beginning after the last command, forever
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping.then_some(LoopSettings {
+            start: 0,
+            clear_drawings: false,
+        });
+    }
+
+    /// Remove the turtle's drawings before every repetition of a looping queue
+    pub fn set_clear_between_loops(&mut self, clear: bool) {
+        if let Some(looping) = &mut self.looping {
+            looping.clear_drawings = clear;
+        }
+    }
+
+    #[must_use]
+    pub fn is_looping(&self) -> bool {
+        self.looping.is_some()
+    }
+
+    /// A looping queue with commands is never complete
     #[must_use]
     pub fn is_complete(&self) -> bool {
-        self.current_index >= self.commands.len()
+        let restarts = match self.looping {
+            Some(looping) => looping.start < self.commands.len(),
+            None => false,
+        };
+        self.current_index >= self.commands.len() && !restarts
     }
     pub fn reset(&mut self) {
         self.current_index = 0;
@@ -103,6 +412,106 @@ impl CommandQueue {
     pub fn remaining(&self) -> usize {
         self.commands.len().saturating_sub(self.current_index)
     }
+
+    /// Commands that haven't been started yet, in order
+    ///
+    /// A looping queue continues at its loop start after these.
+    #[must_use]
+    pub fn upcoming(&self) -> &[TurtleCommand] {
+        &self.commands[self.current_index.min(self.commands.len())..]
+    }
+
+    /// Insert the unstarted commands of `other` before the `index`-th
+    /// upcoming command (0 runs them next, `remaining()` or more appends them)
+    ///
+    /// The loop settings of `other` are ignored. Commands inserted into the
+    /// repeated part of a looping queue become part of the loop.
+    pub fn splice_pending(&mut self, index: usize, other: CommandQueue) {
+        let at = self.current_index.min(self.commands.len()) + index.min(self.remaining());
+        let skipped = other.current_index.min(other.commands.len());
+        let inserted = other.commands.len() - skipped;
+        self.commands
+            .splice(at..at, other.commands.into_iter().skip(skipped));
+        if let Some(looping) = &mut self.looping {
+            if at < looping.start {
+                looping.start += inserted;
+            }
+        }
+    }
+
+    /// Remove upcoming commands, returning them in order
+    ///
+    /// `range` counts from the next command (0) like `upcoming()`, and is
+    /// clamped to the commands that are left. Commands already started are
+    /// never removed.
+    pub fn remove_pending(&mut self, range: impl RangeBounds<usize>) -> Vec<TurtleCommand> {
+        let remaining = self.remaining();
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        }
+        .min(remaining);
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => remaining,
+        }
+        .clamp(start, remaining);
+        let offset = self.current_index.min(self.commands.len());
+        let (start, end) = (offset + start, offset + end);
+        if let Some(looping) = &mut self.looping {
+            if looping.start >= end {
+                looping.start -= end - start;
+            } else if looping.start > start {
+                looping.start = start;
+            }
+        }
+        self.commands.drain(start..end).collect()
+    }
+
+    /// Check every command with `TurtleCommand::validate()`
+    ///
+    /// # Errors
+    ///
+    /// Returns the first invalid command with its index
+    pub fn validate(&self) -> Result<(), TurtleError> {
+        self.commands
+            .iter()
+            .enumerate()
+            .try_for_each(|(index, command)| {
+                command
+                    .validate()
+                    .map_err(|problem| TurtleError::InvalidCommand { index, problem })
+            })
+    }
+
+    /// Drop all invalid commands, returning an error for each of them
+    ///
+    /// The remaining commands keep their order, the loop start and current
+    /// position move along with them.
+    pub fn remove_invalid(&mut self) -> Vec<TurtleError> {
+        let (current_index, looping) = (self.current_index, self.looping);
+        let mut errors = Vec::new();
+        let mut kept = Vec::with_capacity(self.commands.len());
+        for (index, command) in std::mem::take(&mut self.commands).into_iter().enumerate() {
+            let Err(problem) = command.validate() else {
+                kept.push(command);
+                continue;
+            };
+            errors.push(TurtleError::InvalidCommand { index, problem });
+            if index < current_index {
+                self.current_index -= 1;
+            }
+            if let (Some(before), Some(after)) = (looping, &mut self.looping) {
+                if index < before.start {
+                    after.start -= 1;
+                }
+            }
+        }
+        self.commands = kept;
+        errors
+    }
 }
 
 impl Default for CommandQueue {
@@ -115,12 +524,107 @@ impl Iterator for CommandQueue {
     type Item = TurtleCommand;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current_index < self.commands.len() {
-            let cmd = self.commands[self.current_index].clone();
-            self.current_index += 1;
-            Some(cmd)
-        } else {
-            None
+        if self.current_index >= self.commands.len() {
+            let looping = self.looping?;
+            if looping.start >= self.commands.len() {
+                return None;
+            }
+            self.current_index = looping.start;
+            if looping.clear_drawings {
+                return Some(TurtleCommand::Clear);
+            }
         }
+
+        let cmd = self.commands[self.current_index].clone();
+        self.current_index += 1;
+        Some(cmd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looping_queue_restarts_with_clear() {
+        let mut queue = CommandQueue::new();
+        queue.push(TurtleCommand::Move(10.0));
+        queue.push(TurtleCommand::Turn(90.0));
+        queue.set_looping(true);
+        queue.set_clear_between_loops(true);
+
+        let commands: Vec<_> = queue.by_ref().take(5).collect();
+        assert!(matches!(commands[0], TurtleCommand::Move(_)));
+        assert!(matches!(commands[1], TurtleCommand::Turn(_)));
+        assert!(matches!(commands[2], TurtleCommand::Clear));
+        assert!(matches!(commands[3], TurtleCommand::Move(_)));
+        assert!(!queue.is_complete());
+    }
+
+    #[test]
+    fn append_keeps_loop_of_appended_queue() {
+        let mut queue = CommandQueue::new();
+        queue.push(TurtleCommand::PenUp);
+
+        let mut looping = CommandQueue::new();
+        looping.push(TurtleCommand::Move(10.0));
+        looping.set_looping(true);
+        queue.append(looping);
+
+        let commands: Vec<_> = queue.take(3).collect();
+        assert!(matches!(commands[0], TurtleCommand::PenUp));
+        assert!(matches!(commands[1], TurtleCommand::Move(_)));
+        assert!(matches!(commands[2], TurtleCommand::Move(_)));
+    }
+
+    #[test]
+    fn remove_invalid_keeps_the_loop() {
+        let mut queue = CommandQueue::new();
+        queue.push(TurtleCommand::Move(f32::NAN));
+        queue.push(TurtleCommand::Together(vec![TurtleCommand::SetPenWidth(
+            -1.0,
+        )]));
+
+        let mut looping = CommandQueue::new();
+        looping.push(TurtleCommand::Move(10.0));
+        looping.set_looping(true);
+        queue.append(looping);
+
+        let errors = queue.remove_invalid();
+        assert_eq!(
+            errors[1],
+            TurtleError::InvalidCommand {
+                index: 1,
+                problem: CommandProblem::PenWidth(-1.0)
+            }
+        );
+        assert!(queue.validate().is_ok());
+        let commands: Vec<_> = queue.take(2).collect();
+        assert!(matches!(commands[0], TurtleCommand::Move(d) if d == 10.0));
+        assert!(matches!(commands[1], TurtleCommand::Move(d) if d == 10.0));
+    }
+
+    #[test]
+    fn pending_commands_can_be_edited() {
+        let mut queue = CommandQueue::new();
+        queue.push(TurtleCommand::PenUp);
+        queue.push(TurtleCommand::Move(10.0));
+        queue.push(TurtleCommand::Turn(90.0));
+        queue.set_looping(true);
+        assert!(matches!(queue.next(), Some(TurtleCommand::PenUp)));
+
+        let mut inserted = CommandQueue::new();
+        inserted.push(TurtleCommand::PenDown);
+        inserted.push(TurtleCommand::Move(5.0));
+        queue.splice_pending(1, inserted);
+        let removed = queue.remove_pending(..1);
+        assert!(matches!(removed[..], [TurtleCommand::Move(d)] if d == 10.0));
+        assert!(queue.remove_pending(10..).is_empty());
+
+        let commands: Vec<_> = queue.take(4).collect();
+        assert!(matches!(commands[0], TurtleCommand::PenDown));
+        assert!(matches!(commands[1], TurtleCommand::Move(d) if d == 5.0));
+        assert!(matches!(commands[2], TurtleCommand::Turn(_)));
+        assert!(matches!(commands[3], TurtleCommand::PenUp));
     }
 }
@@ -37,8 +37,13 @@
 //! # }
 //! ```
 
+use crate::channel::{bounded, unbounded, Receiver, Sender};
 use crate::commands::CommandQueue;
-use crossbeam::channel::{bounded, Receiver, Sender};
+use crate::state::{Message, SharedWorld, TurtleParams};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Latest state of a turtle, written by the receiver and read by senders
+type SharedParams = Arc<Mutex<Option<TurtleParams>>>;
 
 /// Sender for turtle commands from a game logic thread
 ///
@@ -49,6 +54,9 @@ use crossbeam::channel::{bounded, Receiver, Sender};
 /// Can be cloned and shared across threads. Multiple game threads can send
 /// commands to the same turtle safely.
 ///
+/// In the browser (wasm32) the game logic runs on the render thread, e.g. in
+/// a frame hook or an async task, and the sender works without threads.
+///
 /// # Examples
 /// ```no_run
 /// # use turtle_lib::*;
@@ -70,6 +78,9 @@ use crossbeam::channel::{bounded, Receiver, Sender};
 pub struct TurtleCommandSender {
     turtle_id: usize,
     tx: Sender<CommandQueue>,
+    params: SharedParams,
+    // Mailboxes of the world the turtle lives in, set by `TurtleApp`
+    world: Option<SharedWorld>,
 }
 
 /// Receiver for turtle commands on the render thread
@@ -79,6 +90,7 @@ pub struct TurtleCommandSender {
 pub struct TurtleCommandReceiver {
     turtle_id: usize,
     rx: Receiver<CommandQueue>,
+    params: SharedParams,
 }
 
 impl TurtleCommandSender {
@@ -92,7 +104,8 @@ impl TurtleCommandSender {
     ///
     /// Blocks if the channel buffer is full. This is appropriate for game logic
     /// threads where blocking is acceptable. The buffer size is specified when
-    /// creating the channel.
+    /// creating the channel. On wasm32 there are no threads to wait for, a
+    /// full buffer is an error like with `try_send()`.
     ///
     /// # Errors
     /// Returns error if the receiver has been dropped (render thread exited).
@@ -139,6 +152,66 @@ impl TurtleCommandSender {
             .try_send(queue)
             .map_err(|e| format!("Failed to send: {e}"))
     }
+
+    /// The turtle's state as of the last `TurtleApp::process_commands()`,
+    /// `None` before the first call
+    ///
+    /// Use the geometry helpers of `TurtleParams` to steer the turtle from
+    /// the game thread:
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// # let mut app = TurtleApp::new();
+    /// # let tx = app.create_turtle_channel(100);
+    /// let target = vec2(200.0, -100.0);
+    /// if let Some(params) = tx.params() {
+    ///     let mut plan = create_turtle_plan();
+    ///     plan.right(params.angle_to(target))
+    ///         .forward(params.distance_to(target).min(10.0));
+    ///     tx.try_send(plan.build()).ok();
+    /// }
+    /// ```
+    #[must_use]
+    pub fn params(&self) -> Option<TurtleParams> {
+        self.params
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Take the unread messages sent to this turtle, oldest first
+    ///
+    /// Messages arrive while the turtle's world executes `send_message()`
+    /// commands, so game threads can react to each other's turtles. Always
+    /// empty for channels not created by `TurtleApp`.
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// # let mut app = TurtleApp::new();
+    /// # let tx = app.create_turtle_channel(100);
+    /// for message in tx.read_messages() {
+    ///     if message.payload == "your turn" {
+    ///         let mut plan = create_turtle_plan();
+    ///         plan.forward(50.0).send_message(message.from, "your turn");
+    ///         tx.try_send(plan.build()).ok();
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn read_messages(&self) -> Vec<Message> {
+        self.world.as_ref().map_or_else(Vec::new, |world| {
+            world
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .mailboxes
+                .remove(&self.turtle_id)
+                .unwrap_or_default()
+        })
+    }
+
+    pub(crate) fn attach_world(&mut self, world: &SharedWorld) {
+        self.world = Some(Arc::clone(world));
+    }
 }
 
 impl TurtleCommandReceiver {
@@ -170,6 +243,25 @@ impl TurtleCommandReceiver {
         self.rx.try_iter().collect()
     }
 
+    /// Like `recv_all()`, also telling whether all senders have been dropped
+    ///
+    /// A disconnected channel never delivers commands again.
+    #[must_use]
+    pub fn recv_all_checked(&self) -> (Vec<CommandQueue>, bool) {
+        let mut queues = Vec::new();
+        loop {
+            match self.rx.try_recv() {
+                Ok(queue) => queues.push(queue),
+                Err(error) => return (queues, error.is_disconnected()),
+            }
+        }
+    }
+
+    /// Make `params` the state that the senders' `params()` return
+    pub fn publish(&self, params: &TurtleParams) {
+        *self.params.lock().unwrap_or_else(PoisonError::into_inner) = Some(params.clone());
+    }
+
     /// Try to receive one command batch (non-blocking)
     #[must_use]
     pub fn try_recv(&self) -> Option<CommandQueue> {
@@ -217,9 +309,35 @@ pub fn turtle_command_channel(
     buffer_size: usize,
 ) -> (TurtleCommandSender, TurtleCommandReceiver) {
     assert!(buffer_size > 0, "buffer_size must be > 0");
-    let (tx, rx) = bounded(buffer_size);
+    channel_pair(turtle_id, bounded(buffer_size))
+}
+
+/// Create a command channel for a specific turtle that never blocks
+///
+/// For senders on the render thread, where waiting for the receiver to
+/// drain a full buffer would never end.
+pub(crate) fn unbounded_turtle_command_channel(
+    turtle_id: usize,
+) -> (TurtleCommandSender, TurtleCommandReceiver) {
+    channel_pair(turtle_id, unbounded())
+}
+
+fn channel_pair(
+    turtle_id: usize,
+    (tx, rx): (Sender<CommandQueue>, Receiver<CommandQueue>),
+) -> (TurtleCommandSender, TurtleCommandReceiver) {
+    let params = SharedParams::default();
     (
-        TurtleCommandSender { turtle_id, tx },
-        TurtleCommandReceiver { turtle_id, rx },
+        TurtleCommandSender {
+            turtle_id,
+            tx,
+            params: Arc::clone(&params),
+            world: None,
+        },
+        TurtleCommandReceiver {
+            turtle_id,
+            rx,
+            params,
+        },
     )
 }
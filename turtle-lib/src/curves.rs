@@ -0,0 +1,244 @@
+//! Spirographs, roses and other closed parametric curves
+//!
+//! Drawing these with `forward()` and `left()` needs a lot of trigonometry
+//! and ends up jagged where the curve bends sharply. A `Curve` samples its
+//! formula adaptively instead, with more points where it bends, until every
+//! segment is within the tolerance (a quarter pixel by default) of the true
+//! curve:
+//!
+//! ```
+//! use turtle_lib::*;
+//! use turtle_lib::curves::{lissajous, rose, spirograph};
+//!
+//! let mut turtle = create_turtle_plan();
+//! spirograph(100.0, 35.0, 60.0).draw(&mut turtle);
+//! rose(5.0 / 3.0, 80.0).with_center(vec2(-200.0, 0.0)).draw(&mut turtle);
+//! lissajous(3.0, 2.0, 0.5, vec2(80.0, 60.0))
+//!     .with_center(vec2(200.0, 0.0))
+//!     .draw(&mut turtle);
+//! ```
+//!
+//! Every curve runs exactly once around until it closes, so it can be
+//! filled. Ratios that aren't simple fractions are approximated with a
+//! denominator of at most `MAX_LOOPS`.
+
+use crate::builders::TurtlePlan;
+use crate::general::{Coordinate, Precision};
+use macroquad::prelude::vec2;
+use std::f32::consts::{PI, TAU};
+
+/// Largest number of loops a curve is traced with before it closes
+pub const MAX_LOOPS: u32 = 100;
+
+/// Maximal distance in pixels between the drawn segments and the curve
+/// unless configured otherwise
+pub const DEFAULT_TOLERANCE: Precision = 0.25;
+
+// Uniform segments per loop before refining, so no lobe is skipped
+const SEGMENTS_PER_LOOP: u32 = 32;
+// Limits the refinement of every segment to 2^MAX_DEPTH parts
+const MAX_DEPTH: u32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Spirograph {
+        fixed: f32,
+        rolling: f32,
+        pen: f32,
+    },
+    Rose {
+        k: f32,
+        radius: f32,
+    },
+    Lissajous {
+        a: f32,
+        b: f32,
+        phase: f32,
+        size: Coordinate,
+    },
+}
+
+/// A closed curve, created with `spirograph()`, `rose()` or `lissajous()`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Curve {
+    kind: Kind,
+    center: Coordinate,
+    tolerance: Precision,
+}
+
+/// Hypotrochoid traced by a pen `pen` pixels from the center of a wheel with
+/// radius `rolling`, rolling inside a ring with radius `fixed`
+#[must_use]
+pub fn spirograph(fixed: Precision, rolling: Precision, pen: Precision) -> Curve {
+    Curve::new(Kind::Spirograph {
+        fixed,
+        rolling,
+        pen,
+    })
+}
+
+/// Rose `r = radius · cos(k · θ)`: `k` petals for odd whole `k`, `2k` for
+/// even `k`
+#[must_use]
+pub fn rose(k: Precision, radius: Precision) -> Curve {
+    Curve::new(Kind::Rose { k, radius })
+}
+
+/// Lissajous figure `(size.x · sin(a·t + phase), size.y · sin(b·t))`, with
+/// `phase` in radians
+#[must_use]
+pub fn lissajous(a: Precision, b: Precision, phase: Precision, size: Coordinate) -> Curve {
+    Curve::new(Kind::Lissajous { a, b, phase, size })
+}
+
+impl Curve {
+    fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            center: Coordinate::ZERO,
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Turtle position of the curve's center (the origin by default)
+    #[must_use]
+    pub fn with_center(mut self, center: impl Into<Coordinate>) -> Self {
+        self.center = center.into();
+        self
+    }
+
+    /// Maximal distance in pixels between the segments and the curve,
+    /// smaller values add more points
+    #[must_use]
+    pub fn with_tolerance(mut self, tolerance: Precision) -> Self {
+        self.tolerance = tolerance.max(0.001);
+        self
+    }
+
+    /// Point of the curve at parameter `t`, relative to the center
+    fn at(&self, t: f32) -> Coordinate {
+        match self.kind {
+            Kind::Spirograph {
+                fixed,
+                rolling,
+                pen,
+            } => {
+                let ratio = (fixed - rolling) / rolling;
+                vec2(
+                    (fixed - rolling) * t.cos() + pen * (ratio * t).cos(),
+                    (fixed - rolling) * t.sin() - pen * (ratio * t).sin(),
+                )
+            }
+            Kind::Rose { k, radius } => radius * (k * t).cos() * vec2(t.cos(), t.sin()),
+            Kind::Lissajous { a, b, phase, size } => {
+                vec2(size.x * (a * t + phase).sin(), size.y * (b * t).sin())
+            }
+        }
+    }
+
+    /// Parameter range until the curve closes, and how many loops that is
+    fn period(&self) -> (f32, u32) {
+        match self.kind {
+            Kind::Spirograph { fixed, rolling, .. } => {
+                // Closes once the wheel turned a whole number of times
+                let (_, loops) = fraction(fixed / rolling);
+                (TAU * loops as f32, loops)
+            }
+            Kind::Rose { k, .. } => {
+                let (n, d) = fraction(k);
+                if n % 2 == 1 && d % 2 == 1 {
+                    (PI * d as f32, d)
+                } else {
+                    (TAU * d as f32, 2 * d)
+                }
+            }
+            Kind::Lissajous { a, b, .. } => {
+                let (n, d) = fraction(a / b);
+                let loops = n.max(d);
+                (TAU * d as f32 / b, loops)
+            }
+        }
+    }
+
+    /// Points along the curve from start to end, relative to the turtle
+    /// origin; the last point repeats the first
+    #[must_use]
+    pub fn points(&self) -> Vec<Coordinate> {
+        let (period, loops) = self.period();
+        if !period.is_finite() || period <= 0.0 {
+            return Vec::new();
+        }
+        let segments = SEGMENTS_PER_LOOP * loops.clamp(1, MAX_LOOPS);
+        let mut points = vec![self.at(0.0)];
+        for n in 0..segments {
+            let start = period * n as f32 / segments as f32;
+            let end = period * (n + 1) as f32 / segments as f32;
+            self.refine(start, end, MAX_DEPTH, &mut points);
+        }
+        let first = points[0];
+        if let Some(last) = points.last_mut() {
+            // Avoid a tiny gap from rounding the period
+            *last = first;
+        }
+        points.iter().map(|&point| point + self.center).collect()
+    }
+
+    /// Add the points after `start` up to `end`, splitting the segment while
+    /// its middle is too far from the curve
+    fn refine(&self, start: f32, end: f32, depth: u32, points: &mut Vec<Coordinate>) {
+        let middle = (start + end) / 2.0;
+        let (from, to) = (self.at(start), self.at(end));
+        let deviation = self.at(middle).distance((from + to) / 2.0);
+        if depth > 0 && deviation > self.tolerance {
+            self.refine(start, middle, depth - 1, points);
+            self.refine(middle, end, depth - 1, points);
+        } else {
+            points.push(to);
+        }
+    }
+
+    /// Draw the curve: jump to its start with the pen up, then trace it with
+    /// the pen down
+    pub fn draw(&self, plan: &mut TurtlePlan) {
+        let points = self.points();
+        let Some((&first, rest)) = points.split_first() else {
+            return;
+        };
+        plan.pen_up().go_to(first).pen_down();
+        for &point in rest {
+            plan.go_to(point);
+        }
+    }
+}
+
+/// `value` as a fraction `n / d` in lowest terms with `d <= MAX_LOOPS`
+fn fraction(value: f32) -> (u32, u32) {
+    let value = value.abs();
+    (1..=MAX_LOOPS)
+        .map(|d| ((value * d as f32).round() as u32, d))
+        .find(|&(n, d)| (n as f32 - value * d as f32).abs() < 1e-3 * d as f32)
+        .unwrap_or(((value * MAX_LOOPS as f32).round() as u32, MAX_LOOPS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_close_with_adaptive_points() {
+        assert_eq!(fraction(2.5), (5, 2));
+        assert_eq!(fraction(4.0), (4, 1));
+
+        let coarse = rose(2.0, 100.0).points();
+        let fine = rose(2.0, 100.0).with_tolerance(0.01).points();
+        assert!(fine.len() > coarse.len());
+        assert_eq!(coarse.first(), coarse.last());
+        assert!(coarse.iter().all(|point| point.length() <= 100.001));
+
+        // The wheel turns three times before the pen is back at the start
+        let spiro = spirograph(5.0, 3.0, 5.0);
+        let (period, loops) = spiro.period();
+        assert_eq!(loops, 3);
+        assert!(spiro.at(period).distance(spiro.at(0.0)) < 0.01);
+    }
+}
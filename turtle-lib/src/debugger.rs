@@ -0,0 +1,117 @@
+//! Step debugger overlay
+//!
+//! Shows for every turtle the command it is executing with its parameters,
+//! a bar with the progress of the animation and the next queued commands.
+//! Together with step mode (`S`, then `N` for every command) a program can
+//! be followed command by command. Toggle it with `TurtleApp::set_debugger()`
+//! or the `D` key.
+
+use crate::commands::TurtleCommand;
+use crate::state::TurtleWorld;
+use macroquad::prelude::*;
+
+/// Number of queued commands listed per turtle unless configured otherwise
+pub const DEFAULT_LOOKAHEAD: usize = 5;
+
+const PANEL_WIDTH: f32 = 340.0;
+const MARGIN: f32 = 10.0;
+const PADDING: f32 = 6.0;
+const FONT_SIZE: f32 = 16.0;
+const LINE_HEIGHT: f32 = 18.0;
+const BAR_HEIGHT: f32 = 8.0;
+// Longer descriptions are cut off to fit the panel
+const MAX_DESCRIPTION: usize = 40;
+
+/// Short description of a command with its parameters
+#[must_use]
+pub fn describe(command: &TurtleCommand) -> String {
+    let text = format!("{command:?}");
+    if text.chars().count() <= MAX_DESCRIPTION {
+        text
+    } else {
+        let mut short: String = text.chars().take(MAX_DESCRIPTION - 1).collect();
+        short.push('…');
+        short
+    }
+}
+
+/// Draw one panel per turtle along the right edge of the screen (call after
+/// `render()`, in screen space)
+///
+/// Each panel lists up to `lookahead` upcoming commands.
+pub fn draw(world: &TurtleWorld, lookahead: usize) {
+    let theme = &world.theme;
+    let backdrop = theme
+        .text_backdrop
+        .unwrap_or(Color::new(1.0, 1.0, 1.0, 0.85));
+    let x = screen_width() - PANEL_WIDTH - MARGIN;
+    let mut y = MARGIN;
+
+    for turtle in &world.turtles {
+        let controller = &turtle.tween_controller;
+        let upcoming = controller.upcoming();
+        let listed = upcoming.len().min(lookahead);
+        let mut lines = vec![format!(
+            "Turtle {} ({} queued)",
+            turtle.turtle_id,
+            controller.remaining()
+        )];
+        let current = controller.current_command();
+        lines.push(match current {
+            Some((command, _)) => format!("> {}", describe(command)),
+            None if controller.is_complete() => "> done".to_string(),
+            None => "> waiting".to_string(),
+        });
+        lines.extend(
+            upcoming[..listed]
+                .iter()
+                .enumerate()
+                .map(|(n, command)| format!("  {}. {}", n + 1, describe(command))),
+        );
+        if upcoming.len() > listed {
+            lines.push(format!("  … {} more", upcoming.len() - listed));
+        }
+
+        let height = lines.len() as f32 * LINE_HEIGHT + BAR_HEIGHT + 3.0 * PADDING;
+        if y + height > screen_height() {
+            break;
+        }
+        draw_rectangle(x, y, PANEL_WIDTH, height, backdrop);
+        draw_rectangle_lines(x, y, PANEL_WIDTH, height, 1.0, theme.guides);
+
+        let mut line_y = y + PADDING;
+        for (n, line) in lines.iter().enumerate() {
+            line_y += LINE_HEIGHT;
+            draw_text(line, x + PADDING, line_y - 4.0, FONT_SIZE, theme.text);
+            // The progress bar goes below the running command
+            if n == 1 {
+                let progress = current.map_or(0.0, |(_, progress)| progress);
+                let width = PANEL_WIDTH - 2.0 * PADDING;
+                line_y += PADDING / 2.0;
+                draw_rectangle(
+                    x + PADDING,
+                    line_y,
+                    width * progress,
+                    BAR_HEIGHT,
+                    theme.selection,
+                );
+                draw_rectangle_lines(x + PADDING, line_y, width, BAR_HEIGHT, 1.0, theme.text);
+                line_y += BAR_HEIGHT + PADDING / 2.0;
+            }
+        }
+        y += height + MARGIN;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_descriptions_are_shortened() {
+        assert_eq!(describe(&TurtleCommand::PenUp), "PenUp");
+        let long = describe(&TurtleCommand::Together(vec![TurtleCommand::PenUp; 20]));
+        assert_eq!(long.chars().count(), MAX_DESCRIPTION);
+        assert!(long.ends_with('…'));
+    }
+}
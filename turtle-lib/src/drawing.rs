@@ -1,16 +1,11 @@
 //! Rendering logic using Macroquad and Lyon tessellation
 
 use crate::circle_geometry::{CircleDirection, CircleGeometry};
-use crate::state::{DrawCommand, TurtleParams, TurtleWorld};
+use crate::state::{BlendMode, DrawCommand, TurtleParams, TurtleWorld, DEFAULT_CURSOR_COLOR};
 use crate::tessellation;
+use macroquad::miniquad::{BlendFactor, BlendState, BlendValue, Equation, PipelineParams};
 use macroquad::prelude::*;
-
-// Import the easing function from the tween crate
-// To change the easing, change both this import and the usage in the draw_tween_arc function below
-// Available options: Linear, SineInOut, QuadInOut, CubicInOut, QuartInOut, QuintInOut,
-//                    ExpoInOut, CircInOut, BackInOut, ElasticInOut, BounceInOut, etc.
-// See https://easings.net/ for visual demonstrations
-use tween::CubicInOut;
+use std::cell::RefCell;
 
 /// Render the entire turtle world
 pub fn render_world(world: &TurtleWorld) {
@@ -21,81 +16,248 @@ pub fn render_world(world: &TurtleWorld) {
         ..Default::default()
     };
 
+    draw_background_gradient(world);
+
     // Set camera
     set_camera(&camera);
 
     // Draw all accumulated commands from all turtles
+    draw_accumulated_commands(world);
+
+    draw_ghosts(world);
+
+    // Draw all visible turtles
     for turtle in &world.turtles {
-        for cmd in &turtle.commands {
-            match cmd {
-                DrawCommand::Mesh { data, source: _ } => {
-                    // Rendering wie bisher
+        if turtle.params.visible {
+            let walked = turtle
+                .tween_controller
+                .current_tween()
+                .map_or(0.0, crate::tweening::CommandTween::distance_travelled);
+            draw_themed_turtle(world, &turtle.params, walked);
+        }
+    }
+
+    // Reset to default camera
+    set_default_camera();
+}
+
+/// Draw the finished drawings of all turtles, faded according to `trail_fade`
+///
+/// Drawings of removed turtles in `TurtleWorld::layer` come first and don't
+/// fade.
+fn draw_accumulated_commands(world: &TurtleWorld) {
+    let layer = world.layer.iter().map(|cmd| (cmd, 1.0));
+    let living = world.turtles.iter().flat_map(|turtle| {
+        turtle
+            .commands
+            .iter()
+            .map(move |cmd| (cmd, world.trail_alpha(turtle, cmd)))
+    });
+    for (cmd, alpha) in layer.chain(living) {
+        match cmd {
+            DrawCommand::Mesh { data, source } => {
+                let material = blend_material(source.blend_mode);
+                if let Some(material) = &material {
+                    gl_use_material(material);
+                }
+                if world.trail_fade.is_some() {
+                    draw_mesh(&data.to_mesh_with_alpha(alpha));
+                } else {
                     draw_mesh(&data.to_mesh());
-                    // Hier könnte man das source für Debug/Export loggen
                 }
-                DrawCommand::Text {
-                    text,
-                    position,
-                    heading,
-                    font_size,
-                    color,
-                    source: _,
-                } => {
-                    draw_text_command(text, *position, *heading, *font_size, *color);
-                    // Hier könnte man das source für Debug/Export loggen
+                if material.is_some() {
+                    gl_use_default_material();
+                }
+                if let Some(label) = &source.annotation {
+                    let color = source.color;
+                    let color = Color::new(color.r, color.g, color.b, color.a * alpha);
+                    draw_annotation(label, source, color);
                 }
             }
+            DrawCommand::Text {
+                text,
+                position,
+                heading,
+                font_size,
+                color,
+                source: _,
+            } => {
+                let color = Color::new(color.r, color.g, color.b, color.a * alpha);
+                draw_text_command(text, *position, *heading, *font_size, color);
+            }
         }
     }
+}
 
-    // Draw all visible turtles
-    for turtle in &world.turtles {
-        if turtle.params.visible {
-            draw_turtle(&turtle.params);
+const BLEND_VERTEX_SHADER: &str = r"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+varying lowp vec4 color;
+uniform mat4 Model;
+uniform mat4 Projection;
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+}
+";
+
+const ADDITIVE_FRAGMENT_SHADER: &str = r"#version 100
+varying lowp vec4 color;
+void main() {
+    gl_FragColor = color;
+}
+";
+
+// Multiplying with the destination ignores the source alpha, so the color
+// is faded towards white (which leaves the destination unchanged) instead
+const MULTIPLY_FRAGMENT_SHADER: &str = r"#version 100
+varying lowp vec4 color;
+void main() {
+    gl_FragColor = vec4(mix(vec3(1.0), color.rgb, color.a), 1.0);
+}
+";
+
+thread_local! {
+    static BLEND_MATERIALS: RefCell<Vec<(BlendMode, Option<Material>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Material drawing meshes with `mode`, `None` for `BlendMode::Normal`
+///
+/// Materials are created on first use and cached. If the shader can't be
+/// compiled, the mode falls back to normal blending.
+fn blend_material(mode: BlendMode) -> Option<Material> {
+    let (fragment, blend) = match mode {
+        BlendMode::Normal => return None,
+        BlendMode::Additive => (
+            ADDITIVE_FRAGMENT_SHADER,
+            BlendState::new(
+                Equation::Add,
+                BlendFactor::Value(BlendValue::SourceAlpha),
+                BlendFactor::One,
+            ),
+        ),
+        BlendMode::Multiply => (
+            MULTIPLY_FRAGMENT_SHADER,
+            BlendState::new(
+                Equation::Add,
+                BlendFactor::Value(BlendValue::DestinationColor),
+                BlendFactor::Zero,
+            ),
+        ),
+    };
+    BLEND_MATERIALS.with(|materials| {
+        let mut materials = materials.borrow_mut();
+        if let Some((_, material)) = materials.iter().find(|(cached, _)| *cached == mode) {
+            return material.clone();
         }
-    }
+        let material = load_material(
+            ShaderSource::Glsl {
+                vertex: BLEND_VERTEX_SHADER,
+                fragment,
+            },
+            MaterialParams {
+                pipeline_params: PipelineParams {
+                    color_blend: Some(blend),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .map_err(|error| {
+            tracing::warn!(?mode, %error, "Blend mode not supported, drawing normally");
+        })
+        .ok();
+        materials.push((mode, material.clone()));
+        material
+    })
+}
 
-    // Reset to default camera
+/// Fill the window with the world's background gradient, if any
+pub(crate) fn draw_background_gradient(world: &TurtleWorld) {
+    draw_background_gradient_in(world, Rect::new(0.0, 0.0, screen_width(), screen_height()));
+}
+
+/// Like `draw_background_gradient()`, only filling `rect` (window pixels)
+pub(crate) fn draw_background_gradient_in(world: &TurtleWorld, rect: Rect) {
+    let Some((top, bottom)) = world.background_gradient else {
+        return;
+    };
     set_default_camera();
+    fill_gradient(rect, top, bottom);
 }
 
-/// Render the turtle world with active tween visualization
-#[allow(clippy::too_many_lines)]
-pub fn render_world_with_tweens(world: &TurtleWorld, zoom_level: f32) {
+/// Fill `target` with the world's background color and gradient
+pub(crate) fn draw_background_to(world: &TurtleWorld, target: &RenderTarget) {
+    let size = target.texture.size();
+    let rect = Rect::new(0.0, 0.0, size.x, size.y);
+    set_camera(&Camera2D {
+        render_target: Some(target.clone()),
+        ..Camera2D::from_display_rect(rect)
+    });
+    clear_background(world.background_color);
+    if let Some((top, bottom)) = world.background_gradient {
+        fill_gradient(rect, top, bottom);
+    }
+    set_default_camera();
+}
+
+fn fill_gradient(rect: Rect, top: Color, bottom: Color) {
+    let Rect { x, y, w, h } = rect;
+    let vertex = |x: f32, y: f32, color: Color| Vertex::new(x, y, 0.0, 0.0, 0.0, color);
+    draw_mesh(&Mesh {
+        vertices: vec![
+            vertex(x, y, top),
+            vertex(x + w, y, top),
+            vertex(x + w, y + h, bottom),
+            vertex(x, y + h, bottom),
+        ],
+        indices: vec![0, 1, 2, 0, 2, 3],
+        texture: None,
+    });
+}
+
+/// Camera used to render `world` at the given user zoom level
+#[must_use]
+pub fn world_camera(world: &TurtleWorld, zoom_level: f32) -> Camera2D {
     // Update camera zoom based on current screen size to prevent stretching
     // Apply user zoom level by dividing by it (smaller zoom value = more zoomed in)
-    let camera = Camera2D {
+    Camera2D {
         zoom: vec2(
             1.0 / screen_width() * 2.0 / zoom_level,
             1.0 / screen_height() * 2.0 / zoom_level,
         ),
         target: world.camera.target,
         ..Default::default()
-    };
+    }
+}
 
-    // Set camera
-    set_camera(&camera);
+/// Like `world_camera()`, rendering into `target` instead of the window
+#[must_use]
+pub fn target_camera(world: &TurtleWorld, zoom_level: f32, target: &RenderTarget) -> Camera2D {
+    let size = target.texture.size().max(Vec2::ONE);
+    Camera2D {
+        zoom: vec2(2.0 / size.x, 2.0 / size.y) / zoom_level,
+        target: world.camera.target,
+        render_target: Some(target.clone()),
+        ..Default::default()
+    }
+}
+
+/// Render the turtle world with active tween visualization
+pub fn render_world_with_tweens(world: &TurtleWorld, zoom_level: f32) {
+    draw_background_gradient(world);
+    render_world_with_camera(world, &world_camera(world, zoom_level));
+}
+
+/// Render the drawings, tweens and cursors of `world` through `camera`,
+/// e.g. into one of several viewports
+#[allow(clippy::too_many_lines)]
+pub fn render_world_with_camera(world: &TurtleWorld, camera: &Camera2D) {
+    set_camera(camera);
 
     // Draw all accumulated commands from all turtles
-    for turtle in &world.turtles {
-        for cmd in &turtle.commands {
-            match cmd {
-                DrawCommand::Mesh { data, source: _ } => {
-                    draw_mesh(&data.to_mesh());
-                }
-                DrawCommand::Text {
-                    text,
-                    position,
-                    heading,
-                    font_size,
-                    color,
-                    source: _,
-                } => {
-                    draw_text_command(text, *position, *heading, *font_size, *color);
-                }
-            }
-        }
-    }
+    draw_accumulated_commands(world);
 
     // Draw in-progress tween lines for all active tweens
     for turtle in &world.turtles {
@@ -110,7 +272,10 @@ pub fn render_world_with_tweens(world: &TurtleWorld, zoom_level: f32) {
                         direction,
                     } => {
                         // Draw arc segments from start to current position
-                        draw_tween_arc(tween, *radius, *angle, *steps, *direction);
+                        draw_tween_arc(world, tween, *radius, *angle, *steps, *direction);
+                    }
+                    crate::commands::TurtleCommand::Together(members) => {
+                        draw_tween_group(tween, members);
                     }
                     _ if should_draw_tween_line(&tween.command) => {
                         // Draw straight line for other movement commands (use tween's current position)
@@ -183,9 +348,9 @@ pub fn render_world_with_tweens(world: &TurtleWorld, zoom_level: f32) {
                             *radius,
                             *direction,
                         ); // Calculate progress
-                        let elapsed = get_time() - tween.start_time;
+                        let elapsed = tween.elapsed;
                         let progress = (elapsed / tween.duration).min(1.0);
-                        let eased_progress = CubicInOut.tween(1.0, progress as f32);
+                        let eased_progress = tween.start_params.easing.apply(progress as f32);
 
                         // Generate arc vertices for the partial arc
                         let num_samples = *steps.max(&1);
@@ -211,6 +376,17 @@ pub fn render_world_with_tweens(world: &TurtleWorld, zoom_level: f32) {
                             );
                             current_preview.push(vertex);
                         }
+                    } else if let crate::commands::TurtleCommand::Together(members) = &tween.command
+                    {
+                        // Add the combined path of the group up to the current progress
+                        let progress = (tween.elapsed / tween.duration).min(1.0);
+                        let eased_progress = tween.start_params.easing.apply(progress as f32);
+                        let path = crate::tweening::group_path(
+                            &tween.start_params,
+                            members,
+                            eased_progress,
+                        );
+                        current_preview.extend(path.into_iter().skip(1).map(|(point, _, _)| point));
                     } else if matches!(
                         &tween.command,
                         crate::commands::TurtleCommand::Move(_)
@@ -291,10 +467,16 @@ pub fn render_world_with_tweens(world: &TurtleWorld, zoom_level: f32) {
         }
     }
 
+    draw_ghosts(world);
+
     // Draw all visible turtles
     for turtle in &world.turtles {
         if turtle.params.visible {
-            draw_turtle(&turtle.params);
+            let walked = turtle
+                .tween_controller
+                .current_tween()
+                .map_or(0.0, crate::tweening::CommandTween::distance_travelled);
+            draw_themed_turtle(world, &turtle.params, walked);
         }
     }
 
@@ -343,8 +525,43 @@ fn draw_text_command(
     );
 }
 
+/// Font size of the labels added with `TurtlePlan::annotate()`
+pub const ANNOTATION_FONT_SIZE: u16 = 16;
+
+/// Where the label of a stroke starts and how far it rises above that point
+///
+/// The label is centered on the middle of the stroke and moved away from it
+/// on the side given by `TurtleSource::annotation_anchor()`. Shared with the
+/// SVG export so both place labels alike.
+#[must_use]
+pub fn annotation_position(source: &crate::state::TurtleSource, text_width: f32) -> Vec2 {
+    let (middle, normal) = source.annotation_anchor();
+    let size = f32::from(ANNOTATION_FONT_SIZE);
+    let center = middle + normal * (source.pen_width / 2.0 + size * 0.6);
+    // `center` is the middle of the text, labels are positioned at their
+    // baseline
+    vec2(center.x - text_width / 2.0, center.y + size * 0.35)
+}
+
+/// Draw the label of an annotated stroke, upright next to its middle
+fn draw_annotation(label: &str, source: &crate::state::TurtleSource, color: Color) {
+    let width = measure_text(label, None, ANNOTATION_FONT_SIZE, 1.0).width;
+    let position = annotation_position(source, width);
+    draw_text_ex(
+        label,
+        position.x,
+        position.y,
+        TextParams {
+            font_size: ANNOTATION_FONT_SIZE,
+            color,
+            ..Default::default()
+        },
+    );
+}
+
 /// Draw arc segments for circle tween animation
 fn draw_tween_arc(
+    world: &TurtleWorld,
     tween: &crate::tweening::CommandTween,
     radius: f32,
     total_angle: f32,
@@ -359,16 +576,17 @@ fn draw_tween_arc(
     );
 
     // Debug: draw center using Lyon tessellation
-    if let Ok(mesh_data) = crate::tessellation::tessellate_circle(geom.center, 5.0, GRAY, true, 1.0)
+    if let Ok(mesh_data) =
+        crate::tessellation::tessellate_circle(geom.center, 5.0, world.theme.guides, true, 1.0)
     {
         draw_mesh(&mesh_data.to_mesh());
     }
 
     // Calculate how much of the arc we've traveled based on tween progress
     // Use the same eased progress as the turtle position for synchronized animation
-    let elapsed = get_time() - tween.start_time;
+    let elapsed = tween.elapsed;
     let t = (elapsed / tween.duration).min(1.0);
-    let progress = CubicInOut.tween(1.0, t as f32); // tween from 0 to 1
+    let progress = tween.start_params.easing.apply(t as f32); // tween from 0 to 1
 
     // Use Lyon to tessellate and draw the partial arc
     if let Ok(mesh_data) = crate::tessellation::tessellate_arc(
@@ -385,23 +603,80 @@ fn draw_tween_arc(
     }
 }
 
-/// Draw the turtle shape
-pub fn draw_turtle(turtle_params: &TurtleParams) {
-    let rotated_vertices = turtle_params.shape.rotated_vertices(turtle_params.heading);
+/// Draw the path of a `Together` group from its start to the current progress
+fn draw_tween_group(
+    tween: &crate::tweening::CommandTween,
+    members: &[crate::commands::TurtleCommand],
+) {
+    let t = (tween.elapsed / tween.duration).min(1.0);
+    let progress = tween.start_params.easing.apply(t as f32);
+    let path = crate::tweening::group_path(&tween.start_params, members, progress);
+
+    for segment in path.windows(2) {
+        let ((start, start_width, color), (end, end_width, _)) = (segment[0], segment[1]);
+        let width = (start_width + end_width) / 2.0;
+        draw_line(start.x, start.y, end.x, end.y, width, color);
+        // Round joins between the segments
+        draw_circle(end.x, end.y, width / 2.0, color);
+    }
+}
+
+/// Draw the faded cursor copies of `ghost_trail`, oldest first
+fn draw_ghosts(world: &TurtleWorld) {
+    let Some(trail) = world.ghost_trail else {
+        return;
+    };
+    for turtle in &world.turtles {
+        for (age, ghost) in turtle.ghosts.iter().rev().enumerate().rev() {
+            let alpha = trail.alpha(age);
+            let mut params = ghost.clone();
+            params.cursor_fill.a *= alpha;
+            params.cursor_outline.a *= alpha;
+            draw_themed_turtle(world, &params, 0.0);
+        }
+    }
+}
+
+/// Draw a turtle in the colors of the world's theme, unless it was given
+/// its own cursor colors
+fn draw_themed_turtle(world: &TurtleWorld, params: &TurtleParams, walked: f32) {
+    let theme = &world.theme;
+    // Ghosts are faded copies, so only compare the color itself
+    let is_default = |color: Color| Color { a: 1.0, ..color } == DEFAULT_CURSOR_COLOR;
+    if is_default(params.cursor_fill)
+        && is_default(params.cursor_outline)
+        && (theme.cursor_fill != DEFAULT_CURSOR_COLOR
+            || theme.cursor_outline != DEFAULT_CURSOR_COLOR)
+    {
+        let mut themed = params.clone();
+        themed.cursor_fill = Color {
+            a: params.cursor_fill.a,
+            ..theme.cursor_fill
+        };
+        themed.cursor_outline = Color {
+            a: params.cursor_outline.a,
+            ..theme.cursor_outline
+        };
+        draw_turtle(&themed, walked);
+    } else {
+        draw_turtle(params, walked);
+    }
+}
+
+/// Draw the turtle shape, in the walk frame for `walked` pixels of movement
+pub fn draw_turtle(turtle_params: &TurtleParams, walked: f32) {
+    let absolute_vertices = turtle_params.cursor_vertices_walked(walked);
+    let outline = turtle_params.shape_size.outline;
 
     if turtle_params.shape.filled {
         // Draw filled polygon using Lyon tessellation
-        if rotated_vertices.len() >= 3 {
-            let absolute_vertices: Vec<Vec2> = rotated_vertices
-                .iter()
-                .map(|v| turtle_params.position + *v)
-                .collect();
-
+        if absolute_vertices.len() >= 3 {
             // Use Lyon for turtle shape too
             if let Ok(mesh_data) =
-                tessellation::tessellate_polygon(&absolute_vertices, Color::new(0.0, 0.5, 1.0, 1.0))
+                tessellation::tessellate_polygon(&absolute_vertices, turtle_params.cursor_fill)
             {
                 draw_mesh(&mesh_data.to_mesh());
+                draw_cursor_outline(&absolute_vertices, outline, turtle_params.cursor_outline);
             } else {
                 // Fallback to simple triangle fan if Lyon fails
                 let first = absolute_vertices[0];
@@ -410,20 +685,66 @@ pub fn draw_turtle(turtle_params: &TurtleParams) {
                         first,
                         absolute_vertices[i],
                         absolute_vertices[i + 1],
-                        Color::new(0.0, 0.5, 1.0, 1.0),
+                        turtle_params.cursor_fill,
                     );
                 }
             }
         }
     } else {
-        // Draw outline
-        if !rotated_vertices.is_empty() {
-            for i in 0..rotated_vertices.len() {
-                let next_i = (i + 1) % rotated_vertices.len();
-                let p1 = turtle_params.position + rotated_vertices[i];
-                let p2 = turtle_params.position + rotated_vertices[next_i];
-                draw_line(p1.x, p1.y, p2.x, p2.y, 2.0, Color::new(0.0, 0.5, 1.0, 1.0));
-            }
-        }
+        draw_cursor_outline(&absolute_vertices, outline, turtle_params.cursor_outline);
+    }
+}
+
+/// Outline the cursor of turtle `id`, enlarged by `scale`, e.g. to mark it
+/// as selected
+pub fn draw_cursor_highlight(
+    world: &TurtleWorld,
+    zoom_level: f32,
+    id: usize,
+    scale: f32,
+    color: Color,
+) {
+    let Some(turtle) = world.get_turtle(id).filter(|turtle| turtle.params.visible) else {
+        return;
+    };
+    let mut params = turtle.params.clone();
+    params.shape_size.stretch_wid *= scale;
+    params.shape_size.stretch_len *= scale;
+
+    set_camera(&world_camera(world, zoom_level));
+    draw_cursor_outline(&params.cursor_vertices(), 2.0 * zoom_level, color);
+    set_default_camera();
+}
+
+/// Like `draw_cursor_highlight()`, through `camera` instead of the whole
+/// window's camera
+pub fn draw_cursor_highlight_with_camera(
+    world: &TurtleWorld,
+    camera: &Camera2D,
+    zoom_level: f32,
+    id: usize,
+    scale: f32,
+    color: Color,
+) {
+    let Some(turtle) = world.get_turtle(id).filter(|turtle| turtle.params.visible) else {
+        return;
+    };
+    let mut params = turtle.params.clone();
+    params.shape_size.stretch_wid *= scale;
+    params.shape_size.stretch_len *= scale;
+
+    set_camera(camera);
+    draw_cursor_outline(&params.cursor_vertices(), 2.0 * zoom_level, color);
+    set_default_camera();
+}
+
+/// Draw the closed outline of a cursor polygon (nothing if `width` is 0)
+fn draw_cursor_outline(vertices: &[Vec2], width: f32, color: Color) {
+    if width <= 0.0 {
+        return;
+    }
+    for (i, p1) in vertices.iter().enumerate() {
+        let p2 = vertices[(i + 1) % vertices.len()];
+        draw_line(p1.x, p1.y, p2.x, p2.y, width, color);
     }
 }
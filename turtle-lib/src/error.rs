@@ -0,0 +1,91 @@
+//! Errors reported by the library instead of drawing something broken
+
+use crate::general::Precision;
+
+/// Something went wrong with the commands of a turtle
+///
+/// Besides being returned by `try_build()` and `validate()`, these are
+/// collected per turtle while commands run, see `TurtleApp::take_errors()`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TurtleError {
+    /// Command `index` of a queue has a parameter that can't be drawn
+    InvalidCommand {
+        index: usize,
+        problem: CommandProblem,
+    },
+    /// `end_fill()` without a `begin_fill()` before it
+    EndFillWithoutBeginFill,
+    /// `begin_fill()` while already filling, the earlier fill is discarded
+    BeginFillWhileFilling,
+    /// `begin_fill()` without a fill color, black is used
+    NoFillColor,
+    /// A line, arc, fill or stamp couldn't be turned into triangles and is
+    /// missing from the drawing
+    Tessellation(String),
+    /// `go_to_mark()` with a mark that was never set, the command is skipped
+    UnknownMark(String),
+    /// `shape_named()` with a shape that was never registered, the command
+    /// is skipped
+    UnknownShape(String),
+    /// `stamp_stencil()` before the stencil was recorded, the command is
+    /// skipped
+    UnknownStencil,
+    /// `pop_state()` without a `push_state()` before it, the command is
+    /// skipped
+    PopWithoutPush,
+    /// `offset_last_path()` before anything was drawn, the command is skipped
+    NoPathToOffset,
+    /// All senders of the turtle's command channel were dropped
+    ChannelDisconnected,
+}
+
+impl std::fmt::Display for TurtleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidCommand { index, problem } => write!(f, "command {index}: {problem}"),
+            Self::EndFillWithoutBeginFill => write!(f, "end_fill() without begin_fill()"),
+            Self::BeginFillWhileFilling => write!(f, "begin_fill() while already filling"),
+            Self::NoFillColor => write!(f, "begin_fill() without a fill color, using black"),
+            Self::Tessellation(message) => write!(f, "cannot tessellate drawing: {message}"),
+            Self::UnknownMark(name) => write!(f, "unknown mark \"{name}\""),
+            Self::UnknownShape(name) => write!(f, "unregistered shape \"{name}\""),
+            Self::UnknownStencil => write!(f, "stamp_stencil() before end_stencil()"),
+            Self::PopWithoutPush => write!(f, "pop_state() without push_state()"),
+            Self::NoPathToOffset => write!(f, "offset_last_path() without a drawn path"),
+            Self::ChannelDisconnected => write!(f, "command channel disconnected"),
+        }
+    }
+}
+
+impl std::error::Error for TurtleError {}
+
+/// Why a single command is invalid, see `TurtleCommand::validate()`
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandProblem {
+    /// The named parameter is NaN or infinite
+    NotFinite(&'static str),
+    /// Circles need a radius of at least 0
+    NegativeRadius(Precision),
+    /// Circles need at least one step
+    ZeroSteps,
+    /// Pen widths go from 0 to `MAX_PEN_WIDTH`
+    PenWidth(Precision),
+    /// Durations and turn speeds can't be negative
+    Negative(&'static str),
+}
+
+impl std::fmt::Display for CommandProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFinite(parameter) => write!(f, "{parameter} is not a finite number"),
+            Self::NegativeRadius(radius) => write!(f, "negative radius {radius}"),
+            Self::ZeroSteps => write!(f, "a circle needs at least one step"),
+            Self::PenWidth(width) => write!(
+                f,
+                "pen width {width} is outside of 0 to {}",
+                crate::commands::MAX_PEN_WIDTH
+            ),
+            Self::Negative(parameter) => write!(f, "{parameter} is negative"),
+        }
+    }
+}
@@ -1,9 +1,11 @@
 //! Command execution logic
 
-use crate::circle_geometry::{CircleDirection, CircleGeometry};
+use crate::circle_geometry::CircleGeometry;
 use crate::commands::TurtleCommand;
+use crate::error::TurtleError;
 use crate::state::{DrawCommand, Turtle, TurtleParams, TurtleWorld};
 use crate::tessellation;
+use crate::tweening::normalize_angle;
 use macroquad::prelude::*;
 
 #[cfg(test)]
@@ -20,12 +22,14 @@ pub fn execute_command_side_effects(command: &TurtleCommand, state: &mut Turtle)
                     turtle_id = state.turtle_id,
                     "begin_fill() called while already filling"
                 );
+                state.report_error(TurtleError::BeginFillWhileFilling);
             }
             let fill_color = state.params.fill_color.unwrap_or_else(|| {
                 tracing::warn!(
                     turtle_id = state.turtle_id,
                     "No fill_color set, using black"
                 );
+                state.report_error(TurtleError::NoFillColor);
                 BLACK
             });
             state.begin_fill(fill_color);
@@ -54,33 +58,40 @@ pub fn execute_command_side_effects(command: &TurtleCommand, state: &mut Turtle)
                 }
 
                 if !fill_state.contours.is_empty() {
-                    if let Ok(mesh_data) = tessellation::tessellate_multi_contour(
+                    match tessellation::tessellate_multi_contour(
                         &fill_state.contours,
                         fill_state.fill_color,
                     ) {
-                        tracing::debug!(
-                            turtle_id = state.turtle_id,
-                            contours = fill_state.contours.len(),
-                            "Successfully created fill mesh - persisting to commands"
-                        );
-                        state.commands.push(DrawCommand::Mesh {
-                            data: mesh_data,
-                            source: crate::state::TurtleSource {
-                                command: crate::commands::TurtleCommand::EndFill,
-                                color: state.params.color,
-                                fill_color: fill_state.fill_color,
-                                pen_width: state.params.pen_width,
-                                start_position: fill_state.start_position,
-                                end_position: fill_state.start_position,
-                                start_heading: state.params.heading,
-                                contours: Some(fill_state.contours.clone()),
-                            },
-                        });
-                    } else {
-                        tracing::error!(
-                            turtle_id = state.turtle_id,
-                            "Failed to tessellate contours"
-                        );
+                        Ok(mesh_data) => {
+                            tracing::debug!(
+                                turtle_id = state.turtle_id,
+                                contours = fill_state.contours.len(),
+                                "Successfully created fill mesh - persisting to commands"
+                            );
+                            state.commands.push(DrawCommand::Mesh {
+                                data: mesh_data,
+                                source: crate::state::TurtleSource {
+                                    command: crate::commands::TurtleCommand::EndFill,
+                                    color: state.params.color,
+                                    fill_color: fill_state.fill_color,
+                                    pen_width: state.params.pen_width,
+                                    start_position: fill_state.start_position,
+                                    end_position: fill_state.start_position,
+                                    start_heading: state.params.heading,
+                                    contours: Some(fill_state.contours.clone()),
+                                    created_at: state.tween_controller.now(),
+                                    blend_mode: state.params.blend_mode,
+                                    annotation: None,
+                                },
+                            });
+                        }
+                        Err(error) => {
+                            tracing::error!(
+                                turtle_id = state.turtle_id,
+                                "Failed to tessellate contours"
+                            );
+                            state.report_error(TurtleError::Tessellation(error.to_string()));
+                        }
                     }
                 }
             } else {
@@ -88,6 +99,7 @@ pub fn execute_command_side_effects(command: &TurtleCommand, state: &mut Turtle)
                     turtle_id = state.turtle_id,
                     "end_fill() called without begin_fill()"
                 );
+                state.report_error(TurtleError::EndFillWithoutBeginFill);
             }
             true
         }
@@ -121,6 +133,153 @@ pub fn execute_command_side_effects(command: &TurtleCommand, state: &mut Turtle)
             true
         }
 
+        TurtleCommand::PushState => {
+            state.push_state();
+            true
+        }
+
+        TurtleCommand::PopState => {
+            state.pop_state();
+            true
+        }
+
+        TurtleCommand::OffsetLastPath(distance) => {
+            crate::path_offset::offset_last_path(state, *distance);
+            true
+        }
+
+        TurtleCommand::Clear => {
+            state.clear_drawings(false);
+            true
+        }
+
+        TurtleCommand::Mark(name) => {
+            let marker = crate::state::Marker {
+                position: state.params.position,
+                heading: state.params.heading,
+                turtle_id: state.turtle_id,
+            };
+            state.shared_data().markers.insert(name.clone(), marker);
+            true
+        }
+
+        TurtleCommand::SendMessage { to, payload } => {
+            let message = crate::state::Message {
+                from: state.turtle_id,
+                payload: payload.clone(),
+            };
+            state
+                .shared_data()
+                .mailboxes
+                .entry(*to)
+                .or_default()
+                .push(message);
+            true
+        }
+
+        TurtleCommand::Spawn { plan, offset } => {
+            let mut params = state.params.clone();
+            let pose = params
+                .exact_pose()
+                .moved(offset.x)
+                .turned(-std::f32::consts::FRAC_PI_2)
+                .moved(offset.y)
+                .turned(std::f32::consts::FRAC_PI_2);
+            params.set_exact_pose(pose);
+            state
+                .world_requests
+                .push(crate::state::WorldRequest::Spawn {
+                    params: Box::new(params),
+                    plan: plan.clone(),
+                });
+            true
+        }
+
+        TurtleCommand::Die { keep_drawing } => {
+            // Nothing after the end runs, the world removes the turtle
+            state.tween_controller.remove_pending(..);
+            state.world_requests.push(crate::state::WorldRequest::Die {
+                keep_drawing: *keep_drawing,
+            });
+            true
+        }
+
+        TurtleCommand::ClearScreen { rehome } => {
+            state.clear_drawings(*rehome);
+            state
+                .world_requests
+                .push(crate::state::WorldRequest::ClearScreen { rehome: *rehome });
+            true
+        }
+
+        TurtleCommand::Stamp(_) => {
+            let vertices = state.params.cursor_vertices();
+            let outline = state.params.shape_size.outline;
+            let mesh = if state.params.shape.filled {
+                tessellation::tessellate_polygon(&vertices, state.params.cursor_fill).map(
+                    |mut mesh| {
+                        if outline > 0.0 {
+                            if let Ok(stroke) = tessellation::tessellate_stroke(
+                                &vertices,
+                                state.params.cursor_outline,
+                                outline,
+                                true,
+                            ) {
+                                if let Err(error) = mesh.append(stroke) {
+                                    tracing::warn!(%error, "Stamp drawn without outline");
+                                }
+                            }
+                        }
+                        mesh
+                    },
+                )
+            } else {
+                tessellation::tessellate_stroke(
+                    &vertices,
+                    state.params.cursor_outline,
+                    outline.max(1.0),
+                    true,
+                )
+            };
+            match mesh {
+                Ok(data) => state.commands.push(DrawCommand::Mesh {
+                    data,
+                    source: crate::state::TurtleSource {
+                        command: command.clone(),
+                        color: state.params.cursor_outline,
+                        fill_color: if state.params.shape.filled {
+                            state.params.cursor_fill
+                        } else {
+                            Color::new(0.0, 0.0, 0.0, 0.0)
+                        },
+                        pen_width: outline,
+                        start_position: state.params.position,
+                        end_position: state.params.position,
+                        start_heading: state.params.heading,
+                        contours: Some(vec![vertices]),
+                        created_at: state.tween_controller.now(),
+                        blend_mode: state.params.blend_mode,
+                        annotation: None,
+                    },
+                }),
+                Err(error) => {
+                    tracing::warn!(turtle_id = state.turtle_id, %error, "Failed to stamp");
+                    state.report_error(TurtleError::Tessellation(error.to_string()));
+                }
+            }
+            true
+        }
+
+        TurtleCommand::ClearStamp(id) => {
+            state.clear_stamp(*id);
+            true
+        }
+
+        TurtleCommand::ClearStamps(count) => {
+            state.clear_stamps(*count);
+            true
+        }
+
         TurtleCommand::WriteText { text, font_size } => {
             state.commands.push(DrawCommand::Text {
                 text: text.clone(),
@@ -137,6 +296,9 @@ pub fn execute_command_side_effects(command: &TurtleCommand, state: &mut Turtle)
                     end_position: state.params.position,
                     start_heading: state.params.heading,
                     contours: None,
+                    created_at: state.tween_controller.now(),
+                    blend_mode: state.params.blend_mode,
+                    annotation: None,
                 },
             });
             true
@@ -146,11 +308,28 @@ pub fn execute_command_side_effects(command: &TurtleCommand, state: &mut Turtle)
         | TurtleCommand::Turn(_)
         | TurtleCommand::Circle { .. }
         | TurtleCommand::Goto(_)
+        | TurtleCommand::GoToMark(_)
+        | TurtleCommand::SetShapeNamed(_)
         | TurtleCommand::SetColor(_)
         | TurtleCommand::SetFillColor(_)
         | TurtleCommand::SetPenWidth(_)
         | TurtleCommand::SetSpeed(_)
         | TurtleCommand::SetShape(_)
+        | TurtleCommand::SetEasing(_)
+        | TurtleCommand::SetTurnSpeed(_)
+        | TurtleCommand::SetStyleTransition(_)
+        | TurtleCommand::SetShapeSize(_)
+        | TurtleCommand::Tilt(_)
+        | TurtleCommand::SetTiltAngle(_)
+        | TurtleCommand::SetTurtleColor { .. }
+        | TurtleCommand::SetPenPalette(_)
+        | TurtleCommand::SetBlendMode(_)
+        | TurtleCommand::Annotate(_)
+        | TurtleCommand::BeginStencil(_)
+        | TurtleCommand::EndStencil(_)
+        | TurtleCommand::StampStencil { .. }
+        | TurtleCommand::Barrier(_)
+        | TurtleCommand::Together(_)
         | TurtleCommand::SetHeading(_)
         | TurtleCommand::ShowTurtle
         | TurtleCommand::HideTurtle => false,
@@ -193,6 +372,16 @@ pub fn record_fill_vertices_after_movement(
         TurtleCommand::Move(_) | TurtleCommand::Goto(_) => {
             state.record_fill_vertex();
         }
+        TurtleCommand::Together(members) => {
+            if let Some(fill_state) = &mut state.filling {
+                if state.params.pen_down {
+                    let path = crate::tweening::group_path(start_state, members, 1.0);
+                    fill_state
+                        .current_contour
+                        .extend(path.into_iter().skip(1).map(|(point, _, _)| point));
+                }
+            }
+        }
         _ => {}
     }
 }
@@ -201,6 +390,25 @@ pub fn record_fill_vertices_after_movement(
 #[tracing::instrument]
 #[allow(clippy::too_many_lines)]
 pub fn execute_command(command: &TurtleCommand, state: &mut Turtle) {
+    state.count_command(command);
+    // Stencils replay right away instead of through the animation queue
+    if let TurtleCommand::StampStencil { stencil, transform } = command {
+        for replayed in state.place_stencil(*stencil, *transform) {
+            execute_command(&replayed, state);
+        }
+        return;
+    }
+    // Without `TurtleApp::update()` there is nobody to wait for
+    if let TurtleCommand::Barrier(_) = command {
+        return;
+    }
+    // Convert world-dependent commands (coordinate mode, markers) first
+    let Some(command) = state.resolve_reporting(command.clone()) else {
+        return;
+    };
+    let command = &command;
+    state.apply_pen_palette(command);
+
     // Try to execute as side-effect-only command first
     if execute_command_side_effects(command, state) {
         return; // Command fully handled
@@ -213,38 +421,43 @@ pub fn execute_command(command: &TurtleCommand, state: &mut Turtle) {
     match command {
         TurtleCommand::Move(distance) => {
             let start = state.params.position;
-            let dx = distance * state.params.heading.cos();
-            let dy = distance * state.params.heading.sin();
-            state.params.position =
-                vec2(state.params.position.x + dx, state.params.position.y + dy);
+            let pose = state.params.exact_pose().moved(*distance);
+            state.params.set_exact_pose(pose);
 
             if state.params.pen_down {
                 // Draw line segment with round caps (caps handled by tessellate_stroke)
-                if let Ok(mesh_data) = tessellation::tessellate_stroke(
+                match tessellation::tessellate_stroke(
                     &[start, state.params.position],
                     state.params.color,
                     state.params.pen_width,
                     false, // not closed
                 ) {
-                    state.commands.push(DrawCommand::Mesh {
-                        data: mesh_data,
-                        source: crate::state::TurtleSource {
-                            command: command.clone(),
-                            color: state.params.color,
-                            fill_color: state.params.fill_color.unwrap_or(BLACK),
-                            pen_width: state.params.pen_width,
-                            start_position: start,
-                            end_position: state.params.position,
-                            start_heading: state.params.heading,
-                            contours: None,
-                        },
-                    });
+                    Ok(mesh_data) => {
+                        state.commands.push(DrawCommand::Mesh {
+                            data: mesh_data,
+                            source: crate::state::TurtleSource {
+                                command: command.clone(),
+                                color: state.params.color,
+                                fill_color: state.params.fill_color.unwrap_or(BLACK),
+                                pen_width: state.params.pen_width,
+                                start_position: start,
+                                end_position: state.params.position,
+                                start_heading: state.params.heading,
+                                contours: None,
+                                created_at: state.tween_controller.now(),
+                                blend_mode: state.params.blend_mode,
+                                annotation: state.params.annotation.clone(),
+                            },
+                        });
+                    }
+                    Err(error) => state.report_error(TurtleError::Tessellation(error.to_string())),
                 }
             }
         }
 
         TurtleCommand::Turn(degrees) => {
-            state.params.heading += degrees.to_radians();
+            let pose = state.params.exact_pose().turned(degrees.to_radians());
+            state.params.set_exact_pose(pose);
         }
 
         TurtleCommand::Circle {
@@ -259,7 +472,7 @@ pub fn execute_command(command: &TurtleCommand, state: &mut Turtle) {
 
             if state.params.pen_down {
                 // Use Lyon to tessellate the arc
-                if let Ok(mesh_data) = tessellation::tessellate_arc(
+                match tessellation::tessellate_arc(
                     geom.center,
                     *radius,
                     geom.start_angle_from_center.to_degrees(),
@@ -269,70 +482,112 @@ pub fn execute_command(command: &TurtleCommand, state: &mut Turtle) {
                     *steps,
                     *direction,
                 ) {
-                    state.commands.push(DrawCommand::Mesh {
-                        data: mesh_data,
-                        source: crate::state::TurtleSource {
-                            command: command.clone(),
-                            color: state.params.color,
-                            fill_color: state.params.fill_color.unwrap_or(BLACK),
-                            pen_width: state.params.pen_width,
-                            start_position: state.params.position,
-                            end_position: geom.position_at_angle(angle.to_radians()),
-                            start_heading,
-                            contours: None,
-                        },
-                    });
+                    Ok(mesh_data) => {
+                        state.commands.push(DrawCommand::Mesh {
+                            data: mesh_data,
+                            source: crate::state::TurtleSource {
+                                command: command.clone(),
+                                color: state.params.color,
+                                fill_color: state.params.fill_color.unwrap_or(BLACK),
+                                pen_width: state.params.pen_width,
+                                start_position: state.params.position,
+                                end_position: geom.position_at_angle(angle.to_radians()),
+                                start_heading,
+                                contours: None,
+                                created_at: state.tween_controller.now(),
+                                blend_mode: state.params.blend_mode,
+                                annotation: state.params.annotation.clone(),
+                            },
+                        });
+                    }
+                    Err(error) => state.report_error(TurtleError::Tessellation(error.to_string())),
                 }
             }
 
             // Update turtle position and heading
-            state.params.position = geom.position_at_angle(angle.to_radians());
-            state.params.heading = match direction {
-                CircleDirection::Left => start_heading - angle.to_radians(),
-                CircleDirection::Right => start_heading + angle.to_radians(),
-            };
+            let pose =
+                state
+                    .params
+                    .exact_pose()
+                    .along_circle(*radius, angle.to_radians(), *direction);
+            state.params.set_exact_pose(pose);
         }
 
         TurtleCommand::Goto(coord) => {
             let start = state.params.position;
-            // Flip Y coordinate: turtle graphics uses Y+ = up, but Macroquad uses Y+ = down
-            state.params.position = vec2(coord.x, -coord.y);
+            state.params.position = *coord;
 
             if state.params.pen_down {
                 // Draw line segment with round caps
-                if let Ok(mesh_data) = tessellation::tessellate_stroke(
+                match tessellation::tessellate_stroke(
                     &[start, state.params.position],
                     state.params.color,
                     state.params.pen_width,
                     false, // not closed
                 ) {
-                    state.commands.push(DrawCommand::Mesh {
-                        data: mesh_data,
-                        source: crate::state::TurtleSource {
-                            command: command.clone(),
-                            color: state.params.color,
-                            fill_color: state.params.fill_color.unwrap_or(BLACK),
-                            pen_width: state.params.pen_width,
-                            start_position: start,
-                            end_position: state.params.position,
-                            start_heading: state.params.heading,
-                            contours: None,
-                        },
-                    });
+                    Ok(mesh_data) => {
+                        state.commands.push(DrawCommand::Mesh {
+                            data: mesh_data,
+                            source: crate::state::TurtleSource {
+                                command: command.clone(),
+                                color: state.params.color,
+                                fill_color: state.params.fill_color.unwrap_or(BLACK),
+                                pen_width: state.params.pen_width,
+                                start_position: start,
+                                end_position: state.params.position,
+                                start_heading: state.params.heading,
+                                contours: None,
+                                created_at: state.tween_controller.now(),
+                                blend_mode: state.params.blend_mode,
+                                annotation: state.params.annotation.clone(),
+                            },
+                        });
+                    }
+                    Err(error) => state.report_error(TurtleError::Tessellation(error.to_string())),
                 }
             }
         }
 
         // Appearance commands
-        TurtleCommand::SetColor(color) => state.params.color = *color,
+        TurtleCommand::SetColor(color) => {
+            state.params.color = *color;
+            state.params.pen_palette = None;
+        }
+        TurtleCommand::SetPenPalette(palette) => {
+            state.params.pen_palette = palette.clone().map(|palette| (palette, 0));
+        }
+        TurtleCommand::SetBlendMode(mode) => state.params.blend_mode = *mode,
+        TurtleCommand::Annotate(label) => state.params.annotation = Some(label.clone()),
         TurtleCommand::SetFillColor(color) => state.params.fill_color = *color,
         TurtleCommand::SetPenWidth(width) => state.params.pen_width = *width,
         TurtleCommand::SetSpeed(speed) => state.set_speed(*speed),
         TurtleCommand::SetShape(shape) => state.params.shape = shape.clone(),
+        TurtleCommand::SetEasing(easing) => state.params.easing = *easing,
+        TurtleCommand::SetTurnSpeed(turn_speed) => state.params.turn_speed = *turn_speed,
+        TurtleCommand::SetStyleTransition(seconds) => state.params.style_transition = *seconds,
+        TurtleCommand::SetShapeSize(size) => state.params.shape_size = *size,
+        TurtleCommand::Tilt(angle) => {
+            state.params.tilt = normalize_angle(state.params.tilt + angle.to_radians());
+        }
+        TurtleCommand::SetTiltAngle(angle) => state.params.tilt = normalize_angle(*angle),
+        TurtleCommand::SetTurtleColor { fill, outline } => {
+            state.params.cursor_fill = *fill;
+            state.params.cursor_outline = *outline;
+        }
         TurtleCommand::SetHeading(heading) => state.params.heading = *heading,
         TurtleCommand::ShowTurtle => state.params.visible = true,
         TurtleCommand::HideTurtle => state.params.visible = false,
 
+        TurtleCommand::Together(members) => {
+            let start = state.params.clone();
+            state.params = crate::tweening::group_state_at(&start, members, 1.0);
+            let created_at = state.tween_controller.now();
+            match group_draw_command(command, members, &start, &state.params, created_at) {
+                Ok(drawing) => state.commands.extend(drawing),
+                Err(error) => state.report_error(error),
+            }
+        }
+
         // Reset
         TurtleCommand::Reset => {
             state.reset();
@@ -341,6 +596,19 @@ pub fn execute_command(command: &TurtleCommand, state: &mut Turtle) {
         _ => {} // Already handled by execute_command_side_effects
     }
 
+    // The first stroke drawn after `annotate()` takes the label
+    if start_state.params.pen_down
+        && matches!(
+            command,
+            TurtleCommand::Move(_)
+                | TurtleCommand::Circle { .. }
+                | TurtleCommand::Goto(_)
+                | TurtleCommand::Together(_)
+        )
+    {
+        state.params.annotation = None;
+    }
+
     // Record fill vertices AFTER movement
     record_fill_vertices_after_movement(command, &start_state.params, state);
 }
@@ -355,25 +623,31 @@ pub fn execute_command_with_id(command: &TurtleCommand, turtle_id: usize, world:
         if let Some(turtle_mut) = world.get_turtle_mut(turtle_id) {
             *turtle_mut = state;
         }
+        world.apply_world_requests();
     }
 }
 
 /// Add drawing command for a completed tween
+///
+/// # Errors
+///
+/// Returns an error if the stroke can't be tessellated
 pub fn add_draw_for_completed_tween(
     command: &TurtleCommand,
     start_state: &TurtleParams,
     end_state: &mut TurtleParams,
-) -> Option<DrawCommand> {
+    created_at: f64,
+) -> Result<Option<DrawCommand>, TurtleError> {
     match command {
-        TurtleCommand::Move(_) | TurtleCommand::Goto(_) => {
-            if start_state.pen_down {
-                if let Ok(mesh_data) = tessellation::tessellate_stroke(
-                    &[start_state.position, end_state.position],
-                    start_state.color,
-                    start_state.pen_width,
-                    false,
-                ) {
-                    return Some(DrawCommand::Mesh {
+        TurtleCommand::Move(_) | TurtleCommand::Goto(_) if start_state.pen_down => {
+            match tessellation::tessellate_stroke(
+                &[start_state.position, end_state.position],
+                start_state.color,
+                start_state.pen_width,
+                false,
+            ) {
+                Ok(mesh_data) => {
+                    return Ok(Some(DrawCommand::Mesh {
                         data: mesh_data,
                         source: crate::state::TurtleSource {
                             command: command.clone(),
@@ -384,8 +658,14 @@ pub fn add_draw_for_completed_tween(
                             end_position: end_state.position,
                             start_heading: start_state.heading,
                             contours: None,
+                            created_at,
+                            blend_mode: start_state.blend_mode,
+                            annotation: start_state.annotation.clone(),
                         },
-                    });
+                    }));
+                }
+                Err(error) => {
+                    return Err(TurtleError::Tessellation(error.to_string()));
                 }
             }
         }
@@ -394,25 +674,25 @@ pub fn add_draw_for_completed_tween(
             angle,
             steps,
             direction,
-        } => {
-            if start_state.pen_down {
-                let geom = CircleGeometry::new(
-                    start_state.position,
-                    start_state.heading,
-                    *radius,
-                    *direction,
-                );
-                if let Ok(mesh_data) = tessellation::tessellate_arc(
-                    geom.center,
-                    *radius,
-                    geom.start_angle_from_center.to_degrees(),
-                    *angle,
-                    start_state.color,
-                    start_state.pen_width,
-                    *steps,
-                    *direction,
-                ) {
-                    return Some(DrawCommand::Mesh {
+        } if start_state.pen_down => {
+            let geom = CircleGeometry::new(
+                start_state.position,
+                start_state.heading,
+                *radius,
+                *direction,
+            );
+            match tessellation::tessellate_arc(
+                geom.center,
+                *radius,
+                geom.start_angle_from_center.to_degrees(),
+                *angle,
+                start_state.color,
+                start_state.pen_width,
+                *steps,
+                *direction,
+            ) {
+                Ok(mesh_data) => {
+                    return Ok(Some(DrawCommand::Mesh {
                         data: mesh_data,
                         source: crate::state::TurtleSource {
                             command: command.clone(),
@@ -423,14 +703,76 @@ pub fn add_draw_for_completed_tween(
                             end_position: end_state.position,
                             start_heading: start_state.heading,
                             contours: None,
+                            created_at,
+                            blend_mode: start_state.blend_mode,
+                            annotation: start_state.annotation.clone(),
                         },
-                    });
+                    }));
+                }
+                Err(error) => {
+                    return Err(TurtleError::Tessellation(error.to_string()));
                 }
             }
         }
+        TurtleCommand::Together(members) => {
+            return group_draw_command(command, members, start_state, end_state, created_at);
+        }
         _ => (),
     }
-    None
+    Ok(None)
+}
+
+/// Build the drawing of a completed `Together` group
+///
+/// The combined path is sampled into short segments so that pen width and
+/// color changes within the group show up as a tapered, shaded line. The
+/// sampled points are kept in `TurtleSource::contours` for exporters. Paths
+/// that can't be tessellated or are too long for a single mesh are reported
+/// as `TurtleError::Tessellation`.
+fn group_draw_command(
+    command: &TurtleCommand,
+    members: &[TurtleCommand],
+    start_state: &TurtleParams,
+    end_state: &TurtleParams,
+    created_at: f64,
+) -> Result<Option<DrawCommand>, TurtleError> {
+    if !start_state.pen_down {
+        return Ok(None);
+    }
+
+    let path = crate::tweening::group_path(start_state, members, 1.0);
+    let mut data = crate::state::MeshData {
+        vertices: Vec::new(),
+        indices: Vec::new(),
+    };
+    for segment in path.windows(2) {
+        let ((start, start_width, color), (end, end_width, _)) = (segment[0], segment[1]);
+        let mesh_data = tessellation::tessellate_stroke(
+            &[start, end],
+            color,
+            (start_width + end_width) / 2.0,
+            false,
+        )
+        .map_err(|error| TurtleError::Tessellation(error.to_string()))?;
+        data.append(mesh_data)?;
+    }
+
+    Ok(Some(DrawCommand::Mesh {
+        data,
+        source: crate::state::TurtleSource {
+            command: command.clone(),
+            color: start_state.color,
+            fill_color: start_state.fill_color.unwrap_or(BLACK),
+            pen_width: start_state.pen_width,
+            start_position: start_state.position,
+            end_position: end_state.position,
+            start_heading: start_state.heading,
+            contours: Some(vec![path.into_iter().map(|(point, _, _)| point).collect()]),
+            created_at,
+            blend_mode: start_state.blend_mode,
+            annotation: start_state.annotation.clone(),
+        },
+    }))
 }
 
 #[cfg(test)]
@@ -458,10 +800,30 @@ mod tests {
                 visible: true,
                 shape: TurtleShape::turtle(),
                 speed: AnimationSpeed::Instant(100),
+                easing: crate::tweening::Easing::default(),
+                turn_speed: None,
+                style_transition: 0.0,
+                shape_size: crate::shapes::ShapeSize::default(),
+                tilt: 0.0,
+                cursor_fill: crate::state::DEFAULT_CURSOR_COLOR,
+                cursor_outline: crate::state::DEFAULT_CURSOR_COLOR,
+                pen_palette: None,
+                blend_mode: crate::state::BlendMode::Normal,
+                exact: Default::default(),
+                annotation: None,
             },
             filling: None,
             commands: Vec::new(),
             tween_controller: TweenController::default(),
+            world_requests: Vec::new(),
+            shared: Default::default(),
+            ghosts: Default::default(),
+            executed: Default::default(),
+            errors: Vec::new(),
+            stencil_recordings: Vec::new(),
+            physics: None,
+            follow: None,
+            state_stack: Vec::new(),
         };
 
         // We'll use a dummy world but won't actually call drawing commands
@@ -476,6 +838,12 @@ mod tests {
                 viewport: None,
             },
             background_color: Color::new(1.0, 1.0, 1.0, 1.0),
+            shared: Default::default(),
+            trail_fade: None,
+            ghost_trail: None,
+            background_gradient: None,
+            theme: crate::theme::Theme::standard(),
+            layer: Vec::new(),
         };
         let mut state = world.turtles[0].clone();
 
@@ -532,4 +900,116 @@ mod tests {
             state.params.position.y
         );
     }
+
+    #[test]
+    fn shape_named_uses_registered_shape() {
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        world.turtles[0].tween_controller.set_fixed_timestep(true);
+        world.register_shape("dot", vec![vec2(1.0, 0.0), vec2(0.0, 1.0), vec2(-1.0, 0.0)]);
+
+        execute_command_with_id(&TurtleCommand::SetShapeNamed("dot".into()), 0, &mut world);
+        assert_eq!(world.turtles[0].params.shape.vertices.len(), 3);
+
+        // Unknown names keep the current shape
+        execute_command_with_id(
+            &TurtleCommand::SetShapeNamed("missing".into()),
+            0,
+            &mut world,
+        );
+        assert_eq!(world.turtles[0].params.shape.vertices.len(), 3);
+    }
+
+    #[test]
+    fn stamps_can_be_cleared_by_id_and_count() {
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        world.turtles[0].tween_controller.set_fixed_timestep(true);
+
+        let ids: Vec<_> = (0..4).map(|_| crate::commands::StampId::next()).collect();
+        for id in &ids {
+            execute_command_with_id(&TurtleCommand::Stamp(*id), 0, &mut world);
+            execute_command_with_id(&TurtleCommand::Move(20.0), 0, &mut world);
+        }
+        assert_eq!(world.turtles[0].stamps().count(), 4);
+
+        assert!(world.clear_stamp(ids[1]));
+        assert!(!world.clear_stamp(ids[1]));
+        execute_command_with_id(&TurtleCommand::ClearStamps(Some(-1)), 0, &mut world);
+        let left: Vec<_> = world.turtles[0].stamps().collect();
+        assert_eq!(left, vec![ids[0], ids[2]]);
+
+        // Lines are not stamps
+        execute_command_with_id(&TurtleCommand::ClearStamps(None), 0, &mut world);
+        assert_eq!(world.turtles[0].stamps().count(), 0);
+        assert_eq!(world.turtles[0].commands.len(), 4);
+    }
+
+    #[test]
+    fn exact_pose_restarts_after_direct_changes() {
+        let mut state = Turtle::default();
+        state.tween_controller.set_fixed_timestep(true);
+        for command in [
+            TurtleCommand::PenUp,
+            TurtleCommand::Move(10.0),
+            TurtleCommand::Turn(90.0),
+        ] {
+            execute_command(&command, &mut state);
+        }
+        let pose = state.params.exact_pose();
+        assert_eq!((pose.x, pose.y), (10.0, 0.0));
+        assert_eq!(pose, state.params.exact);
+
+        state.params.position = vec2(3.0, 4.0);
+        let pose = state.params.exact_pose();
+        assert_eq!((pose.x, pose.y), (3.0, 4.0));
+    }
+
+    #[test]
+    fn annotation_goes_to_next_stroke() {
+        let mut state = Turtle::default();
+        state.tween_controller.set_fixed_timestep(true);
+        for command in [
+            TurtleCommand::Annotate("a".to_string()),
+            TurtleCommand::PenUp,
+            TurtleCommand::Move(10.0),
+            TurtleCommand::PenDown,
+            TurtleCommand::Move(10.0),
+            TurtleCommand::Move(10.0),
+        ] {
+            execute_command(&command, &mut state);
+        }
+        let labels: Vec<_> = state
+            .commands
+            .iter()
+            .map(|command| command.source().annotation.as_deref())
+            .collect();
+        assert_eq!(labels, vec![Some("a"), None]);
+        assert_eq!(state.params.annotation, None);
+    }
+
+    #[test]
+    fn new_turtles_execute_without_a_window() {
+        // No `set_fixed_timestep()`, new turtles never read the wall clock
+        let mut state = Turtle::default();
+        execute_command(&TurtleCommand::Move(10.0), &mut state);
+        assert!(state.tween_controller.is_fixed_timestep());
+        assert_eq!(state.commands.len(), 1);
+        assert!(state.commands[0].source().created_at.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn misuse_is_reported() {
+        let mut state = Turtle::default();
+        state.params.pen_down = false;
+        execute_command(&TurtleCommand::EndFill, &mut state);
+        execute_command(&TurtleCommand::GoToMark("nowhere".to_string()), &mut state);
+        assert_eq!(
+            state.errors,
+            vec![
+                TurtleError::EndFillWithoutBeginFill,
+                TurtleError::UnknownMark("nowhere".to_string())
+            ]
+        );
+    }
 }
@@ -7,6 +7,9 @@ use crate::TurtlePlan;
 pub enum ExportError {
     Io(std::io::Error),
     Format(String),
+    /// The system clipboard is unavailable or refused the data
+    #[cfg(feature = "clipboard")]
+    Clipboard(String),
     // Weitere Formate können ergänzt werden
 }
 
@@ -42,26 +45,33 @@ pub fn parse_svg_export_arg() -> Option<String> {
 ///
 /// The feature gating lives inside `turtle-lib`, so the `turtle_main` macro
 /// no longer needs to reference cfg flags from the consuming crate.
-pub fn handle_svg_export<F>(build_commands: F)
+pub fn handle_svg_export<F>(mut build_commands: F)
 where
     F: FnMut(&mut TurtlePlan),
+{
+    handle_svg_export_app(|app: &mut crate::TurtleApp| {
+        let mut turtle = crate::create_turtle_plan();
+        build_commands(&mut turtle);
+        app.append_to_queue(0, turtle);
+    });
+}
+
+/// Handle the optional `--export-svg` CLI flag for programs that set up a
+/// whole `TurtleApp` (for example with several turtles).
+pub fn handle_svg_export_app<F>(build_app: F)
+where
+    F: FnMut(&mut crate::TurtleApp),
 {
     // Avoid unused warnings when the feature is disabled
-    let _ = &build_commands;
+    let _ = &build_app;
 
     if let Some(filename) = parse_svg_export_arg() {
         #[cfg(feature = "svg")]
         {
-            let mut build_commands = build_commands;
-            let mut turtle = crate::create_turtle_plan();
-            build_commands(&mut turtle);
-
-            let mut app = crate::TurtleApp::new().with_commands(turtle.build());
-            app.set_all_turtles_speed(crate::AnimationSpeed::Instant(1000));
-
-            while !app.all_animations_complete() {
-                app.update();
-            }
+            let mut build_app = build_app;
+            let mut app = crate::TurtleApp::new();
+            build_app(&mut app);
+            app.finish_all();
 
             match app.export_drawing(&filename, crate::export::DrawingFormat::Svg) {
                 Ok(_) => {
@@ -84,3 +94,140 @@ where
         }
     }
 }
+
+/// Export the drawing of `app` as SVG, if the `svg` feature is enabled
+///
+/// # Errors
+///
+/// Returns an error if the export fails or the feature is disabled
+pub fn export_svg(app: &crate::TurtleApp, filename: &str) -> Result<(), ExportError> {
+    #[cfg(feature = "svg")]
+    {
+        app.export_drawing(filename, DrawingFormat::Svg)
+    }
+
+    #[cfg(not(feature = "svg"))]
+    {
+        let _ = (app, filename);
+        Err(ExportError::Format(
+            "SVG export feature is not enabled, rebuild with --features svg".to_string(),
+        ))
+    }
+}
+
+/// Files written automatically once the drawing is finished
+///
+/// Used by the `turtle_main` macro for its `export` and `screenshot` options.
+/// The files are written as soon as all animations are complete, or when the
+/// window closes (after completing the drawing instantly).
+#[derive(Clone, Debug, Default)]
+pub struct AutoExport {
+    svg: Option<String>,
+    screenshot: Option<String>,
+    done: bool,
+}
+
+impl AutoExport {
+    #[must_use]
+    pub fn new(svg: Option<&str>, screenshot: Option<&str>) -> Self {
+        Self {
+            svg: svg.map(str::to_string),
+            screenshot: screenshot.map(str::to_string),
+            done: false,
+        }
+    }
+
+    /// Call after `TurtleApp::render()`: exports once the drawing is complete
+    ///
+    /// Call before drawing any overlay that should not end up in the screenshot.
+    pub fn after_render(&mut self, app: &crate::TurtleApp) {
+        if !self.done && app.is_complete() {
+            self.write(app);
+        }
+    }
+
+    /// Call before closing the window: completes the drawing and exports it,
+    /// unless that already happened
+    pub fn before_exit(&mut self, app: &mut crate::TurtleApp, background: macroquad::color::Color) {
+        if self.done {
+            return;
+        }
+        app.finish_all();
+        if self.screenshot.is_some() {
+            macroquad::prelude::clear_background(background);
+            app.render();
+        }
+        self.write(app);
+    }
+
+    fn write(&mut self, app: &crate::TurtleApp) {
+        self.done = true;
+
+        if let Some(filename) = &self.svg {
+            match export_svg(app, filename) {
+                Ok(()) => println!("SVG exported successfully to: {}", filename),
+                Err(e) => eprintln!("Error exporting SVG: {:?}", e),
+            }
+        }
+
+        if let Some(filename) = &self.screenshot {
+            match app.save_screenshot(filename) {
+                Ok(()) => println!("Screenshot saved to: {}", filename),
+                Err(e) => eprintln!("Error saving screenshot: {:?}", e),
+            }
+        }
+    }
+}
+
+/// Numbered PNG frames written while an animation plays
+///
+/// See `TurtleApp::start_frame_capture()`. Frames are named
+/// `frame_00001.png`, `frame_00002.png`, ... so they can be assembled into a
+/// video, e.g. with `ffmpeg -framerate 30 -i frame_%05d.png out.mp4`.
+#[derive(Clone, Debug)]
+pub struct FrameCapture {
+    dir: std::path::PathBuf,
+    fps: f64,
+    frames: usize,
+}
+
+impl FrameCapture {
+    /// Capture into `dir` (created if missing) at `fps` frames per second
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created
+    pub fn new(dir: impl Into<std::path::PathBuf>, fps: f64) -> Result<Self, ExportError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(ExportError::Io)?;
+        Ok(Self {
+            dir,
+            fps: fps.max(1.0),
+            frames: 0,
+        })
+    }
+
+    /// Simulated time between two frames in seconds
+    #[must_use]
+    pub fn frame_time(&self) -> f64 {
+        1.0 / self.fps
+    }
+
+    /// Number of frames written so far
+    #[must_use]
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Write the current window contents as the next frame
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame cannot be written, it isn't counted
+    pub fn write_frame(&mut self, app: &crate::TurtleApp) -> Result<(), ExportError> {
+        let path = self.dir.join(format!("frame_{:05}.png", self.frames + 1));
+        app.save_screenshot(&path.to_string_lossy())?;
+        self.frames += 1;
+        Ok(())
+    }
+}
@@ -4,10 +4,16 @@
 pub mod svg_export {
     use crate::commands::TurtleCommand;
     use crate::export::{DrawingExporter, ExportError};
+    use crate::general::CoordinateMode;
     use crate::state::{DrawCommand, TurtleWorld};
+    use macroquad::prelude::{vec2, Vec2};
+    #[cfg(not(target_arch = "wasm32"))]
     use std::fs::File;
     use svg::{
-        node::element::{Circle, Line, Polygon, Text as SvgText},
+        node::element::{
+            Circle, Definitions, Group, Line, LinearGradient, Polygon, Rectangle, Stop,
+            Text as SvgText, Title,
+        },
         Document,
     };
 
@@ -15,7 +21,44 @@ pub mod svg_export {
 
     impl DrawingExporter for SvgExporter {
         fn export(&self, world: &TurtleWorld, filename: &str) -> Result<(), ExportError> {
-            let mut doc = Document::new();
+            let document = Self::document(world);
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let mut file = File::create(filename).map_err(ExportError::Io)?;
+                svg::write(&mut file, &document).map_err(ExportError::Io)?;
+            }
+            // No file system in the browser, see `web`
+            #[cfg(target_arch = "wasm32")]
+            crate::web::download(filename, "image/svg+xml", document.to_string().as_bytes());
+            Ok(())
+        }
+    }
+
+    impl SvgExporter {
+        /// The drawings of `world` as SVG document, e.g. to write it somewhere
+        /// else than a file
+        ///
+        /// Coordinates are written in the world's `CoordinateMode`, like
+        /// `go_to()` and all position queries. For `CoordinateMode::MathYUp`
+        /// the drawing is wrapped in a `scale(1,-1)` group, so the image
+        /// still looks like the window.
+        #[must_use]
+        pub fn document(world: &TurtleWorld) -> Document {
+            // Drawing elements, placed into the document (above an optional
+            // background) once the bounds are known
+            let mut doc = Group::new();
+
+            // Bounds are collected in screen space, which the viewBox uses
+            let mode = world.coordinate_mode();
+            let flip = mode == CoordinateMode::MathYUp;
+            // Adding zero turns the `-0` of flipped zeros into `0`
+            let at = |point: Vec2| mode.from_screen(point) + Vec2::ZERO;
+            // Keeps text upright inside the flipped group
+            let text_transform = |point: Vec2| {
+                let point = at(point);
+                let flip = if flip { " scale(1,-1)" } else { "" };
+                format!("translate({} {}){flip}", point.x, point.y)
+            };
 
             let mut min_x = f32::INFINITY;
             let mut max_x = f32::NEG_INFINITY;
@@ -36,10 +79,17 @@ pub mod svg_export {
                 *max_y = max_y.max(y);
             }
 
-            for turtle in &world.turtles {
-                for cmd in &turtle.commands {
+            let living = world.turtles.iter().map(|turtle| &turtle.commands);
+            for commands in std::iter::once(&world.layer).chain(living) {
+                for cmd in commands {
                     match cmd {
                         DrawCommand::Mesh { source, .. } => {
+                            // Annotated strokes are collected in a group of
+                            // their own, which gets the label as its title
+                            let outer = source
+                                .annotation
+                                .as_ref()
+                                .map(|_| std::mem::replace(&mut doc, Group::new()));
                             match &source.command {
                                 TurtleCommand::Move(_) | TurtleCommand::Goto(_) => {
                                     // Linie als <line>
@@ -53,6 +103,7 @@ pub mod svg_export {
                                         &mut min_x, &mut max_x, &mut min_y, &mut max_y, end.x,
                                         end.y,
                                     );
+                                    let (start, end) = (at(start), at(end));
                                     let line = Line::new()
                                         .set("x1", start.x)
                                         .set("y1", start.y)
@@ -94,6 +145,7 @@ pub mod svg_export {
                                             center.x + radius,
                                             center.y + radius,
                                         );
+                                        let center = at(center);
                                         let circle = Circle::new()
                                             .set("cx", center.x)
                                             .set("cy", center.y)
@@ -128,6 +180,9 @@ pub mod svg_export {
                                             crate::circle_geometry::CircleDirection::Left => 0,
                                             crate::circle_geometry::CircleDirection::Right => 1,
                                         };
+                                        // Mirrored coordinates turn the other way
+                                        let sweep = if flip { 1 - sweep } else { sweep };
+                                        let (start, end) = (at(start), at(end));
                                         let d = format!(
                                             "M {} {} A {} {} 0 {} {} {} {}",
                                             start.x,
@@ -147,6 +202,28 @@ pub mod svg_export {
                                         doc = doc.add(path);
                                     }
                                 }
+                                TurtleCommand::Together(_) | TurtleCommand::OffsetLastPath(_) => {
+                                    // Gruppen als offener <path> entlang der abgetasteten Punkte
+                                    let points = source.contours.iter().flatten().flatten();
+                                    let mut d = String::new();
+                                    for (i, point) in points.enumerate() {
+                                        update_bounds(
+                                            &mut min_x, &mut max_x, &mut min_y, &mut max_y,
+                                            point.x, point.y,
+                                        );
+                                        let op = if i == 0 { "M" } else { " L" };
+                                        let point = at(*point);
+                                        d.push_str(&format!("{} {} {}", op, point.x, point.y));
+                                    }
+                                    if !d.is_empty() {
+                                        let path = svg::node::element::Path::new()
+                                            .set("d", d)
+                                            .set("stroke", color_to_svg(source.color))
+                                            .set("stroke-width", source.pen_width)
+                                            .set("fill", "none");
+                                        doc = doc.add(path);
+                                    }
+                                }
                                 TurtleCommand::EndFill => {
                                     // Fills werden als <path> mit Konturen ausgegeben
                                     if let Some(contours) = &source.contours {
@@ -164,11 +241,10 @@ pub mod svg_export {
                                                 if i > 0 {
                                                     d.push(' ');
                                                 }
-                                                d.push_str(&format!(
-                                                    "M {} {}",
-                                                    contour[0].x, contour[0].y
-                                                ));
+                                                let first = at(contour[0]);
+                                                d.push_str(&format!("M {} {}", first.x, first.y));
                                                 for point in contour.iter().skip(1) {
+                                                    let point = at(*point);
                                                     d.push_str(&format!(
                                                         " L {} {}",
                                                         point.x, point.y
@@ -211,26 +287,64 @@ pub mod svg_export {
                                             source.start_position.x + 5.0,
                                             source.start_position.y + 15.0,
                                         );
+                                        let start = source.start_position;
+                                        let points = [
+                                            start,
+                                            start + vec2(10.0, 10.0),
+                                            start + vec2(5.0, 15.0),
+                                        ]
+                                        .map(|point| {
+                                            let point = at(point);
+                                            format!("{},{}", point.x, point.y)
+                                        });
                                         let poly = Polygon::new()
-                                            .set(
-                                                "points",
-                                                format!(
-                                                    "{},{} {},{} {},{}",
-                                                    source.start_position.x,
-                                                    source.start_position.y,
-                                                    source.start_position.x + 10.0,
-                                                    source.start_position.y + 10.0,
-                                                    source.start_position.x + 5.0,
-                                                    source.start_position.y + 15.0
-                                                ),
-                                            )
+                                            .set("points", points.join(" "))
                                             .set("fill", color_to_svg(source.fill_color))
                                             .set("stroke", color_to_svg(source.color));
                                         doc = doc.add(poly);
                                     }
                                 }
+                                TurtleCommand::Stamp(_) => {
+                                    // Stamps als <polygon> des Cursors
+                                    let vertices = source.contours.iter().flatten().flatten();
+                                    let mut points = Vec::new();
+                                    for vertex in vertices {
+                                        update_bounds(
+                                            &mut min_x, &mut max_x, &mut min_y, &mut max_y,
+                                            vertex.x, vertex.y,
+                                        );
+                                        let vertex = at(*vertex);
+                                        points.push(format!("{},{}", vertex.x, vertex.y));
+                                    }
+                                    let poly = Polygon::new()
+                                        .set("points", points.join(" "))
+                                        .set("fill", color_to_svg(source.fill_color))
+                                        .set("stroke", color_to_svg(source.color))
+                                        .set("stroke-width", source.pen_width);
+                                    doc = doc.add(poly);
+                                }
                                 _ => {}
                             }
+                            if let (Some(label), Some(outer)) = (&source.annotation, outer) {
+                                let position = crate::drawing::annotation_position(source, 0.0);
+                                update_bounds(
+                                    &mut min_x, &mut max_x, &mut min_y, &mut max_y, position.x,
+                                    position.y,
+                                );
+                                let stroke = std::mem::replace(&mut doc, outer);
+                                let txt = SvgText::new()
+                                    .set("transform", text_transform(position))
+                                    .set("text-anchor", "middle")
+                                    .set("font-size", crate::drawing::ANNOTATION_FONT_SIZE)
+                                    .set("fill", color_to_svg(source.color))
+                                    .add(svg::node::Text::new(label.clone()));
+                                doc = doc.add(
+                                    Group::new()
+                                        .add(Title::new().add(svg::node::Text::new(label.clone())))
+                                        .add(stroke)
+                                        .add(txt),
+                                );
+                            }
                         }
                         DrawCommand::Text {
                             text,
@@ -243,8 +357,7 @@ pub mod svg_export {
                                 position.y,
                             );
                             let txt = SvgText::new()
-                                .set("x", position.x)
-                                .set("y", position.y)
+                                .set("transform", text_transform(*position))
                                 .set("fill", color_to_svg(source.color))
                                 .add(svg::node::Text::new(text.clone()));
                             doc = doc.add(txt);
@@ -253,20 +366,85 @@ pub mod svg_export {
                 }
             }
 
-            // Set viewBox with 20px padding
-            if min_x.is_finite() && max_x.is_finite() && min_y.is_finite() && max_y.is_finite() {
-                let width = (max_x - min_x) + 40.0;
-                let height = (max_y - min_y) + 40.0;
-                let view_box = format!("{} {} {} {}", min_x - 20.0, min_y - 20.0, width, height);
-                doc = doc.set("viewBox", view_box);
-            } else {
-                // Default viewBox if no elements
-                doc = doc.set("viewBox", "0 0 400 400");
+            // Visible turtle cursors on top of the drawing
+            for turtle in world.turtles.iter().filter(|turtle| turtle.params.visible) {
+                let params = &turtle.params;
+                let vertices = params.cursor_vertices();
+                for vertex in &vertices {
+                    update_bounds(
+                        &mut min_x, &mut max_x, &mut min_y, &mut max_y, vertex.x, vertex.y,
+                    );
+                }
+                let points = vertices
+                    .iter()
+                    .map(|vertex| {
+                        let vertex = at(*vertex);
+                        format!("{},{}", vertex.x, vertex.y)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let fill = if params.shape.filled {
+                    color_to_svg(params.cursor_fill)
+                } else {
+                    "none".to_string()
+                };
+                let cursor = Polygon::new()
+                    .set("points", points)
+                    .set("fill", fill)
+                    .set("stroke", color_to_svg(params.cursor_outline))
+                    .set("stroke-width", params.shape_size.outline);
+                doc = doc.add(cursor);
             }
 
-            let mut file = File::create(filename).map_err(ExportError::Io)?;
-            svg::write(&mut file, &doc).map_err(ExportError::Io)?;
-            Ok(())
+            // Set viewBox with 20px padding
+            let (x, y, width, height) =
+                if min_x.is_finite() && max_x.is_finite() && min_y.is_finite() && max_y.is_finite()
+                {
+                    (
+                        min_x - 20.0,
+                        min_y - 20.0,
+                        (max_x - min_x) + 40.0,
+                        (max_y - min_y) + 40.0,
+                    )
+                } else {
+                    // Default viewBox if no elements
+                    (0.0, 0.0, 400.0, 400.0)
+                };
+            let mut document =
+                Document::new().set("viewBox", format!("{} {} {} {}", x, y, width, height));
+
+            // Hintergrundverlauf als Rechteck über die ganze viewBox
+            if let Some((top, bottom)) = world.background_gradient {
+                let gradient = LinearGradient::new()
+                    .set("id", "background")
+                    .set("x1", 0)
+                    .set("y1", 0)
+                    .set("x2", 0)
+                    .set("y2", 1)
+                    .add(
+                        Stop::new()
+                            .set("offset", 0)
+                            .set("stop-color", color_to_svg(top)),
+                    )
+                    .add(
+                        Stop::new()
+                            .set("offset", 1)
+                            .set("stop-color", color_to_svg(bottom)),
+                    );
+                let background = Rectangle::new()
+                    .set("x", x)
+                    .set("y", y)
+                    .set("width", width)
+                    .set("height", height)
+                    .set("fill", "url(#background)");
+                document = document
+                    .add(Definitions::new().add(gradient))
+                    .add(background);
+            }
+            if flip {
+                doc = doc.set("transform", "scale(1,-1)");
+            }
+            document.add(doc)
         }
     }
 
@@ -280,4 +458,33 @@ pub mod svg_export {
             format!("rgb({},{},{})", r, g, b)
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::builders::TurtlePlan;
+        use crate::headless;
+
+        #[test]
+        fn coordinates_follow_the_coordinate_mode() {
+            let mut plan = TurtlePlan::new();
+            plan.go_to(vec2(30.0, 100.0)).hide();
+            let svg = |mode| {
+                let mut world = TurtleWorld::headless();
+                world.set_coordinate_mode(mode);
+                headless::execute(&mut world, 0, &plan.clone().build());
+                SvgExporter::document(&world).to_string()
+            };
+
+            // Y-up values, flipped back for display
+            let math = svg(CoordinateMode::MathYUp);
+            assert!(math.contains(r#"x2="30""#) && math.contains(r#"y2="100""#));
+            assert!(math.contains(r#"y1="0""#), "{math}");
+            assert!(math.contains(r#"transform="scale(1,-1)""#));
+
+            let screen = svg(CoordinateMode::ScreenYDown);
+            assert!(screen.contains(r#"y2="100""#), "{screen}");
+            assert!(!screen.contains("scale(1,-1)"));
+        }
+    }
 }
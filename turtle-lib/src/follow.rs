@@ -0,0 +1,139 @@
+//! Follow-the-leader: turtles trailing along another turtle's path
+//!
+//! After `TurtleApp::set_follow(follower, leader, distance)` the follower
+//! walks the path the leader took, always `distance` pixels behind it along
+//! that path, and draws it again while its pen is down. Followers can be
+//! followed themselves, which makes snakes and parades:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//!
+//! #[macroquad::main("Snake")]
+//! async fn main() {
+//!     let mut head = create_turtle_plan();
+//!     head.set_speed(150.0).pen_up();
+//!     for _ in 0..8 {
+//!         head.circle_left(80.0, 180.0, 24).circle_right(80.0, 180.0, 24);
+//!     }
+//!
+//!     let mut app = TurtleApp::new().with_commands(head.build());
+//!     for segment in 1..6 {
+//!         app.add_turtle();
+//!         app.set_follow(segment, segment - 1, 25.0);
+//!     }
+//!     app.run(RunConfig::new()).await;
+//! }
+//! ```
+//!
+//! Followers start from where they are when `set_follow()` is called and
+//! wait there until the leader is `distance` ahead. They only move once their
+//! own queue is done and stand still with `pause()`.
+
+use crate::commands::TurtleCommand;
+use crate::execution::execute_command;
+use crate::state::TurtleWorld;
+use macroquad::prelude::Vec2;
+use std::collections::VecDeque;
+
+/// Who a turtle follows and how far behind, see `Turtle::follow`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Follow {
+    /// ID of the turtle to follow
+    pub leader: usize,
+    /// Distance in pixels along the leader's path
+    pub distance: f32,
+    // Leader positions the follower hasn't passed yet, oldest first, in
+    // screen space
+    trail: VecDeque<Vec2>,
+}
+
+impl Follow {
+    /// Follow `leader` starting at `start` (screen space)
+    #[must_use]
+    pub fn new(leader: usize, distance: f32, start: Vec2) -> Self {
+        Self {
+            leader,
+            distance: distance.max(0.0),
+            trail: VecDeque::from([start]),
+        }
+    }
+
+    /// Record the leader at `position` and return where the follower belongs,
+    /// `None` while the trail is shorter than `distance`
+    fn advance(&mut self, position: Vec2) -> Option<Vec2> {
+        if self.trail.back() != Some(&position) {
+            self.trail.push_back(position);
+        }
+        let mut remaining = self.distance;
+        for index in (1..self.trail.len()).rev() {
+            let (from, to) = (self.trail[index - 1], self.trail[index]);
+            let length = from.distance(to);
+            if remaining <= length {
+                // Points further back are passed for good
+                self.trail.drain(..index - 1);
+                return Some(to.lerp(from, remaining / length));
+            }
+            remaining -= length;
+        }
+        None
+    }
+}
+
+/// Move every follower to its place behind its leader
+///
+/// Runs after all turtles were updated, so followers use this frame's leader
+/// positions when they come later in the turtle list.
+pub(crate) fn step(world: &mut TurtleWorld) {
+    for index in 0..world.turtles.len() {
+        let Some(leader) = world.turtles[index].follow.as_ref().map(|f| f.leader) else {
+            continue;
+        };
+        let Some(position) = world.get_turtle(leader).map(|t| t.params.position) else {
+            continue;
+        };
+        let turtle = &mut world.turtles[index];
+        let Some(target) = turtle.follow.as_mut().and_then(|f| f.advance(position)) else {
+            continue;
+        };
+        if !turtle.tween_controller.is_complete() || target == turtle.params.position {
+            continue;
+        }
+        let direction = target - turtle.params.position;
+        let mode = turtle.coordinate_mode();
+        execute_command(&TurtleCommand::Goto(mode.from_screen(target)), turtle);
+        turtle.params.heading = direction.y.atan2(direction.x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::prelude::vec2;
+
+    #[test]
+    fn follower_keeps_its_distance_along_the_path() {
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        world.add_turtle();
+        world.turtles[1].follow = Some(Follow::new(0, 15.0, Vec2::ZERO));
+
+        let mut positions = Vec::new();
+        for command in [
+            TurtleCommand::Move(10.0),
+            TurtleCommand::Move(10.0),
+            TurtleCommand::Turn(90.0),
+            TurtleCommand::Move(10.0),
+        ] {
+            execute_command(&command, &mut world.turtles[0]);
+            step(&mut world);
+            positions.push(world.turtles[1].params.position);
+        }
+
+        // Waits until the leader is far enough ahead, then cuts no corners
+        assert_eq!(positions[0], Vec2::ZERO);
+        assert!(positions[1].distance(vec2(5.0, 0.0)) < 1e-4);
+        assert_eq!(positions[2], positions[1]);
+        assert!(positions[3].distance(vec2(15.0, 0.0)) < 1e-4);
+        assert_eq!(world.turtles[1].commands.len(), 2);
+    }
+}
@@ -0,0 +1,182 @@
+//! Formations: places for the members of a `TurtleGroup`
+//!
+//! A `Formation` is a layout (line, circle or grid) with a center and a
+//! heading. `TurtleGroup::arrange()` sends every member to its place, and
+//! `translate()` and `rotate()` move the whole formation with one call:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//!
+//! #[macroquad::main("Formation")]
+//! async fn main() {
+//!     let mut app = TurtleApp::new();
+//!     let squad = TurtleGroup::spawn(&mut app, 9);
+//!     let mut formation = Formation::grid(3, 40.0).with_heading(90.0);
+//!
+//!     squad.send(&mut app, |_, plan| {
+//!         plan.pen_up();
+//!     });
+//!     squad.arrange(&mut app, &formation);
+//!     squad.send(&mut app, |_, plan| {
+//!         plan.pen_down();
+//!     });
+//!     squad.translate(&mut app, &mut formation, vec2(0.0, 150.0));
+//!     squad.rotate(&mut app, &mut formation, -90.0);
+//!     squad.translate(&mut app, &mut formation, vec2(150.0, 0.0));
+//!     app.run(RunConfig::new()).await;
+//! }
+//! ```
+//!
+//! Positions are in the world's `CoordinateMode` and headings in degrees like
+//! `TurtlePlan::set_heading()`. The first member takes the front left place.
+
+use crate::general::{Coordinate, Precision};
+use macroquad::prelude::vec2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Layout {
+    Line { spacing: Precision },
+    Circle { radius: Precision },
+    Grid { columns: usize, spacing: Precision },
+}
+
+/// Arrangement of a group of turtles, see the module documentation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Formation {
+    layout: Layout,
+    center: Coordinate,
+    heading: Precision,
+}
+
+impl Formation {
+    /// Side by side, `spacing` pixels apart, across the heading
+    #[must_use]
+    pub fn line(spacing: Precision) -> Self {
+        Self::new(Layout::Line { spacing })
+    }
+
+    /// Evenly around a circle, the first member in front of the center
+    #[must_use]
+    pub fn circle(radius: Precision) -> Self {
+        Self::new(Layout::Circle { radius })
+    }
+
+    /// Rows of `columns` members, `spacing` pixels apart in both directions
+    #[must_use]
+    pub fn grid(columns: usize, spacing: Precision) -> Self {
+        Self::new(Layout::Grid {
+            columns: columns.max(1),
+            spacing,
+        })
+    }
+
+    fn new(layout: Layout) -> Self {
+        Self {
+            layout,
+            center: Coordinate::ZERO,
+            heading: 0.0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_center(mut self, center: impl Into<Coordinate>) -> Self {
+        self.center = center.into();
+        self
+    }
+
+    /// Direction the formation and its members face, in degrees
+    #[must_use]
+    pub fn with_heading(mut self, heading: Precision) -> Self {
+        self.heading = heading;
+        self
+    }
+
+    #[must_use]
+    pub fn center(&self) -> Coordinate {
+        self.center
+    }
+
+    /// Heading in degrees
+    #[must_use]
+    pub fn heading(&self) -> Precision {
+        self.heading
+    }
+
+    /// Move the formation by `offset`
+    pub fn translate(&mut self, offset: impl Into<Coordinate>) {
+        self.center += offset.into();
+    }
+
+    /// Turn the formation around its center, positive degrees turn left
+    pub fn rotate(&mut self, degrees: Precision) {
+        self.heading += degrees;
+    }
+
+    /// Places of `count` members, in member order
+    #[must_use]
+    pub fn positions(&self, count: usize) -> Vec<Coordinate> {
+        let forward = Coordinate::from_angle(self.heading.to_radians());
+        let left = forward.perp();
+        // Offsets centered around zero for `n` places `spacing` apart
+        let centered = |index: usize, n: usize, spacing: Precision| {
+            ((n as Precision - 1.0) / 2.0 - index as Precision) * spacing
+        };
+        (0..count)
+            .map(|index| {
+                let local = match self.layout {
+                    Layout::Line { spacing } => vec2(0.0, centered(index, count, spacing)),
+                    Layout::Circle { radius } => {
+                        let angle = std::f32::consts::TAU * index as Precision / count as Precision;
+                        Coordinate::from_angle(angle) * radius
+                    }
+                    Layout::Grid { columns, spacing } => {
+                        let rows = count.div_ceil(columns);
+                        vec2(
+                            centered(index / columns, rows, spacing),
+                            centered(index % columns, columns, spacing),
+                        )
+                    }
+                };
+                self.center + forward * local.x + left * local.y
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_follow_the_formation() {
+        let close = |a: &[Coordinate], b: &[Coordinate]| {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.distance(*b) < 1e-4)
+        };
+
+        let line = Formation::line(10.0);
+        assert!(close(
+            &line.positions(3),
+            &[vec2(0.0, 10.0), vec2(0.0, 0.0), vec2(0.0, -10.0)]
+        ));
+
+        // Two rows of two, facing up and moved right
+        let mut grid = Formation::grid(2, 10.0).with_heading(90.0);
+        grid.translate(vec2(100.0, 0.0));
+        assert!(close(
+            &grid.positions(4),
+            &[
+                vec2(95.0, 5.0),
+                vec2(105.0, 5.0),
+                vec2(95.0, -5.0),
+                vec2(105.0, -5.0)
+            ]
+        ));
+
+        let mut circle = Formation::circle(20.0);
+        circle.rotate(90.0);
+        assert!(close(
+            &circle.positions(2),
+            &[vec2(0.0, 20.0), vec2(0.0, -20.0)]
+        ));
+    }
+}
@@ -3,16 +3,40 @@
 use macroquad::prelude::*;
 
 pub mod angle;
+pub mod bounds;
+pub mod color;
+pub mod coordinate_mode;
 pub mod fontsize;
 pub mod length;
 
 pub use angle::Angle;
+pub use bounds::Bounds;
+pub use color::{color, color_from_hex, mix_colors, parse_color, ColorExt};
+pub use coordinate_mode::CoordinateMode;
 pub use fontsize::FontSize;
 pub use length::Length;
 
 /// Precision type for calculations
 pub type Precision = f32;
 
+/// Precision of the turtle position and heading between commands, `f64`
+/// with the `f64` feature
+///
+/// Long drawings made of many relative moves accumulate rounding errors in
+/// `f32`. The exact pose (see `TurtleParams::exact_pose()`) keeps the
+/// position and heading at this precision and only rounds them to `f32` for
+/// rendering.
+#[cfg(feature = "f64")]
+pub type StatePrecision = f64;
+#[cfg(not(feature = "f64"))]
+pub type StatePrecision = f32;
+
+/// Round a state value for rendering
+#[allow(clippy::unnecessary_cast)]
+pub(crate) fn state_to_f32(value: StatePrecision) -> f32 {
+    value as f32
+}
+
 /// 2D coordinate in screen space
 pub type Coordinate = Vec2;
 
@@ -23,6 +47,7 @@ pub type Visibility = bool;
 /// - `Instant(draw_calls)`: Fast execution with limited draw calls per frame (speed - 1000, minimum 1)
 /// - `Animated(speed)`: Smooth animation at specified pixels/second
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AnimationSpeed {
     Instant(u32),  // Number of draw calls per frame (minimum 1)
     Animated(f32), // pixels per second
@@ -28,6 +28,18 @@ impl Default for Angle {
     }
 }
 
+impl From<i8> for Angle {
+    fn from(i: i8) -> Self {
+        Self::degrees(Precision::from(i))
+    }
+}
+
+impl From<u8> for Angle {
+    fn from(i: u8) -> Self {
+        Self::degrees(Precision::from(i))
+    }
+}
+
 impl From<i16> for Angle {
     fn from(i: i16) -> Self {
         Self {
@@ -36,6 +48,18 @@ impl From<i16> for Angle {
     }
 }
 
+impl From<u16> for Angle {
+    fn from(i: u16) -> Self {
+        Self::degrees(Precision::from(i))
+    }
+}
+
+impl From<i32> for Angle {
+    fn from(i: i32) -> Self {
+        Self::degrees(i as Precision)
+    }
+}
+
 impl From<f32> for Angle {
     fn from(f: f32) -> Self {
         Self {
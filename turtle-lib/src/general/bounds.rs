@@ -0,0 +1,101 @@
+//! Axis-aligned bounding box type
+
+use super::{Coordinate, Precision};
+
+/// Axis-aligned bounding box in world coordinates
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bounds {
+    pub min: Coordinate,
+    pub max: Coordinate,
+}
+
+impl Bounds {
+    /// Create bounds from two corner points (in any order)
+    #[must_use]
+    pub fn new(a: Coordinate, b: Coordinate) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// Degenerate bounds containing a single point
+    #[must_use]
+    pub fn from_point(point: Coordinate) -> Self {
+        Self {
+            min: point,
+            max: point,
+        }
+    }
+
+    /// Bounds of a set of points, `None` if there are no points
+    #[must_use]
+    pub fn from_points(points: impl IntoIterator<Item = Coordinate>) -> Option<Self> {
+        let mut bounds: Option<Self> = None;
+        for point in points {
+            Self::extend(&mut bounds, point);
+        }
+        bounds
+    }
+
+    /// Grow optional bounds to include `point`, creating them if necessary
+    pub fn extend(bounds: &mut Option<Self>, point: Coordinate) {
+        match bounds {
+            Some(b) => b.include(point),
+            None => *bounds = Some(Self::from_point(point)),
+        }
+    }
+
+    /// Grow the bounds to include `point`
+    pub fn include(&mut self, point: Coordinate) {
+        self.min = self.min.min(point);
+        self.max = self.max.max(point);
+    }
+
+    /// Smallest bounds containing both `self` and `other`
+    #[must_use]
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Bounds grown by `margin` on every side
+    #[must_use]
+    pub fn expanded(self, margin: Precision) -> Self {
+        Self {
+            min: self.min - Coordinate::splat(margin),
+            max: self.max + Coordinate::splat(margin),
+        }
+    }
+
+    #[must_use]
+    pub fn width(&self) -> Precision {
+        self.max.x - self.min.x
+    }
+
+    #[must_use]
+    pub fn height(&self) -> Precision {
+        self.max.y - self.min.y
+    }
+
+    #[must_use]
+    pub fn size(&self) -> Coordinate {
+        self.max - self.min
+    }
+
+    #[must_use]
+    pub fn center(&self) -> Coordinate {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Check whether `point` lies inside (or on the edge of) the bounds
+    #[must_use]
+    pub fn contains(&self, point: Coordinate) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+    }
+}
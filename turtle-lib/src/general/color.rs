@@ -0,0 +1,478 @@
+//! Color helpers
+
+use super::Color;
+
+/// Blend two colors in the perceptual Oklab color space
+///
+/// `t = 0.0` returns `from`, `t = 1.0` returns `to`. Unlike mixing the RGB
+/// channels directly, intermediate colors keep an even brightness and do not
+/// turn muddy (red to green passes through yellow-ish, not dark brown).
+/// Alpha is interpolated linearly.
+#[must_use]
+pub fn mix_colors(from: Color, to: Color, t: f32) -> Color {
+    if t <= 0.0 || from == to {
+        return from;
+    }
+    if t >= 1.0 {
+        return to;
+    }
+    let a = srgb_to_oklab(from);
+    let b = srgb_to_oklab(to);
+    let mixed = [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+    ];
+    let [r, g, b] = oklab_to_srgb(mixed);
+    Color::new(r, g, b, from.a + (to.a - from.a) * t)
+}
+
+/// Color from a hex string like `"#ff8800"`
+///
+/// Accepts `rgb`, `rgba`, `rrggbb` and `rrggbbaa` digits, with or without
+/// the leading `#`. Returns `None` for anything else.
+#[must_use]
+pub fn color_from_hex(hex: &str) -> Option<Color> {
+    let digits = hex.trim().trim_start_matches('#');
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize, len: usize| -> Option<f32> {
+        let value = u8::from_str_radix(digits.get(i * len..(i + 1) * len)?, 16).ok()?;
+        // A single digit stands for itself repeated (`f` is `ff`)
+        let value = if len == 1 { value * 17 } else { value };
+        Some(f32::from(value) / 255.0)
+    };
+    let (len, alpha) = match digits.len() {
+        3 => (1, false),
+        4 => (1, true),
+        6 => (2, false),
+        8 => (2, true),
+        _ => return None,
+    };
+    Some(Color::new(
+        channel(0, len)?,
+        channel(1, len)?,
+        channel(2, len)?,
+        if alpha { channel(3, len)? } else { 1.0 },
+    ))
+}
+
+/// Color from a CSS color name (`"cornflowerblue"`) or a hex string
+/// (`"#6495ed"`), ignoring case
+///
+/// Meant for colors read from data files or user input. Names follow CSS,
+/// so `"green"` is darker than macroquad's `GREEN`.
+#[must_use]
+pub fn parse_color(text: &str) -> Option<Color> {
+    let text = text.trim();
+    if text.starts_with('#') {
+        return color_from_hex(text);
+    }
+    let name = text.to_lowercase().replace([' ', '_', '-'], "");
+    CSS_COLORS
+        .binary_search_by(|(candidate, _)| candidate.cmp(&name.as_str()))
+        .ok()
+        .map(|index| {
+            let rgb = CSS_COLORS[index].1;
+            Color::from_rgba((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+        })
+}
+
+/// Color by CSS name or hex string, for use in plans
+///
+/// ```
+/// # use turtle_lib::*;
+/// let mut turtle = create_turtle_plan();
+/// turtle.set_pen_color(color("cornflowerblue")).forward(100.0);
+/// ```
+///
+/// Unknown colors log a warning and fall back to black, use
+/// `parse_color()` to handle them yourself.
+#[must_use]
+pub fn color(name: &str) -> Color {
+    parse_color(name).unwrap_or_else(|| {
+        tracing::warn!(name, "Unknown color, using black");
+        Color::new(0.0, 0.0, 0.0, 1.0)
+    })
+}
+
+/// Hue based constructors and adjustments for `Color`
+///
+/// Hues are in degrees (0 red, 120 green, 240 blue), all other components
+/// range from 0.0 to 1.0. Use macroquad's `Color::with_alpha()` to change the
+/// opacity.
+///
+/// ```
+/// # use turtle_lib::*;
+/// let mut turtle = create_turtle_plan();
+/// for i in 0..36 {
+///     let hue = i as f32 * 10.0;
+///     turtle.set_pen_color(Color::from_hsv(hue, 1.0, 1.0)).forward(10.0).right(10.0);
+/// }
+/// ```
+pub trait ColorExt: Sized {
+    /// Color from hue, saturation and value (brightness), alpha 1.0
+    fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self;
+    /// Color from hue, saturation and lightness, alpha 1.0
+    fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self;
+    /// Hue, saturation and value of the color
+    fn to_hsv(&self) -> (f32, f32, f32);
+    /// Hue, saturation and lightness of the color
+    fn to_hsl(&self) -> (f32, f32, f32);
+    /// Raise the lightness by `amount`, keeping hue and alpha
+    fn lighten(&self, amount: f32) -> Self;
+    /// Lower the lightness by `amount`, keeping hue and alpha
+    fn darken(&self, amount: f32) -> Self;
+    /// Turn the hue by `degrees`, e.g. for rainbow drawings
+    fn rotate_hue(&self, degrees: f32) -> Self;
+}
+
+impl ColorExt for Color {
+    fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+        let chroma = value * saturation;
+        from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = hue_max_min(self);
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (hue, saturation, max)
+    }
+
+    fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = hue_max_min(self);
+        let lightness = (max + min) / 2.0;
+        let saturation = if max - min > 0.0 {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        } else {
+            0.0
+        };
+        (hue, saturation.clamp(0.0, 1.0), lightness)
+    }
+
+    fn lighten(&self, amount: f32) -> Self {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Color::from_hsl(hue, saturation, lightness + amount).with_alpha(self.a)
+    }
+
+    fn darken(&self, amount: f32) -> Self {
+        self.lighten(-amount)
+    }
+
+    fn rotate_hue(&self, degrees: f32) -> Self {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Color::from_hsl(hue + degrees, saturation, lightness).with_alpha(self.a)
+    }
+}
+
+/// RGB color on the hue circle with the given chroma, lifted by `offset`
+fn from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Color {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    Color::new(r + offset, g + offset, b + offset, 1.0)
+}
+
+/// Hue in degrees plus the largest and smallest RGB channel
+fn hue_max_min(color: &Color) -> (f32, f32, f32) {
+    let (r, g, b) = (color.r, color.g, color.b);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let hue = if delta <= 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (hue, max, min)
+}
+
+/// The 148 named colors of CSS, sorted by name
+const CSS_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn srgb_to_oklab(color: Color) -> [f32; 3] {
+    let r = srgb_to_linear(color.r);
+    let g = srgb_to_linear(color.g);
+    let b = srgb_to_linear(color.b);
+
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+fn oklab_to_srgb([l, a, b]: [f32; 3]) -> [f32; 3] {
+    let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+
+    [
+        linear_to_srgb(4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_),
+        linear_to_srgb(-1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_),
+        linear_to_srgb(-0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Color, b: Color) {
+        for (x, y) in [(a.r, b.r), (a.g, b.g), (a.b, b.b), (a.a, b.a)] {
+            assert!((x - y).abs() < 1e-3, "{a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn mix_returns_endpoints() {
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        let blue = Color::new(0.0, 0.0, 1.0, 0.5);
+        assert_close(mix_colors(red, blue, 0.0), red);
+        assert_close(mix_colors(red, blue, 1.0), blue);
+    }
+
+    #[test]
+    fn mix_keeps_gray_neutral() {
+        let black = Color::new(0.0, 0.0, 0.0, 1.0);
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let mid = mix_colors(black, white, 0.5);
+        assert!((mid.r - mid.g).abs() < 1e-3 && (mid.g - mid.b).abs() < 1e-3);
+        assert!(mid.r > 0.0 && mid.r < 1.0);
+    }
+
+    #[test]
+    fn parses_hex_strings_and_css_names() {
+        assert_close(
+            color_from_hex("#ff8800").unwrap(),
+            Color::new(1.0, 0.533, 0.0, 1.0),
+        );
+        assert_close(
+            color_from_hex("f80c").unwrap(),
+            Color::new(1.0, 0.533, 0.0, 0.8),
+        );
+        assert!(color_from_hex("#ff88").is_some());
+        assert!(color_from_hex("#ff880").is_none());
+        assert!(color_from_hex("#gg8800").is_none());
+
+        assert!(CSS_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_close(
+            parse_color("Cornflower Blue").unwrap(),
+            color_from_hex("#6495ed").unwrap(),
+        );
+        assert!(parse_color("notacolor").is_none());
+    }
+
+    #[test]
+    fn hsv_and_hsl_round_trip() {
+        assert_close(
+            Color::from_hsv(0.0, 1.0, 1.0),
+            Color::new(1.0, 0.0, 0.0, 1.0),
+        );
+        assert_close(
+            Color::from_hsv(240.0, 1.0, 0.5),
+            Color::new(0.0, 0.0, 0.5, 1.0),
+        );
+        assert_close(
+            Color::from_hsl(120.0, 1.0, 0.5),
+            Color::new(0.0, 1.0, 0.0, 1.0),
+        );
+
+        let orange = Color::new(1.0, 0.5, 0.0, 0.5);
+        let (h, s, v) = orange.to_hsv();
+        assert_close(Color::from_hsv(h, s, v).with_alpha(0.5), orange);
+        assert_close(orange.lighten(0.2).darken(0.2), orange);
+        assert_close(orange.rotate_hue(360.0), orange);
+    }
+}
@@ -0,0 +1,52 @@
+//! Coordinate conventions for absolute positions and headings
+
+use super::{Coordinate, Precision};
+use macroquad::prelude::vec2;
+
+/// How absolute positions and headings are interpreted
+///
+/// Internally turtles live in screen space (Y grows downwards, as in macroquad).
+/// The mode only affects values that refer to the world as a whole: `go_to()`,
+/// `set_heading()` and queries like positions, markers or bounds. Relative
+/// commands (`forward`, `left`, `right`, circles) behave the same in both modes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CoordinateMode {
+    /// Y grows upwards and headings count counter-clockwise from east (`90°` is up)
+    #[default]
+    MathYUp,
+    /// Y grows downwards and headings count clockwise from east (`90°` is down)
+    ScreenYDown,
+}
+
+impl CoordinateMode {
+    /// Convert a point in this mode to screen space
+    #[must_use]
+    pub fn to_screen(self, point: Coordinate) -> Coordinate {
+        match self {
+            CoordinateMode::MathYUp => vec2(point.x, -point.y),
+            CoordinateMode::ScreenYDown => point,
+        }
+    }
+
+    /// Convert a point in screen space to this mode
+    #[must_use]
+    pub fn from_screen(self, point: Coordinate) -> Coordinate {
+        // Both conversions are their own inverse
+        self.to_screen(point)
+    }
+
+    /// Convert a heading (radians) in this mode to screen space
+    #[must_use]
+    pub fn heading_to_screen(self, heading: Precision) -> Precision {
+        match self {
+            CoordinateMode::MathYUp => -heading,
+            CoordinateMode::ScreenYDown => heading,
+        }
+    }
+
+    /// Convert a heading (radians) in screen space to this mode
+    #[must_use]
+    pub fn heading_from_screen(self, heading: Precision) -> Precision {
+        self.heading_to_screen(heading)
+    }
+}
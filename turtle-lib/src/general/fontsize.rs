@@ -1,6 +1,7 @@
 //! `FontSize` type for text rendering
 
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FontSize(pub u16);
 
 impl FontSize {
@@ -1,16 +1,84 @@
 //! Length type for distance measurements
 
 use super::Precision;
+use std::sync::{Mutex, PoisonError};
 
+/// A distance in pixels
+///
+/// Accepted by `forward()`, `backward()` and the circle radius, so distances
+/// can be given in real-world units: `turtle.forward(Length::cm(2.0))`. Plain
+/// numbers still work and are pixels.
 #[derive(Default, Copy, Clone, Debug, PartialEq)]
 pub struct Length(pub Precision);
 
+/// 96 pixels per inch, like CSS
+const DEFAULT_PIXELS_PER_CM: Precision = 96.0 / 2.54;
+
+static PIXELS_PER_CM: Mutex<Precision> = Mutex::new(DEFAULT_PIXELS_PER_CM);
+
+impl Length {
+    #[must_use]
+    pub fn pixels(value: Precision) -> Self {
+        Self(value)
+    }
+
+    /// Centimeters at the current scale, see `set_scale()`
+    #[must_use]
+    pub fn cm(value: Precision) -> Self {
+        Self(value * Self::scale())
+    }
+
+    /// Millimeters at the current scale, see `set_scale()`
+    #[must_use]
+    pub fn mm(value: Precision) -> Self {
+        Self::cm(value / 10.0)
+    }
+
+    /// Inches at the current scale, see `set_scale()`
+    #[must_use]
+    pub fn inches(value: Precision) -> Self {
+        Self::cm(value * 2.54)
+    }
+
+    /// Set how many pixels make a centimeter (about 37.8 by default)
+    ///
+    /// The scale applies to all lengths created afterwards, e.g. to print
+    /// drawings at their real size.
+    pub fn set_scale(pixels_per_cm: Precision) {
+        *PIXELS_PER_CM.lock().unwrap_or_else(PoisonError::into_inner) = pixels_per_cm;
+    }
+
+    /// Pixels per centimeter used by `cm()`, `mm()` and `inches()`
+    #[must_use]
+    pub fn scale() -> Precision {
+        *PIXELS_PER_CM.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl From<i8> for Length {
+    fn from(i: i8) -> Self {
+        Self(Precision::from(i))
+    }
+}
+
+impl From<u8> for Length {
+    fn from(i: u8) -> Self {
+        Self(Precision::from(i))
+    }
+}
+
 impl From<i16> for Length {
     fn from(i: i16) -> Self {
         Self(Precision::from(i))
     }
 }
 
+impl From<u16> for Length {
+    fn from(i: u16) -> Self {
+        Self(Precision::from(i))
+    }
+}
+
 impl From<f32> for Length {
     fn from(f: f32) -> Self {
         Self(f)
@@ -0,0 +1,181 @@
+//! Groups of turtles that get their commands together
+//!
+//! A `TurtleGroup` is a list of turtle IDs. `send()` builds one plan per
+//! member from the same closure, which gets the member's index to vary the
+//! plan, e.g. to spread the turtles out:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//!
+//! #[macroquad::main("Group")]
+//! async fn main() {
+//!     let mut app = TurtleApp::new();
+//!     let flock = TurtleGroup::spawn(&mut app, 6);
+//!     flock.send(&mut app, |index, plan| {
+//!         plan.right(index as f32 * 60.0);
+//!         for _ in 0..5 {
+//!             plan.forward(80.0).right(144.0);
+//!         }
+//!     });
+//!     flock.set_speed(&mut app, 300.0);
+//!     app.run(RunConfig::new()).await;
+//! }
+//! ```
+//!
+//! Groups only hold IDs, so a turtle can be in several groups and groups can
+//! be created and dropped freely.
+
+use crate::builders::TurtlePlan;
+use crate::formation::Formation;
+use crate::general::{AnimationSpeed, Coordinate, Precision};
+use crate::TurtleApp;
+
+/// A set of turtle IDs, in the order they were added
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TurtleGroup {
+    ids: Vec<usize>,
+}
+
+impl TurtleGroup {
+    /// Group of existing turtles, duplicate IDs are ignored
+    #[must_use]
+    pub fn new(ids: impl IntoIterator<Item = usize>) -> Self {
+        let mut group = Self::default();
+        for id in ids {
+            group.add(id);
+        }
+        group
+    }
+
+    /// Add `count` new turtles to `app` and group them
+    #[must_use]
+    pub fn spawn(app: &mut TurtleApp, count: usize) -> Self {
+        Self::new((0..count).map(|_| app.add_turtle()))
+    }
+
+    /// Member IDs, in the order of the indices passed to `send()`
+    #[must_use]
+    pub fn ids(&self) -> &[usize] {
+        &self.ids
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    #[must_use]
+    pub fn contains(&self, id: usize) -> bool {
+        self.ids.contains(&id)
+    }
+
+    /// Add a turtle at the end, returns `false` if it already is a member
+    pub fn add(&mut self, id: usize) -> bool {
+        if self.contains(id) {
+            return false;
+        }
+        self.ids.push(id);
+        true
+    }
+
+    /// Remove a turtle, returns `false` if it wasn't a member
+    ///
+    /// Later members move up one index.
+    pub fn remove(&mut self, id: usize) -> bool {
+        let before = self.ids.len();
+        self.ids.retain(|&member| member != id);
+        self.ids.len() != before
+    }
+
+    /// Queue a plan for every member, built by `plan` from the member's index
+    ///
+    /// The plans are appended like `TurtleApp::append_to_queue()`, so each
+    /// turtle starts once it finished what it was doing.
+    pub fn send(&self, app: &mut TurtleApp, mut plan: impl FnMut(usize, &mut TurtlePlan)) {
+        for (index, &id) in self.ids.iter().enumerate() {
+            let mut member = TurtlePlan::new();
+            plan(index, &mut member);
+            app.append_to_queue(id, member);
+        }
+    }
+
+    /// Send every member to its place in `formation`, facing its heading
+    ///
+    /// Members move and turn at once and draw while their pen is down. Extra
+    /// places are left empty. See `formation` for an example.
+    pub fn arrange(&self, app: &mut TurtleApp, formation: &Formation) {
+        let positions = formation.positions(self.len());
+        self.send(app, |index, plan| {
+            plan.together(|group| {
+                group
+                    .go_to(positions[index])
+                    .set_heading(formation.heading());
+            });
+        });
+    }
+
+    /// Move `formation` by `offset` and the members along with it
+    pub fn translate(
+        &self,
+        app: &mut TurtleApp,
+        formation: &mut Formation,
+        offset: impl Into<Coordinate>,
+    ) {
+        formation.translate(offset);
+        self.arrange(app, formation);
+    }
+
+    /// Turn `formation` around its center and the members along with it,
+    /// positive degrees turn left
+    ///
+    /// Members take the direct way to their new places.
+    pub fn rotate(&self, app: &mut TurtleApp, formation: &mut Formation, degrees: Precision) {
+        formation.rotate(degrees);
+        self.arrange(app, formation);
+    }
+
+    /// Change the animation speed of all members right away
+    pub fn set_speed(&self, app: &mut TurtleApp, speed: impl Into<AnimationSpeed>) {
+        let speed = speed.into();
+        for &id in &self.ids {
+            app.set_turtle_speed(id, speed);
+        }
+    }
+
+    /// Show the cursors of all members right away
+    pub fn show(&self, app: &mut TurtleApp) {
+        self.set_visible(app, true);
+    }
+
+    /// Hide the cursors of all members right away, their drawings stay
+    pub fn hide(&self, app: &mut TurtleApp) {
+        self.set_visible(app, false);
+    }
+
+    fn set_visible(&self, app: &mut TurtleApp, visible: bool) {
+        for &id in &self.ids {
+            app.set_turtle_visible(id, visible);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn members_are_unique_and_ordered() {
+        let mut group = TurtleGroup::new([3, 1, 3, 2]);
+        assert_eq!(group.ids(), &[3, 1, 2]);
+        assert!(!group.add(1));
+        assert!(group.remove(1));
+        assert!(!group.remove(1));
+        assert!(group.add(1));
+        assert_eq!(group.ids(), &[3, 2, 1]);
+    }
+}
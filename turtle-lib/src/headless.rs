@@ -0,0 +1,452 @@
+//! Drawing without a window
+//!
+//! Executes command queues against a `TurtleWorld` instantly and exports the
+//! result, without opening a macroquad window. Useful for rendering examples
+//! in CI or generating drawings on a server.
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//!
+//! let mut plan = create_turtle_plan();
+//! plan.set_pen_color(RED).forward(100.0).right(90.0).forward(100.0);
+//!
+//! let mut world = TurtleWorld::headless();
+//! headless::execute(&mut world, 0, &plan.build());
+//! headless::export_png(&world, "square.png", 400, 300).expect("cannot write square.png");
+//! ```
+//!
+//! For unit tests, `simulate()` runs a single turtle and reports where it
+//! ended up.
+
+use crate::circle_geometry::CircleGeometry;
+use crate::commands::{CommandQueue, TurtleCommand};
+use crate::execution;
+use crate::export::ExportError;
+use crate::general::{Angle, AnimationSpeed, Bounds, Coordinate, CoordinateMode};
+use crate::state::{BlendMode, DrawCommand, TurtleParams, TurtleWorld};
+use crate::stats::DrawingStats;
+use crate::tweening::TweenController;
+use macroquad::prelude::*;
+
+/// Execute all commands of `queue` instantly on a turtle of `world`
+///
+/// Missing turtles are created. Looping queues run a single repetition.
+/// Invalid commands are skipped, see `TurtleCommand::validate()`.
+/// The turtles' animation clocks are switched to fixed-timestep mode, so
+/// execution never reads the wall clock of a (missing) window.
+pub fn execute(world: &mut TurtleWorld, turtle_id: usize, queue: &CommandQueue) {
+    while world.turtles.len() <= turtle_id {
+        world.add_turtle();
+    }
+    for turtle in &mut world.turtles {
+        turtle.tween_controller.set_fixed_timestep(true);
+    }
+    for (index, command) in queue.commands().iter().enumerate() {
+        if let Err(problem) = command.validate() {
+            tracing::error!(turtle_id, %problem, "Skipping invalid command");
+            world.turtles[turtle_id]
+                .report_error(crate::error::TurtleError::InvalidCommand { index, problem });
+            continue;
+        }
+        execution::execute_command_with_id(command, turtle_id, world);
+    }
+}
+
+/// Outcome of `simulate()`
+#[derive(Clone, Debug)]
+pub struct SimulationResult {
+    /// State of the turtle after the last command
+    pub params: TurtleParams,
+    /// Start position followed by every position the turtle moved to, one
+    /// per command (arcs only contribute their end point)
+    pub path: Vec<Coordinate>,
+    /// Like `path`, with arcs and `together()` groups sampled finely, so the
+    /// straight lines between the points follow where the turtle went
+    pub trace: Vec<Coordinate>,
+    /// Bounding box of the trace and of everything drawn
+    pub bounds: Bounds,
+    /// Statistics of the drawing
+    pub stats: DrawingStats,
+}
+
+/// Predicted outcome of a plan, see `TurtlePlan::analyze()`
+///
+/// Positions, headings and bounds use the default `CoordinateMode::MathYUp`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlanAnalysis {
+    /// Where the turtle ends up
+    pub position: Coordinate,
+    /// Heading after the last command
+    pub heading: Angle,
+    /// Bounding box of everything drawn and every point passed
+    pub bounds: Bounds,
+    /// Seconds the animation takes at the analyzed speed, without pauses
+    /// and at a time scale of 1
+    pub duration: f64,
+}
+
+/// Predict the final pose, bounds and animation time of `queue`, starting at
+/// `speed` (see `TurtlePlan::analyze()`)
+#[must_use]
+pub fn analyze(queue: &CommandQueue, speed: AnimationSpeed) -> PlanAnalysis {
+    let (result, duration) = run_simulation(queue, speed);
+    let mode = CoordinateMode::default();
+    PlanAnalysis {
+        position: mode.from_screen(result.params.position),
+        heading: Angle::radians(mode.heading_from_screen(result.params.heading)),
+        bounds: Bounds::new(
+            mode.from_screen(result.bounds.min),
+            mode.from_screen(result.bounds.max),
+        ),
+        duration,
+    }
+}
+
+/// Run `queue` on a fresh turtle, purely in memory
+///
+/// Needs no window and never reads the wall clock, so the result is the
+/// same on every run. Meant for unit tests in CI:
+///
+/// ```
+/// use turtle_lib::*;
+///
+/// let mut plan = create_turtle_plan();
+/// for _ in 0..4 {
+///     plan.forward(100.0).right(90.0);
+/// }
+/// let result = simulate(&plan.build());
+/// assert!(result.params.position.length() < 0.01);
+/// assert!((result.bounds.width() - 100.0).abs() < 0.01);
+/// assert_eq!(result.path.len(), 5);
+/// ```
+#[must_use]
+pub fn simulate(queue: &CommandQueue) -> SimulationResult {
+    run_simulation(queue, AnimationSpeed::default()).0
+}
+
+/// `simulate()` starting at `speed`, with the animation time in seconds
+fn run_simulation(queue: &CommandQueue, speed: AnimationSpeed) -> (SimulationResult, f64) {
+    let mut world = TurtleWorld::headless();
+    world.add_turtle();
+    world.turtles[0].tween_controller.set_fixed_timestep(true);
+    world.turtles[0].set_speed(speed);
+
+    let mut path = vec![world.turtles[0].params.position];
+    let mut trace = path.clone();
+    let mut duration = 0.0;
+    for command in queue
+        .commands()
+        .iter()
+        .filter(|command| command.validate().is_ok())
+    {
+        let turtle = &world.turtles[0];
+        if let Some(resolved) = turtle.resolve_command(command.clone()) {
+            duration += TweenController::animated_duration(&resolved, turtle);
+        }
+        let before = turtle.params.clone();
+        execution::execute_command_with_id(command, 0, &mut world);
+        let position = world.turtles[0].params.position;
+        if path.last() != Some(&position) {
+            path.push(position);
+        }
+        trace_command(&mut trace, command, &before, position);
+    }
+
+    let turtle = &world.turtles[0];
+    let stats = DrawingStats::from_commands(&turtle.commands);
+    let mut bounds = Bounds::from_point(trace[0]);
+    for point in &trace {
+        bounds.include(*point);
+    }
+    if let Some(drawn) = stats.bounds {
+        bounds = bounds.union(drawn);
+    }
+    let result = SimulationResult {
+        params: turtle.params.clone(),
+        path,
+        trace,
+        bounds,
+        stats,
+    };
+    (result, duration)
+}
+
+/// Append the points `command` passes on its way from `before` to `end`
+pub(crate) fn trace_command(
+    trace: &mut Vec<Coordinate>,
+    command: &TurtleCommand,
+    before: &TurtleParams,
+    end: Coordinate,
+) {
+    match command {
+        TurtleCommand::Circle {
+            radius,
+            angle,
+            direction,
+            ..
+        } => {
+            let geometry =
+                CircleGeometry::new(before.position, before.heading, *radius, *direction);
+            // One point per degree keeps the traced length within 0.01%
+            let samples = angle.abs().ceil().max(1.0) as usize;
+            trace.extend((1..samples).map(|sample| {
+                geometry.position_at_angle(angle.to_radians() * sample as f32 / samples as f32)
+            }));
+        }
+        TurtleCommand::Together(members) => {
+            let path = crate::tweening::group_path(before, members, 1.0);
+            trace.extend(path.into_iter().skip(1).map(|(point, _, _)| point));
+        }
+        _ => {}
+    }
+    if trace.last() != Some(&end) {
+        trace.push(end);
+    }
+}
+
+/// Export the drawings of `world` as SVG
+///
+/// # Errors
+///
+/// Returns an error if writing fails or the `svg` feature is disabled
+pub fn export_svg(world: &TurtleWorld, filename: &str) -> Result<(), ExportError> {
+    #[cfg(feature = "svg")]
+    {
+        use crate::export::DrawingExporter;
+        crate::export_svg::svg_export::SvgExporter.export(world, filename)
+    }
+
+    #[cfg(not(feature = "svg"))]
+    {
+        let _ = (world, filename);
+        Err(ExportError::Format(
+            "SVG export feature is not enabled, rebuild with --features svg".to_string(),
+        ))
+    }
+}
+
+/// Rasterize the drawings of `world` into an image of the given size
+///
+/// The world origin (or the camera target) ends up in the center of the
+/// image, like in the window at zoom level 1. A background gradient fills
+/// the whole image. Turtle cursors and text are not drawn, text needs a
+/// window to load fonts.
+#[must_use]
+pub fn render_image(world: &TurtleWorld, width: u16, height: u16) -> Image {
+    let mut image = Image::gen_image_color(width, height, world.background_color);
+    if let Some((top, bottom)) = world.background_gradient {
+        for y in 0..u32::from(height) {
+            let t = (y as f32 + 0.5) / f32::from(height);
+            let color = Color::new(
+                top.r + (bottom.r - top.r) * t,
+                top.g + (bottom.g - top.g) * t,
+                top.b + (bottom.b - top.b) * t,
+                top.a + (bottom.a - top.a) * t,
+            );
+            for x in 0..u32::from(width) {
+                image.set_pixel(x, y, color);
+            }
+        }
+    }
+    let offset = vec2(f32::from(width), f32::from(height)) / 2.0 - world.camera.target;
+
+    let layer = world.layer.iter().map(|cmd| (cmd, 1.0));
+    let living = world.turtles.iter().flat_map(|turtle| {
+        turtle
+            .commands
+            .iter()
+            .map(move |cmd| (cmd, world.trail_alpha(turtle, cmd)))
+    });
+    for (cmd, alpha) in layer.chain(living) {
+        let DrawCommand::Mesh { data, source } = cmd else {
+            continue;
+        };
+        for triangle in data.indices.chunks_exact(3) {
+            let vertices = [
+                &data.vertices[usize::from(triangle[0])],
+                &data.vertices[usize::from(triangle[1])],
+                &data.vertices[usize::from(triangle[2])],
+            ];
+            fill_triangle(&mut image, vertices, offset, alpha, source.blend_mode);
+        }
+    }
+    image
+}
+
+/// Rasterize the drawings of `world` and save them as PNG
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written
+pub fn export_png(
+    world: &TurtleWorld,
+    filename: &str,
+    width: u16,
+    height: u16,
+) -> Result<(), ExportError> {
+    write_png(&render_image(world, width, height), filename)
+}
+
+/// Save an image with the first row at the top
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written
+pub(crate) fn write_png(image: &Image, filename: &str) -> Result<(), ExportError> {
+    image::save_buffer(
+        filename,
+        &image.bytes,
+        u32::from(image.width),
+        u32::from(image.height),
+        image::ColorType::Rgba8,
+    )
+    .map_err(|error| match error {
+        image::ImageError::IoError(error) => ExportError::Io(error),
+        error => ExportError::Format(error.to_string()),
+    })
+}
+
+/// Blend a triangle into `image`, sampling at pixel centers
+fn fill_triangle(
+    image: &mut Image,
+    vertices: [&Vertex; 3],
+    offset: Vec2,
+    alpha: f32,
+    mode: BlendMode,
+) {
+    let points = vertices.map(|vertex| vertex.position.truncate() + offset);
+    let area = edge(points[0], points[1], points[2]);
+    if area.abs() < f32::EPSILON {
+        return;
+    }
+
+    let size = vec2(f32::from(image.width), f32::from(image.height));
+    let min = points[0]
+        .min(points[1])
+        .min(points[2])
+        .floor()
+        .max(Vec2::ZERO);
+    let max = points[0].max(points[1]).max(points[2]).ceil().min(size);
+    if min.x >= max.x || min.y >= max.y {
+        return;
+    }
+
+    for y in min.y as u32..max.y as u32 {
+        for x in min.x as u32..max.x as u32 {
+            let pixel = vec2(x as f32 + 0.5, y as f32 + 0.5);
+            let weights = [
+                edge(points[1], points[2], pixel) / area,
+                edge(points[2], points[0], pixel) / area,
+                edge(points[0], points[1], pixel) / area,
+            ];
+            if weights.iter().any(|weight| *weight < 0.0) {
+                continue;
+            }
+
+            let mut color = [0.0; 4];
+            for (weight, vertex) in weights.iter().zip(vertices) {
+                for (channel, value) in color.iter_mut().zip(vertex.color) {
+                    *channel += weight * f32::from(value) / 255.0;
+                }
+            }
+            let source = Color::new(color[0], color[1], color[2], color[3] * alpha);
+            let target = image.get_pixel(x, y);
+            image.set_pixel(x, y, blend(source, target, mode));
+        }
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `c`
+fn edge(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    (b - a).perp_dot(c - a)
+}
+
+/// Draw `source` over `target`, matching the blend states of the window
+fn blend(source: Color, target: Color, mode: BlendMode) -> Color {
+    match mode {
+        BlendMode::Normal => {
+            let alpha = source.a + target.a * (1.0 - source.a);
+            if alpha <= 0.0 {
+                return Color::new(0.0, 0.0, 0.0, 0.0);
+            }
+            let mix = |s: f32, t: f32| (s * source.a + t * target.a * (1.0 - source.a)) / alpha;
+            Color::new(
+                mix(source.r, target.r),
+                mix(source.g, target.g),
+                mix(source.b, target.b),
+                alpha,
+            )
+        }
+        BlendMode::Additive => {
+            let add = |s: f32, t: f32| (t + s * source.a).min(1.0);
+            Color::new(
+                add(source.r, target.r),
+                add(source.g, target.g),
+                add(source.b, target.b),
+                (target.a + source.a).min(1.0),
+            )
+        }
+        BlendMode::Multiply => {
+            // Fade the source towards white, which keeps the target as is
+            let multiply = |s: f32, t: f32| t * (1.0 + (s - 1.0) * source.a);
+            Color::new(
+                multiply(source.r, target.r),
+                multiply(source.g, target.g),
+                multiply(source.b, target.b),
+                target.a,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::{CurvedMovement, DirectionalMovement, TurtlePlan};
+
+    #[test]
+    fn png_write_errors_are_returned() {
+        let world = TurtleWorld::headless();
+        let missing = std::env::temp_dir().join("turtlers-missing-dir/drawing.png");
+        let result = export_png(&world, &missing.to_string_lossy(), 4, 4);
+        assert!(matches!(result, Err(ExportError::Io(_))));
+    }
+
+    #[test]
+    fn renders_lines_without_a_window() {
+        let mut plan = TurtlePlan::new();
+        plan.set_pen_color(RED).set_pen_width(4.0).forward(40.0);
+
+        let mut world = TurtleWorld::headless();
+        execute(&mut world, 0, &plan.build());
+        assert_eq!(world.turtles[0].commands.len(), 1);
+
+        let image = render_image(&world, 100, 100);
+        // The line runs right from the center of the image
+        let on_line = image.get_pixel(70, 50);
+        let close = |a: f32, b: f32| (a - b).abs() < 0.01;
+        assert!(close(on_line.r, RED.r) && close(on_line.g, RED.g) && close(on_line.b, RED.b));
+        let background = image.get_pixel(30, 50);
+        assert!(background.g > 0.9);
+    }
+
+    #[test]
+    fn simulation_bounds_include_arcs() {
+        let mut plan = TurtlePlan::new();
+        plan.pen_up()
+            .forward(10.0)
+            .pen_down()
+            .circle_left(50.0, 180.0, 18);
+
+        let result = simulate(&plan.build());
+        assert_eq!(result.path.len(), 3);
+        assert_eq!(result.trace.len(), 2 + 180);
+        let (short, long) = (
+            result.bounds.width().min(result.bounds.height()),
+            result.bounds.width().max(result.bounds.height()),
+        );
+        assert!((short - 60.0).abs() < 0.5, "{short}");
+        assert!((long - 100.0).abs() < 0.5, "{long}");
+        assert_eq!(result.stats.segments, 1);
+    }
+}
@@ -0,0 +1,326 @@
+//! World-level undo/redo history
+//!
+//! Every command queue submitted to a `TurtleApp` is recorded together with the
+//! turtle it was sent to. Undoing removes the latest entry and rebuilds the world
+//! by replaying the remaining entries instantly, so the history does not depend
+//! on how far the animation has progressed.
+//!
+//! Only the latest `WorldHistory::limit()` entries can be undone. Older
+//! entries are folded into a snapshot of the turtles and the markers,
+//! stencils and mailboxes that replaying starts from, so long sessions don't
+//! keep every queue around.
+
+use crate::commands::CommandQueue;
+use crate::execution;
+use crate::state::{SharedWorldData, Turtle, TurtleWorld};
+use std::sync::{Arc, Mutex};
+
+/// A command queue as it was submitted to a turtle
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub turtle_id: usize,
+    pub queue: CommandQueue,
+    /// Number of turtles in the world when the queue was submitted
+    pub turtles_before: usize,
+}
+
+/// Undo and redo stacks spanning all turtles of a world
+#[derive(Clone, Debug)]
+pub struct WorldHistory {
+    entries: Vec<HistoryEntry>,
+    undone: Vec<HistoryEntry>,
+    limit: usize,
+    // Turtles and shared data after the entries that fell out of the history
+    base: Vec<Turtle>,
+    base_shared: SharedWorldData,
+}
+
+impl Default for WorldHistory {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            undone: Vec::new(),
+            limit: Self::DEFAULT_LIMIT,
+            base: Vec::new(),
+            base_shared: SharedWorldData::default(),
+        }
+    }
+}
+
+impl WorldHistory {
+    /// How many entries can be undone unless changed with `set_limit()`
+    pub const DEFAULT_LIMIT: usize = 1000;
+
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many entries can be undone at most
+    #[must_use]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Change how many entries can be undone, older entries of `world` are
+    /// made permanent
+    pub fn set_limit(&mut self, limit: usize, world: &TurtleWorld) {
+        self.limit = limit;
+        self.fold_excess(world);
+    }
+
+    /// Record a newly submitted queue of `world` (clears the redo stack)
+    ///
+    /// If the history is full, the oldest entry can no longer be undone.
+    pub fn record(&mut self, turtle_id: usize, queue: &CommandQueue, world: &TurtleWorld) {
+        self.entries.push(HistoryEntry {
+            turtle_id,
+            queue: queue.clone(),
+            turtles_before: world.turtles.len(),
+        });
+        self.undone.clear();
+        self.fold_excess(world);
+    }
+
+    /// Apply the entries beyond the limit to the base snapshot
+    fn fold_excess(&mut self, world: &TurtleWorld) {
+        if self.entries.len() <= self.limit {
+            return;
+        }
+        // Execute on a scratch world with the base data, `world` only
+        // lends its settings
+        let mut shared = std::mem::take(&mut self.base_shared);
+        {
+            let live = world.shared_data();
+            shared.coordinate_mode = live.coordinate_mode;
+            shared.shapes.clone_from(&live.shapes);
+        }
+        let shared = Arc::new(Mutex::new(shared));
+        let mut turtles = std::mem::take(&mut self.base);
+        for turtle in &mut turtles {
+            turtle.shared = Arc::clone(&shared);
+        }
+        let mut scratch = TurtleWorld {
+            turtles,
+            shared,
+            ..TurtleWorld::headless()
+        };
+        let excess = self.entries.len() - self.limit;
+        for entry in self.entries.drain(..excess) {
+            apply_entry(&entry, &mut scratch);
+        }
+        self.base_shared = scratch.shared_data().clone();
+        self.base = scratch.turtles;
+    }
+
+    /// All entries that can be undone, oldest first
+    #[must_use]
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Forget all recorded entries, replaying starts from new turtles
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.undone.clear();
+        self.base.clear();
+        self.base_shared = SharedWorldData::default();
+    }
+
+    /// Remove the latest entry and rebuild `world` from the remaining history
+    ///
+    /// Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self, world: &mut TurtleWorld) -> bool {
+        let Some(entry) = self.entries.pop() else {
+            return false;
+        };
+        self.undone.push(entry);
+        self.replay(world);
+        true
+    }
+
+    /// Re-apply the most recently undone entry on top of the current world
+    ///
+    /// Returns `false` if there is nothing to redo.
+    pub fn redo(&mut self, world: &mut TurtleWorld) -> bool {
+        let Some(entry) = self.undone.pop() else {
+            return false;
+        };
+        apply_entry(&entry, world);
+        self.entries.push(entry);
+        true
+    }
+
+    /// Reset every turtle to the base snapshot and execute all recorded
+    /// entries instantly
+    ///
+    /// Turtles spawned by the replayed (or undone) entries are removed
+    /// first, and markers, stencils and mailboxes are restored to the base
+    /// snapshot, so replaying doesn't deliver messages twice.
+    pub fn replay(&self, world: &mut TurtleWorld) {
+        {
+            let mut shared = world.shared_data();
+            shared.markers.clone_from(&self.base_shared.markers);
+            shared.stencils.clone_from(&self.base_shared.stencils);
+            shared.mailboxes.clone_from(&self.base_shared.mailboxes);
+        }
+        // The undone entries are stacked newest first
+        let first = self.entries.first().or(self.undone.last());
+        if let Some(entry) = first {
+            world
+                .turtles
+                .truncate(entry.turtles_before.max(self.base.len()));
+        }
+        while world.turtles.len() < self.base.len() {
+            world.add_turtle();
+        }
+        for turtle in &mut world.turtles {
+            let base = self.base.get(turtle.turtle_id).cloned().unwrap_or_default();
+            *turtle = Turtle {
+                turtle_id: turtle.turtle_id,
+                shared: Arc::clone(&turtle.shared),
+                ..base
+            };
+        }
+        for entry in &self.entries {
+            apply_entry(entry, world);
+        }
+    }
+}
+
+/// Execute an entry's commands immediately on its turtle
+fn apply_entry(entry: &HistoryEntry, world: &mut TurtleWorld) {
+    while world.turtles.len() <= entry.turtle_id {
+        world.add_turtle();
+    }
+
+    if let Some(turtle) = world.get_turtle_mut(entry.turtle_id) {
+        // Looping queues are replayed once
+        for command in entry.queue.commands() {
+            execution::execute_command(command, turtle);
+        }
+        // Keep the controller in sync with the replayed speed
+        let speed = turtle.params.speed;
+        turtle.tween_controller.set_speed(speed);
+    }
+    world.apply_world_requests();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::DirectionalMovement;
+    use crate::create_turtle_plan;
+    use macroquad::prelude::Vec2;
+
+    fn forward(distance: f32) -> CommandQueue {
+        let mut plan = create_turtle_plan();
+        plan.forward(distance);
+        plan.build()
+    }
+
+    /// Record and apply `queue`, returning where turtle 0 ended up
+    fn submit(history: &mut WorldHistory, world: &mut TurtleWorld, queue: &CommandQueue) -> Vec2 {
+        history.record(0, queue, world);
+        let entry = history.entries().last().cloned().unwrap();
+        apply_entry(&entry, world);
+        world.turtles[0].params.position
+    }
+
+    #[test]
+    fn undo_and_redo_rebuild_the_world() {
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        let mut history = WorldHistory::new();
+        let first = submit(&mut history, &mut world, &forward(100.0));
+        let second = submit(&mut history, &mut world, &forward(50.0));
+
+        assert!(history.undo(&mut world));
+        assert_eq!(world.turtles[0].params.position, first);
+        assert!(history.redo(&mut world));
+        assert_eq!(world.turtles[0].params.position, second);
+        assert!(!history.redo(&mut world));
+
+        assert!(history.undo(&mut world));
+        assert!(history.undo(&mut world));
+        assert_eq!(world.turtles[0].params.position, Vec2::ZERO);
+        assert!(!history.undo(&mut world));
+
+        // New commands drop what was undone
+        assert!(history.can_redo());
+        submit(&mut history, &mut world, &forward(10.0));
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn entries_beyond_the_limit_become_permanent() {
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        let mut history = WorldHistory::new();
+        history.set_limit(2, &world);
+        submit(&mut history, &mut world, &forward(100.0));
+        let second = submit(&mut history, &mut world, &forward(50.0));
+        let third = submit(&mut history, &mut world, &forward(25.0));
+        submit(&mut history, &mut world, &forward(10.0));
+        assert_eq!(history.entries().len(), 2);
+
+        assert!(history.undo(&mut world));
+        assert_eq!(world.turtles[0].params.position, third);
+        assert!(history.undo(&mut world));
+        assert_eq!(world.turtles[0].params.position, second);
+        assert!(!history.undo(&mut world));
+    }
+
+    #[test]
+    fn undo_forgets_messages_and_spawned_turtles() {
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        world.add_turtle();
+        let mut history = WorldHistory::new();
+        let mut plan = create_turtle_plan();
+        plan.send_message(1, "hello");
+        submit(&mut history, &mut world, &plan.build());
+        let mut plan = create_turtle_plan();
+        plan.spawn((10.0, 0.0), |child| {
+            child.forward(5.0);
+        });
+        submit(&mut history, &mut world, &plan.build());
+        submit(&mut history, &mut world, &forward(10.0));
+        assert_eq!(world.turtles.len(), 3);
+
+        assert!(history.undo(&mut world));
+        assert_eq!(world.turtles.len(), 3);
+        assert!(history.undo(&mut world));
+        assert_eq!(world.turtles.len(), 2);
+        assert_eq!(world.read_messages(1).len(), 1);
+        assert!(history.undo(&mut world));
+        assert!(world.read_messages(1).is_empty());
+    }
+
+    #[test]
+    fn folding_leaves_the_live_world_alone() {
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        world.add_turtle();
+        let mut history = WorldHistory::new();
+        history.set_limit(1, &world);
+        let mut plan = create_turtle_plan();
+        plan.send_message(1, "hello");
+        submit(&mut history, &mut world, &plan.build());
+        assert_eq!(world.read_messages(1).len(), 1);
+
+        // Folding the message doesn't deliver it again
+        submit(&mut history, &mut world, &forward(10.0));
+        assert!(world.read_messages(1).is_empty());
+    }
+}
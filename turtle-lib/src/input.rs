@@ -0,0 +1,70 @@
+//! Per-frame input state for interactive programs
+//!
+//! `TurtleApp::frame_input()` captures keyboard and mouse state once per
+//! frame. The `turtle_main` macro passes it to the `update` function of
+//! interactive programs.
+
+use macroquad::prelude::*;
+
+/// Keyboard and mouse state of a single frame
+#[derive(Clone, Debug, Default)]
+pub struct FrameInput {
+    /// Mouse position in world coordinates (in the world's `CoordinateMode`)
+    pub mouse_position: Vec2,
+    /// Mouse buttons held down
+    pub mouse_down: Vec<MouseButton>,
+    /// Mouse buttons pressed this frame
+    pub mouse_pressed: Vec<MouseButton>,
+    /// Keys held down
+    pub keys_down: Vec<KeyCode>,
+    /// Keys pressed this frame
+    pub keys_pressed: Vec<KeyCode>,
+    /// Characters typed this frame, in order
+    pub chars: Vec<char>,
+    /// Seconds since the previous frame
+    pub frame_time: f32,
+}
+
+impl FrameInput {
+    /// Capture the current state, with `mouse_position` already converted
+    /// to world coordinates
+    #[must_use]
+    pub fn capture(mouse_position: Vec2) -> Self {
+        let buttons = [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+        Self {
+            mouse_position,
+            mouse_down: buttons
+                .into_iter()
+                .filter(|button| is_mouse_button_down(*button))
+                .collect(),
+            mouse_pressed: buttons
+                .into_iter()
+                .filter(|button| is_mouse_button_pressed(*button))
+                .collect(),
+            keys_down: get_keys_down().into_iter().collect(),
+            keys_pressed: get_keys_pressed().into_iter().collect(),
+            chars: std::iter::from_fn(get_char_pressed).collect(),
+            frame_time: get_frame_time(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_key_down(&self, key: KeyCode) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    #[must_use]
+    pub fn is_key_pressed(&self, key: KeyCode) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+
+    #[must_use]
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.mouse_down.contains(&button)
+    }
+
+    #[must_use]
+    pub fn is_mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_pressed.contains(&button)
+    }
+}
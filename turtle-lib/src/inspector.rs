@@ -0,0 +1,64 @@
+//! Inspector panel for the macroquad window
+//!
+//! Shows every turtle's position, heading, pen state and queue length in an
+//! egui window and allows changing speed, pen color and visibility while the
+//! program runs. Enabled with the `inspector` feature, see
+//! `TurtleApp::set_inspector()`.
+
+use crate::general::AnimationSpeed;
+use crate::state::TurtleWorld;
+use egui_macroquad::egui;
+use macroquad::prelude::Color;
+
+/// Draw the inspector window on top of everything drawn so far
+pub fn draw(world: &mut TurtleWorld) {
+    egui_macroquad::ui(|ctx| {
+        egui::Window::new("Turtles")
+            .default_width(240.0)
+            .show(ctx, |ui| {
+                for turtle in &mut world.turtles {
+                    egui::CollapsingHeader::new(format!("Turtle {}", turtle.turtle_id))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            let params = &mut turtle.params;
+                            ui.label(format!(
+                                "Position: ({:.1}, {:.1})",
+                                params.position.x, params.position.y
+                            ));
+                            ui.label(format!("Heading: {:.1}°", params.heading.to_degrees()));
+                            ui.label(format!(
+                                "Queued commands: {}",
+                                turtle.tween_controller.remaining()
+                            ));
+
+                            let mut speed = params.speed.value();
+                            let slider = egui::Slider::new(&mut speed, 1.0..=1100.0)
+                                .logarithmic(true)
+                                .text("Speed");
+                            if ui.add(slider).changed() {
+                                let speed = AnimationSpeed::from_value(speed);
+                                params.speed = speed;
+                                turtle.tween_controller.set_speed(speed);
+                            }
+
+                            let mut color = [
+                                params.color.r,
+                                params.color.g,
+                                params.color.b,
+                                params.color.a,
+                            ];
+                            ui.horizontal(|ui| {
+                                ui.label("Pen color");
+                                if ui.color_edit_button_rgba_unmultiplied(&mut color).changed() {
+                                    params.color =
+                                        Color::new(color[0], color[1], color[2], color[3]);
+                                }
+                            });
+                            ui.checkbox(&mut params.pen_down, "Pen down");
+                            ui.checkbox(&mut params.visible, "Visible");
+                        });
+                }
+            });
+    });
+    egui_macroquad::draw();
+}
@@ -45,42 +45,155 @@
 //!     }
 //! }
 //! ```
+//!
+//! `TurtleApp::run()` runs the same loop as the macro (including the playback
+//! and quit keys), with hooks for per-frame code:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//!
+//! #[macroquad::main("Turtle")]
+//! async fn main() {
+//!     let mut plan = create_turtle_plan();
+//!     plan.forward(100.0).right(90.0).forward(100.0);
+//!
+//!     let mut app = TurtleApp::new().with_commands(plan.build());
+//!     app.run(RunConfig::new().with_background(WHITE)).await;
+//! }
+//! ```
 
+pub mod assertions;
 pub mod builders;
+mod channel;
 pub mod circle_geometry;
+#[cfg(all(feature = "clipboard", not(target_arch = "wasm32")))]
+pub mod clipboard;
+pub mod collision;
 pub mod commands;
 pub mod commands_channel;
+pub mod curves;
+pub mod debugger;
 pub mod drawing;
+pub mod error;
 pub mod execution;
+pub mod follow;
+pub mod formation;
 pub mod general;
+pub mod group;
+pub mod headless;
+pub mod history;
+pub mod input;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+#[cfg(feature = "logging")]
+pub mod logging;
+pub mod maze;
+pub mod noise;
+pub mod palettes;
+pub mod path_offset;
+pub mod physics;
+pub mod plot;
+pub mod random;
+pub mod run;
+pub mod scene;
+pub mod schedule;
+pub mod script;
 pub mod shapes;
 pub mod state;
+pub mod stats;
+pub mod stencil;
+pub mod swarm;
 pub mod tessellation;
+pub mod testing;
+pub mod theme;
+pub mod tiling;
+#[cfg(feature = "trace")]
+pub mod trace;
 pub mod tweening;
+pub mod viewport;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
+#[cfg(feature = "serde")]
+pub mod wire;
 
 // Re-export commonly used types
 pub use builders::{CurvedMovement, DirectionalMovement, Turnable, TurtlePlan, WithCommands};
-pub use commands::{CommandQueue, TurtleCommand};
+pub use collision::{CollisionEvent, CollisionKind, TurtleEventReceiver};
+pub use commands::{CommandQueue, StampId, TurtleCommand, MAX_PEN_WIDTH};
 pub use commands_channel::{turtle_command_channel, TurtleCommandReceiver, TurtleCommandSender};
-pub use general::{Angle, AnimationSpeed, Color, Coordinate, Length, Precision};
-pub use shapes::{ShapeType, TurtleShape};
-pub use state::{DrawCommand, Turtle, TurtleWorld};
-pub use tweening::TweenController;
+pub use error::{CommandProblem, TurtleError};
+pub use formation::Formation;
+pub use general::{
+    color, color_from_hex, parse_color, Angle, AnimationSpeed, Bounds, Color, ColorExt, Coordinate,
+    CoordinateMode, Length, Precision, StatePrecision,
+};
+pub use group::TurtleGroup;
+pub use headless::{simulate, PlanAnalysis, SimulationResult};
+pub use history::WorldHistory;
+pub use input::FrameInput;
+#[cfg(feature = "logging")]
+pub use logging::{init_logging, LevelFilter};
+pub use palettes::Palette;
+pub use random::{random_color, random_heading, random_range, random_seed, seed_random, Rng};
+pub use run::{FrameHook, RunConfig};
+pub use scene::{Scene, StepId};
+pub use script::{parse_script, ScriptError};
+pub use shapes::{ShapeRegistry, ShapeSize, ShapeType, SvgPathError, TurtleShape};
+pub use state::{
+    BlendMode, DrawCommand, ExactPose, GhostTrail, Marker, Message, SavedState, TrailFade, Turtle,
+    TurtleParams, TurtleWorld, MAX_PENDING_ERRORS,
+};
+pub use stats::{DrawingStats, WorldSummary};
+pub use stencil::{Stencil, StencilTransform};
+pub use theme::Theme;
+pub use tweening::{Easing, TweenController};
 
 pub mod export;
 #[cfg(feature = "svg")]
 pub mod export_svg;
 
 // Re-export the turtle_main macro
+/// Turn a drawing function into a complete turtle program
+///
+/// See the crate documentation for the supported signatures and options.
+/// The `TurtlePlan` and `TurtleApp` parameter types don't need to be
+/// imported:
+///
+/// ```no_run
+/// use turtle_lib::turtle_main;
+///
+/// #[turtle_main("Two Turtles")]
+/// fn draw(red: &mut TurtlePlan, blue: &mut TurtlePlan) {
+///     red.set_pen_color(turtle_lib::RED);
+///     blue.set_pen_color(turtle_lib::BLUE);
+/// }
+/// ```
 pub use turtle_lib_macros::turtle_main;
 
+// The egui integration `set_canvas()` cooperates with, same version
+#[cfg(feature = "egui")]
+pub use egui_macroquad;
+
 // Re-export common macroquad types and colors for convenience
 pub use macroquad::prelude::{
-    vec2, BLACK, BLUE, DARKGRAY, GOLD, GREEN, ORANGE, PURPLE, RED, WHITE, YELLOW,
+    vec2, KeyCode, MouseButton, BLACK, BLUE, DARKGRAY, GOLD, GREEN, ORANGE, PURPLE, RED, WHITE,
+    YELLOW,
 };
 
 use macroquad::prelude::*;
 use std::collections::HashMap;
+use std::ops::RangeBounds;
+
+/// Callback for finished commands, see `TurtleApp::on_command_complete()`
+///
+/// Receives the command together with the turtle's state before and after it.
+pub type CommandCallback = Box<dyn FnMut(&TurtleCommand, &TurtleParams, &TurtleParams)>;
+
+/// Help line drawn by `TurtleApp::draw_overlay()` unless configured otherwise
+pub const DEFAULT_OVERLAY_TEXT: &str =
+    "ESC/Q: quit, SPACE: pause, S: step mode, N: next step, UP/DOWN: speed, D: debugger";
 
 /// Main turtle application struct
 pub struct TurtleApp {
@@ -92,6 +205,67 @@ pub struct TurtleApp {
     last_mouse_pos: Option<Vec2>,
     // Zoom state
     zoom_level: f32,
+    // Undo/redo history of submitted command queues
+    history: WorldHistory,
+    // Collision detection (disabled unless enabled explicitly)
+    collisions: Option<collision::CollisionDetector>,
+    // All animations frozen by `pause()`
+    paused: bool,
+    // Queues fill up but nothing runs until `release_all()`
+    held: bool,
+    // Commands only run one at a time via `step()`
+    step_mode: bool,
+    // Playback speed multiplier for all turtles
+    time_scale: f32,
+    // Extra playback speed multipliers by turtle ID, on top of `time_scale`
+    turtle_time_scales: HashMap<usize, f32>,
+    // Lightweight turtles updated and drawn in batches, see `swarm`
+    swarms: Vec<swarm::Swarm>,
+    // Window areas with their own camera, see `viewport`
+    viewports: Vec<viewport::Viewport>,
+    // Window area `render()` and the mouse handling are restricted to
+    canvas: Option<Rect>,
+    // Animation clock and queues waiting for their start, see `schedule`
+    schedule: schedule::Schedule,
+    // Scenes still playing, see `scene`
+    scenes: Vec<scene::Scene>,
+    // Callbacks for finished commands by turtle ID
+    command_callbacks: HashMap<usize, Vec<CommandCallback>>,
+    // Animations advance by explicit time steps instead of the wall clock
+    fixed_timestep: bool,
+    // Keys that make `handle_keys()` report a quit request
+    quit_keys: Vec<KeyCode>,
+    // SPACE/S/N/UP/DOWN/D control the playback in `handle_keys()`
+    playback_keys: bool,
+    // Help line drawn by `draw_overlay()`, `None` hides it
+    overlay_text: Option<String>,
+    // Heading and status line drawn by `draw_overlay()`
+    title_text: Option<String>,
+    status_text: Option<String>,
+    // Key that saves a screenshot in `handle_keys()`
+    screenshot_key: Option<KeyCode>,
+    // Frames written by `capture_frame()`, with the timestep mode to restore
+    frame_capture: Option<(export::FrameCapture, bool)>,
+    // Zoom out whenever the drawing leaves the view, with the number of
+    // draw commands per turtle already included
+    auto_fit: bool,
+    auto_fit_seen: HashMap<usize, usize>,
+    // Turtle picked with `select_turtle()` or a click, and how it is marked
+    selected_turtle: Option<usize>,
+    click_selection: bool,
+    selection_highlight: Option<Color>,
+    // Pulsing turtles: ID, seconds elapsed and total duration
+    highlights: Vec<(usize, f32, f32)>,
+    // Step debugger drawn by `draw_overlay()`, with the number of queued
+    // commands listed per turtle
+    debugger: bool,
+    debugger_lookahead: usize,
+    // Inspector panel drawn by `draw_overlay()`
+    #[cfg(feature = "inspector")]
+    inspector: bool,
+    // Script file re-run on changes, see `watch_script()`
+    #[cfg(feature = "watch")]
+    script_watch: Option<watch::ScriptWatch>,
 }
 
 impl TurtleApp {
@@ -99,6 +273,8 @@ impl TurtleApp {
     #[allow(unused_variables)]
     /// Export the current drawing to a file in the specified format
     ///
+    /// In the browser (wasm32) the file is offered as a download, see `web`.
+    ///
     /// # Errors
     ///
     /// Returns an error if the export fails (e.g., unsupported format, file I/O error)
@@ -131,6 +307,39 @@ impl TurtleApp {
             is_dragging: false,
             last_mouse_pos: None,
             zoom_level: 1.0,
+            history: WorldHistory::new(),
+            collisions: None,
+            paused: false,
+            held: false,
+            step_mode: false,
+            time_scale: 1.0,
+            turtle_time_scales: HashMap::new(),
+            swarms: Vec::new(),
+            viewports: Vec::new(),
+            canvas: None,
+            schedule: schedule::Schedule::new(),
+            scenes: Vec::new(),
+            command_callbacks: HashMap::new(),
+            fixed_timestep: false,
+            quit_keys: vec![KeyCode::Escape, KeyCode::Q],
+            playback_keys: true,
+            overlay_text: Some(DEFAULT_OVERLAY_TEXT.to_string()),
+            title_text: None,
+            status_text: None,
+            screenshot_key: None,
+            frame_capture: None,
+            auto_fit: false,
+            auto_fit_seen: HashMap::new(),
+            selected_turtle: None,
+            click_selection: false,
+            selection_highlight: Some(ORANGE),
+            highlights: Vec::new(),
+            debugger: false,
+            debugger_lookahead: debugger::DEFAULT_LOOKAHEAD,
+            #[cfg(feature = "inspector")]
+            inspector: false,
+            #[cfg(feature = "watch")]
+            script_watch: None,
         }
     }
 
@@ -172,8 +381,51 @@ impl TurtleApp {
     /// ```
     pub fn create_turtle_channel(&mut self, buffer_size: usize) -> TurtleCommandSender {
         let turtle_id = self.world.add_turtle();
-        let (tx, rx) = commands_channel::turtle_command_channel(turtle_id, buffer_size);
-        self.receivers.insert(turtle_id, rx);
+        self.attach_channel(commands_channel::turtle_command_channel(
+            turtle_id,
+            buffer_size,
+        ))
+    }
+
+    /// Create a command channel for an existing turtle (added if missing)
+    ///
+    /// Like `create_turtle_channel()`, but for a turtle that already got
+    /// commands otherwise, e.g. the default turtle 0. Replaces an earlier
+    /// channel of the same turtle.
+    pub fn turtle_channel(&mut self, turtle_id: usize, buffer_size: usize) -> TurtleCommandSender {
+        while self.world.turtles.len() <= turtle_id {
+            self.world.add_turtle();
+        }
+        self.attach_channel(commands_channel::turtle_command_channel(
+            turtle_id,
+            buffer_size,
+        ))
+    }
+
+    /// Create an unbounded command channel for an existing turtle (added if
+    /// missing)
+    ///
+    /// Like `turtle_channel()`, but sending never blocks. Use it for senders
+    /// on the render thread, e.g. in `RunConfig::on_frame()` hooks: commands
+    /// are only received between frames, so a bounded channel filled within
+    /// one frame would block forever.
+    pub fn unbounded_turtle_channel(&mut self, turtle_id: usize) -> TurtleCommandSender {
+        while self.world.turtles.len() <= turtle_id {
+            self.world.add_turtle();
+        }
+        self.attach_channel(commands_channel::unbounded_turtle_command_channel(
+            turtle_id,
+        ))
+    }
+
+    /// Register the receiver of a channel, replacing an earlier one of the
+    /// same turtle
+    fn attach_channel(
+        &mut self,
+        (mut tx, rx): (TurtleCommandSender, TurtleCommandReceiver),
+    ) -> TurtleCommandSender {
+        tx.attach_world(&self.world.shared);
+        self.receivers.insert(tx.turtle_id(), rx);
         tx
     }
 
@@ -181,6 +433,7 @@ impl TurtleApp {
     ///
     /// Call this once per frame in your render loop, before `update()`.
     /// Drains all receivers and applies commands to their respective turtles.
+    /// Also publishes each turtle's state for `TurtleCommandSender::params()`.
     ///
     /// # Examples
     /// ```no_run
@@ -205,13 +458,84 @@ impl TurtleApp {
 
         for turtle_id in turtle_ids {
             if let Some(receiver) = self.receivers.get(&turtle_id) {
-                for queue in receiver.recv_all() {
+                if let Some(turtle) = self.world.get_turtle(turtle_id) {
+                    receiver.publish(&turtle.params);
+                }
+                let (queues, disconnected) = receiver.recv_all_checked();
+                for queue in queues {
                     self.append_commands(turtle_id, queue);
                 }
+                if disconnected {
+                    self.receivers.remove(&turtle_id);
+                    if let Some(turtle) = self.world.get_turtle_mut(turtle_id) {
+                        turtle.report_error(TurtleError::ChannelDisconnected);
+                    }
+                }
             }
         }
     }
 
+    /// Take the problems of all turtles since the last call, with the ID of
+    /// the turtle they happened to
+    ///
+    /// Collects invalid commands dropped when they were queued, fills and
+    /// strokes that couldn't be drawn, unknown marks and shapes and
+    /// disconnected command channels, which are otherwise only logged. Each
+    /// turtle keeps up to `MAX_PENDING_ERRORS` errors until they are taken.
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// # let mut app = TurtleApp::new();
+    /// app.update();
+    /// for (turtle_id, error) in app.take_errors() {
+    ///     eprintln!("turtle {turtle_id}: {error}");
+    /// }
+    /// ```
+    pub fn take_errors(&mut self) -> Vec<(usize, TurtleError)> {
+        self.world
+            .turtles
+            .iter_mut()
+            .flat_map(|turtle| {
+                let id = turtle.turtle_id;
+                std::mem::take(&mut turtle.errors)
+                    .into_iter()
+                    .map(move |error| (id, error))
+            })
+            .collect()
+    }
+
+    /// Set how absolute positions and headings are interpreted
+    ///
+    /// Defaults to `CoordinateMode::MathYUp`. Applies to `go_to()`,
+    /// `set_heading()`, markers and all position queries.
+    #[must_use]
+    pub fn with_coordinate_mode(mut self, mode: CoordinateMode) -> Self {
+        self.world.set_coordinate_mode(mode);
+        self
+    }
+
+    /// Fade out old drawings (see `set_trail_fade()`)
+    #[must_use]
+    pub fn with_trail_fade(mut self, fade: TrailFade) -> Self {
+        self.set_trail_fade(Some(fade));
+        self
+    }
+
+    /// Draw faded copies of the cursors along their paths (see
+    /// `set_ghost_trail()`)
+    #[must_use]
+    pub fn with_ghost_trail(mut self, trail: GhostTrail) -> Self {
+        self.set_ghost_trail(Some(trail));
+        self
+    }
+
+    /// Render into and take input from `canvas` only (see `set_canvas()`)
+    #[must_use]
+    pub fn with_canvas(mut self, canvas: Rect) -> Self {
+        self.set_canvas(Some(canvas));
+        self
+    }
+
     /// Add commands from a turtle plan to the application for the default turtle (ID 0)
     ///
     /// Speed is controlled by `SetSpeed` commands in the queue.
@@ -234,153 +558,1630 @@ impl TurtleApp {
     /// # Arguments
     /// * `turtle_id` - The ID of the turtle to control
     /// * `queue` - The command queue to execute
+    ///
+    /// Invalid commands are dropped and logged like with `append_commands()`.
     #[must_use]
     pub fn with_commands_for_turtle(mut self, turtle_id: usize, queue: CommandQueue) -> Self {
-        // Ensure turtle exists
-        while self.world.turtles.len() <= turtle_id {
-            self.world.add_turtle();
-        }
-
-        // Append commands to the turtle's controller
-        if let Some(turtle) = self.world.get_turtle_mut(turtle_id) {
-            turtle.tween_controller.append_commands(queue);
-        }
+        self.append_commands(turtle_id, queue);
         self
     }
 
     /// Execute a plan immediately on a specific turtle (no animation)
     pub fn execute_immediate(&mut self, turtle_id: usize, plan: TurtlePlan) {
-        for ref cmd in plan.build() {
+        let mut queue = plan.build();
+        while self.world.turtles.len() <= turtle_id {
+            self.world.add_turtle();
+        }
+        for error in queue.remove_invalid() {
+            tracing::error!(turtle_id, %error, "Dropping invalid command");
+            self.world.turtles[turtle_id].report_error(error);
+        }
+        self.history.record(turtle_id, &queue, &self.world);
+        // Looping plans run a single repetition
+        for cmd in queue.commands() {
             execution::execute_command_with_id(cmd, turtle_id, &mut self.world);
         }
     }
 
     /// Append commands to a turtle's animation queue
     pub fn append_to_queue(&mut self, turtle_id: usize, plan: TurtlePlan) {
+        // Ensure turtle exists
+        self.append_commands(turtle_id, plan.build());
+    }
+
+    /// Append `queue` to a turtle's animation queue `delay` seconds from now
+    ///
+    /// Seconds count on the animation clock, see `schedule`.
+    pub fn schedule(&mut self, turtle_id: usize, delay: f64, queue: CommandQueue) {
+        self.schedule.schedule(turtle_id, delay, queue);
+    }
+
+    /// Append `queue` to a turtle's animation queue once the animation clock
+    /// reaches `sim_time` seconds, see `schedule`
+    pub fn schedule_at(&mut self, turtle_id: usize, sim_time: f64, queue: CommandQueue) {
+        self.schedule.schedule_at(turtle_id, sim_time, queue);
+    }
+
+    /// Seconds on the animation clock, which stops while paused or held
+    #[must_use]
+    pub fn sim_time(&self) -> f64 {
+        self.schedule.now()
+    }
+
+    /// Play the steps of `scene` as their dependencies finish, see `scene`
+    pub fn play_scene(&mut self, scene: Scene) {
+        self.scenes.push(scene);
+    }
+
+    /// Whether a scene has steps that aren't done yet
+    #[must_use]
+    pub fn is_scene_playing(&self) -> bool {
+        !self.scenes.is_empty()
+    }
+
+    /// Stop all scenes, running steps finish but no new ones start
+    pub fn stop_scenes(&mut self) {
+        self.scenes.clear();
+    }
+
+    /// Drop all scheduled queues that didn't start yet
+    pub fn clear_schedule(&mut self) {
+        self.schedule.clear();
+    }
+
+    /// Append commands from a `CommandQueue` to a turtle's animation queue
+    ///
+    /// Used internally by `process_commands()` and can be used directly
+    /// when you have a `CommandQueue` instead of a `TurtlePlan`. Commands
+    /// with invalid parameters are dropped and logged, see
+    /// `CommandQueue::remove_invalid()`.
+    pub fn append_commands(&mut self, turtle_id: usize, mut queue: CommandQueue) {
         // Ensure turtle exists
         while self.world.turtles.len() <= turtle_id {
             self.world.add_turtle();
         }
 
+        for error in queue.remove_invalid() {
+            tracing::error!(turtle_id, %error, "Dropping invalid command");
+            self.world.turtles[turtle_id].report_error(error);
+        }
+
+        self.history.record(turtle_id, &queue, &self.world);
+
         if let Some(turtle) = self.world.get_turtle_mut(turtle_id) {
-            turtle.tween_controller.append_commands(plan.build());
+            turtle.tween_controller.append_commands(queue);
         }
     }
 
-    /// Append commands from a `CommandQueue` to a turtle's animation queue
+    /// Insert commands before the `index`-th not yet started command of a
+    /// turtle (0 runs them next)
     ///
-    /// Used internally by `process_commands()` and can be used directly
-    /// when you have a `CommandQueue` instead of a `TurtlePlan`.
-    pub fn append_commands(&mut self, turtle_id: usize, queue: CommandQueue) {
-        // Ensure turtle exists
+    /// Meant for editors that change a program while it is animated. Invalid
+    /// commands are dropped like in `append_commands()`. Edits of pending
+    /// commands aren't recorded in the undo history, which replays queues as
+    /// they were submitted.
+    pub fn splice_commands(&mut self, turtle_id: usize, index: usize, mut queue: CommandQueue) {
         while self.world.turtles.len() <= turtle_id {
             self.world.add_turtle();
         }
 
+        for error in queue.remove_invalid() {
+            tracing::error!(turtle_id, %error, "Dropping invalid command");
+            self.world.turtles[turtle_id].report_error(error);
+        }
+
         if let Some(turtle) = self.world.get_turtle_mut(turtle_id) {
-            turtle.tween_controller.append_commands(queue);
+            turtle.tween_controller.splice_commands(index, queue);
         }
     }
 
-    /// Update animation state (call every frame)
-    pub fn update(&mut self) {
-        // Handle mouse panning and zoom
-        self.handle_mouse_panning();
-        self.handle_mouse_zoom();
+    /// Remove not yet started commands of a turtle, returning them
+    ///
+    /// `range` counts from the next command (0), see
+    /// `CommandQueue::remove_pending()`. The command being animated finishes.
+    /// Like `splice_commands()` this isn't recorded in the undo history.
+    pub fn remove_pending_range(
+        &mut self,
+        turtle_id: usize,
+        range: impl RangeBounds<usize>,
+    ) -> Vec<TurtleCommand> {
+        self.world
+            .get_turtle_mut(turtle_id)
+            .map(|turtle| turtle.tween_controller.remove_pending(range))
+            .unwrap_or_default()
+    }
 
-        // Update all turtles' tween controllers
-        for turtle in &mut self.world.turtles {
-            // Extract draw_commands and controller temporarily to avoid borrow conflicts
+    /// Undo the most recently submitted command queue
+    ///
+    /// The history is world-wide: the latest queue is removed no matter which
+    /// turtle it was sent to. All turtles are rebuilt by replaying the remaining
+    /// history instantly, so running animations are completed.
+    ///
+    /// Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        self.history.undo(&mut self.world)
+    }
 
-            // Update the controller
-            let completed_commands = TweenController::update(turtle);
+    /// Re-apply the most recently undone command queue (instantly)
+    ///
+    /// Submitting new commands clears the redo stack.
+    /// Returns `false` if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        self.history.redo(&mut self.world)
+    }
 
-            // Process all completed commands and add to the turtle's commands
-            for (completed_cmd, tween_start, mut end_state) in completed_commands {
-                let draw_command = execution::add_draw_for_completed_tween(
-                    &completed_cmd,
-                    &tween_start,
-                    &mut end_state,
-                );
-                // Add the new draw commands to the turtle
-                turtle.commands.extend(draw_command);
-            }
+    /// Register a filled polygon shape under `name` for all turtles
+    ///
+    /// Vertices are relative to the turtle position with the shape pointing
+    /// right (heading 0). Select the shape with `TurtlePlan::shape_named()`.
+    /// Registering a name again replaces the shape for later commands.
+    pub fn register_shape(&mut self, name: impl Into<String>, vertices: Vec<Vec2>) {
+        self.world.register_shape(name, vertices);
+    }
+
+    /// Clear the drawings of all turtles
+    ///
+    /// Equivalent to a `ClearScreen` command issued outside of any plan.
+    /// With `rehome` set, all turtles also move back to the origin facing right.
+    pub fn clear_all(&mut self, rehome: bool) {
+        self.world.clear_screen(None, rehome);
+    }
+
+    /// Enable turtle-to-turtle collision detection
+    ///
+    /// Every `update()` checks all visible turtles. Two turtles collide when their
+    /// cursor shapes overlap or their positions are closer than `proximity`
+    /// (pass `0.0` to only consider shape overlap).
+    pub fn enable_collisions(&mut self, proximity: f32) {
+        self.collisions
+            .get_or_insert_with(collision::CollisionDetector::default)
+            .set_proximity(proximity);
+    }
+
+    /// Also let turtles collide with the lines other turtles drew, reported as
+    /// `CollisionKind::Drawing` (enables collision detection if needed)
+    pub fn enable_drawing_collisions(&mut self, enabled: bool) {
+        self.collisions
+            .get_or_insert_with(collision::CollisionDetector::default)
+            .set_drawings(enabled);
+    }
+
+    /// Receive collision events on another thread, see `collision`
+    ///
+    /// Enables collision detection (shape overlap only) if needed. Events
+    /// still show up in `take_collision_events()` too.
+    pub fn subscribe_collisions(&mut self) -> TurtleEventReceiver {
+        self.collisions
+            .get_or_insert_with(collision::CollisionDetector::default)
+            .subscribe()
+    }
+
+    /// Disable collision detection and drop pending collision events
+    pub fn disable_collisions(&mut self) {
+        self.collisions = None;
+    }
+
+    /// Pairs of turtle IDs that collided during the last `update()`
+    ///
+    /// Returns an empty list if collision detection is disabled.
+    #[must_use]
+    pub fn colliding_turtles(&self) -> Vec<(usize, usize)> {
+        self.collisions
+            .as_ref()
+            .map(collision::CollisionDetector::colliding_pairs)
+            .unwrap_or_default()
+    }
+
+    /// Take all collision events since the last call
+    ///
+    /// An event is emitted once when two turtles start colliding; it is emitted
+    /// again only after they separated. Only the latest
+    /// `collision::MAX_PENDING_EVENTS` are kept, so programs that use
+    /// `subscribe_collisions()` alone don't collect events forever.
+    pub fn take_collision_events(&mut self) -> Vec<CollisionEvent> {
+        self.collisions
+            .as_mut()
+            .map(collision::CollisionDetector::take_events)
+            .unwrap_or_default()
+    }
+
+    /// Let a turtle fly with `velocity` once its queue is done, see
+    /// `physics`
+    ///
+    /// The velocity is in pixels per second in the world's `CoordinateMode`,
+    /// like `go_to()`. Starts the physics mode of the turtle if needed.
+    pub fn set_velocity(&mut self, turtle_id: usize, velocity: impl Into<Coordinate>) {
+        let velocity = self.world.coordinate_mode().to_screen(velocity.into());
+        self.physics_mut(turtle_id).velocity = velocity;
+    }
+
+    /// Accelerate a turtle by `force` (pixels per second²) during the next
+    /// frame
+    ///
+    /// Forces add up and are used up by the next `update()`, so continuous
+    /// forces are applied every frame. Starts the physics mode if needed.
+    pub fn apply_force(&mut self, turtle_id: usize, force: impl Into<Coordinate>) {
+        let force = self.world.coordinate_mode().to_screen(force.into());
+        self.physics_mut(turtle_id).force += force;
+    }
+
+    /// Constant acceleration of a turtle in physics mode, e.g.
+    /// `vec2(0.0, -300.0)` to fall down
+    pub fn set_gravity(&mut self, turtle_id: usize, gravity: impl Into<Coordinate>) {
+        let gravity = self.world.coordinate_mode().to_screen(gravity.into());
+        self.physics_mut(turtle_id).gravity = gravity;
+    }
+
+    /// Share of the velocity a turtle in physics mode loses per second
+    /// (clamped to at least `0.0`)
+    pub fn set_drag(&mut self, turtle_id: usize, drag: f32) {
+        self.physics_mut(turtle_id).drag = drag.max(0.0);
+    }
+
+    /// Current velocity of a turtle in the world's `CoordinateMode`, `None`
+    /// if it isn't in physics mode
+    #[must_use]
+    pub fn velocity(&self, turtle_id: usize) -> Option<Coordinate> {
+        let physics = self.world.get_turtle(turtle_id)?.physics?;
+        Some(self.world.coordinate_mode().from_screen(physics.velocity))
+    }
+
+    /// Leave the physics mode, the turtle stays where it is
+    pub fn stop_physics(&mut self, turtle_id: usize) {
+        if let Some(turtle) = self.world.get_turtle_mut(turtle_id) {
+            turtle.physics = None;
         }
     }
-    /// Handle mouse click and drag for panning
-    fn handle_mouse_panning(&mut self) {
-        let mouse_pos = mouse_position();
-        let mouse_pos = vec2(mouse_pos.0, mouse_pos.1);
 
-        if is_mouse_button_pressed(MouseButton::Left) {
-            self.is_dragging = true;
-            self.last_mouse_pos = Some(mouse_pos);
+    fn physics_mut(&mut self, turtle_id: usize) -> &mut physics::Physics {
+        while self.world.turtles.len() <= turtle_id {
+            self.world.add_turtle();
         }
+        self.world.turtles[turtle_id]
+            .physics
+            .get_or_insert_with(physics::Physics::default)
+    }
 
-        if is_mouse_button_released(MouseButton::Left) {
-            self.is_dragging = false;
-            self.last_mouse_pos = None;
+    /// Let turtle `follower_id` trail along the path of `leader_id`,
+    /// `distance` pixels behind it, see `follow`
+    ///
+    /// Replaces what the follower followed before. It draws the path again
+    /// while its pen is down.
+    pub fn set_follow(&mut self, follower_id: usize, leader_id: usize, distance: f32) {
+        while self.world.turtles.len() <= follower_id {
+            self.world.add_turtle();
         }
+        let follower = &mut self.world.turtles[follower_id];
+        follower.follow = Some(follow::Follow::new(
+            leader_id,
+            distance,
+            follower.params.position,
+        ));
+    }
 
-        if self.is_dragging {
-            if let Some(last_pos) = self.last_mouse_pos {
-                // Calculate delta in screen space
-                let delta = mouse_pos - last_pos;
+    /// Stop following, the turtle stays where it is
+    pub fn stop_follow(&mut self, turtle_id: usize) {
+        if let Some(turtle) = self.world.get_turtle_mut(turtle_id) {
+            turtle.follow = None;
+        }
+    }
 
-                // Convert screen delta to world space delta
-                // The camera zoom is 2.0 / screen_width, so world_units = screen_pixels / (screen_size * zoom / 2)
-                let world_delta = vec2(
-                    -delta.x, -delta.y, // Flip Y because screen Y is down
-                );
+    /// Add a swarm of lightweight turtles, returns its ID, see `swarm`
+    ///
+    /// Swarms move with `update()`, follow `pause()` and `set_time_scale()`
+    /// and are drawn on top of the turtles.
+    pub fn add_swarm(&mut self, swarm: swarm::Swarm) -> usize {
+        self.swarms.push(swarm);
+        self.swarms.len() - 1
+    }
 
-                self.world.camera.target += world_delta * self.zoom_level;
-            }
-            self.last_mouse_pos = Some(mouse_pos);
-        }
+    /// The swarm with the ID returned by `add_swarm()`, e.g. to read
+    /// positions for game logic
+    #[must_use]
+    pub fn swarm(&self, id: usize) -> Option<&swarm::Swarm> {
+        self.swarms.get(id)
     }
 
-    /// Handle mouse wheel for zooming
-    fn handle_mouse_zoom(&mut self) {
-        let (_wheel_x, wheel_y) = mouse_wheel();
+    /// Change a swarm, e.g. to queue moves or `steer()` it from a frame hook
+    pub fn swarm_mut(&mut self, id: usize) -> Option<&mut swarm::Swarm> {
+        self.swarms.get_mut(id)
+    }
 
-        if wheel_y != 0.0 {
-            // Zoom factor: positive wheel_y = zoom in, negative = zoom out
-            let zoom_factor = 1.0 + wheel_y * 0.1;
-            self.zoom_level *= zoom_factor;
+    /// Get a marker recorded with `TurtlePlan::mark()`
+    #[must_use]
+    pub fn marker(&self, name: &str) -> Option<Marker> {
+        self.world.marker(name)
+    }
 
-            // Clamp zoom level to reasonable values
-            self.zoom_level = self.zoom_level.clamp(0.1, 10.0);
+    /// All markers recorded so far, by name
+    #[must_use]
+    pub fn markers(&self) -> HashMap<String, Marker> {
+        self.world.markers()
+    }
+
+    /// Take the unread messages sent to a turtle with
+    /// `TurtlePlan::send_message()`, oldest first
+    pub fn read_messages(&mut self, turtle_id: usize) -> Vec<Message> {
+        self.world.read_messages(turtle_id)
+    }
+
+    /// Freeze all animations without losing the progress of running tweens
+    pub fn pause(&mut self) {
+        self.paused = true;
+        for turtle in &mut self.world.turtles {
+            turtle.tween_controller.pause();
         }
     }
 
-    /// Render the turtle world (call every frame)
-    pub fn render(&self) {
-        drawing::render_world_with_tweens(&self.world, self.zoom_level);
+    /// Continue all animations exactly where `pause()` stopped them
+    pub fn resume(&mut self) {
+        self.paused = false;
+        if self.held {
+            return;
+        }
+        for turtle in &mut self.world.turtles {
+            turtle.tween_controller.resume();
+        }
     }
 
-    /// Check if all commands have been executed
-    #[must_use]
-    pub fn is_complete(&self) -> bool {
-        self.world
-            .turtles
-            .iter()
-            .all(|turtle| turtle.tween_controller.is_complete())
+    /// Pause if running, resume if paused
+    pub fn toggle_pause(&mut self) {
+        if self.paused {
+            self.resume();
+        } else {
+            self.pause();
+        }
     }
 
-    /// Check if all animations are complete (alias for is_complete)
     #[must_use]
-    pub fn all_animations_complete(&self) -> bool {
-        self.is_complete()
+    pub fn is_paused(&self) -> bool {
+        self.paused
     }
 
-    /// Set the animation speed for all turtles
+    /// Let all turtles collect commands without running them until
+    /// `release_all()`
     ///
-    /// # Arguments
-    /// * `speed` - The animation speed to set for all turtles
-    pub fn set_all_turtles_speed(&mut self, speed: AnimationSpeed) {
+    /// Made for races: every thread or student submits its plan whenever it
+    /// is ready, and all turtles start in the same frame. Running animations
+    /// stop where they are, like with `pause()`.
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// # use macroquad::prelude::is_key_pressed;
+    /// # #[macroquad::main("Race")]
+    /// # async fn main() {
+    /// let mut app = TurtleApp::new();
+    /// app.hold_all();
+    /// let runners: Vec<_> = (0..4).map(|_| app.create_turtle_channel(10)).collect();
+    /// // ... threads send their plans through `runners` ...
+    /// let config = RunConfig::new().on_frame(|app| {
+    ///     if is_key_pressed(KeyCode::Enter) {
+    ///         app.release_all();
+    ///     }
+    /// });
+    /// app.run(config).await;
+    /// # }
+    /// ```
+    pub fn hold_all(&mut self) {
+        self.held = true;
         for turtle in &mut self.world.turtles {
-            turtle.set_speed(speed);
-            turtle.tween_controller.set_speed(speed);
+            turtle.tween_controller.pause();
+        }
+    }
+
+    /// Start all turtles held by `hold_all()` at once
+    ///
+    /// Turtles stay frozen while the app is paused and start with `resume()`.
+    pub fn release_all(&mut self) {
+        self.held = false;
+        if self.paused {
+            return;
+        }
+        for turtle in &mut self.world.turtles {
+            turtle.tween_controller.resume();
+        }
+    }
+
+    /// Whether `hold_all()` keeps the turtles from starting
+    #[must_use]
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    /// Scale the playback speed of all animations at runtime
+    ///
+    /// `0.5` plays in slow motion, `4.0` four times as fast. This multiplies
+    /// the speed set in the plan with `set_speed()`, so the program itself
+    /// does not need to change. Values are clamped to at least `0.01`.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.time_scale = scale.max(0.01);
+        let scales = &self.turtle_time_scales;
+        for turtle in &mut self.world.turtles {
+            let own = scales.get(&turtle.turtle_id).copied().unwrap_or(1.0);
+            turtle
+                .tween_controller
+                .set_time_scale(self.time_scale * own);
+        }
+    }
+
+    #[must_use]
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Scale the playback speed of one turtle, e.g. to show one algorithm in
+    /// slow motion next to others running normally
+    ///
+    /// Multiplies the app's `set_time_scale()`, the turtle plays at the
+    /// product of both. `pause()` still stops all turtles. Values are clamped
+    /// to at least `0.01`.
+    pub fn set_turtle_time_scale(&mut self, turtle_id: usize, scale: f32) {
+        let scale = scale.max(0.01);
+        if scale == 1.0 {
+            self.turtle_time_scales.remove(&turtle_id);
+        } else {
+            self.turtle_time_scales.insert(turtle_id, scale);
+        }
+        if let Some(turtle) = self.world.get_turtle_mut(turtle_id) {
+            turtle
+                .tween_controller
+                .set_time_scale(self.time_scale * scale);
+        }
+    }
+
+    /// Playback speed multiplier of one turtle, `1.0` unless set with
+    /// `set_turtle_time_scale()`
+    #[must_use]
+    pub fn turtle_time_scale(&self, turtle_id: usize) -> f32 {
+        self.turtle_time_scales
+            .get(&turtle_id)
+            .copied()
+            .unwrap_or(1.0)
+    }
+
+    /// Let drawings fade out as they get older, or `None` to keep them all
+    ///
+    /// `TrailFade::Fade` lowers the opacity of each drawing with its age,
+    /// `TrailFade::Window` only shows what was drawn in the last seconds.
+    /// Fully faded drawings are removed, so endless animations stay cheap.
+    pub fn set_trail_fade(&mut self, fade: Option<TrailFade>) {
+        self.world.trail_fade = fade;
+    }
+
+    #[must_use]
+    pub fn trail_fade(&self) -> Option<TrailFade> {
+        self.world.trail_fade
+    }
+
+    /// Leave faded copies of the turtle cursors along their recent paths,
+    /// or `None` to only draw the cursors themselves
+    ///
+    /// Useful to visualize motion, e.g. in screenshots for teaching
+    /// materials. The ghosts are not part of the drawing and are not
+    /// exported.
+    pub fn set_ghost_trail(&mut self, trail: Option<GhostTrail>) {
+        self.world.ghost_trail = trail;
+    }
+
+    #[must_use]
+    pub fn ghost_trail(&self) -> Option<GhostTrail> {
+        self.world.ghost_trail
+    }
+
+    /// Enable or disable single-step execution
+    ///
+    /// In step mode every turtle stops before its next command until `step()`
+    /// is called. A command that is currently animating still finishes.
+    pub fn set_step_mode(&mut self, enabled: bool) {
+        self.step_mode = enabled;
+        for turtle in &mut self.world.turtles {
+            turtle.tween_controller.set_step_mode(enabled);
+        }
+    }
+
+    #[must_use]
+    pub fn is_step_mode(&self) -> bool {
+        self.step_mode
+    }
+
+    /// Let every turtle run exactly one more command (only in step mode)
+    pub fn step(&mut self) {
+        self.sync_controllers();
+        for turtle in &mut self.world.turtles {
+            turtle.tween_controller.step();
+        }
+    }
+
+    /// Call `callback` whenever a command of turtle `turtle_id` finishes
+    ///
+    /// The callback receives the command and the turtle's state before and
+    /// after it and runs inside `update()`, so it can trigger sounds, update
+    /// a score or queue further commands based on the result. Commands that
+    /// are skipped with `finish_all()` or replayed by undo are not reported.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// let mut app = TurtleApp::new();
+    /// app.on_command_complete(0, |cmd, _start, end| {
+    ///     println!("{cmd:?} finished at {:?}", end.position);
+    /// });
+    /// ```
+    pub fn on_command_complete(
+        &mut self,
+        turtle_id: usize,
+        callback: impl FnMut(&TurtleCommand, &TurtleParams, &TurtleParams) + 'static,
+    ) {
+        self.command_callbacks
+            .entry(turtle_id)
+            .or_default()
+            .push(Box::new(callback));
+    }
+
+    /// Remove all completion callbacks of a turtle
+    pub fn clear_command_callbacks(&mut self, turtle_id: usize) {
+        self.command_callbacks.remove(&turtle_id);
+    }
+
+    /// Run the completion callbacks for all commands finished since the last call
+    fn run_command_callbacks(&mut self) {
+        for turtle in &mut self.world.turtles {
+            let finished = turtle.tween_controller.take_finished();
+            let Some(callbacks) = self.command_callbacks.get_mut(&turtle.turtle_id) else {
+                continue;
+            };
+            for (command, start, end) in &finished {
+                for callback in &mut *callbacks {
+                    callback(command, start, end);
+                }
+            }
+        }
+    }
+
+    /// Apply the app's step mode and time scales to turtles added later
+    fn sync_controllers(&mut self) {
+        for turtle in &mut self.world.turtles {
+            let has_callbacks = self.command_callbacks.contains_key(&turtle.turtle_id);
+            let time_scale = self.time_scale
+                * self
+                    .turtle_time_scales
+                    .get(&turtle.turtle_id)
+                    .copied()
+                    .unwrap_or(1.0);
+            let controller = &mut turtle.tween_controller;
+            if controller.is_tracking_finished() != has_callbacks {
+                controller.set_track_finished(has_callbacks);
+            }
+            if controller.is_step_mode() != self.step_mode {
+                controller.set_step_mode(self.step_mode);
+            }
+            if (controller.time_scale() - time_scale).abs() > f32::EPSILON {
+                controller.set_time_scale(time_scale);
+            }
+            if controller.is_fixed_timestep() != self.fixed_timestep {
+                controller.set_fixed_timestep(self.fixed_timestep);
+            }
+        }
+    }
+
+    /// Advance animations by explicit time steps instead of the wall clock
+    ///
+    /// In fixed-timestep mode `update()` advances by the last frame time and
+    /// `update_with_dt()` by the given step. Enabled automatically by
+    /// `update_with_dt()`.
+    pub fn set_fixed_timestep(&mut self, enabled: bool) {
+        self.fixed_timestep = enabled;
+        self.sync_controllers();
+    }
+
+    #[must_use]
+    pub fn is_fixed_timestep(&self) -> bool {
+        self.fixed_timestep
+    }
+
+    /// Get reference to the undo/redo history
+    #[must_use]
+    pub fn history(&self) -> &WorldHistory {
+        &self.history
+    }
+
+    /// Change how many submitted queues `undo()` can take back (1000 by
+    /// default), older ones become permanent
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history.set_limit(limit, &self.world);
+    }
+
+    /// Update animation state (call every frame)
+    pub fn update(&mut self) {
+        // Handle mouse panning and zoom
+        self.handle_mouse_panning();
+        self.handle_mouse_zoom();
+        if self.click_selection
+            && is_mouse_button_pressed(MouseButton::Left)
+            && self.is_pointer_over()
+        {
+            self.selected_turtle = self.turtle_at(mouse_position().into());
+        }
+        #[cfg(feature = "watch")]
+        self.reload_script();
+
+        let dt = match &self.frame_capture {
+            Some((capture, _)) => capture.frame_time(),
+            None => f64::from(get_frame_time()),
+        };
+        self.update_animations(dt);
+        self.age_highlights(dt);
+        if self.auto_fit {
+            self.fit_drawing();
+        }
+    }
+
+    /// Zoom out automatically whenever the drawing grows beyond the view
+    ///
+    /// Keeps programs that draw off-screen visible. The view only ever
+    /// widens (around the current center), zooming in again is left to the
+    /// mouse wheel. Disabled by default.
+    pub fn set_auto_fit(&mut self, enabled: bool) {
+        self.auto_fit = enabled;
+        self.auto_fit_seen.clear();
+    }
+
+    #[must_use]
+    pub fn is_auto_fit(&self) -> bool {
+        self.auto_fit
+    }
+
+    /// Widen the zoom level so new drawings and all turtles are visible
+    fn fit_drawing(&mut self) {
+        // Pixels kept free between the drawing and the window border
+        const MARGIN: f32 = 20.0;
+
+        let mut bounds = None;
+        for turtle in &self.world.turtles {
+            // Only look at drawings added since the last frame
+            let seen = self.auto_fit_seen.entry(turtle.turtle_id).or_default();
+            if *seen > turtle.commands.len() {
+                *seen = 0;
+            }
+            for cmd in &turtle.commands[*seen..] {
+                match cmd {
+                    DrawCommand::Mesh { data, .. } => {
+                        for vertex in &data.vertices {
+                            Bounds::extend(&mut bounds, vertex.position.truncate());
+                        }
+                    }
+                    DrawCommand::Text { position, .. } => Bounds::extend(&mut bounds, *position),
+                }
+            }
+            *seen = turtle.commands.len();
+
+            let position = turtle
+                .tween_controller
+                .current_tween()
+                .map_or(turtle.params.position, |tween| tween.current_position);
+            Bounds::extend(&mut bounds, position);
+        }
+        let Some(bounds) = bounds else {
+            return;
+        };
+
+        let target = self.world.camera.target;
+        let needed = (bounds.max - target).max(target - bounds.min);
+        let size = self
+            .canvas
+            .map_or(vec2(screen_width(), screen_height()), |canvas| {
+                canvas.size()
+            });
+        let available = size / 2.0 - MARGIN;
+        let zoom = (needed / available.max(Vec2::ONE)).max_element();
+        if zoom > self.zoom_level {
+            self.zoom_level = zoom;
+        }
+    }
+
+    /// Write every rendered frame as numbered PNG into `dir`
+    ///
+    /// While capturing, `update()` advances the animations by exactly
+    /// `1 / fps` seconds per frame (fixed timestep), so the frames play back
+    /// at the right speed no matter how fast they were rendered. Call
+    /// `capture_frame()` after `render()` every frame (`run()` and the
+    /// `turtle_main` macro do this). Capturing stops once all animations are
+    /// complete or with `stop_frame_capture()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created
+    pub fn start_frame_capture(
+        &mut self,
+        dir: impl Into<std::path::PathBuf>,
+        fps: f64,
+    ) -> Result<(), export::ExportError> {
+        let capture = export::FrameCapture::new(dir, fps)?;
+        let previous = self
+            .frame_capture
+            .take()
+            .map_or(self.fixed_timestep, |(_, previous)| previous);
+        self.frame_capture = Some((capture, previous));
+        self.set_fixed_timestep(true);
+        Ok(())
+    }
+
+    /// Stop capturing frames and return the number of frames written
+    pub fn stop_frame_capture(&mut self) -> usize {
+        let Some((capture, previous)) = self.frame_capture.take() else {
+            return 0;
+        };
+        self.set_fixed_timestep(previous);
+        tracing::info!(frames = capture.frames(), "Frame capture finished");
+        capture.frames()
+    }
+
+    #[must_use]
+    pub fn is_capturing_frames(&self) -> bool {
+        self.frame_capture.is_some()
+    }
+
+    /// Write the current frame if a frame capture is running (call after
+    /// `render()`)
+    ///
+    /// A frame that cannot be written is logged and stops the capture.
+    pub fn capture_frame(&mut self) {
+        let Some((mut capture, previous)) = self.frame_capture.take() else {
+            return;
+        };
+        let written = capture.write_frame(self);
+        self.frame_capture = Some((capture, previous));
+        if let Err(error) = written {
+            tracing::error!(?error, "Cannot write frame, stopping the capture");
+            self.stop_frame_capture();
+        } else if self.is_complete() {
+            self.stop_frame_capture();
+        }
+    }
+
+    /// Run a script file on turtle 0 and re-run it whenever the file changes
+    ///
+    /// On every change the previous drawings, turtles and the undo history
+    /// are reset before the new script starts. Scripts with errors are
+    /// skipped (the error is logged) and the previous drawing stays visible.
+    /// See the `script` module for the script language.
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// # #[macroquad::main("Live")]
+    /// # async fn main() {
+    /// let mut app = TurtleApp::new();
+    /// app.watch_script("drawing.turtle");
+    /// # }
+    /// ```
+    #[cfg(feature = "watch")]
+    pub fn watch_script(&mut self, path: impl Into<std::path::PathBuf>) {
+        self.script_watch = Some(watch::ScriptWatch::new(path));
+    }
+
+    /// Stop watching the script file (the current drawing stays)
+    #[cfg(feature = "watch")]
+    pub fn stop_watching(&mut self) {
+        self.script_watch = None;
+    }
+
+    /// Re-run the watched script if it changed
+    #[cfg(feature = "watch")]
+    fn reload_script(&mut self) {
+        let Some(plan) = self
+            .script_watch
+            .as_mut()
+            .and_then(|watch| watch.poll(get_time()))
+        else {
+            return;
+        };
+        self.history.clear();
+        // Replaying the empty history resets all turtles and markers
+        self.history.replay(&mut self.world);
+        self.append_to_queue(0, plan);
+    }
+
+    /// Advance all animations by exactly `dt` seconds
+    ///
+    /// Switches to fixed-timestep mode, so the result only depends on the
+    /// sequence of steps and not on wall-clock timing. Useful for tests and
+    /// offline rendering: calling `update_with_dt(1.0 / 60.0)` once per frame
+    /// produces identical frames on every run. Mouse input is not handled.
+    pub fn update_with_dt(&mut self, dt: f64) {
+        self.fixed_timestep = true;
+        self.update_animations(dt);
+        self.age_highlights(dt);
+    }
+
+    /// Advance the pulses of `highlight_turtle()`, also while paused
+    fn age_highlights(&mut self, dt: f64) {
+        for highlight in &mut self.highlights {
+            highlight.1 += dt as f32;
+        }
+        self.highlights
+            .retain(|(_, elapsed, duration)| elapsed < duration);
+    }
+
+    /// Advance all turtles (`dt` only counts for tweens in fixed-timestep mode,
+    /// physics always uses it)
+    fn update_animations(&mut self, dt: f64) {
+        if self.paused || self.held {
+            return;
+        }
+
+        for (turtle_id, queue) in self.schedule.advance(dt * f64::from(self.time_scale)) {
+            self.append_commands(turtle_id, queue);
+        }
+        let mut starts = Vec::new();
+        for scene in &mut self.scenes {
+            starts.extend(scene.update(&self.world));
+        }
+        self.scenes.retain(|scene| !scene.is_finished());
+        for (turtle_id, queue) in starts {
+            self.append_commands(turtle_id, queue);
+        }
+        self.sync_controllers();
+        self.world.release_barriers();
+
+        // Update all turtles' tween controllers
+        for turtle in &mut self.world.turtles {
+            turtle.tween_controller.advance(dt);
+
+            // Update the controller
+            let completed_commands = TweenController::update(turtle);
+
+            // Process all completed commands and add to the turtle's commands
+            for (completed_cmd, tween_start, mut end_state) in completed_commands {
+                match execution::add_draw_for_completed_tween(
+                    &completed_cmd,
+                    &tween_start,
+                    &mut end_state,
+                    turtle.tween_controller.now(),
+                ) {
+                    // Add the new draw commands to the turtle
+                    Ok(draw_command) => turtle.commands.extend(draw_command),
+                    Err(error) => turtle.report_error(error),
+                }
+            }
+
+            let time_scale = turtle.tween_controller.time_scale();
+            physics::step(turtle, dt as f32 * time_scale);
+        }
+        follow::step(&mut self.world);
+        for swarm in &mut self.swarms {
+            swarm.update(dt as f32 * self.time_scale);
+        }
+
+        self.world.apply_world_requests();
+        self.world.prune_faded();
+        self.world.record_ghosts();
+        self.run_command_callbacks();
+
+        if let Some(detector) = &mut self.collisions {
+            detector.update(&self.world);
+        }
+    }
+    /// Handle mouse click and drag for panning
+    fn handle_mouse_panning(&mut self) {
+        let mouse_pos = mouse_position();
+        let mouse_pos = vec2(mouse_pos.0, mouse_pos.1);
+
+        if is_mouse_button_pressed(MouseButton::Left) && self.is_pointer_over() {
+            self.is_dragging = true;
+            self.last_mouse_pos = Some(mouse_pos);
+        }
+
+        if is_mouse_button_released(MouseButton::Left) {
+            self.is_dragging = false;
+            self.last_mouse_pos = None;
+        }
+
+        if self.is_dragging {
+            if let Some(last_pos) = self.last_mouse_pos {
+                // Calculate delta in screen space
+                let delta = mouse_pos - last_pos;
+
+                // Convert screen delta to world space delta
+                // The camera zoom is 2.0 / screen_width, so world_units = screen_pixels / (screen_size * zoom / 2)
+                let world_delta = vec2(
+                    -delta.x, -delta.y, // Flip Y because screen Y is down
+                );
+
+                self.world.camera.target += world_delta * self.zoom_level;
+            }
+            self.last_mouse_pos = Some(mouse_pos);
+        }
+    }
+
+    /// Handle mouse wheel for zooming
+    fn handle_mouse_zoom(&mut self) {
+        let (_wheel_x, wheel_y) = mouse_wheel();
+
+        if wheel_y != 0.0 && self.is_pointer_over() {
+            // Zoom factor: positive wheel_y = zoom in, negative = zoom out
+            let zoom_factor = 1.0 + wheel_y * 0.1;
+            self.zoom_level *= zoom_factor;
+
+            // Clamp zoom level to reasonable values
+            self.zoom_level = self.zoom_level.clamp(0.1, 10.0);
+        }
+    }
+
+    /// Keys that make `handle_keys()` return `true` (ESC and Q by default)
+    ///
+    /// Pass an empty list if the embedding application handles quitting itself.
+    pub fn set_quit_keys(&mut self, keys: &[KeyCode]) {
+        self.quit_keys = keys.to_vec();
+    }
+
+    #[must_use]
+    pub fn quit_keys(&self) -> &[KeyCode] {
+        &self.quit_keys
+    }
+
+    /// Enable or disable the playback keys of `handle_keys()`
+    ///
+    /// SPACE pauses, S toggles step mode, N runs the next step and UP/DOWN
+    /// double or halve the playback speed. Enabled by default.
+    pub fn set_playback_keys(&mut self, enabled: bool) {
+        self.playback_keys = enabled;
+    }
+
+    #[must_use]
+    pub fn playback_keys(&self) -> bool {
+        self.playback_keys
+    }
+
+    /// Key that makes `handle_keys()` save a screenshot (`None` by default)
+    ///
+    /// Screenshots are numbered `turtle-screenshot-1.png`,
+    /// `turtle-screenshot-2.png`, ... in the working directory, existing
+    /// files are not overwritten.
+    pub fn set_screenshot_key(&mut self, key: Option<KeyCode>) {
+        self.screenshot_key = key;
+    }
+
+    #[must_use]
+    pub fn screenshot_key(&self) -> Option<KeyCode> {
+        self.screenshot_key
+    }
+
+    /// Save the current window contents as PNG
+    ///
+    /// Captures everything drawn so far in this frame, so call it after
+    /// `render()` (and before `draw_overlay()` to leave out the help line).
+    /// Unlike `export_drawing()` this includes the turtles and whatever else
+    /// was drawn in the window. Use `headless::export_png()` to render without
+    /// a window.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub fn save_screenshot(&self, path: &str) -> Result<(), export::ExportError> {
+        let screen = get_screen_data();
+        // The framebuffer is stored bottom-up
+        let row = usize::from(screen.width) * 4;
+        let image = Image {
+            bytes: screen
+                .bytes
+                .chunks_exact(row)
+                .rev()
+                .flatten()
+                .copied()
+                .collect(),
+            width: screen.width,
+            height: screen.height,
+        };
+        headless::write_png(&image, path)?;
+        tracing::info!(path, "Screenshot saved");
+        Ok(())
+    }
+
+    /// Place the current drawing on the system clipboard, see `clipboard`
+    ///
+    /// Images have the size of the window (or the canvas, see
+    /// `set_canvas()`) and are centered on the camera target like at zoom
+    /// level 1.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExportError::Clipboard` if the clipboard is unavailable
+    #[cfg(all(feature = "clipboard", not(target_arch = "wasm32")))]
+    pub fn copy_to_clipboard(&self, format: clipboard::Format) -> Result<(), export::ExportError> {
+        let size = self
+            .canvas
+            .map_or(vec2(screen_width(), screen_height()), |canvas| {
+                canvas.size()
+            });
+        clipboard::copy(&self.world, format, size.x as u16, size.y as u16)?;
+        tracing::info!(?format, "Drawing copied to the clipboard");
+        Ok(())
+    }
+
+    /// Set the help line drawn by `draw_overlay()`, or `None` to hide it
+    pub fn set_overlay_text(&mut self, text: Option<impl Into<String>>) {
+        self.overlay_text = text.map(Into::into);
+    }
+
+    #[must_use]
+    pub fn overlay_text(&self) -> Option<&str> {
+        self.overlay_text.as_deref()
+    }
+
+    /// Show a heading at the top of the window, e.g. the name of the current
+    /// exercise (`None` removes it)
+    ///
+    /// Drawn by `draw_overlay()` above the help line. The title bar of the
+    /// window itself is set once at startup (see the `title` option of
+    /// `turtle_main`), macroquad cannot change it while running.
+    pub fn set_title(&mut self, title: Option<impl Into<String>>) {
+        self.title_text = title.map(Into::into);
+    }
+
+    #[must_use]
+    pub fn title(&self) -> Option<&str> {
+        self.title_text.as_deref()
+    }
+
+    /// Show a status line at the bottom of the window, e.g. progress or a
+    /// score (`None` removes it)
+    ///
+    /// Drawn by `draw_overlay()`; can be changed every frame.
+    pub fn set_status_text(&mut self, text: Option<impl Into<String>>) {
+        self.status_text = text.map(Into::into);
+    }
+
+    #[must_use]
+    pub fn status_text(&self) -> Option<&str> {
+        self.status_text.as_deref()
+    }
+
+    /// Handle the playback, screenshot and quit keys (call every frame)
+    ///
+    /// Returns `true` if one of the quit keys was pressed; leaving the main
+    /// loop is up to the caller. With a canvas (see `set_canvas()`) the
+    /// playback and screenshot keys only work while the mouse is over it.
+    pub fn handle_keys(&mut self) -> bool {
+        // Apps sharing the window only react while the mouse is over them
+        let focused = self.canvas.is_none() || self.is_pointer_over();
+        if self.playback_keys && focused {
+            if is_key_pressed(KeyCode::Space) {
+                self.toggle_pause();
+            }
+            if is_key_pressed(KeyCode::S) {
+                self.set_step_mode(!self.step_mode);
+            }
+            if is_key_pressed(KeyCode::N) {
+                self.step();
+            }
+            if is_key_pressed(KeyCode::Up) {
+                self.set_time_scale(self.time_scale * 2.0);
+            }
+            if is_key_pressed(KeyCode::Down) {
+                self.set_time_scale(self.time_scale / 2.0);
+            }
+            if is_key_pressed(KeyCode::D) {
+                self.set_debugger(!self.is_debugger_visible());
+            }
+        }
+
+        if let Some(key) = self.screenshot_key.filter(|_| focused) {
+            if is_key_pressed(key) {
+                let path = (1..)
+                    .map(|n| format!("turtle-screenshot-{n}.png"))
+                    .find(|path| !std::path::Path::new(path).exists())
+                    .unwrap_or_default();
+                if let Err(error) = self.save_screenshot(&path) {
+                    tracing::error!(path, ?error, "Cannot save screenshot");
+                }
+            }
+        }
+
+        self.quit_keys.iter().any(|key| is_key_pressed(*key))
+    }
+
+    /// Run the standard main loop until a quit key is pressed or the window
+    /// is closed
+    ///
+    /// Every frame clears the background, calls the `on_frame` hooks,
+    /// processes channel commands, advances the animations, renders the world
+    /// (capturing it, see `start_frame_capture()`), calls the `after_render`
+    /// hooks, draws the help line and handles the playback and quit keys (see
+    /// `handle_keys()`). The `turtle_main` macro runs the same loop.
+    ///
+    /// ```no_run
+    /// # use turtle_lib::*;
+    /// # #[macroquad::main("Turtle")]
+    /// # async fn main() {
+    /// let mut plan = create_turtle_plan();
+    /// plan.forward(100.0).right(90.0).forward(100.0);
+    ///
+    /// let mut app = TurtleApp::new().with_commands(plan.build());
+    /// app.run(RunConfig::new()).await;
+    /// # }
+    /// ```
+    pub async fn run(&mut self, mut config: RunConfig) {
+        if config.auto_export.is_some() {
+            // Closing the window exports first
+            prevent_quit();
+        }
+
+        loop {
+            clear_background(config.background);
+            for hook in &mut config.frame_hooks {
+                hook(self);
+            }
+            self.process_commands();
+            self.update();
+            self.render();
+            self.capture_frame();
+            if let Some(export) = &mut config.auto_export {
+                export.after_render(self);
+            }
+            for hook in &mut config.render_hooks {
+                hook(self);
+            }
+            // Keys first, so hotkey screenshots leave out the help line
+            let quit = self.handle_keys();
+            self.draw_overlay();
+
+            if quit || is_quit_requested() {
+                if let Some(export) = &mut config.auto_export {
+                    export.before_exit(self, config.background);
+                }
+                break;
+            }
+
+            next_frame().await;
+        }
+    }
+
+    /// Show or hide the step debugger
+    ///
+    /// For every turtle it shows the running command with its parameters,
+    /// the progress of its animation and the next few queued commands. Drawn
+    /// by `draw_overlay()`; the `D` key toggles it while the playback keys
+    /// are enabled.
+    pub fn set_debugger(&mut self, visible: bool) {
+        self.debugger = visible;
+    }
+
+    #[must_use]
+    pub fn is_debugger_visible(&self) -> bool {
+        self.debugger
+    }
+
+    /// Number of queued commands the step debugger lists per turtle
+    /// (default `debugger::DEFAULT_LOOKAHEAD`)
+    pub fn set_debugger_lookahead(&mut self, count: usize) {
+        self.debugger_lookahead = count;
+    }
+
+    /// Show or hide the inspector panel
+    ///
+    /// The panel lists every turtle's position, heading and queued commands
+    /// and allows changing speed, pen color, pen state and visibility live.
+    /// Drawn by `draw_overlay()`.
+    #[cfg(feature = "inspector")]
+    pub fn set_inspector(&mut self, visible: bool) {
+        self.inspector = visible;
+    }
+
+    #[cfg(feature = "inspector")]
+    #[must_use]
+    pub fn is_inspector_visible(&self) -> bool {
+        self.inspector
+    }
+
+    /// Draw the title, help line, status line, step debugger and (with the
+    /// `inspector` feature) the inspector panel in screen space (call after
+    /// `render()`)
+    pub fn draw_overlay(&mut self) {
+        let theme = &self.world.theme;
+        let draw_text_line = |text: &str, y: f32, font_size: f32| {
+            if let Some(backdrop) = theme.text_backdrop {
+                let size = measure_text(text, None, font_size as u16, 1.0);
+                draw_rectangle(
+                    6.0,
+                    y - size.offset_y - 4.0,
+                    size.width + 8.0,
+                    size.height + 8.0,
+                    backdrop,
+                );
+            }
+            draw_text(text, 10.0, y, font_size, theme.text);
+        };
+        let mut help_y = 40.0;
+        if let Some(title) = &self.title_text {
+            draw_text_line(title, 36.0, 32.0);
+            help_y = 60.0;
+        }
+        if let Some(text) = &self.overlay_text {
+            draw_text_line(text, help_y, 16.0);
+        }
+        if let Some(status) = &self.status_text {
+            draw_text_line(status, screen_height() - 12.0, 20.0);
+        }
+        if self.debugger {
+            debugger::draw(&self.world, self.debugger_lookahead);
+        }
+        #[cfg(feature = "inspector")]
+        if self.inspector {
+            inspector::draw(&mut self.world);
+        }
+    }
+
+    /// Capture this frame's keyboard and mouse state
+    ///
+    /// The mouse position is converted to world coordinates, taking panning,
+    /// zoom and the `CoordinateMode` into account.
+    #[must_use]
+    pub fn frame_input(&self) -> FrameInput {
+        let camera = self.camera();
+        let mouse = camera.screen_to_world(mouse_position().into());
+        FrameInput::capture(self.world.coordinate_mode().from_screen(mouse))
+    }
+
+    /// Render the turtle world (call every frame)
+    pub fn render(&self) {
+        if !self.viewports.is_empty() {
+            self.render_viewports();
+            return;
+        }
+        match self.canvas {
+            Some(canvas) => {
+                set_default_camera();
+                let Rect { x, y, w, h } = canvas;
+                draw_rectangle(x, y, w, h, self.world.background_color);
+                drawing::draw_background_gradient_in(&self.world, canvas);
+            }
+            None => drawing::draw_background_gradient(&self.world),
+        }
+        self.render_with_camera(&self.camera());
+    }
+
+    /// Render the turtle world into `target` instead of the window, e.g. to
+    /// show a drawing as a texture inside a larger game
+    ///
+    /// The target is cleared with the world's background first. Panning and
+    /// zoom apply as in `render()`, viewports and the canvas are ignored.
+    /// Mouse handling in `update()` still refers to the window, disable it
+    /// with `set_click_selection(false)` if the texture is drawn elsewhere.
+    ///
+    /// ```no_run
+    /// # async fn doc() {
+    /// use macroquad::prelude::*;
+    /// use turtle_lib::*;
+    ///
+    /// let target = render_target(400, 300);
+    /// let mut app = TurtleApp::new();
+    /// loop {
+    ///     app.update();
+    ///     app.render_to(&target);
+    ///
+    ///     clear_background(DARKGRAY);
+    ///     draw_texture(&target.texture, 100.0, 80.0, WHITE);
+    ///     next_frame().await;
+    /// }
+    /// # }
+    /// ```
+    pub fn render_to(&self, target: &RenderTarget) {
+        drawing::draw_background_to(&self.world, target);
+        self.render_with_camera(&drawing::target_camera(
+            &self.world,
+            self.zoom_level,
+            target,
+        ));
+    }
+
+    /// Drawings, swarms and highlights through `camera`
+    fn render_with_camera(&self, camera: &Camera2D) {
+        drawing::render_world_with_camera(&self.world, camera);
+        for swarm in &self.swarms {
+            swarm.draw(self.world.coordinate_mode(), camera);
+        }
+        let highlight = |id, scale, color| {
+            drawing::draw_cursor_highlight_with_camera(
+                &self.world,
+                camera,
+                self.zoom_level,
+                id,
+                scale,
+                color,
+            );
+        };
+        if let (Some(id), Some(color)) = (self.selected_turtle, self.selection_highlight) {
+            highlight(id, 1.3, color);
+        }
+        for &(id, elapsed, _) in &self.highlights {
+            // Two pulses per second
+            let pulse = (elapsed * std::f32::consts::TAU * 2.0).sin() * 0.5 + 0.5;
+            let color = Color {
+                a: 0.4 + 0.6 * pulse,
+                ..self.world.theme.highlight
+            };
+            let scale = 1.2 + 0.4 * pulse;
+            highlight(id, scale, color);
+        }
+    }
+
+    /// Camera of the main view, limited to the canvas if there is one
+    fn camera(&self) -> Camera2D {
+        match self.canvas {
+            Some(canvas) => viewport::Viewport::new(canvas, viewport::CameraMode::World)
+                .camera(&self.world, self.zoom_level),
+            None => drawing::world_camera(&self.world, self.zoom_level),
+        }
+    }
+
+    /// Whether the mouse is over the canvas and not over an egui window or
+    /// panel, so clicks, drags and the wheel belong to the turtles
+    ///
+    /// Without a canvas this is the whole window. With several apps in one
+    /// window, input goes to the app under the mouse, see `viewport`.
+    #[must_use]
+    pub fn is_pointer_over(&self) -> bool {
+        let mouse = Vec2::from(mouse_position());
+        if self.canvas.is_some_and(|canvas| !canvas.contains(mouse)) {
+            return false;
+        }
+        #[cfg(feature = "egui")]
+        {
+            let mut egui_wants_pointer = false;
+            egui_macroquad::cfg(|ctx| {
+                egui_wants_pointer = ctx.wants_pointer_input() || ctx.is_pointer_over_area();
+            });
+            if egui_wants_pointer {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Restrict `render()` and the mouse handling to `canvas` (window pixels
+    /// from the top left), e.g. to leave room for control panels next to
+    /// the drawing
+    ///
+    /// The world is centered in the canvas and clicks, drags and the mouse
+    /// wheel outside of it are ignored. With the `egui` feature the same
+    /// goes for input egui uses, so `egui-macroquad` panels (re-exported as
+    /// `turtle_lib::egui_macroquad`) work next to or on top of the canvas:
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "egui")]
+    /// # async fn doc() {
+    /// use macroquad::prelude::*;
+    /// use turtle_lib::egui_macroquad::{self, egui};
+    /// use turtle_lib::*;
+    ///
+    /// let mut app = TurtleApp::new();
+    /// let mut speed = 1.0;
+    /// loop {
+    ///     let mut panel_width = 0.0;
+    ///     egui_macroquad::ui(|ctx| {
+    ///         panel_width = egui::SidePanel::left("controls")
+    ///             .show(ctx, |ui| ui.add(egui::Slider::new(&mut speed, 0.1..=5.0)))
+    ///             .response
+    ///             .rect
+    ///             .width();
+    ///     });
+    ///     app.set_time_scale(speed);
+    ///     app.set_canvas(Some(Rect::new(
+    ///         panel_width,
+    ///         0.0,
+    ///         screen_width() - panel_width,
+    ///         screen_height(),
+    ///     )));
+    ///
+    ///     clear_background(WHITE);
+    ///     app.update();
+    ///     app.render();
+    ///     egui_macroquad::draw();
+    ///     next_frame().await;
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// The canvas is filled with the world's background color, so several
+    /// apps can share a window with their own backgrounds (see `viewport`).
+    /// `None` (the default) uses the whole window. Viewports take precedence
+    /// over the canvas.
+    pub fn set_canvas(&mut self, canvas: Option<Rect>) {
+        self.canvas = canvas;
+    }
+
+    #[must_use]
+    pub fn canvas(&self) -> Option<Rect> {
+        self.canvas
+    }
+
+    /// Render the world into each viewport, see `viewport`
+    fn render_viewports(&self) {
+        drawing::draw_background_gradient(&self.world);
+        for viewport in &self.viewports {
+            let camera = viewport.camera(&self.world, self.zoom_level);
+            drawing::render_world_with_camera(&self.world, &camera);
+            for swarm in &self.swarms {
+                swarm.draw(self.world.coordinate_mode(), &camera);
+            }
+        }
+    }
+
+    /// Render the world into `rect` (window pixels from the top left) as seen
+    /// by `mode`, returns the viewport's ID
+    ///
+    /// With viewports `render()` only draws into them, without selection
+    /// and highlight outlines. See `viewport` for a split screen example.
+    pub fn add_viewport(&mut self, rect: Rect, mode: viewport::CameraMode) -> usize {
+        self.viewports.push(viewport::Viewport::new(rect, mode));
+        self.viewports.len() - 1
+    }
+
+    /// Change the rectangle or camera of a viewport, e.g. after the window
+    /// was resized
+    pub fn viewport_mut(&mut self, id: usize) -> Option<&mut viewport::Viewport> {
+        self.viewports.get_mut(id)
+    }
+
+    #[must_use]
+    pub fn viewports(&self) -> &[viewport::Viewport] {
+        &self.viewports
+    }
+
+    /// Remove all viewports, `render()` draws the whole window again
+    pub fn clear_viewports(&mut self) {
+        self.viewports.clear();
+    }
+
+    /// Draw attention to a turtle by pulsing an outline around its cursor
+    /// for `duration` seconds
+    ///
+    /// Handy to point out a single turtle in programs with many of them.
+    /// Highlighting a turtle again restarts its pulse.
+    pub fn highlight_turtle(&mut self, id: usize, duration: f32) {
+        self.highlights
+            .retain(|(highlighted, _, _)| *highlighted != id);
+        self.highlights.push((id, 0.0, duration));
+    }
+
+    /// Whether `highlight_turtle()` is still pulsing the turtle
+    #[must_use]
+    pub fn is_highlighted(&self, id: usize) -> bool {
+        self.highlights
+            .iter()
+            .any(|(highlighted, _, _)| *highlighted == id)
+    }
+
+    /// ID of the topmost visible turtle under a position in window pixels,
+    /// e.g. `mouse_position()`
+    ///
+    /// Hits are tested against the rotated and scaled cursor shape, taking
+    /// panning and zoom into account.
+    #[must_use]
+    pub fn turtle_at(&self, screen_pos: Vec2) -> Option<usize> {
+        let camera = self.camera();
+        self.world.turtle_at(camera.screen_to_world(screen_pos))
+    }
+
+    /// Select turtles by clicking on them with the left mouse button
+    ///
+    /// Clicking the background clears the selection. Disabled by default.
+    pub fn set_click_selection(&mut self, enabled: bool) {
+        self.click_selection = enabled;
+    }
+
+    #[must_use]
+    pub fn is_click_selection(&self) -> bool {
+        self.click_selection
+    }
+
+    /// Select a turtle (or none), as if it was clicked
+    pub fn select_turtle(&mut self, id: Option<usize>) {
+        self.selected_turtle = id;
+    }
+
+    /// The turtle selected with `select_turtle()` or by clicking
+    #[must_use]
+    pub fn selected_turtle(&self) -> Option<usize> {
+        self.selected_turtle
+    }
+
+    /// Color of the outline drawn around the selected turtle, `None` to not
+    /// mark the selection (orange by default)
+    pub fn set_selection_highlight(&mut self, color: Option<Color>) {
+        self.selection_highlight = color;
+    }
+
+    /// Switch to high-contrast, colorblind-safe colors for everything the
+    /// library draws itself
+    ///
+    /// The overlay text gets a white backdrop, turtles that keep the default
+    /// cursor color and the selection and highlight outlines use strong
+    /// Okabe-Ito colors, and `theme().palette` becomes `Palette::classroom()`.
+    /// Drawings keep their colors. See `Theme::high_contrast()`.
+    pub fn set_high_contrast(&mut self, enabled: bool) {
+        let theme = if enabled {
+            Theme::high_contrast()
+        } else {
+            Theme::standard()
+        };
+        // Only replace a selection color that was not chosen explicitly
+        if self.selection_highlight == Some(self.world.theme.selection) {
+            self.selection_highlight = Some(theme.selection);
+        }
+        self.world.theme = theme;
+    }
+
+    #[must_use]
+    pub fn is_high_contrast(&self) -> bool {
+        self.world.theme == Theme::high_contrast()
+    }
+
+    /// Colors of the overlay, cursors and helper marks
+    #[must_use]
+    pub fn theme(&self) -> &Theme {
+        &self.world.theme
+    }
+
+    /// Jump to the final picture
+    ///
+    /// Completes all running tweens and executes every queued command
+    /// instantly, regardless of speed, pause or step mode.
+    pub fn finish_all(&mut self) {
+        for turtle in &mut self.world.turtles {
+            TweenController::finish(turtle);
+        }
+
+        self.world.apply_world_requests();
+
+        if let Some(detector) = &mut self.collisions {
+            detector.update(&self.world);
+        }
+    }
+
+    /// Check if all commands have been executed
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.world
+            .turtles
+            .iter()
+            .all(|turtle| turtle.tween_controller.is_complete())
+    }
+
+    /// Check if all animations are complete (alias for is_complete)
+    #[must_use]
+    pub fn all_animations_complete(&self) -> bool {
+        self.is_complete()
+    }
+
+    /// Set the animation speed for all turtles
+    ///
+    /// # Arguments
+    /// * `speed` - The animation speed to set for all turtles
+    pub fn set_all_turtles_speed(&mut self, speed: AnimationSpeed) {
+        for turtle in &mut self.world.turtles {
+            turtle.set_speed(speed);
+            turtle.tween_controller.set_speed(speed);
+        }
+    }
+
+    /// Set the animation speed of one turtle right away, see
+    /// `set_all_turtles_speed()`
+    pub fn set_turtle_speed(&mut self, turtle_id: usize, speed: impl Into<AnimationSpeed>) {
+        let speed = speed.into();
+        if let Some(turtle) = self.world.get_turtle_mut(turtle_id) {
+            turtle.set_speed(speed);
+            turtle.tween_controller.set_speed(speed);
+        }
+    }
+
+    /// Show or hide the cursor of a turtle right away, without waiting for
+    /// its queue like `TurtlePlan::hide()`
+    pub fn set_turtle_visible(&mut self, turtle_id: usize, visible: bool) {
+        if let Some(turtle) = self.world.get_turtle_mut(turtle_id) {
+            turtle.params.visible = visible;
+            turtle.tween_controller.set_visible(visible);
         }
     }
 
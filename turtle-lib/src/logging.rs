@@ -0,0 +1,30 @@
+//! Tracing subscriber setup for programs that just want to see the log
+//!
+//! Enabled with the `logging` feature. Programs with their own subscriber do
+//! not need this module.
+
+use tracing_subscriber::EnvFilter;
+
+pub use tracing_subscriber::filter::LevelFilter;
+
+/// Print tracing events up to `level` to stderr
+///
+/// The `RUST_LOG` environment variable takes precedence over `level` if it
+/// is set (e.g. `RUST_LOG=turtle_lib=trace`). Does nothing if a global
+/// subscriber is already installed, so calling it twice is harmless.
+///
+/// ```no_run
+/// turtle_lib::init_logging(turtle_lib::LevelFilter::DEBUG);
+/// ```
+pub fn init_logging(level: LevelFilter) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::default().add_directive(level.into()));
+    let result = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(true)
+        .with_line_number(true)
+        .try_init();
+    if result.is_err() {
+        tracing::debug!("A tracing subscriber is already installed");
+    }
+}
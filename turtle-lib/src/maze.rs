@@ -0,0 +1,378 @@
+//! Grid mazes to generate, draw and solve
+//!
+//! A `Maze` is plain data: a grid of cells with walls between them. Draw it
+//! with one turtle and animate a solution with another, or write your own
+//! solver against `open_neighbors()` and check it with `is_path()`:
+//!
+//! ```
+//! use turtle_lib::*;
+//! use turtle_lib::maze::{Maze, MazeAlgorithm};
+//!
+//! let maze = Maze::generate(12, 8, MazeAlgorithm::Backtracker, &mut Rng::new(7));
+//! let mut builder = create_turtle_plan();
+//! maze.draw(&mut builder, 30.0);
+//!
+//! let path = maze.solve().expect("generated mazes are connected");
+//! let mut solver = create_turtle_plan();
+//! solver.set_pen_color(RED);
+//! maze.walk(&mut solver, &path, 30.0);
+//! ```
+//!
+//! Cells are `(column, row)` with row 0 at the top. The maze is drawn
+//! centered on the turtle origin. Generated mazes have exactly one path
+//! between any two cells, and are entered on the west side of `start()` and
+//! left on the east side of `goal()`.
+
+use crate::builders::TurtlePlan;
+use crate::general::{Coordinate, Precision};
+use crate::random::Rng;
+use macroquad::prelude::vec2;
+use std::collections::VecDeque;
+
+/// A cell as `(column, row)`
+pub type Cell = (usize, usize);
+
+/// Side of a cell, north is up
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    #[must_use]
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+
+    /// Turtle heading in degrees that points this way
+    #[must_use]
+    pub fn heading(self) -> Precision {
+        match self {
+            Direction::North => 90.0,
+            Direction::East => 0.0,
+            Direction::South => 270.0,
+            Direction::West => 180.0,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// How `Maze::generate()` carves the passages
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MazeAlgorithm {
+    /// Depth-first search with backtracking: long, winding corridors
+    #[default]
+    Backtracker,
+    /// Every cell opens north or east: fast, with two open outer sides and
+    /// a bias towards the north-east corner
+    BinaryTree,
+}
+
+/// Rectangular grid maze, see the module documentation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Maze {
+    width: usize,
+    height: usize,
+    // One bit per closed side, see `Direction::bit()`
+    walls: Vec<u8>,
+}
+
+impl Maze {
+    /// Maze with every wall standing (at least 1×1 cells)
+    #[must_use]
+    pub fn closed(width: usize, height: usize) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+        Self {
+            width,
+            height,
+            walls: vec![0b1111; width * height],
+        }
+    }
+
+    /// Random maze with one path between any two cells
+    #[must_use]
+    pub fn generate(width: usize, height: usize, algorithm: MazeAlgorithm, rng: &mut Rng) -> Self {
+        let mut maze = Self::closed(width, height);
+        match algorithm {
+            MazeAlgorithm::Backtracker => maze.carve_backtracker(rng),
+            MazeAlgorithm::BinaryTree => maze.carve_binary_tree(rng),
+        }
+        maze.carve(maze.start(), Direction::West);
+        maze.carve(maze.goal(), Direction::East);
+        maze
+    }
+
+    fn carve_backtracker(&mut self, rng: &mut Rng) {
+        let mut visited = vec![false; self.walls.len()];
+        let mut stack = vec![self.start()];
+        visited[self.index(self.start())] = true;
+        while let Some(&cell) = stack.last() {
+            let unvisited: Vec<_> = Direction::ALL
+                .into_iter()
+                .filter_map(|direction| Some((direction, self.neighbor(cell, direction)?)))
+                .filter(|&(_, next)| !visited[self.index(next)])
+                .collect();
+            match rng.choose(&unvisited) {
+                Some(&(direction, next)) => {
+                    self.carve(cell, direction);
+                    visited[self.index(next)] = true;
+                    stack.push(next);
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    fn carve_binary_tree(&mut self, rng: &mut Rng) {
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let cell = (column, row);
+                let open: Vec<_> = [Direction::North, Direction::East]
+                    .into_iter()
+                    .filter(|&direction| self.neighbor(cell, direction).is_some())
+                    .collect();
+                if let Some(&direction) = rng.choose(&open) {
+                    self.carve(cell, direction);
+                }
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The top left cell
+    #[must_use]
+    pub fn start(&self) -> Cell {
+        (0, 0)
+    }
+
+    /// The bottom right cell
+    #[must_use]
+    pub fn goal(&self) -> Cell {
+        (self.width - 1, self.height - 1)
+    }
+
+    fn index(&self, (column, row): Cell) -> usize {
+        row * self.width + column
+    }
+
+    /// The adjacent cell in `direction`, walls or not, `None` at the border
+    #[must_use]
+    pub fn neighbor(&self, (column, row): Cell, direction: Direction) -> Option<Cell> {
+        let next = match direction {
+            Direction::North => (column, row.checked_sub(1)?),
+            Direction::East => (column + 1, row),
+            Direction::South => (column, row + 1),
+            Direction::West => (column.checked_sub(1)?, row),
+        };
+        (next.0 < self.width && next.1 < self.height).then_some(next)
+    }
+
+    /// Whether the side of `cell` in `direction` is closed
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell` is outside of the maze
+    #[must_use]
+    pub fn has_wall(&self, cell: Cell, direction: Direction) -> bool {
+        self.walls[self.index(cell)] & direction.bit() != 0
+    }
+
+    /// Remove the wall on the side of `cell` in `direction`, from both cells
+    /// it separates
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell` is outside of the maze
+    pub fn carve(&mut self, cell: Cell, direction: Direction) {
+        let index = self.index(cell);
+        self.walls[index] &= !direction.bit();
+        if let Some(next) = self.neighbor(cell, direction) {
+            let index = self.index(next);
+            self.walls[index] &= !direction.opposite().bit();
+        }
+    }
+
+    /// Cells reachable from `cell` in one step
+    #[must_use]
+    pub fn open_neighbors(&self, cell: Cell) -> Vec<Cell> {
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| !self.has_wall(cell, direction))
+            .filter_map(|direction| self.neighbor(cell, direction))
+            .collect()
+    }
+
+    /// Whether `path` leads from `start()` to `goal()` through open sides only
+    #[must_use]
+    pub fn is_path(&self, path: &[Cell]) -> bool {
+        path.first() == Some(&self.start())
+            && path.last() == Some(&self.goal())
+            && path
+                .iter()
+                .all(|&(column, row)| column < self.width && row < self.height)
+            && path
+                .windows(2)
+                .all(|step| self.open_neighbors(step[0]).contains(&step[1]))
+    }
+
+    /// Shortest path from `start()` to `goal()` (breadth-first search),
+    /// `None` if the goal can't be reached
+    #[must_use]
+    pub fn solve(&self) -> Option<Vec<Cell>> {
+        let mut came_from: Vec<Option<Cell>> = vec![None; self.walls.len()];
+        let mut queue = VecDeque::from([self.start()]);
+        came_from[self.index(self.start())] = Some(self.start());
+        while let Some(cell) = queue.pop_front() {
+            if cell == self.goal() {
+                let mut path = vec![cell];
+                let mut current = cell;
+                while current != self.start() {
+                    current = came_from[self.index(current)]?;
+                    path.push(current);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.open_neighbors(cell) {
+                let seen = &mut came_from[self.index(next)];
+                if seen.is_none() {
+                    *seen = Some(cell);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Turtle position of the top left corner of `cell`, for cells
+    /// `cell_size` pixels wide
+    fn corner(&self, (column, row): Cell, cell_size: Precision) -> Coordinate {
+        let size = vec2(self.width as Precision, self.height as Precision) * cell_size;
+        vec2(-size.x / 2.0, size.y / 2.0)
+            + vec2(column as Precision, -(row as Precision)) * cell_size
+    }
+
+    /// Turtle position of the middle of `cell`
+    #[must_use]
+    pub fn cell_center(&self, cell: Cell, cell_size: Precision) -> Coordinate {
+        self.corner(cell, cell_size) + vec2(cell_size, -cell_size) / 2.0
+    }
+
+    /// Draw all walls, joining walls in a row into one line
+    ///
+    /// Moves with the pen up between walls and leaves it down.
+    pub fn draw(&self, plan: &mut TurtlePlan, cell_size: Precision) {
+        for row in 0..=self.height {
+            let closed = |column| {
+                (row < self.height && self.has_wall((column, row), Direction::North))
+                    || (row > 0 && self.has_wall((column, row - 1), Direction::South))
+            };
+            for (first, last) in runs(self.width, closed) {
+                let from = self.corner((first, row), cell_size);
+                let to = self.corner((last + 1, row), cell_size);
+                plan.pen_up().go_to(from).pen_down().go_to(to);
+            }
+        }
+        for column in 0..=self.width {
+            let closed = |row| {
+                (column < self.width && self.has_wall((column, row), Direction::West))
+                    || (column > 0 && self.has_wall((column - 1, row), Direction::East))
+            };
+            for (first, last) in runs(self.height, closed) {
+                let from = self.corner((column, first), cell_size);
+                let to = self.corner((column, last + 1), cell_size);
+                plan.pen_up().go_to(from).pen_down().go_to(to);
+            }
+        }
+    }
+
+    /// Move through the middle of every cell of `path`, e.g. the result of
+    /// `solve()`
+    ///
+    /// The turtle jumps to the first cell with the pen up and draws the rest.
+    pub fn walk(&self, plan: &mut TurtlePlan, path: &[Cell], cell_size: Precision) {
+        let mut centers = path.iter().map(|&cell| self.cell_center(cell, cell_size));
+        let Some(first) = centers.next() else {
+            return;
+        };
+        plan.pen_up().go_to(first).pen_down();
+        for center in centers {
+            plan.go_to(center);
+        }
+    }
+}
+
+/// Ranges `(first, last)` of consecutive indices below `count` for which
+/// `closed` is true
+fn runs(count: usize, closed: impl Fn(usize) -> bool) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut first = None;
+    for index in 0..=count {
+        match (first, index < count && closed(index)) {
+            (None, true) => first = Some(index),
+            (Some(start), false) => {
+                runs.push((start, index - 1));
+                first = None;
+            }
+            _ => {}
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_mazes_are_perfect() {
+        for algorithm in [MazeAlgorithm::Backtracker, MazeAlgorithm::BinaryTree] {
+            let maze = Maze::generate(9, 6, algorithm, &mut Rng::new(3));
+            // A spanning tree of the grid has one passage less than cells
+            let passages: usize = (0..6)
+                .flat_map(|row| (0..9).map(move |column| (column, row)))
+                .map(|cell| {
+                    maze.open_neighbors(cell)
+                        .into_iter()
+                        .filter(|&next| next > cell)
+                        .count()
+                })
+                .sum();
+            assert_eq!(passages, 9 * 6 - 1);
+
+            let path = maze.solve().unwrap();
+            assert!(maze.is_path(&path));
+            assert!(!maze.is_path(&path[1..]));
+        }
+        assert_eq!(Maze::closed(3, 3).solve(), None);
+    }
+}
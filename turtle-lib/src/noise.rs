@@ -0,0 +1,251 @@
+//! Smooth noise and noise-driven wandering for generative drawings
+//!
+//! Random turns make a turtle jitter, noise makes it meander: neighbouring
+//! inputs give similar values, so paths bend gradually like a river or a
+//! root. `wander()` turns this into commands, `Noise` is there for your own
+//! flow fields, terrains or color gradients:
+//!
+//! ```
+//! use turtle_lib::*;
+//! use turtle_lib::noise::{wander, Noise};
+//!
+//! let mut turtle = create_turtle_plan();
+//! for seed in 0..5 {
+//!     turtle.pen_up().go_to(vec2(0.0, 0.0)).pen_down();
+//!     wander(300, 0.05).with_seed(seed).draw(&mut turtle);
+//! }
+//!
+//! let noise = Noise::new(42);
+//! let height = noise.fractal(1.5, 2.5, 4);
+//! assert!((-1.0..=1.0).contains(&height));
+//! ```
+//!
+//! The same seed always gives the same noise, on every platform. Without
+//! `with_seed()` the seed is drawn from the shared generator, so
+//! `seed_random()` makes wandering reproducible too.
+
+use crate::builders::{DirectionalMovement, Turnable, TurtlePlan};
+use crate::general::Precision;
+use crate::random::{random_u64, Rng};
+
+/// Distance in pixels `wander()` moves per step unless configured otherwise
+pub const DEFAULT_STEP_LENGTH: Precision = 4.0;
+
+/// Largest turn in degrees per step of `wander()` unless configured otherwise
+pub const DEFAULT_MAX_TURN: Precision = 30.0;
+
+/// Seeded 2D gradient noise with values in `-1.0..=1.0`
+///
+/// Features are about one unit apart: scale the input down for slower
+/// changes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Noise {
+    // Shuffled 0..=255, twice, so two lookups never wrap
+    permutation: [u8; 512],
+}
+
+impl Noise {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        for i in (1..table.len()).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+        Self {
+            permutation: std::array::from_fn(|i| table[i % 256]),
+        }
+    }
+
+    fn hash(&self, x: i32, y: i32) -> u8 {
+        let x = self.permutation[(x & 255) as usize];
+        self.permutation[usize::from(x) + (y & 255) as usize]
+    }
+
+    /// Classic Perlin noise at `(x, y)`, `0.0` at whole coordinates
+    #[must_use]
+    pub fn perlin(&self, x: f32, y: f32) -> f32 {
+        let (cell_x, cell_y) = (x.floor(), y.floor());
+        let (fx, fy) = (x - cell_x, y - cell_y);
+        let (ix, iy) = (cell_x as i32, cell_y as i32);
+        let corner = |dx: i32, dy: i32| {
+            let hash = self.hash(ix.wrapping_add(dx), iy.wrapping_add(dy));
+            gradient(hash, fx - dx as f32, fy - dy as f32)
+        };
+        let (u, v) = (fade(fx), fade(fy));
+        let top = lerp(corner(0, 0), corner(1, 0), u);
+        let bottom = lerp(corner(0, 1), corner(1, 1), u);
+        lerp(top, bottom, v).clamp(-1.0, 1.0)
+    }
+
+    /// Simplex noise at `(x, y)`: cheaper than `perlin()` and without its
+    /// grid-aligned artifacts
+    #[must_use]
+    pub fn simplex(&self, x: f32, y: f32) -> f32 {
+        // Skew factors between the square grid and the triangle grid
+        const F2: f32 = 0.366_025_42;
+        const G2: f32 = 0.211_324_87;
+
+        let skew = (x + y) * F2;
+        let (i, j) = ((x + skew).floor(), (y + skew).floor());
+        let unskew = (i + j) * G2;
+        let (x0, y0) = (x - (i - unskew), y - (j - unskew));
+        // Which of the two triangles of the cell the point is in
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let (ix, iy) = (i as i32, j as i32);
+        let corners = [
+            (0, 0, x0, y0),
+            (i1, j1, x0 - i1 as f32 + G2, y0 - j1 as f32 + G2),
+            (1, 1, x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2),
+        ];
+        let sum: f32 = corners
+            .into_iter()
+            .map(|(di, dj, cx, cy)| {
+                let falloff = 0.5 - cx * cx - cy * cy;
+                if falloff <= 0.0 {
+                    0.0
+                } else {
+                    let hash = self.hash(ix.wrapping_add(di), iy.wrapping_add(dj));
+                    falloff.powi(4) * gradient(hash, cx, cy)
+                }
+            })
+            .sum();
+        (70.0 * sum).clamp(-1.0, 1.0)
+    }
+
+    /// Perlin noise of `octaves` layers, each with twice the detail and half
+    /// the strength of the previous one
+    #[must_use]
+    pub fn fractal(&self, x: f32, y: f32, octaves: u32) -> f32 {
+        let (mut sum, mut total) = (0.0, 0.0);
+        let (mut frequency, mut amplitude) = (1.0, 1.0);
+        for octave in 0..octaves.max(1) {
+            // Offset the layers so their zeros don't line up
+            let offset = octave as f32 * 17.31;
+            sum += amplitude * self.perlin(x * frequency + offset, y * frequency + offset);
+            total += amplitude;
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+        sum / total
+    }
+}
+
+fn gradient(hash: u8, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => y - x,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+/// Smoothstep with zero first and second derivative at both ends
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+/// A meandering path, created with `wander()`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wander {
+    steps: usize,
+    noise_scale: Precision,
+    seed: Option<u64>,
+    step_length: Precision,
+    max_turn: Precision,
+}
+
+/// Path of `steps` short moves, each turning by noise sampled `noise_scale`
+/// further along than the last
+///
+/// Small scales (around `0.02`) give wide, lazy curves, large ones (around
+/// `0.5`) tight wiggles.
+#[must_use]
+pub fn wander(steps: usize, noise_scale: Precision) -> Wander {
+    Wander {
+        steps,
+        noise_scale,
+        seed: None,
+        step_length: DEFAULT_STEP_LENGTH,
+        max_turn: DEFAULT_MAX_TURN,
+    }
+}
+
+impl Wander {
+    /// Noise seed, the same seed gives the same path
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Distance in pixels per step
+    #[must_use]
+    pub fn with_step_length(mut self, length: Precision) -> Self {
+        self.step_length = length;
+        self
+    }
+
+    /// Largest turn in degrees per step
+    #[must_use]
+    pub fn with_max_turn(mut self, degrees: Precision) -> Self {
+        self.max_turn = degrees;
+        self
+    }
+
+    /// Turn in degrees before every step, positive turns right
+    #[must_use]
+    pub fn turns(&self) -> Vec<Precision> {
+        let noise = Noise::new(self.seed.unwrap_or_else(random_u64));
+        (0..self.steps)
+            // Off the grid lines, where Perlin noise is always zero
+            .map(|step| noise.perlin(step as f32 * self.noise_scale, 0.5) * self.max_turn)
+            .collect()
+    }
+
+    /// Add the turns and moves to `plan`, starting from its current pose
+    pub fn draw(&self, plan: &mut TurtlePlan) {
+        for turn in self.turns() {
+            plan.right(turn).forward(self.step_length);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_smooth_and_seeded() {
+        let noise = Noise::new(1);
+        assert_eq!(noise.perlin(3.0, -2.0), 0.0);
+        for step in 0..1000 {
+            let x = step as f32 * 0.013 - 5.0;
+            let y = x * 0.7 + 0.3;
+            for value in [
+                noise.perlin(x, y),
+                noise.simplex(x, y),
+                noise.fractal(x, y, 4),
+            ] {
+                assert!((-1.0..=1.0).contains(&value));
+            }
+            // Neighbouring inputs give neighbouring values
+            assert!((noise.perlin(x, y) - noise.perlin(x + 0.01, y)).abs() < 0.05);
+        }
+        assert_ne!(Noise::new(1), Noise::new(2));
+
+        let path = wander(50, 0.1).with_seed(9);
+        assert_eq!(path.turns(), path.turns());
+        assert_eq!(path.turns().len(), 50);
+        assert!(path.turns().iter().any(|&turn| turn != 0.0));
+    }
+}
@@ -0,0 +1,108 @@
+//! Color palettes for generative drawings
+//!
+//! A `Palette` is a fixed list of colors that repeats. Pick colors by index
+//! with `Palette::color()`, or let a turtle cycle through them with
+//! `TurtlePlan::set_pen_palette()`:
+//!
+//! ```
+//! # use turtle_lib::*;
+//! let mut turtle = create_turtle_plan();
+//! turtle.set_pen_palette(Palette::viridis());
+//! for _ in 0..12 {
+//!     // Every line gets the next color
+//!     turtle.forward(100.0).right(150.0);
+//! }
+//! ```
+
+use crate::general::{Color, ColorExt};
+
+/// A repeating list of colors
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Palette {
+    #[cfg_attr(feature = "serde", serde(with = "crate::wire::color_list"))]
+    colors: Vec<Color>,
+}
+
+impl Palette {
+    /// Palette of the given colors, in order
+    #[must_use]
+    pub fn new(colors: Vec<Color>) -> Self {
+        Self { colors }
+    }
+
+    /// Perceptually uniform dark blue to yellow gradient (matplotlib's viridis)
+    #[must_use]
+    pub fn viridis() -> Self {
+        Self::from_rgb(&[
+            0x44_01_54, 0x46_32_7e, 0x36_5c_8d, 0x27_7f_8e, 0x1f_a1_87, 0x4a_c1_6d, 0xa0_da_39,
+            0xfd_e7_25,
+        ])
+    }
+
+    /// Soft light colors (ColorBrewer's Pastel1)
+    #[must_use]
+    pub fn pastel() -> Self {
+        Self::from_rgb(&[
+            0xfb_b4_ae, 0xb3_cd_e3, 0xcc_eb_c5, 0xde_cb_e4, 0xfe_d9_a6, 0xff_ff_cc, 0xe5_d8_bd,
+            0xfd_da_ec,
+        ])
+    }
+
+    /// Strong colors that stay distinguishable with color blindness and on
+    /// projectors (Okabe-Ito)
+    #[must_use]
+    pub fn classroom() -> Self {
+        Self::from_rgb(&[
+            0xe6_9f_00, 0x56_b4_e9, 0x00_9e_73, 0xf0_e4_42, 0x00_72_b2, 0xd5_5e_00, 0xcc_79_a7,
+            0x00_00_00,
+        ])
+    }
+
+    /// `steps` fully saturated colors evenly spread around the hue circle
+    #[must_use]
+    pub fn rainbow(steps: usize) -> Self {
+        let steps = steps.max(1);
+        Self::new(
+            (0..steps)
+                .map(|i| Color::from_hsv(360.0 * i as f32 / steps as f32, 1.0, 1.0))
+                .collect(),
+        )
+    }
+
+    fn from_rgb(table: &[u32]) -> Self {
+        Self::new(
+            table
+                .iter()
+                .map(|rgb| Color::from_rgba((rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8, 255))
+                .collect(),
+        )
+    }
+
+    /// The color at `index`, starting over after the last color
+    ///
+    /// An empty palette returns black.
+    #[must_use]
+    pub fn color(&self, index: usize) -> Color {
+        if self.colors.is_empty() {
+            return Color::new(0.0, 0.0, 0.0, 1.0);
+        }
+        self.colors[index % self.colors.len()]
+    }
+
+    /// All colors of the palette
+    #[must_use]
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+}
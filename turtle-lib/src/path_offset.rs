@@ -0,0 +1,228 @@
+//! Parallel outlines of drawn paths, for borders, roads and plotter work
+//!
+//! `TurtlePlan::offset_last_path()` takes the path the turtle drew last (all
+//! connected lines and arcs up to the most recent one) and draws it again
+//! `distance` pixels to the side, with the current pen:
+//!
+//! ```
+//! use turtle_lib::*;
+//!
+//! let mut turtle = create_turtle_plan();
+//! // A road: the center line with a curb on both sides
+//! turtle.forward(100.0).circle_left(60.0, 90.0, 18).forward(50.0);
+//! turtle.set_pen_color(GREEN);
+//! turtle.offset_last_path(15.0).offset_last_path(-15.0);
+//! ```
+//!
+//! Open paths are offset to the left of the turtle's direction for positive
+//! distances and to the right for negative ones. Closed paths grow for
+//! positive and shrink for negative distances. Corners are mitered, sharp ones
+//! are cut off at `MITER_LIMIT` times the distance.
+
+use crate::commands::TurtleCommand;
+use crate::error::TurtleError;
+use crate::general::{Coordinate, Precision};
+use crate::state::{DrawCommand, Turtle, TurtleSource};
+use crate::stats::{arc_points, polygon_area};
+use crate::tessellation;
+
+/// Longest miter of a corner, in multiples of the offset distance
+pub const MITER_LIMIT: Precision = 4.0;
+
+// Points closer than this are merged, and a path ending this close to its
+// start is closed
+const EPSILON: Precision = 1e-3;
+
+/// Points of the most recent connected path in `commands`, in drawing order
+///
+/// Fills, stamps, text and earlier outlines in between are skipped. `None`
+/// if nothing was drawn with the pen.
+#[must_use]
+pub fn last_path(commands: &[DrawCommand]) -> Option<Vec<Coordinate>> {
+    let mut pieces: Vec<Vec<Coordinate>> = Vec::new();
+    for command in commands.iter().rev() {
+        let DrawCommand::Mesh { source, .. } = command else {
+            continue;
+        };
+        let Some(points) = stroke_points(source) else {
+            continue;
+        };
+        let connected = match (pieces.last().and_then(|piece| piece.first()), points.last()) {
+            (None, _) => true,
+            (Some(start), Some(end)) => start.distance(*end) < EPSILON,
+            (Some(_), None) => false,
+        };
+        if !connected {
+            break;
+        }
+        pieces.push(points);
+    }
+
+    let mut path: Vec<Coordinate> = Vec::new();
+    for point in pieces.into_iter().rev().flatten() {
+        if !path
+            .last()
+            .is_some_and(|last| last.distance(point) < EPSILON)
+        {
+            path.push(point);
+        }
+    }
+    (path.len() >= 2).then_some(path)
+}
+
+/// Points along a stroke, `None` for drawings that aren't lines or arcs
+pub(crate) fn stroke_points(source: &TurtleSource) -> Option<Vec<Coordinate>> {
+    match &source.command {
+        TurtleCommand::Move(_) | TurtleCommand::Goto(_) => {
+            Some(vec![source.start_position, source.end_position])
+        }
+        TurtleCommand::Circle {
+            radius,
+            angle,
+            steps,
+            direction,
+        } => Some(arc_points(source, *radius, *angle, *steps, *direction).collect()),
+        // The sampled path of a group is kept as a single contour
+        TurtleCommand::Together(_) => source.contours.as_ref()?.first().cloned(),
+        // Outlines don't count, so a path can be offset several times
+        _ => None,
+    }
+}
+
+/// Path `distance` pixels beside `points` (screen space)
+///
+/// Closed paths repeat their first point at the end. See the module
+/// documentation for the direction of the offset.
+#[must_use]
+pub fn offset_path(points: &[Coordinate], distance: Precision) -> Vec<Coordinate> {
+    let mut points = points.to_vec();
+    points.dedup_by(|a, b| a.distance(*b) < EPSILON);
+    let closed = points.len() > 2 && points[0].distance(points[points.len() - 1]) < EPSILON;
+    if closed {
+        points.pop();
+    }
+    if points.len() < 2 {
+        return points;
+    }
+
+    // Screen space has y down: a positive area runs clockwise on screen,
+    // with the outside to the left
+    let distance = if closed && polygon_area(&points) < 0.0 {
+        -distance
+    } else {
+        distance
+    };
+    // Left of the direction of travel as seen on screen
+    let normal = |from: Coordinate, to: Coordinate| {
+        let direction = (to - from).normalize_or_zero();
+        Coordinate::new(direction.y, -direction.x)
+    };
+
+    let count = points.len();
+    let mut outline: Vec<Coordinate> = (0..count)
+        .map(|i| {
+            let previous = (closed || i > 0).then(|| points[(i + count - 1) % count]);
+            let next = (closed || i + 1 < count).then(|| points[(i + 1) % count]);
+            let point = points[i];
+            match (previous, next) {
+                (Some(previous), Some(next)) => {
+                    let (incoming, outgoing) = (normal(previous, point), normal(point, next));
+                    let miter = (incoming + outgoing).normalize_or_zero();
+                    let cosine = miter.dot(incoming);
+                    if cosine < EPSILON {
+                        // The path turns back on itself
+                        point + incoming * distance
+                    } else {
+                        let length = (distance / cosine)
+                            .clamp(-MITER_LIMIT * distance.abs(), MITER_LIMIT * distance.abs());
+                        point + miter * length
+                    }
+                }
+                (Some(previous), None) => point + normal(previous, point) * distance,
+                (None, Some(next)) => point + normal(point, next) * distance,
+                (None, None) => point,
+            }
+        })
+        .collect();
+    if closed {
+        outline.push(outline[0]);
+    }
+    outline
+}
+
+/// Draw the outline of the turtle's last path, see
+/// `TurtlePlan::offset_last_path()`
+pub(crate) fn offset_last_path(state: &mut Turtle, distance: Precision) {
+    let Some(path) = last_path(&state.commands) else {
+        tracing::warn!(
+            turtle_id = state.turtle_id,
+            "offset_last_path() without a drawn path"
+        );
+        state.report_error(TurtleError::NoPathToOffset);
+        return;
+    };
+    let outline = offset_path(&path, distance);
+    let closed = outline.len() > 2 && outline.first() == outline.last();
+    let vertices = if closed {
+        &outline[..outline.len() - 1]
+    } else {
+        &outline[..]
+    };
+    match tessellation::tessellate_stroke(
+        vertices,
+        state.params.color,
+        state.params.pen_width,
+        closed,
+    ) {
+        Ok(mesh_data) => state.commands.push(DrawCommand::Mesh {
+            data: mesh_data,
+            source: TurtleSource {
+                command: TurtleCommand::OffsetLastPath(distance),
+                color: state.params.color,
+                fill_color: state.params.fill_color.unwrap_or(macroquad::prelude::BLACK),
+                pen_width: state.params.pen_width,
+                start_position: outline[0],
+                end_position: outline[outline.len() - 1],
+                start_heading: state.params.heading,
+                contours: Some(vec![outline]),
+                created_at: state.tween_controller.now(),
+                blend_mode: state.params.blend_mode,
+                annotation: None,
+            },
+        }),
+        Err(error) => state.report_error(TurtleError::Tessellation(error.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::prelude::vec2;
+
+    #[test]
+    fn outlines_grow_closed_paths() {
+        // Drawn with left turns in screen space (counter-clockwise on screen)
+        let square = [
+            vec2(0.0, 0.0),
+            vec2(10.0, 0.0),
+            vec2(10.0, -10.0),
+            vec2(0.0, -10.0),
+            vec2(0.0, 0.0),
+        ];
+        let grown = offset_path(&square, 2.0);
+        assert_eq!(grown.len(), 5);
+        assert_eq!(grown.first(), grown.last());
+        assert!((polygon_area(&grown[..4]).abs() - 196.0).abs() < 1e-3);
+        assert!(grown[0].distance(vec2(-2.0, 2.0)) < 1e-4);
+        let shrunk = offset_path(&square, -2.0);
+        assert!((polygon_area(&shrunk[..4]).abs() - 36.0).abs() < 1e-3);
+
+        // Open paths go to the left of the direction of travel (up on screen)
+        let line = offset_path(&[vec2(0.0, 0.0), vec2(10.0, 0.0)], 3.0);
+        assert_eq!(line, vec![vec2(0.0, -3.0), vec2(10.0, -3.0)]);
+
+        // A hairpin is cut at the miter limit instead of shooting off
+        let hairpin = offset_path(&[vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(0.0, 0.1)], 1.0);
+        assert!(hairpin[1].distance(vec2(10.0, 0.0)) <= MITER_LIMIT + 1e-4);
+    }
+}
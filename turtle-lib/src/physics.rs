@@ -0,0 +1,155 @@
+//! Velocity-based motion for projectiles, orbits and other simulations
+//!
+//! Besides working through its commands, a turtle can fly on its own:
+//! `TurtleApp::set_velocity()` starts the physics mode, after which the
+//! velocity is integrated every frame together with gravity, drag and the
+//! forces added with `apply_force()`. The turtle faces where it flies and
+//! draws its path while the pen is down:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//!
+//! #[macroquad::main("Projectile")]
+//! async fn main() {
+//!     let mut plan = create_turtle_plan();
+//!     plan.pen_up().go_to(vec2(-300.0, -100.0)).pen_down();
+//!
+//!     let mut app = TurtleApp::new().with_commands(plan.build());
+//!     app.set_velocity(0, vec2(200.0, 350.0));
+//!     app.set_gravity(0, vec2(0.0, -300.0));
+//!     app.set_drag(0, 0.1);
+//!     app.run(RunConfig::new()).await;
+//! }
+//! ```
+//!
+//! Forces only last for one frame, so a force that depends on the position
+//! is applied from a frame hook:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//!
+//! #[macroquad::main("Orbit")]
+//! async fn main() {
+//!     let mut plan = create_turtle_plan();
+//!     plan.pen_up().go_to(vec2(0.0, 150.0)).pen_down();
+//!
+//!     let mut app = TurtleApp::new().with_commands(plan.build());
+//!     app.set_velocity(0, vec2(120.0, 0.0));
+//!     let config = RunConfig::new().on_frame(|app| {
+//!         // Pull towards the origin, 96 px/s² at the starting distance
+//!         let offset = app.world().turtles[0].position();
+//!         let pull = -offset.normalize_or_zero() * 96.0 * 150.0_f32.powi(2)
+//!             / offset.length_squared().max(1.0);
+//!         app.apply_force(0, pull);
+//!     });
+//!     app.run(config).await;
+//! }
+//! ```
+//!
+//! Physics only moves a turtle once its queue is done, so a plan can place it
+//! first. It stops with `pause()`, follows `set_time_scale()` and advances with
+//! `update_with_dt()` in fixed-timestep mode. All vectors are in pixels per
+//! second (squared) and, like `go_to()`, in the world's `CoordinateMode`.
+
+use crate::commands::TurtleCommand;
+use crate::execution::execute_command;
+use crate::state::Turtle;
+use macroquad::prelude::Vec2;
+
+// Longest integration step in seconds, slow frames take several steps
+const MAX_STEP: f32 = 1.0 / 120.0;
+
+/// Motion state of a turtle in physics mode, see `Turtle::physics`
+///
+/// Vectors are stored in screen space like `TurtleParams::position`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Physics {
+    /// Pixels per second
+    pub velocity: Vec2,
+    /// Constant acceleration in pixels per second²
+    pub gravity: Vec2,
+    /// Share of the velocity lost per second (`0.0` keeps it forever)
+    pub drag: f32,
+    /// Acceleration from `apply_force()` for the next frame only
+    pub force: Vec2,
+}
+
+impl Physics {
+    /// Advance by `dt` seconds, returning the distance travelled
+    fn integrate(&mut self, dt: f32) -> Vec2 {
+        let steps = (dt / MAX_STEP).ceil().max(1.0);
+        let step = dt / steps;
+        let acceleration = self.gravity + self.force;
+        let mut travelled = Vec2::ZERO;
+        for _ in 0..steps as u32 {
+            // Semi-implicit Euler: new velocity first, keeps orbits stable
+            self.velocity = (self.velocity + acceleration * step) * (-self.drag * step).exp();
+            travelled += self.velocity * step;
+        }
+        self.force = Vec2::ZERO;
+        travelled
+    }
+}
+
+/// Move a turtle in physics mode by `dt` seconds of simulated time
+///
+/// Does nothing while the turtle has commands left. The movement is executed
+/// as a `Goto`, so it draws and fills like one.
+pub(crate) fn step(turtle: &mut Turtle, dt: f32) {
+    if !turtle.tween_controller.is_complete() || dt <= 0.0 {
+        return;
+    }
+    let Some(physics) = &mut turtle.physics else {
+        return;
+    };
+    let travelled = physics.integrate(dt);
+    let velocity = physics.velocity;
+
+    let target = turtle.params.position + travelled;
+    if target != turtle.params.position {
+        let mode = turtle.coordinate_mode();
+        execute_command(&TurtleCommand::Goto(mode.from_screen(target)), turtle);
+    }
+    if velocity != Vec2::ZERO {
+        turtle.params.heading = velocity.y.atan2(velocity.x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::prelude::vec2;
+
+    #[test]
+    fn flies_a_parabola() {
+        let mut turtle = Turtle {
+            physics: Some(Physics {
+                velocity: vec2(100.0, 0.0),
+                gravity: vec2(0.0, 100.0),
+                ..Physics::default()
+            }),
+            ..Turtle::default()
+        };
+        for _ in 0..10 {
+            step(&mut turtle, 0.1);
+        }
+
+        // Falls faster every frame while keeping its horizontal speed
+        let position = turtle.params.position;
+        assert!((position.x - 100.0).abs() < 0.01);
+        assert!(position.y > 45.0 && position.y < 55.0);
+        assert!(turtle.params.heading > 0.7);
+        assert_eq!(turtle.commands.len(), 10);
+
+        // Forces last for one step
+        let physics = turtle.physics.as_mut().unwrap();
+        *physics = Physics {
+            force: vec2(-100.0, 0.0),
+            ..Physics::default()
+        };
+        step(&mut turtle, 1.0);
+        step(&mut turtle, 1.0);
+        let velocity = turtle.physics.unwrap().velocity;
+        assert!(velocity.distance(vec2(-100.0, 0.0)) < 0.01);
+    }
+}
@@ -0,0 +1,242 @@
+//! Function plots drawn by a turtle
+//!
+//! A `Plot` maps a region of the math plane onto the screen and draws axes
+//! with tick marks and graphs into a `TurtlePlan`, so the graph is animated
+//! like any other drawing and exported as vectors with `--export-svg`:
+//!
+//! ```
+//! use turtle_lib::*;
+//! use turtle_lib::plot::Plot;
+//!
+//! let plot = Plot::new(-5.0..=5.0, -2.0..=10.0).with_scale(40.0, 25.0);
+//! let mut turtle = create_turtle_plan();
+//! plot.draw_axes(&mut turtle, 1.0, 2.0);
+//! plot.graph(&mut turtle, RED, |x| x * x / 3.0);
+//! plot.parametric(&mut turtle, BLUE, 0.0..=std::f32::consts::TAU, |t| {
+//!     (2.0 * t.cos(), 2.0 * t.sin())
+//! });
+//! ```
+//!
+//! The helpers move with the pen up between strokes and leave it down.
+//! Points outside the plotted region or where the function isn't finite
+//! (like `1 / x` at 0) leave a gap in the graph.
+
+use crate::builders::TurtlePlan;
+use crate::general::{Color, Coordinate, Precision};
+use macroquad::prelude::{vec2, DARKGRAY};
+use std::ops::RangeInclusive;
+
+/// Number of segments a graph is sampled with unless configured otherwise
+pub const DEFAULT_SAMPLES: usize = 200;
+
+const TICK_LENGTH: Precision = 5.0;
+const LABEL_SIZE: f32 = 12.0;
+const LABEL_OFFSET: Precision = 16.0;
+
+/// Region of the math plane shown as a graph, see the module documentation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Plot {
+    x_range: RangeInclusive<Precision>,
+    y_range: RangeInclusive<Precision>,
+    scale: Coordinate,
+    origin: Coordinate,
+    samples: usize,
+    axis_color: Color,
+}
+
+impl Plot {
+    /// Plot `x_range` × `y_range` with 20 pixels per unit, with the math
+    /// origin at the turtle origin
+    #[must_use]
+    pub fn new(x_range: RangeInclusive<Precision>, y_range: RangeInclusive<Precision>) -> Self {
+        Self {
+            x_range,
+            y_range,
+            scale: vec2(20.0, 20.0),
+            origin: Coordinate::ZERO,
+            samples: DEFAULT_SAMPLES,
+            axis_color: DARKGRAY,
+        }
+    }
+
+    /// Pixels per unit along each axis
+    #[must_use]
+    pub fn with_scale(mut self, x: Precision, y: Precision) -> Self {
+        self.scale = vec2(x, y);
+        self
+    }
+
+    /// Turtle position of the math origin `(0, 0)`
+    #[must_use]
+    pub fn with_origin(mut self, origin: impl Into<Coordinate>) -> Self {
+        self.origin = origin.into();
+        self
+    }
+
+    /// Number of straight segments per graph (at least 1)
+    #[must_use]
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    /// Color of the axes, tick marks and labels
+    #[must_use]
+    pub fn with_axis_color(mut self, color: Color) -> Self {
+        self.axis_color = color;
+        self
+    }
+
+    /// Turtle position of the math point `(x, y)`
+    #[must_use]
+    pub fn to_turtle(&self, x: Precision, y: Precision) -> Coordinate {
+        self.origin + vec2(x, y) * self.scale
+    }
+
+    /// Whether `(x, y)` lies within the plotted region
+    #[must_use]
+    pub fn contains(&self, x: Precision, y: Precision) -> bool {
+        self.x_range.contains(&x) && self.y_range.contains(&y)
+    }
+
+    /// Draw both axes with a tick mark every `x_step` and `y_step` units
+    ///
+    /// An axis is drawn through the origin, or along the edge of the region
+    /// if the origin is outside of it. Ticks are labeled with their value,
+    /// except at the origin. A step of 0 or less draws no ticks.
+    pub fn draw_axes(&self, plan: &mut TurtlePlan, x_step: Precision, y_step: Precision) {
+        let (x_start, x_end) = (*self.x_range.start(), *self.x_range.end());
+        let (y_start, y_end) = (*self.y_range.start(), *self.y_range.end());
+        let axis_x = 0.0_f32.clamp(x_start.min(x_end), x_start.max(x_end));
+        let axis_y = 0.0_f32.clamp(y_start.min(y_end), y_start.max(y_end));
+
+        plan.set_pen_color(self.axis_color).set_heading(0.0);
+        line(
+            plan,
+            self.to_turtle(x_start, axis_y),
+            self.to_turtle(x_end, axis_y),
+        );
+        line(
+            plan,
+            self.to_turtle(axis_x, y_start),
+            self.to_turtle(axis_x, y_end),
+        );
+
+        for x in ticks(x_start, x_end, x_step) {
+            let at = self.to_turtle(x, axis_y);
+            line(
+                plan,
+                at - vec2(0.0, TICK_LENGTH),
+                at + vec2(0.0, TICK_LENGTH),
+            );
+            if x != 0.0 {
+                label(plan, at - vec2(0.0, LABEL_OFFSET), x, x_step);
+            }
+        }
+        for y in ticks(y_start, y_end, y_step) {
+            let at = self.to_turtle(axis_x, y);
+            line(
+                plan,
+                at - vec2(TICK_LENGTH, 0.0),
+                at + vec2(TICK_LENGTH, 0.0),
+            );
+            if y != 0.0 {
+                label(plan, at + vec2(TICK_LENGTH * 2.0, 0.0), y, y_step);
+            }
+        }
+    }
+
+    /// Draw the graph of `y = f(x)` over the x range of the plot
+    pub fn graph(&self, plan: &mut TurtlePlan, color: Color, f: impl Fn(Precision) -> Precision) {
+        let (start, end) = (*self.x_range.start(), *self.x_range.end());
+        self.curve(plan, color, start, end, |x| (x, f(x)));
+    }
+
+    /// Draw the curve `(x, y) = f(t)` for `t` in `t_range`
+    pub fn parametric(
+        &self,
+        plan: &mut TurtlePlan,
+        color: Color,
+        t_range: RangeInclusive<Precision>,
+        f: impl Fn(Precision) -> (Precision, Precision),
+    ) {
+        self.curve(plan, color, *t_range.start(), *t_range.end(), f);
+    }
+
+    fn curve(
+        &self,
+        plan: &mut TurtlePlan,
+        color: Color,
+        start: Precision,
+        end: Precision,
+        f: impl Fn(Precision) -> (Precision, Precision),
+    ) {
+        plan.set_pen_color(color);
+        let mut drawing = false;
+        for n in 0..=self.samples {
+            let t = start + (end - start) * n as Precision / self.samples as Precision;
+            let (x, y) = f(t);
+            if !(x.is_finite() && y.is_finite() && self.contains(x, y)) {
+                drawing = false;
+                continue;
+            }
+            if !drawing {
+                plan.pen_up();
+            }
+            plan.go_to(self.to_turtle(x, y));
+            if !drawing {
+                plan.pen_down();
+                drawing = true;
+            }
+        }
+    }
+}
+
+fn line(plan: &mut TurtlePlan, from: Coordinate, to: Coordinate) {
+    plan.pen_up().go_to(from).pen_down().go_to(to);
+}
+
+fn label(plan: &mut TurtlePlan, at: Coordinate, value: Precision, step: Precision) {
+    // Enough decimals to tell neighbouring ticks apart
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    plan.pen_up()
+        .go_to(at)
+        .write_text(format!("{value:.decimals$}"), LABEL_SIZE);
+}
+
+/// Multiples of `step` between `start` and `end`
+fn ticks(start: Precision, end: Precision, step: Precision) -> Vec<Precision> {
+    if step <= 0.0 || !step.is_finite() {
+        return Vec::new();
+    }
+    let (low, high) = (start.min(end), start.max(end));
+    let first = (low / step).ceil() as i64;
+    let last = (high / step).floor() as i64;
+    (first..=last).map(|n| n as Precision * step).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assertions::{expect_position, expect_visits_point};
+    use crate::commands::TurtleCommand;
+
+    #[test]
+    fn graphs_leave_the_region_with_gaps() {
+        let plot = Plot::new(-5.0..=5.0, -5.0..=5.0).with_scale(10.0, 10.0);
+        assert_eq!(ticks(-5.0, 5.0, 2.0), vec![-4.0, -2.0, 0.0, 2.0, 4.0]);
+
+        let mut plan = TurtlePlan::new();
+        plot.graph(&mut plan, Color::default(), |x| 1.0 / x);
+        expect_position(&plan, (50.0, 2.0), 0.01);
+        expect_visits_point(&plan, (10.0, 10.0), 0.01);
+        // One gap around x = 0 where the graph leaves the region
+        let queue = plan.build();
+        let lifts = queue
+            .commands()
+            .iter()
+            .filter(|command| matches!(command, TurtleCommand::PenUp))
+            .count();
+        assert_eq!(lifts, 2);
+    }
+}
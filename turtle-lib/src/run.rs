@@ -0,0 +1,87 @@
+//! Configuration of the main loop run by `TurtleApp::run()`
+
+use crate::export::AutoExport;
+use crate::TurtleApp;
+use macroquad::prelude::*;
+
+/// Per-frame callback, see `RunConfig::on_frame()`
+pub type FrameHook = Box<dyn FnMut(&mut TurtleApp)>;
+
+/// Settings and hooks for `TurtleApp::run()`
+///
+/// ```no_run
+/// # use turtle_lib::*;
+/// # #[macroquad::main("Run")]
+/// # async fn main() {
+/// let mut plan = create_turtle_plan();
+/// plan.forward(100.0);
+///
+/// let mut app = TurtleApp::new().with_commands(plan.build());
+/// let config = RunConfig::new()
+///     .with_background(BLACK)
+///     .on_frame(|app| {
+///         if app.is_complete() {
+///             app.set_status_text(Some("Done!"));
+///         }
+///     });
+/// app.run(config).await;
+/// # }
+/// ```
+pub struct RunConfig {
+    pub(crate) background: Color,
+    pub(crate) frame_hooks: Vec<FrameHook>,
+    pub(crate) render_hooks: Vec<FrameHook>,
+    pub(crate) auto_export: Option<AutoExport>,
+}
+
+impl RunConfig {
+    /// White background, no hooks
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            background: WHITE,
+            frame_hooks: Vec::new(),
+            render_hooks: Vec::new(),
+            auto_export: None,
+        }
+    }
+
+    /// Clear every frame with `color`
+    #[must_use]
+    pub fn with_background(mut self, color: Color) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Call `hook` every frame before the animations advance
+    ///
+    /// Commands sent through turtle channels in the hook are processed in
+    /// the same frame.
+    #[must_use]
+    pub fn on_frame(mut self, hook: impl FnMut(&mut TurtleApp) + 'static) -> Self {
+        self.frame_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` every frame after rendering the world, e.g. to draw a
+    /// custom overlay in screen space
+    #[must_use]
+    pub fn after_render(mut self, hook: impl FnMut(&mut TurtleApp) + 'static) -> Self {
+        self.render_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Write the files of `export` once the drawing is complete or the
+    /// window closes
+    #[must_use]
+    pub fn with_auto_export(mut self, export: AutoExport) -> Self {
+        self.auto_export = Some(export);
+        self
+    }
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
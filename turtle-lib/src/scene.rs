@@ -0,0 +1,197 @@
+//! Scenes: plans for several turtles that start when other plans finished
+//!
+//! A `Scene` is a list of steps, each a plan for one turtle. A step starts
+//! once the steps it depends on are done and its turtle is idle, so the
+//! storyboard is written down once instead of being coordinated by threads:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//!
+//! #[macroquad::main("Scene")]
+//! async fn main() {
+//!     let (gallows, smiley) = (0, 1);
+//!     let mut scene = Scene::new();
+//!     let mut last = None;
+//!     for _ in 0..5 {
+//!         let mut plan = create_turtle_plan();
+//!         plan.forward(60.0).right(72.0);
+//!         last = Some(scene.add(gallows, plan));
+//!     }
+//!     // The smiley starts after the fifth step of the gallows
+//!     let mut plan = create_turtle_plan();
+//!     plan.pen_up().go_to(vec2(150.0, 0.0)).pen_down().circle_left(30.0, 360.0, 36);
+//!     scene.add_after(smiley, plan, &[last.unwrap()]);
+//!
+//!     let mut app = TurtleApp::new();
+//!     app.play_scene(scene);
+//!     app.run(RunConfig::new()).await;
+//! }
+//! ```
+//!
+//! Steps of the same turtle run in the order they were added. A step counts
+//! as done when its turtle becomes idle, so commands sent to the turtle from
+//! elsewhere while the step runs delay it.
+
+use crate::builders::TurtlePlan;
+use crate::commands::CommandQueue;
+use crate::state::TurtleWorld;
+
+/// A step of a `Scene`, returned by `Scene::add()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StepId(usize);
+
+#[derive(Clone, Debug)]
+enum Progress {
+    Waiting(CommandQueue),
+    Running,
+    Done,
+}
+
+#[derive(Clone, Debug)]
+struct Step {
+    turtle_id: usize,
+    after: Vec<StepId>,
+    progress: Progress,
+}
+
+/// Plans with dependencies, played with `TurtleApp::play_scene()`
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    steps: Vec<Step>,
+}
+
+impl Scene {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a step that only waits for its turtle
+    pub fn add(&mut self, turtle_id: usize, plan: TurtlePlan) -> StepId {
+        self.add_after(turtle_id, plan, &[])
+    }
+
+    /// Add a step that starts once all steps in `after` are done
+    ///
+    /// # Panics
+    ///
+    /// Panics if a step in `after` wasn't added to this scene.
+    pub fn add_after(&mut self, turtle_id: usize, plan: TurtlePlan, after: &[StepId]) -> StepId {
+        assert!(
+            after.iter().all(|step| step.0 < self.steps.len()),
+            "steps can only wait for earlier steps of the same scene"
+        );
+        self.steps.push(Step {
+            turtle_id,
+            after: after.to_vec(),
+            progress: Progress::Waiting(plan.build()),
+        });
+        StepId(self.steps.len() - 1)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Whether `step` is done
+    #[must_use]
+    pub fn is_done(&self, step: StepId) -> bool {
+        self.steps
+            .get(step.0)
+            .is_some_and(|step| matches!(step.progress, Progress::Done))
+    }
+
+    /// Whether every step is done
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|step| matches!(step.progress, Progress::Done))
+    }
+
+    /// Mark finished steps as done and take the queues of the steps that
+    /// start now, with their turtle IDs
+    pub fn update(&mut self, world: &TurtleWorld) -> Vec<(usize, CommandQueue)> {
+        let idle = |turtle_id: usize| {
+            world
+                .get_turtle(turtle_id)
+                .is_none_or(|turtle| turtle.tween_controller.is_complete())
+        };
+        for step in &mut self.steps {
+            if matches!(step.progress, Progress::Running) && idle(step.turtle_id) {
+                step.progress = Progress::Done;
+            }
+        }
+
+        let mut starts: Vec<(usize, CommandQueue)> = Vec::new();
+        for index in 0..self.steps.len() {
+            let step = &self.steps[index];
+            let turtle_id = step.turtle_id;
+            let ready = matches!(step.progress, Progress::Waiting(_))
+                && step.after.iter().all(|after| self.is_done(*after))
+                && idle(turtle_id)
+                // One step per turtle at a time, earlier steps first
+                && !self.steps[..index].iter().any(|earlier| {
+                    earlier.turtle_id == turtle_id
+                        && !matches!(earlier.progress, Progress::Done)
+                });
+            if ready {
+                let progress =
+                    std::mem::replace(&mut self.steps[index].progress, Progress::Running);
+                if let Progress::Waiting(queue) = progress {
+                    starts.push((turtle_id, queue));
+                }
+            }
+        }
+        starts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::DirectionalMovement;
+    use crate::tweening::TweenController;
+
+    #[test]
+    fn steps_wait_for_their_dependencies() {
+        let plan = |distance| {
+            let mut plan = TurtlePlan::new();
+            plan.forward(distance);
+            plan
+        };
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        world.add_turtle();
+
+        let mut scene = Scene::new();
+        let first = scene.add(0, plan(10.0));
+        let second = scene.add(0, plan(20.0));
+        let smiley = scene.add_after(1, plan(30.0), &[first]);
+
+        // Only the first step can start, the second one waits for turtle 0
+        let starts = scene.update(&world);
+        assert_eq!(starts.len(), 1);
+        for (id, queue) in starts {
+            world.turtles[id].tween_controller.append_commands(queue);
+        }
+        assert!(scene.update(&world).is_empty());
+
+        TweenController::finish(&mut world.turtles[0]);
+        // Both start together, their queues are left out here
+        let ids: Vec<usize> = scene.update(&world).iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![0, 1]);
+        assert!(scene.is_done(first));
+        assert!(!scene.is_done(second) && !scene.is_done(smiley));
+
+        // So both turtles are idle and done right away
+        assert!(scene.update(&world).is_empty());
+        assert!(scene.is_finished());
+    }
+}
@@ -0,0 +1,120 @@
+//! Timed plans: queues that start at a given time of the animation clock
+//!
+//! `TurtleApp::schedule()` and `schedule_at()` hold a queue back until its
+//! start time and then append it like `append_commands()`. Storyboards with
+//! several turtles become a list of start times instead of threads that sleep:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//!
+//! #[macroquad::main("Storyboard")]
+//! async fn main() {
+//!     let mut app = TurtleApp::new();
+//!     let mut square = create_turtle_plan();
+//!     for _ in 0..4 {
+//!         square.forward(100.0).right(90.0);
+//!     }
+//!     // The first turtle starts right away, the second two seconds later
+//!     app.append_to_queue(0, square.clone());
+//!     app.schedule(1, 2.0, square.clone().build());
+//!     // And the third five seconds after the start of the scene
+//!     app.schedule_at(2, 5.0, square.build());
+//!     app.run(RunConfig::new()).await;
+//! }
+//! ```
+//!
+//! The clock runs in seconds of animation time: it stops while the app is
+//! paused or held and follows `set_time_scale()`.
+
+use crate::commands::CommandQueue;
+
+/// Queues waiting for their start time, by turtle ID
+#[derive(Clone, Debug, Default)]
+pub struct Schedule {
+    now: f64,
+    // Start time, turtle ID and queue, in the order they were scheduled
+    pending: Vec<(f64, usize, CommandQueue)>,
+}
+
+impl Schedule {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seconds of animation time so far
+    #[must_use]
+    pub fn now(&self) -> f64 {
+        self.now
+    }
+
+    /// Start `queue` on turtle `turtle_id` `delay` seconds from now
+    pub fn schedule(&mut self, turtle_id: usize, delay: f64, queue: CommandQueue) {
+        self.schedule_at(turtle_id, self.now + delay.max(0.0), queue);
+    }
+
+    /// Start `queue` on turtle `turtle_id` at `time` seconds of the clock,
+    /// times in the past start with the next update
+    pub fn schedule_at(&mut self, turtle_id: usize, time: f64, queue: CommandQueue) {
+        self.pending.push((time, turtle_id, queue));
+    }
+
+    /// Number of queues that didn't start yet
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Drop all queues that didn't start yet
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Advance the clock by `dt` seconds and take the queues that are due,
+    /// earliest first
+    pub fn advance(&mut self, dt: f64) -> Vec<(usize, CommandQueue)> {
+        self.now += dt.max(0.0);
+        let now = self.now;
+        let (mut due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(time, _, _)| *time <= now);
+        self.pending = pending;
+        // Stable, so queues with the same start keep their order
+        due.sort_by(|a, b| a.0.total_cmp(&b.0));
+        due.into_iter()
+            .map(|(_, turtle_id, queue)| (turtle_id, queue))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::{DirectionalMovement, TurtlePlan};
+
+    #[test]
+    fn queues_start_when_they_are_due() {
+        let queue = |distance| {
+            let mut plan = TurtlePlan::new();
+            plan.forward(distance);
+            plan.build()
+        };
+
+        let mut schedule = Schedule::new();
+        schedule.schedule_at(0, 1.0, queue(10.0));
+        schedule.schedule(1, 0.5, queue(20.0));
+        schedule.schedule_at(2, 0.5, queue(30.0));
+        assert!(schedule.advance(0.25).is_empty());
+
+        let due = schedule.advance(0.25);
+        let ids: Vec<usize> = due.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(schedule.pending(), 1);
+
+        // Delays count from the current time
+        schedule.schedule(3, 0.25, queue(40.0));
+        let ids: Vec<usize> = schedule.advance(1.0).iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![3, 0]);
+        assert!((schedule.now() - 1.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,376 @@
+//! Plain-text turtle scripts
+//!
+//! A small command language for quick experiments and live coding (see
+//! `TurtleApp::watch_script()` with the `watch` feature). Commands and their
+//! arguments are separated by whitespace, `//` starts a comment:
+//!
+//! ```text
+//! // A red square
+//! pen_color red
+//! repeat 4 [ forward 100 right 90 ]
+//! write "done" 20
+//! ```
+//!
+//! Supported commands (aliases in parentheses): `forward` (`fd`), `backward`
+//! (`back`, `bk`), `left` (`lt`), `right` (`rt`), `pen_up` (`pu`), `pen_down`
+//! (`pd`), `pen_color`, `fill_color`, `pen_width`, `speed`, `go_to` (`goto`)
+//! with x and y, `set_heading` (`heading`), `circle_left` and `circle_right`
+//! with radius, angle and steps, `begin_fill`, `end_fill`, `hide`, `show`,
+//! `write` with a quoted text and a font size, `mark`, `go_to_mark`, `clear`,
+//! `reset` and `repeat` with a count and a block in brackets.
+//!
+//! Colors are hex strings (`#ff8800`) or CSS names (`cornflowerblue`), where
+//! the basic names like `red` and `green` are macroquad's colors.
+//!
+//! `repeat` blocks are expanded while parsing. Scripts that expand to more
+//! than `MAX_COMMANDS` commands or nest more than `MAX_REPEAT_DEPTH` repeats
+//! are rejected.
+
+use crate::builders::{CurvedMovement, DirectionalMovement, Turnable, TurtlePlan, WithCommands};
+use crate::general::{Color, Precision};
+use macroquad::prelude::{
+    vec2, BLACK, BLUE, DARKGRAY, GOLD, GREEN, ORANGE, PURPLE, RED, WHITE, YELLOW,
+};
+
+/// Most commands a script may expand to
+pub const MAX_COMMANDS: usize = 1_000_000;
+
+/// Most `repeat` blocks that may be nested inside each other
+pub const MAX_REPEAT_DEPTH: usize = 32;
+
+/// Error while parsing a script, with the 1-based line it occurred on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// Parse a script into a plan
+///
+/// # Errors
+///
+/// Returns the first unknown command or invalid argument
+pub fn parse_script(source: &str) -> Result<TurtlePlan, ScriptError> {
+    let tokens = tokenize(source)?;
+    let mut plan = TurtlePlan::new();
+    let mut parser = Parser {
+        tokens: &tokens,
+        index: 0,
+        depth: 0,
+    };
+    parser.parse_block(&mut plan, false)?;
+    Ok(plan)
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    text: String,
+    line: usize,
+    quoted: bool,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ScriptError> {
+    let mut tokens = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut chars = line.chars().peekable();
+        // `//` outside of strings starts a comment
+        let at_comment = |chars: &std::iter::Peekable<std::str::Chars>| {
+            let mut ahead = chars.clone();
+            ahead.next() == Some('/') && ahead.next() == Some('/')
+        };
+        while let Some(&c) = chars.peek() {
+            if at_comment(&chars) {
+                break;
+            } else if c.is_whitespace() {
+                chars.next();
+            } else if c == '[' || c == ']' {
+                chars.next();
+                tokens.push(Token {
+                    text: c.to_string(),
+                    line: line_number,
+                    quoted: false,
+                });
+            } else if c == '"' {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => text.push(c),
+                        None => {
+                            return Err(ScriptError {
+                                line: line_number,
+                                message: "unterminated string".to_string(),
+                            })
+                        }
+                    }
+                }
+                tokens.push(Token {
+                    text,
+                    line: line_number,
+                    quoted: true,
+                });
+            } else {
+                let mut text = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '[' || c == ']' || c == '"' || at_comment(&chars) {
+                        break;
+                    }
+                    text.push(c);
+                    chars.next();
+                }
+                tokens.push(Token {
+                    text,
+                    line: line_number,
+                    quoted: false,
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    index: usize,
+    // Number of `repeat` blocks around the current token
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Parse commands until the end of input, or until `]` inside a block
+    fn parse_block(&mut self, plan: &mut TurtlePlan, nested: bool) -> Result<(), ScriptError> {
+        while let Some(token) = self.next_token() {
+            if !token.quoted && token.text == "]" {
+                if nested {
+                    return Ok(());
+                }
+                return Err(error(token, "unexpected `]`"));
+            }
+            self.parse_command(plan, token)?;
+        }
+        if nested {
+            let line = self.tokens.last().map_or(1, |token| token.line);
+            return Err(ScriptError {
+                line,
+                message: "missing `]`".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_command(&mut self, plan: &mut TurtlePlan, token: &Token) -> Result<(), ScriptError> {
+        if token.quoted {
+            return Err(error(token, "expected a command, found a string"));
+        }
+        let name = token.text.to_lowercase();
+        match name.as_str() {
+            "forward" | "fd" => plan.forward(self.number(token)?),
+            "backward" | "back" | "bk" => plan.backward(self.number(token)?),
+            "left" | "lt" => plan.left(self.number(token)?),
+            "right" | "rt" => plan.right(self.number(token)?),
+            "pen_up" | "penup" | "pu" => plan.pen_up(),
+            "pen_down" | "pendown" | "pd" => plan.pen_down(),
+            "pen_color" | "color" => plan.set_pen_color(self.color(token)?),
+            "fill_color" => plan.set_fill_color(self.color(token)?),
+            "pen_width" | "width" => plan.set_pen_width(self.number(token)?),
+            "speed" => plan.set_speed(self.number(token)?),
+            "go_to" | "goto" => {
+                let x = self.number(token)?;
+                let y = self.number(token)?;
+                plan.go_to(vec2(x, y))
+            }
+            "set_heading" | "heading" => plan.set_heading(self.number(token)?),
+            "circle_left" | "circle_right" => {
+                let radius = self.number(token)?;
+                let angle = self.number(token)?;
+                let steps = self.count(token)?;
+                if name == "circle_left" {
+                    plan.circle_left(radius, angle, steps)
+                } else {
+                    plan.circle_right(radius, angle, steps)
+                }
+            }
+            "begin_fill" => plan.begin_fill(),
+            "end_fill" => plan.end_fill(),
+            "hide" => plan.hide(),
+            "show" => plan.show(),
+            "write" => {
+                let text = self.argument(token)?.text.clone();
+                let size = self.count(token)?;
+                let size = u16::try_from(size).map_err(|_| error(token, "font size too large"))?;
+                plan.write_text(text, size)
+            }
+            "mark" => plan.mark(self.argument(token)?.text.clone()),
+            "go_to_mark" => plan.go_to_mark(self.argument(token)?.text.clone()),
+            "clear" => plan.clear(),
+            "reset" => plan.reset(),
+            "push_state" | "push" => plan.push_state(),
+            "pop_state" | "pop" => plan.pop_state(),
+            "repeat" => {
+                let count = self.count(token)?;
+                let open = self.argument(token)?;
+                if open.quoted || open.text != "[" {
+                    return Err(error(open, "expected `[` after the repeat count"));
+                }
+                if self.depth == MAX_REPEAT_DEPTH {
+                    return Err(error(
+                        token,
+                        &format!("more than {MAX_REPEAT_DEPTH} nested repeats"),
+                    ));
+                }
+                let mut body = TurtlePlan::new();
+                self.depth += 1;
+                let parsed = self.parse_block(&mut body, true);
+                self.depth -= 1;
+                parsed?;
+                let body = body.build();
+                let total = body
+                    .commands()
+                    .len()
+                    .checked_mul(count)
+                    .and_then(|expanded| expanded.checked_add(plan.get_commands_mut().len()));
+                if total.is_none_or(|total| total > MAX_COMMANDS) {
+                    return Err(error(
+                        token,
+                        &format!("repeat expands to more than {MAX_COMMANDS} commands"),
+                    ));
+                }
+                for _ in 0..count {
+                    plan.get_commands_mut()
+                        .extend(body.commands().iter().cloned());
+                }
+                plan
+            }
+            other => return Err(error(token, &format!("unknown command `{other}`"))),
+        };
+        Ok(())
+    }
+
+    fn next_token(&mut self) -> Option<&'a Token> {
+        let tokens = self.tokens;
+        let token = tokens.get(self.index)?;
+        self.index += 1;
+        Some(token)
+    }
+
+    fn argument(&mut self, command: &Token) -> Result<&'a Token, ScriptError> {
+        self.next_token()
+            .ok_or_else(|| error(command, &format!("`{}` needs more arguments", command.text)))
+    }
+
+    fn number(&mut self, command: &Token) -> Result<Precision, ScriptError> {
+        let token = self.argument(command)?;
+        token
+            .text
+            .parse()
+            .map_err(|_| error(token, &format!("expected a number, found `{}`", token.text)))
+    }
+
+    fn count(&mut self, command: &Token) -> Result<usize, ScriptError> {
+        let token = self.argument(command)?;
+        token.text.parse().map_err(|_| {
+            error(
+                token,
+                &format!("expected a whole number, found `{}`", token.text),
+            )
+        })
+    }
+
+    fn color(&mut self, command: &Token) -> Result<Color, ScriptError> {
+        let token = self.argument(command)?;
+        named_color(&token.text)
+            .or_else(|| crate::general::parse_color(&token.text))
+            .ok_or_else(|| error(token, &format!("unknown color `{}`", token.text)))
+    }
+}
+
+fn named_color(name: &str) -> Option<Color> {
+    Some(match name.to_lowercase().as_str() {
+        "black" => BLACK,
+        "white" => WHITE,
+        "red" => RED,
+        "green" => GREEN,
+        "blue" => BLUE,
+        "yellow" => YELLOW,
+        "orange" => ORANGE,
+        "purple" => PURPLE,
+        "gold" => GOLD,
+        "gray" | "grey" | "darkgray" => DARKGRAY,
+        _ => return None,
+    })
+}
+
+fn error(token: &Token, message: &str) -> ScriptError {
+    ScriptError {
+        line: token.line,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::TurtleCommand;
+
+    #[test]
+    fn parses_repeat_blocks_and_comments() {
+        let plan = parse_script(
+            "// square\npen_color red\nrepeat 4 [ fd 100 rt 90 ] // done\nwrite \"hi there\" 12",
+        )
+        .unwrap();
+        let queue = plan.build();
+        let commands = queue.commands();
+        assert_eq!(commands.len(), 10);
+        assert!(matches!(commands[0], TurtleCommand::SetColor(_)));
+        assert!(matches!(commands[1], TurtleCommand::Move(d) if d == 100.0));
+        assert!(matches!(commands[2], TurtleCommand::Turn(a) if a == 90.0));
+        assert!(
+            matches!(&commands[9], TurtleCommand::WriteText { text, .. } if text == "hi there")
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let error = parse_script("forward 10\njump 5").unwrap_err();
+        assert_eq!(error.line, 2);
+
+        let error = parse_script("repeat 2 [ forward 10").unwrap_err();
+        assert!(error.message.contains("missing"));
+    }
+
+    #[test]
+    fn repeats_are_limited() {
+        let error = parse_script("fd 1\nrepeat 100000 [ repeat 100000 [ fd 1 ] ]").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("more than"));
+
+        let nested = format!("{}fd 1{}", "repeat 1 [ ".repeat(40), " ]".repeat(40));
+        let error = parse_script(&nested).unwrap_err();
+        assert!(error.message.contains("nested"));
+
+        let fitting = format!("repeat {MAX_COMMANDS} [ fd 1 ]");
+        assert_eq!(parse_script(&fitting).unwrap().build().len(), MAX_COMMANDS);
+    }
+
+    #[test]
+    fn comment_markers_in_strings_are_text() {
+        let plan = parse_script("write \"http://x\" 12 // link\nfd 10// step").unwrap();
+        let queue = plan.build();
+        let commands = queue.commands();
+        assert_eq!(commands.len(), 2);
+        assert!(
+            matches!(&commands[0], TurtleCommand::WriteText { text, .. } if text == "http://x")
+        );
+        assert!(matches!(commands[1], TurtleCommand::Move(d) if d == 10.0));
+    }
+}
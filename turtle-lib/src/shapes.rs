@@ -1,29 +1,88 @@
 //! Turtle shape definitions
 
 use macroquad::prelude::*;
+use std::collections::HashMap;
 use std::f32::consts::PI;
 
+mod svg_path;
+
+pub use svg_path::SvgPathError;
+
 /// A shape that can be drawn for the turtle
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TurtleShape {
     /// Vertices of the shape (relative to turtle position)
+    #[cfg_attr(feature = "serde", serde(with = "crate::wire::vec2_list"))]
     pub vertices: Vec<Vec2>,
     /// Whether to draw as filled polygon (true) or outline (false)
     pub filled: bool,
+    /// Vertices shown in turn while the turtle moves, see `with_walk_frames()`
+    #[cfg_attr(feature = "serde", serde(with = "crate::wire::vec2_lists"))]
+    pub walk_frames: Vec<Vec<Vec2>>,
+    /// Distance in pixels the turtle moves per walk frame
+    pub walk_step: f32,
+    /// Point of the shape placed at the turtle position and rotated around,
+    /// see `with_pivot()`
+    #[cfg_attr(feature = "serde", serde(with = "crate::wire::vec2"))]
+    pub pivot: Vec2,
 }
 
 impl TurtleShape {
     /// Create a new custom shape from vertices
     #[must_use]
     pub fn new(vertices: Vec<Vec2>, filled: bool) -> Self {
-        Self { vertices, filled }
+        Self {
+            vertices,
+            filled,
+            walk_frames: Vec::new(),
+            walk_step: 0.0,
+            pivot: Vec2::ZERO,
+        }
+    }
+
+    /// Rotate the shape around `pivot` instead of its origin
+    ///
+    /// The pivot is given in the shape's own coordinates and ends up at the
+    /// turtle position, e.g. `vec2(12.0, 0.0)` lets `arrow()` turn around its
+    /// tip. Rendering, stamps and hit-testing all use the pivot.
+    #[must_use]
+    pub fn with_pivot(mut self, pivot: Vec2) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
+    /// Animate the shape while the turtle moves
+    ///
+    /// The frames are shown in turn, advancing every `step` pixels the turtle
+    /// travels, so the cursor appears to walk at any speed. A standing turtle
+    /// shows the regular `vertices`. Frames are scaled and rotated like the
+    /// shape itself.
+    #[must_use]
+    pub fn with_walk_frames(mut self, frames: Vec<Vec<Vec2>>, step: f32) -> Self {
+        self.walk_frames = frames;
+        self.walk_step = step;
+        self
     }
 
-    /// Get vertices rotated by the given angle
+    /// Vertices to draw after the turtle walked `distance` pixels in the
+    /// current movement (0 while standing)
+    #[must_use]
+    pub fn frame_vertices(&self, distance: f32) -> &[Vec2] {
+        if distance <= 0.0 || self.walk_frames.is_empty() || self.walk_step <= 0.0 {
+            return &self.vertices;
+        }
+        let frame = (distance / self.walk_step) as usize % self.walk_frames.len();
+        &self.walk_frames[frame]
+    }
+
+    /// Get vertices rotated by the given angle around the pivot, which is
+    /// moved to the origin
     #[must_use]
     pub fn rotated_vertices(&self, angle: f32) -> Vec<Vec2> {
         self.vertices
             .iter()
+            .map(|v| *v - self.pivot)
             .map(|v| {
                 let cos_a = angle.cos();
                 let sin_a = angle.sin();
@@ -35,61 +94,38 @@ impl TurtleShape {
     /// Triangle shape (simple arrow pointing right)
     #[must_use]
     pub fn triangle() -> Self {
-        Self {
-            vertices: vec![
+        Self::new(
+            vec![
                 vec2(15.0, 0.0),   // Point
                 vec2(-10.0, -8.0), // Bottom left
                 vec2(-10.0, 8.0),  // Top left
             ],
-            filled: true,
-        }
+            true,
+        )
     }
 
-    /// Classic turtle shape
+    /// Classic turtle silhouette with head, four legs and a tail
+    ///
+    /// Modelled after the turtle shape of Python's `turtle` module, with a
+    /// short tail added. The head points along the heading.
     #[must_use]
     pub fn turtle() -> Self {
-        // Based on the original turtle shape from turtle-lib
-        let polygon: &[[f32; 2]; 23] = &[
-            [-2.5, 14.0],
-            [-1.25, 10.0],
-            [-4.0, 7.0],
-            [-7.0, 9.0],
-            [-9.0, 8.0],
-            [-6.0, 5.0],
-            [-7.0, 1.0],
-            [-5.0, -3.0],
-            [-8.0, -6.0],
-            [-6.0, -8.0],
-            [-4.0, -5.0],
-            [0.0, -7.0],
-            [4.0, -5.0],
-            [6.0, -8.0],
-            [8.0, -6.0],
-            [5.0, -3.0],
-            [7.0, 1.0],
-            [6.0, 5.0],
-            [9.0, 8.0],
-            [7.0, 9.0],
-            [4.0, 7.0],
-            [1.25, 10.0],
-            [2.5, 14.0],
-        ];
-
-        // Rotate by -90 degrees to point right (original points up)
-        let vertices: Vec<Vec2> = polygon
-            .iter()
-            .map(|[x, y]| {
-                let v = vec2(*x, *y);
-                let cos_a = (-PI / 2.0).cos();
-                let sin_a = (-PI / 2.0).sin();
-                vec2(v.x * cos_a - v.y * sin_a, v.x * sin_a + v.y * cos_a)
-            })
-            .collect();
+        // Concave polygon, tessellated by Lyon
+        Self::new(turtle_outline(0.0), true)
+    }
 
-        Self {
-            vertices,
-            filled: true, // Now uses ear clipping for proper concave polygon rendering
-        }
+    /// `turtle()` that swings its legs while it moves
+    #[must_use]
+    pub fn walking_turtle() -> Self {
+        Self::turtle().with_walk_frames(
+            vec![
+                turtle_outline(2.5),
+                turtle_outline(0.0),
+                turtle_outline(-2.5),
+                turtle_outline(0.0),
+            ],
+            5.0,
+        )
     }
 
     /// Circle shape
@@ -104,45 +140,183 @@ impl TurtleShape {
             })
             .collect();
 
-        Self {
-            vertices,
-            filled: true,
-        }
+        Self::new(vertices, true)
     }
 
     /// Square shape
     #[must_use]
     pub fn square() -> Self {
-        Self {
-            vertices: vec![
+        Self::new(
+            vec![
                 vec2(8.0, 8.0),
                 vec2(-8.0, 8.0),
                 vec2(-8.0, -8.0),
                 vec2(8.0, -8.0),
             ],
-            filled: true,
-        }
+            true,
+        )
     }
 
     /// Arrow shape (simple arrow pointing right)
     #[must_use]
     pub fn arrow() -> Self {
-        Self {
-            vertices: vec![
+        Self::new(
+            vec![
                 vec2(12.0, 0.0),  // Point
                 vec2(-8.0, 6.0),  // Top back
                 vec2(-4.0, 0.0),  // Middle back
                 vec2(-8.0, -6.0), // Bottom back
             ],
-            filled: true,
+            true,
+        )
+    }
+
+    /// Shape from SVG path data, e.g. the `d` attribute of a path drawn in
+    /// Inkscape
+    ///
+    /// The shape should point right (+x) in SVG coordinates (y pointing
+    /// down). Only the first subpath is used, curves and arcs are flattened
+    /// with a tolerance of 0.5 units. The vertices are centered on the
+    /// bounding box of the path, use `TurtlePlan::set_shape_size()` to scale
+    /// the shape.
+    ///
+    /// ```
+    /// # use turtle_lib::*;
+    /// let kite = TurtleShape::from_svg_path("M 15 0 L 0 8 L -10 0 L 0 -8 Z").unwrap();
+    /// assert_eq!(kite.vertices.len(), 4);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `d` is not valid path data or describes fewer
+    /// than three points
+    pub fn from_svg_path(d: &str) -> Result<Self, SvgPathError> {
+        Self::from_svg_path_with_tolerance(d, 0.5)
+    }
+
+    /// Like `from_svg_path()`, with curves flattened to line segments that
+    /// deviate at most `tolerance` from the curve
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `d` is not valid path data or describes fewer
+    /// than three points
+    pub fn from_svg_path_with_tolerance(d: &str, tolerance: f32) -> Result<Self, SvgPathError> {
+        let mut vertices = svg_path::flatten(d, tolerance)?;
+        let (min, max) = vertices.iter().fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |(min, max), v| (min.min(*v), max.max(*v)),
+        );
+        let center = (min + max) / 2.0;
+        for vertex in &mut vertices {
+            *vertex -= center;
+        }
+        Ok(Self::new(vertices, true))
+    }
+}
+
+/// Vertices of `TurtleShape::turtle()` with the feet of diagonal leg pairs
+/// moved `swing` pixels towards the head or the tail
+fn turtle_outline(swing: f32) -> Vec<Vec2> {
+    // Outline with the head pointing up (+y) and the turtle's left
+    // side at -x, as in Python's turtle module
+    let polygon: &[[f32; 2]] = &[
+        [0.0, 16.0], // Head
+        [-2.0, 14.0],
+        [-1.0, 10.0],
+        [-4.0, 7.0], // Front left leg
+        [-7.0, 9.0],
+        [-9.0, 8.0],
+        [-6.0, 5.0],
+        [-7.0, 1.0],
+        [-5.0, -3.0], // Back left leg
+        [-8.0, -6.0],
+        [-6.0, -8.0],
+        [-4.0, -5.0],
+        [-1.0, -7.0], // Tail
+        [0.0, -11.0],
+        [1.0, -7.0],
+        [4.0, -5.0], // Back right leg
+        [6.0, -8.0],
+        [8.0, -6.0],
+        [5.0, -3.0],
+        [7.0, 1.0],
+        [6.0, 5.0], // Front right leg
+        [9.0, 8.0],
+        [7.0, 9.0],
+        [4.0, 7.0],
+        [1.0, 10.0],
+        [2.0, 14.0],
+    ];
+
+    // Feet of the front left and back right leg swing together, the other
+    // two the opposite way
+    let offset = |index: usize| match index {
+        4 | 5 | 16 | 17 => swing,
+        9 | 10 | 21 | 22 => -swing,
+        _ => 0.0,
+    };
+
+    // Heading 0 points right and the y axis points down on screen, so
+    // "up" becomes +x and the left side (-x) becomes -y
+    polygon
+        .iter()
+        .enumerate()
+        .map(|(index, [x, y])| vec2(*y + offset(index), *x))
+        .collect()
+}
+
+/// Scaling of the turtle cursor, see `TurtlePlan::set_shape_size()`
+///
+/// Like Python turtle's `turtlesize()`: `stretch_len` scales the shape along
+/// the heading, `stretch_wid` perpendicular to it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShapeSize {
+    pub stretch_wid: f32,
+    pub stretch_len: f32,
+    /// Width of the cursor outline in pixels
+    pub outline: f32,
+}
+
+impl ShapeSize {
+    #[must_use]
+    pub fn new(stretch_wid: f32, stretch_len: f32, outline: f32) -> Self {
+        Self {
+            stretch_wid,
+            stretch_len,
+            outline,
+        }
+    }
+
+    /// Apply the stretch to a shape vertex (before rotating it to the heading)
+    #[must_use]
+    pub fn apply(&self, vertex: Vec2) -> Vec2 {
+        vec2(vertex.x * self.stretch_len, vertex.y * self.stretch_wid)
+    }
+
+    /// Interpolate between two sizes (`t` from 0.0 to 1.0)
+    #[must_use]
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            stretch_wid: self.stretch_wid + (other.stretch_wid - self.stretch_wid) * t,
+            stretch_len: self.stretch_len + (other.stretch_len - self.stretch_len) * t,
+            outline: self.outline + (other.outline - self.outline) * t,
         }
     }
 }
 
+impl Default for ShapeSize {
+    fn default() -> Self {
+        Self::new(1.0, 1.0, 1.0)
+    }
+}
+
 /// Pre-defined shape types
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub enum ShapeType {
     Triangle,
+    /// Turtle silhouette with head, legs and tail (the default cursor)
     #[default]
     Turtle,
     Circle,
@@ -163,3 +337,36 @@ impl ShapeType {
         }
     }
 }
+
+/// Custom turtle shapes by name, shared by all turtles of a world
+///
+/// Like Python turtle's `register_shape()`: register a shape once with
+/// `TurtleApp::register_shape()` and select it on any turtle with
+/// `TurtlePlan::shape_named()`.
+#[derive(Clone, Debug, Default)]
+pub struct ShapeRegistry {
+    shapes: HashMap<String, TurtleShape>,
+}
+
+impl ShapeRegistry {
+    /// Register a filled polygon (vertices relative to the turtle position,
+    /// pointing right), replacing any shape with the same name
+    pub fn register_shape(&mut self, name: impl Into<String>, vertices: Vec<Vec2>) {
+        self.register(name, TurtleShape::new(vertices, true));
+    }
+
+    /// Register any shape, replacing any shape with the same name
+    pub fn register(&mut self, name: impl Into<String>, shape: TurtleShape) {
+        self.shapes.insert(name.into(), shape);
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&TurtleShape> {
+        self.shapes.get(name)
+    }
+
+    /// Names of all registered shapes
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.shapes.keys().map(String::as_str)
+    }
+}
@@ -0,0 +1,257 @@
+//! Parsing of SVG path data, see `TurtleShape::from_svg_path()`
+
+use lyon::math::{point, vector, Angle};
+use lyon::path::builder::SvgPathBuilder;
+use lyon::path::iterator::PathIterator;
+use lyon::path::{ArcFlags, Path, PathEvent};
+use macroquad::prelude::*;
+
+/// Invalid or unsupported SVG path data
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SvgPathError {
+    pub message: String,
+}
+
+impl std::fmt::Display for SvgPathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid SVG path: {}", self.message)
+    }
+}
+
+impl std::error::Error for SvgPathError {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    Command(char),
+    Number(f32),
+}
+
+/// Vertices of the first subpath of `d`, with curves flattened to line
+/// segments that deviate at most `tolerance` from the curve
+pub(crate) fn flatten(d: &str, tolerance: f32) -> Result<Vec<Vec2>, SvgPathError> {
+    let path = parse(d)?;
+
+    let mut vertices = Vec::new();
+    for event in path.iter().flattened(tolerance.max(0.001)) {
+        match event {
+            PathEvent::Begin { at } => vertices.push(vec2(at.x, at.y)),
+            PathEvent::Line { to, .. } => vertices.push(vec2(to.x, to.y)),
+            PathEvent::End { .. } => break,
+            PathEvent::Quadratic { .. } | PathEvent::Cubic { .. } => {}
+        }
+    }
+    // A closed path ends where it started
+    if vertices.len() > 1 && vertices.first() == vertices.last() {
+        vertices.pop();
+    }
+    if vertices.len() < 3 {
+        return Err(error("a shape needs at least three points"));
+    }
+    Ok(vertices)
+}
+
+fn parse(d: &str) -> Result<Path, SvgPathError> {
+    let tokens = tokenize(d)?;
+    let mut builder = Path::svg_builder();
+    let mut index = 0;
+    let mut previous: Option<char> = None;
+
+    while index < tokens.len() {
+        let command = match tokens[index] {
+            Token::Command(command) => {
+                index += 1;
+                command
+            }
+            // Repeated arguments repeat the command, after a move as lines
+            Token::Number(_) => match previous {
+                Some('M') => 'L',
+                Some('m') => 'l',
+                Some('Z' | 'z') | None => return Err(error("expected a command")),
+                Some(command) => command,
+            },
+        };
+        previous = Some(command);
+
+        let mut next = |count: usize| numbers(&tokens, &mut index, count, command);
+        match command {
+            'M' => {
+                let n = next(2)?;
+                builder.move_to(point(n[0], n[1]));
+            }
+            'm' => {
+                let n = next(2)?;
+                builder.relative_move_to(vector(n[0], n[1]));
+            }
+            'L' => {
+                let n = next(2)?;
+                builder.line_to(point(n[0], n[1]));
+            }
+            'l' => {
+                let n = next(2)?;
+                builder.relative_line_to(vector(n[0], n[1]));
+            }
+            'H' => builder.horizontal_line_to(next(1)?[0]),
+            'h' => builder.relative_horizontal_line_to(next(1)?[0]),
+            'V' => builder.vertical_line_to(next(1)?[0]),
+            'v' => builder.relative_vertical_line_to(next(1)?[0]),
+            'C' => {
+                let n = next(6)?;
+                builder.cubic_bezier_to(point(n[0], n[1]), point(n[2], n[3]), point(n[4], n[5]));
+            }
+            'c' => {
+                let n = next(6)?;
+                builder.relative_cubic_bezier_to(
+                    vector(n[0], n[1]),
+                    vector(n[2], n[3]),
+                    vector(n[4], n[5]),
+                );
+            }
+            'S' => {
+                let n = next(4)?;
+                builder.smooth_cubic_bezier_to(point(n[0], n[1]), point(n[2], n[3]));
+            }
+            's' => {
+                let n = next(4)?;
+                builder.smooth_relative_cubic_bezier_to(vector(n[0], n[1]), vector(n[2], n[3]));
+            }
+            'Q' => {
+                let n = next(4)?;
+                builder.quadratic_bezier_to(point(n[0], n[1]), point(n[2], n[3]));
+            }
+            'q' => {
+                let n = next(4)?;
+                builder.relative_quadratic_bezier_to(vector(n[0], n[1]), vector(n[2], n[3]));
+            }
+            'T' => {
+                let n = next(2)?;
+                builder.smooth_quadratic_bezier_to(point(n[0], n[1]));
+            }
+            't' => {
+                let n = next(2)?;
+                builder.smooth_relative_quadratic_bezier_to(vector(n[0], n[1]));
+            }
+            'A' | 'a' => {
+                let n = next(7)?;
+                let radii = vector(n[0], n[1]);
+                let rotation = Angle::degrees(n[2]);
+                let flags = ArcFlags {
+                    large_arc: n[3] != 0.0,
+                    sweep: n[4] != 0.0,
+                };
+                if command == 'A' {
+                    builder.arc_to(radii, rotation, flags, point(n[5], n[6]));
+                } else {
+                    builder.relative_arc_to(radii, rotation, flags, vector(n[5], n[6]));
+                }
+            }
+            'Z' | 'z' => builder.close(),
+            other => return Err(error(&format!("unknown command `{other}`"))),
+        }
+    }
+
+    Ok(builder.build())
+}
+
+/// The next `count` arguments of `command`
+fn numbers(
+    tokens: &[Token],
+    index: &mut usize,
+    count: usize,
+    command: char,
+) -> Result<Vec<f32>, SvgPathError> {
+    let mut values = Vec::with_capacity(count);
+    for _ in 0..count {
+        match tokens.get(*index) {
+            Some(Token::Number(value)) => values.push(*value),
+            _ => return Err(error(&format!("`{command}` needs {count} numbers"))),
+        }
+        *index += 1;
+    }
+    Ok(values)
+}
+
+fn tokenize(d: &str) -> Result<Vec<Token>, SvgPathError> {
+    let bytes = d.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c.is_ascii_whitespace() || c == b',' {
+            i += 1;
+        } else if c.is_ascii_alphabetic() && c != b'e' && c != b'E' {
+            tokens.push(Token::Command(char::from(c)));
+            i += 1;
+        } else {
+            // Numbers like `-1.5e3`; `1.5.5` is two numbers and `1-2` too
+            let start = i;
+            if c == b'+' || c == b'-' {
+                i += 1;
+            }
+            let mut seen_dot = false;
+            let mut seen_exponent = false;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'0'..=b'9' => i += 1,
+                    b'.' if !seen_dot && !seen_exponent => {
+                        seen_dot = true;
+                        i += 1;
+                    }
+                    b'e' | b'E' if !seen_exponent && i > start => {
+                        seen_exponent = true;
+                        i += 1;
+                        if matches!(bytes.get(i), Some(b'+' | b'-')) {
+                            i += 1;
+                        }
+                    }
+                    _ => break,
+                }
+            }
+
+            let text = &d[start..i];
+            if text.is_empty() {
+                let unexpected = d[start..].chars().next().unwrap_or_default();
+                return Err(error(&format!("unexpected character `{unexpected}`")));
+            }
+            let value = text
+                .parse()
+                .map_err(|_| error(&format!("invalid number `{text}`")))?;
+            tokens.push(Token::Number(value));
+        }
+    }
+    Ok(tokens)
+}
+
+fn error(message: &str) -> SvgPathError {
+    SvgPathError {
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_lines_and_relative_commands() {
+        let vertices = flatten("M 0,0 L 10 0 l0-10 H0z", 0.1).unwrap();
+        assert_eq!(
+            vertices,
+            vec![
+                vec2(0.0, 0.0),
+                vec2(10.0, 0.0),
+                vec2(10.0, -10.0),
+                vec2(0.0, -10.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn flattens_curves_and_rejects_garbage() {
+        let vertices = flatten("M0 0 C 0 10 10 10 10 0 Z", 0.01).unwrap();
+        assert!(vertices.len() > 4);
+
+        assert!(flatten("M0 0 L 10", 0.1).is_err());
+        assert!(flatten("M0 0 L 10 0 # 5", 0.1).is_err());
+    }
+}
@@ -1,10 +1,21 @@
 //! Turtle state and world state management
 
-use crate::commands::CommandQueue;
-use crate::general::{Angle, AnimationSpeed, Color, Coordinate};
+use crate::commands::{CommandQueue, StampId, TurtleCommand};
+use crate::error::TurtleError;
+use crate::follow::Follow;
+use crate::general::{
+    state_to_f32, Angle, AnimationSpeed, Color, Coordinate, CoordinateMode, StatePrecision,
+};
+use crate::palettes::Palette;
+use crate::physics::Physics;
 use crate::shapes::TurtleShape;
-use crate::tweening::TweenController;
+use crate::stencil::{Stencil, StencilRecording, StencilTransform};
+use crate::theme::Theme;
+use crate::tweening::{Easing, TweenController};
 use macroquad::prelude::*;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::f32::consts::{PI, TAU};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
 /// State during active fill operation
 #[derive(Clone, Debug)]
@@ -25,18 +36,247 @@ pub struct FillState {
 
 /// Parameters that define a turtle's visual state
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TurtleParams {
+    #[cfg_attr(feature = "serde", serde(with = "crate::wire::vec2"))]
     pub position: Vec2,
     pub heading: f32,
     pub pen_down: bool,
     pub pen_width: f32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::wire::color"))]
     pub color: Color,
+    #[cfg_attr(feature = "serde", serde(with = "crate::wire::option_color"))]
     pub fill_color: Option<Color>,
     pub visible: bool,
     pub shape: crate::shapes::TurtleShape,
     pub speed: AnimationSpeed,
+    pub easing: Easing,
+    /// Rotation speed in degrees per second, `None` derives it from `speed`
+    pub turn_speed: Option<f32>,
+    /// Seconds that pen color and width changes take, `0.0` switches immediately
+    pub style_transition: f32,
+    /// Stretch and outline of the cursor
+    pub shape_size: crate::shapes::ShapeSize,
+    /// Rotation of the cursor relative to `heading` in radians
+    pub tilt: f32,
+    /// Fill and outline color of the cursor
+    #[cfg_attr(feature = "serde", serde(with = "crate::wire::color"))]
+    pub cursor_fill: Color,
+    #[cfg_attr(feature = "serde", serde(with = "crate::wire::color"))]
+    pub cursor_outline: Color,
+    /// Palette the pen color is taken from, with the index of the next stroke
+    pub pen_palette: Option<(Palette, usize)>,
+    /// How new strokes combine with the drawing below them
+    pub blend_mode: BlendMode,
+    /// Unrounded `position` and `heading`, see `exact_pose()`
+    pub exact: ExactPose,
+    /// Label for the next stroke, see `TurtlePlan::annotate()`
+    pub annotation: Option<String>,
 }
 
+/// What `TurtlePlan::push_state()` saves and `pop_state()` restores
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SavedState {
+    pub pose: ExactPose,
+    pub pen_width: f32,
+    pub color: Color,
+}
+
+/// Turtle position and heading (radians) at `StatePrecision`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExactPose {
+    pub x: StatePrecision,
+    pub y: StatePrecision,
+    pub heading: StatePrecision,
+}
+
+impl ExactPose {
+    /// Pose moved `distance` pixels along the heading
+    #[must_use]
+    pub fn moved(self, distance: f32) -> Self {
+        let distance = StatePrecision::from(distance);
+        Self {
+            x: self.x + distance * self.heading.cos(),
+            y: self.y + distance * self.heading.sin(),
+            ..self
+        }
+    }
+
+    /// Pose turned by `angle` radians (positive turns right)
+    #[must_use]
+    pub fn turned(self, angle: f32) -> Self {
+        Self {
+            heading: self.heading + StatePrecision::from(angle),
+            ..self
+        }
+    }
+
+    /// Pose at the end of an arc of `angle` radians, like `CircleGeometry`
+    #[must_use]
+    pub fn along_circle(
+        self,
+        radius: f32,
+        angle: f32,
+        direction: crate::circle_geometry::CircleDirection,
+    ) -> Self {
+        use crate::circle_geometry::CircleDirection;
+        #[cfg(not(feature = "f64"))]
+        use std::f32::consts::FRAC_PI_2;
+        #[cfg(feature = "f64")]
+        use std::f64::consts::FRAC_PI_2;
+
+        let (radius, angle) = (StatePrecision::from(radius), StatePrecision::from(angle));
+        let (to_center, heading) = match direction {
+            CircleDirection::Left => (self.heading - FRAC_PI_2, self.heading - angle),
+            CircleDirection::Right => (self.heading + FRAC_PI_2, self.heading + angle),
+        };
+        let center_x = self.x + radius * to_center.cos();
+        let center_y = self.y + radius * to_center.sin();
+        // The turtle stays at a right angle to the direction of the center
+        let from_center = heading + (self.heading - to_center);
+        Self {
+            x: center_x + radius * from_center.cos(),
+            y: center_y + radius * from_center.sin(),
+            heading,
+        }
+    }
+
+    /// Pose with the heading wrapped to -PI..=PI
+    #[must_use]
+    pub fn normalized(self) -> Self {
+        #[cfg(not(feature = "f64"))]
+        use std::f32::consts::{PI, TAU};
+        #[cfg(feature = "f64")]
+        use std::f64::consts::{PI, TAU};
+
+        let mut heading = self.heading % TAU;
+        if heading > PI {
+            heading -= TAU;
+        } else if heading < -PI {
+            heading += TAU;
+        }
+        Self { heading, ..self }
+    }
+}
+
+impl TurtleParams {
+    /// Position and heading at `StatePrecision`
+    ///
+    /// Moves, turns and circles update the exact pose and round it into
+    /// `position` and `heading`. If those were changed directly since, the
+    /// exact pose starts over from them.
+    #[must_use]
+    pub fn exact_pose(&self) -> ExactPose {
+        let rounded = (
+            state_to_f32(self.exact.x),
+            state_to_f32(self.exact.y),
+            state_to_f32(self.exact.heading),
+        );
+        if rounded == (self.position.x, self.position.y, self.heading) {
+            self.exact
+        } else {
+            ExactPose {
+                x: StatePrecision::from(self.position.x),
+                y: StatePrecision::from(self.position.y),
+                heading: StatePrecision::from(self.heading),
+            }
+        }
+    }
+
+    /// Set `position` and `heading` from an exact pose
+    pub fn set_exact_pose(&mut self, pose: ExactPose) {
+        self.exact = pose;
+        self.position = vec2(state_to_f32(pose.x), state_to_f32(pose.y));
+        self.heading = state_to_f32(pose.heading);
+    }
+
+    /// Distance in pixels from the turtle to `point`
+    ///
+    /// Like `position`, the geometry helpers take and return points in
+    /// screen space and headings in radians with positive angles turning
+    /// right.
+    #[must_use]
+    pub fn distance_to(&self, point: Coordinate) -> f32 {
+        self.position.distance(point)
+    }
+
+    /// Heading (radians) that points from the turtle to `point`
+    #[must_use]
+    pub fn heading_to(&self, point: Coordinate) -> f32 {
+        let offset = point - self.position;
+        offset.y.atan2(offset.x)
+    }
+
+    /// Turn that makes the turtle face `point`, between -180° and 180°
+    ///
+    /// Positive values turn right, so `plan.right(params.angle_to(target))`
+    /// turns towards the target, e.g. for pursuit curves.
+    #[must_use]
+    pub fn angle_to(&self, point: Coordinate) -> Angle {
+        let turn = (self.heading_to(point) - self.heading).rem_euclid(TAU);
+        let turn = if turn > PI { turn - TAU } else { turn };
+        Angle::degrees(turn.to_degrees())
+    }
+
+    /// Whether `point` is within `tolerance` of the turtle's heading
+    #[must_use]
+    pub fn is_facing(&self, point: Coordinate, tolerance: impl Into<Angle>) -> bool {
+        let tolerance = tolerance.into().to_degrees().value();
+        self.angle_to(point).value().abs() <= tolerance
+    }
+
+    /// The point `distance` pixels ahead of the turtle
+    #[must_use]
+    pub fn point_ahead(&self, distance: f32) -> Coordinate {
+        self.position + Vec2::from_angle(self.heading) * distance
+    }
+
+    /// Heading after bouncing off a wall running along `axis` (any
+    /// direction vector of the wall), like light from a mirror
+    ///
+    /// Returns the current heading if `axis` has no length.
+    #[must_use]
+    pub fn reflect_heading(&self, axis: Vec2) -> f32 {
+        let Some(axis) = axis.try_normalize() else {
+            return self.heading;
+        };
+        let direction = Vec2::from_angle(self.heading);
+        let reflected = 2.0 * direction.dot(axis) * axis - direction;
+        reflected.y.atan2(reflected.x)
+    }
+
+    /// Absolute vertices of the turtle cursor as it is drawn on screen
+    ///
+    /// Used for rendering as well as hit-testing and collision detection.
+    #[must_use]
+    pub fn cursor_vertices(&self) -> Vec<Vec2> {
+        self.cursor_vertices_walked(0.0)
+    }
+
+    /// Like `cursor_vertices()`, showing the walk frame of a turtle that
+    /// moved `distance` pixels in its current movement
+    #[must_use]
+    pub fn cursor_vertices_walked(&self, distance: f32) -> Vec<Vec2> {
+        let shape = TurtleShape::new(
+            self.shape
+                .frame_vertices(distance)
+                .iter()
+                .map(|v| self.shape_size.apply(*v - self.shape.pivot))
+                .collect(),
+            self.shape.filled,
+        );
+        shape
+            .rotated_vertices(self.heading + self.tilt)
+            .into_iter()
+            .map(|v| self.position + v)
+            .collect()
+    }
+}
+
+/// Color of the turtle cursor unless set with `TurtlePlan::set_turtle_color()`
+pub const DEFAULT_CURSOR_COLOR: Color = Color::new(0.0, 0.5, 1.0, 1.0);
+
 impl Default for TurtleParams {
     /// Create `TurtleParams` from default values
     fn default() -> Self {
@@ -50,10 +290,84 @@ impl Default for TurtleParams {
             visible: true,
             shape: TurtleShape::turtle(),
             speed: AnimationSpeed::default(),
+            easing: Easing::default(),
+            turn_speed: None,
+            style_transition: 0.0,
+            shape_size: crate::shapes::ShapeSize::default(),
+            tilt: 0.0,
+            cursor_fill: DEFAULT_CURSOR_COLOR,
+            cursor_outline: DEFAULT_CURSOR_COLOR,
+            pen_palette: None,
+            blend_mode: BlendMode::Normal,
+            exact: ExactPose::default(),
+            annotation: None,
+        }
+    }
+}
+
+/// Message left by `TurtlePlan::send_message()`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    /// The turtle that sent the message
+    pub from: usize,
+    pub payload: String,
+}
+
+/// Named position recorded by `TurtlePlan::mark()`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Marker {
+    /// Recorded position
+    pub position: Coordinate,
+    /// Recorded heading in radians
+    pub heading: f32,
+    /// The turtle that recorded the marker
+    pub turtle_id: usize,
+}
+
+impl Marker {
+    /// Convert a marker stored in screen space to `mode`
+    #[must_use]
+    pub fn from_screen(self, mode: CoordinateMode) -> Self {
+        Self {
+            position: mode.from_screen(self.position),
+            heading: mode.heading_from_screen(self.heading),
+            ..self
         }
     }
 }
 
+/// World data that turtles read and write while executing commands
+#[derive(Clone, Debug, Default)]
+pub struct SharedWorldData {
+    pub markers: HashMap<String, Marker>,
+    pub coordinate_mode: CoordinateMode,
+    pub shapes: crate::shapes::ShapeRegistry,
+    /// Recordings finished by `end_stencil()`, relative to their start pose
+    pub stencils: HashMap<Stencil, Vec<TurtleCommand>>,
+    /// Unread messages by receiving turtle ID, oldest first
+    pub mailboxes: HashMap<usize, Vec<Message>>,
+}
+
+/// Handle to the `SharedWorldData`, shared between a `TurtleWorld` and its turtles
+pub type SharedWorld = Arc<Mutex<SharedWorldData>>;
+
+/// Requests raised while executing a turtle's commands that affect the whole world
+///
+/// Turtles only have access to their own state during execution, so these are
+/// collected on the turtle and applied by `TurtleWorld::apply_world_requests()`.
+#[derive(Clone, Debug)]
+pub enum WorldRequest {
+    /// Clear the drawings of all other turtles
+    ClearScreen { rehome: bool },
+    /// Remove the turtle, keeping its drawings in `TurtleWorld::layer`
+    Die { keep_drawing: bool },
+    /// Add a turtle in the given state that works through `plan`
+    Spawn {
+        params: Box<TurtleParams>,
+        plan: Vec<TurtleCommand>,
+    },
+}
+
 /// State of a single turtle
 #[derive(Clone, Debug)]
 pub struct Turtle {
@@ -68,8 +382,38 @@ pub struct Turtle {
 
     // Animation controller for this turtle
     pub tween_controller: TweenController,
+
+    // Pending requests for the world, drained by `TurtleWorld::apply_world_requests()`
+    pub world_requests: Vec<WorldRequest>,
+
+    // World data shared with the other turtles (markers, ...)
+    pub shared: SharedWorld,
+
+    // Recent cursor states for `TurtleWorld::ghost_trail`, oldest first
+    pub ghosts: VecDeque<TurtleParams>,
+
+    // Number of commands executed so far per `TurtleCommand::name()`
+    pub executed: BTreeMap<&'static str, usize>,
+
+    // Problems since the last `TurtleApp::take_errors()`, oldest first
+    pub errors: Vec<TurtleError>,
+
+    // Stencils between `begin_stencil()` and `end_stencil()`, innermost last
+    pub stencil_recordings: Vec<StencilRecording>,
+
+    // Velocity-based motion once the queue is done, see `crate::physics`
+    pub physics: Option<Physics>,
+
+    // Leader whose path the turtle trails, see `crate::follow`
+    pub follow: Option<Follow>,
+
+    // States saved by `push_state()`, most recent last
+    pub state_stack: Vec<SavedState>,
 }
 
+/// Errors kept per turtle until they are taken, older ones are dropped
+pub const MAX_PENDING_ERRORS: usize = 100;
+
 impl Default for Turtle {
     fn default() -> Self {
         Self {
@@ -78,6 +422,15 @@ impl Default for Turtle {
             filling: None,
             commands: Vec::new(),
             tween_controller: TweenController::new(CommandQueue::new(), AnimationSpeed::default()),
+            world_requests: Vec::new(),
+            shared: SharedWorld::default(),
+            ghosts: VecDeque::new(),
+            executed: BTreeMap::new(),
+            errors: Vec::new(),
+            stencil_recordings: Vec::new(),
+            physics: None,
+            follow: None,
+            state_stack: Vec::new(),
         }
     }
 }
@@ -87,18 +440,241 @@ impl Turtle {
         self.params.speed = speed;
     }
 
+    /// Current heading in the world's `CoordinateMode`
     #[must_use]
     pub fn heading_angle(&self) -> Angle {
-        Angle::radians(self.params.heading)
+        Angle::radians(
+            self.coordinate_mode()
+                .heading_from_screen(self.params.heading),
+        )
+    }
+
+    /// Current position in the world's `CoordinateMode`
+    #[must_use]
+    pub fn position(&self) -> Coordinate {
+        self.coordinate_mode().from_screen(self.params.position)
+    }
+
+    #[must_use]
+    pub fn coordinate_mode(&self) -> CoordinateMode {
+        self.shared_data().coordinate_mode
+    }
+
+    /// Lock the world data shared with the other turtles
+    pub fn shared_data(&self) -> MutexGuard<'_, SharedWorldData> {
+        self.shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Replace commands that depend on shared world data with concrete ones
+    ///
+    /// Absolute `Goto`/`SetHeading` values are converted from the world's
+    /// `CoordinateMode` to screen space, `GoToMark` becomes a `Goto` to the
+    /// recorded position and `SetShapeNamed` a `SetShape` with the registered
+    /// shape. Returns `None` (and logs a warning) if the command
+    /// can't be resolved, see `try_resolve_command()` for the reason.
+    #[must_use]
+    pub fn resolve_command(&self, command: TurtleCommand) -> Option<TurtleCommand> {
+        self.try_resolve_command(command).ok()
+    }
+
+    /// Like `resolve_command()`, keeping the error for `TurtleApp::take_errors()`
+    ///
+    /// Also records the command into the open stencils. Stencil commands are
+    /// carried out here and return `None`: a stamped stencil is queued for
+    /// replay with `TweenController::replay()`. A `Barrier` makes the turtle
+    /// wait, see `TurtleWorld::release_barriers()`.
+    pub(crate) fn resolve_reporting(&mut self, command: TurtleCommand) -> Option<TurtleCommand> {
+        match command {
+            TurtleCommand::BeginStencil(stencil) => {
+                let recording = StencilRecording::new(stencil, &self.params);
+                self.stencil_recordings.push(recording);
+                return None;
+            }
+            TurtleCommand::EndStencil(stencil) => {
+                self.end_stencil(stencil);
+                return None;
+            }
+            TurtleCommand::StampStencil { stencil, transform } => {
+                let commands = self.place_stencil(stencil, transform);
+                self.tween_controller.replay(commands);
+                return None;
+            }
+            TurtleCommand::Barrier(tag) => {
+                self.tween_controller.wait_at(tag);
+                return None;
+            }
+            _ => {}
+        }
+        match self.try_resolve_command(command) {
+            Ok(command) => {
+                for recording in &mut self.stencil_recordings {
+                    recording.record(&command);
+                }
+                Some(command)
+            }
+            Err(error) => {
+                self.report_error(error);
+                None
+            }
+        }
+    }
+
+    /// Like `resolve_command()`, with the reason a command can't be resolved
+    ///
+    /// Members of a `Together` group that can't be resolved are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns `UnknownMark` or `UnknownShape` for names that don't exist
+    pub fn try_resolve_command(
+        &self,
+        command: TurtleCommand,
+    ) -> Result<TurtleCommand, TurtleError> {
+        let shared = self.shared_data();
+        match command {
+            TurtleCommand::Goto(coord) => {
+                Ok(TurtleCommand::Goto(shared.coordinate_mode.to_screen(coord)))
+            }
+            TurtleCommand::SetHeading(heading) => Ok(TurtleCommand::SetHeading(
+                shared.coordinate_mode.heading_to_screen(heading),
+            )),
+            TurtleCommand::Together(members) => {
+                drop(shared);
+                Ok(TurtleCommand::Together(
+                    members
+                        .into_iter()
+                        .filter_map(|member| self.resolve_command(member))
+                        .collect(),
+                ))
+            }
+            TurtleCommand::GoToMark(name) => {
+                if let Some(marker) = shared.markers.get(&name) {
+                    Ok(TurtleCommand::Goto(marker.position))
+                } else {
+                    tracing::warn!(
+                        turtle_id = self.turtle_id,
+                        mark = %name,
+                        "go_to_mark() with unknown mark, skipping"
+                    );
+                    Err(TurtleError::UnknownMark(name))
+                }
+            }
+            TurtleCommand::SetShapeNamed(name) => {
+                if let Some(shape) = shared.shapes.get(&name) {
+                    Ok(TurtleCommand::SetShape(shape.clone()))
+                } else {
+                    tracing::warn!(
+                        turtle_id = self.turtle_id,
+                        shape = %name,
+                        "shape_named() with unregistered shape, skipping"
+                    );
+                    Err(TurtleError::UnknownShape(name))
+                }
+            }
+            other => Ok(other),
+        }
+    }
+
+    /// Take the pen color of a drawing command from the pen palette
+    ///
+    /// Called right before `command` starts, advances the palette for every
+    /// stroke drawn with the pen down.
+    pub fn apply_pen_palette(&mut self, command: &TurtleCommand) {
+        let draws = matches!(
+            command,
+            TurtleCommand::Move(_)
+                | TurtleCommand::Circle { .. }
+                | TurtleCommand::Goto(_)
+                | TurtleCommand::Together(_)
+        );
+        if !draws || !self.params.pen_down {
+            return;
+        }
+        if let Some((palette, index)) = &mut self.params.pen_palette {
+            self.params.color = palette.color(*index);
+            *index += 1;
+        }
+    }
+
+    /// Statistics about everything this turtle has drawn so far
+    ///
+    /// Includes total path length, segment and turn counts, fill areas and the
+    /// bounding box, all computed from the retained `TurtleSource` data. The
+    /// bounds use the world's `CoordinateMode`.
+    #[must_use]
+    pub fn stats(&self) -> crate::stats::DrawingStats {
+        let mut stats = crate::stats::DrawingStats::from_commands(&self.commands);
+        let mode = self.coordinate_mode();
+        stats.bounds = stats
+            .bounds
+            .map(|b| crate::general::Bounds::new(mode.from_screen(b.min), mode.from_screen(b.max)));
+        stats
+    }
+
+    /// Keep `error` until it is taken, see `MAX_PENDING_ERRORS`
+    pub(crate) fn report_error(&mut self, error: TurtleError) {
+        if self.errors.len() >= MAX_PENDING_ERRORS {
+            self.errors.remove(0);
+        }
+        self.errors.push(error);
+    }
+
+    /// Store the recording of `stencil` in the world
+    fn end_stencil(&mut self, stencil: Stencil) {
+        let Some(index) = self
+            .stencil_recordings
+            .iter()
+            .rposition(|recording| recording.stencil == stencil)
+        else {
+            tracing::warn!(
+                turtle_id = self.turtle_id,
+                "end_stencil() without begin_stencil(), ignoring"
+            );
+            return;
+        };
+        let recording = self.stencil_recordings.remove(index);
+        self.shared_data()
+            .stencils
+            .insert(stencil, recording.into_commands());
+    }
+
+    /// Commands that replay `stencil` from the current pose, empty if it
+    /// wasn't recorded yet
+    pub(crate) fn place_stencil(
+        &mut self,
+        stencil: Stencil,
+        transform: StencilTransform,
+    ) -> Vec<TurtleCommand> {
+        let placed = {
+            let shared = self.shared_data();
+            shared.stencils.get(&stencil).map(|commands| {
+                crate::stencil::place(commands, &self.params, transform, shared.coordinate_mode)
+            })
+        };
+        placed.unwrap_or_else(|| {
+            tracing::warn!(
+                turtle_id = self.turtle_id,
+                "stamp_stencil() before end_stencil(), skipping"
+            );
+            self.report_error(TurtleError::UnknownStencil);
+            Vec::new()
+        })
+    }
+
+    /// Count `command` in `executed`
+    pub(crate) fn count_command(&mut self, command: &TurtleCommand) {
+        *self.executed.entry(command.name()).or_default() += 1;
     }
 
     /// Reset turtle to default state (preserves `turtle_id` and queued commands)
     pub fn reset(&mut self) {
         // Clear all drawings
         self.commands.clear();
+        self.ghosts.clear();
 
         // Clear fill state
         self.filling = None;
+        self.state_stack.clear();
 
         // Reset parameters to defaults
         self.params = TurtleParams::default();
@@ -106,6 +682,84 @@ impl Turtle {
         // Keep turtle_id and tween_controller (preserves queued commands)
     }
 
+    /// Save the pose, pen width and pen color for `pop_state()`
+    pub fn push_state(&mut self) {
+        self.state_stack.push(SavedState {
+            pose: self.params.exact_pose(),
+            pen_width: self.params.pen_width,
+            color: self.params.color,
+        });
+    }
+
+    /// Restore what the last `push_state()` saved
+    ///
+    /// The turtle jumps back without drawing, an ongoing fill continues with
+    /// a new contour from there.
+    pub fn pop_state(&mut self) {
+        let Some(saved) = self.state_stack.pop() else {
+            tracing::warn!(
+                turtle_id = self.turtle_id,
+                "pop_state() without push_state()"
+            );
+            self.report_error(TurtleError::PopWithoutPush);
+            return;
+        };
+        self.close_fill_contour();
+        self.params.set_exact_pose(saved.pose);
+        self.params.pen_width = saved.pen_width;
+        self.params.color = saved.color;
+        if self.params.pen_down {
+            self.start_fill_contour();
+        }
+    }
+
+    /// Remove all drawings and any ongoing fill, optionally moving back home
+    ///
+    /// Unlike `reset()` the pen, colors and other parameters are kept.
+    pub fn clear_drawings(&mut self, rehome: bool) {
+        self.commands.clear();
+        self.ghosts.clear();
+        self.filling = None;
+        if rehome {
+            self.params.position = vec2(0.0, 0.0);
+            self.params.heading = 0.0;
+        }
+    }
+
+    /// IDs of this turtle's stamps, oldest first
+    pub fn stamps(&self) -> impl Iterator<Item = StampId> + '_ {
+        self.commands
+            .iter()
+            .filter_map(|cmd| match cmd.source().command {
+                TurtleCommand::Stamp(id) => Some(id),
+                _ => None,
+            })
+    }
+
+    /// Remove the stamp with the given ID, returns whether it existed
+    pub fn clear_stamp(&mut self, id: StampId) -> bool {
+        let before = self.commands.len();
+        self.commands.retain(
+            |cmd| !matches!(cmd.source().command, TurtleCommand::Stamp(stamp) if stamp == id),
+        );
+        self.commands.len() != before
+    }
+
+    /// Remove the first `n` stamps (positive), the last `n` (negative) or all
+    /// of them (`None`), like Python turtle's `clearstamps`
+    pub fn clear_stamps(&mut self, count: Option<i32>) {
+        let stamps: Vec<StampId> = self.stamps().collect();
+        let remove = match count {
+            None => &stamps[..],
+            Some(n) if n >= 0 => &stamps[..stamps.len().min(n.unsigned_abs() as usize)],
+            Some(n) => &stamps[stamps.len().saturating_sub(n.unsigned_abs() as usize)..],
+        };
+        self.commands.retain(|cmd| match cmd.source().command {
+            TurtleCommand::Stamp(id) => !remove.contains(&id),
+            _ => true,
+        });
+    }
+
     /// Start recording fill vertices
     pub fn begin_fill(&mut self, fill_color: Color) {
         self.filling = Some(FillState {
@@ -259,12 +913,52 @@ impl Turtle {
 
 /// Cached mesh data that can be cloned and converted to Mesh when needed
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MeshData {
+    #[cfg_attr(feature = "serde", serde(with = "crate::wire::vertex_list"))]
     pub vertices: Vec<macroquad::prelude::Vertex>,
     pub indices: Vec<u16>,
 }
 
 impl MeshData {
+    /// Most vertices a mesh can hold, indices are `u16`
+    pub const MAX_VERTICES: usize = u16::MAX as usize + 1;
+
+    /// Append the triangles of another mesh
+    ///
+    /// # Errors
+    ///
+    /// Returns `TurtleError::Tessellation` and leaves the mesh unchanged if
+    /// the combined mesh would exceed `MAX_VERTICES`
+    pub fn append(&mut self, other: MeshData) -> Result<(), TurtleError> {
+        let too_large = || {
+            TurtleError::Tessellation(format!(
+                "a mesh holds at most {} vertices",
+                Self::MAX_VERTICES
+            ))
+        };
+        if self.vertices.len() + other.vertices.len() > Self::MAX_VERTICES {
+            return Err(too_large());
+        }
+        let Ok(offset) = u16::try_from(self.vertices.len()) else {
+            // Only possible when `other` has no vertices to point at
+            return if other.indices.is_empty() {
+                Ok(())
+            } else {
+                Err(too_large())
+            };
+        };
+        let indices = other
+            .indices
+            .iter()
+            .map(|index| index.checked_add(offset))
+            .collect::<Option<Vec<u16>>>()
+            .ok_or_else(too_large)?;
+        self.indices.extend(indices);
+        self.vertices.extend(other.vertices);
+        Ok(())
+    }
+
     #[must_use]
     pub fn to_mesh(&self) -> macroquad::prelude::Mesh {
         macroquad::prelude::Mesh {
@@ -273,6 +967,51 @@ impl MeshData {
             texture: None,
         }
     }
+
+    /// Like `to_mesh()`, with every vertex's alpha scaled by `factor` (0.0..=1.0)
+    #[must_use]
+    pub fn to_mesh_with_alpha(&self, factor: f32) -> macroquad::prelude::Mesh {
+        let mut mesh = self.to_mesh();
+        for vertex in &mut mesh.vertices {
+            vertex.color[3] = (f32::from(vertex.color[3]) * factor.clamp(0.0, 1.0)) as u8;
+        }
+        mesh
+    }
+
+    /// Color of the first triangle that contains `point`, if any
+    #[must_use]
+    pub fn color_at(&self, point: Vec2) -> Option<Color> {
+        self.indices.chunks_exact(3).find_map(|triangle| {
+            let a = &self.vertices[usize::from(triangle[0])];
+            let b = &self.vertices[usize::from(triangle[1])];
+            let c = &self.vertices[usize::from(triangle[2])];
+            if point_in_triangle(
+                point,
+                a.position.truncate(),
+                b.position.truncate(),
+                c.position.truncate(),
+            ) {
+                let [red, green, blue, alpha] = a.color;
+                Some(Color::from_rgba(red, green, blue, alpha))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Check whether `p` lies inside (or on an edge of) the triangle `a`, `b`, `c`
+fn point_in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    fn edge(p: Vec2, from: Vec2, to: Vec2) -> f32 {
+        (p.x - to.x) * (from.y - to.y) - (from.x - to.x) * (p.y - to.y)
+    }
+
+    let d1 = edge(p, a, b);
+    let d2 = edge(p, b, c);
+    let d3 = edge(p, c, a);
+    let has_neg = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_pos = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_neg && has_pos)
 }
 
 /// Drawable elements in the world
@@ -287,6 +1026,47 @@ pub struct TurtleSource {
     pub end_position: Vec2,
     pub start_heading: f32,
     pub contours: Option<Vec<Vec<crate::general::Coordinate>>>,
+    /// Animation time at which the drawing was created (see `TweenController::now()`)
+    pub created_at: f64,
+    /// How the drawing combines with what is below it
+    pub blend_mode: BlendMode,
+    /// Label drawn next to the stroke, see `TurtlePlan::annotate()`
+    pub annotation: Option<String>,
+}
+
+impl TurtleSource {
+    /// Middle of the stroke and the direction its label is moved away in
+    ///
+    /// The direction points to the left of the stroke as seen on screen,
+    /// i.e. upwards for a stroke drawn to the right.
+    #[must_use]
+    pub fn annotation_anchor(&self) -> (Vec2, Vec2) {
+        let middle = match &self.command {
+            TurtleCommand::Circle {
+                radius,
+                angle,
+                direction,
+                ..
+            } => crate::circle_geometry::CircleGeometry::new(
+                self.start_position,
+                self.start_heading,
+                *radius,
+                *direction,
+            )
+            .position_at_angle(angle.to_radians() / 2.0),
+            _ => match self.contours.iter().flatten().find(|c| !c.is_empty()) {
+                Some(points) => points[points.len() / 2],
+                None => self.start_position.lerp(self.end_position, 0.5),
+            },
+        };
+        let along = (self.end_position - self.start_position).normalize_or_zero();
+        let normal = if along == Vec2::ZERO {
+            vec2(0.0, -1.0)
+        } else {
+            vec2(along.y, -along.x)
+        };
+        (middle, normal)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -307,26 +1087,193 @@ pub enum DrawCommand {
     },
 }
 
+impl DrawCommand {
+    /// The turtle command this drawing was created from
+    #[must_use]
+    pub fn source(&self) -> &TurtleSource {
+        match self {
+            DrawCommand::Mesh { source, .. } | DrawCommand::Text { source, .. } => source,
+        }
+    }
+}
+
+/// How a stroke's colors combine with the drawing below it
+///
+/// Applied when rendering in the window and by the headless renderer. The
+/// blending happens against whatever is already drawn at that moment, so
+/// the order of drawings matters, and lines that are still animating are
+/// drawn normally until they are finished. SVG exports ignore blend modes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlendMode {
+    /// Regular alpha blending
+    #[default]
+    Normal,
+    /// Colors add up and overlaps get brighter, for glow effects on dark
+    /// backgrounds
+    Additive,
+    /// Colors multiply and overlaps get darker, like layers of ink
+    Multiply,
+}
+
+/// How drawings disappear as they get older
+///
+/// Keeps long-running animations from accumulating drawings forever and makes
+/// motion paths visible like an onion skin. Ages are measured in animation time
+/// (see `TweenController::now()`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrailFade {
+    /// Drawings fade out linearly and are removed after `seconds`
+    Fade { seconds: f32 },
+    /// Only drawings of the last `seconds` are shown, at full opacity
+    Window { seconds: f32 },
+}
+
+impl TrailFade {
+    /// Opacity factor (0.0..=1.0) of a drawing that is `age` seconds old
+    #[must_use]
+    pub fn alpha(self, age: f64) -> f32 {
+        let age = age.max(0.0) as f32;
+        match self {
+            TrailFade::Fade { seconds } if seconds > 0.0 => (1.0 - age / seconds).clamp(0.0, 1.0),
+            TrailFade::Fade { .. } => 0.0,
+            TrailFade::Window { seconds } => {
+                if age <= seconds {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Faded copies of the turtle cursor along its recent path (onion skinning)
+///
+/// A ghost is left behind every `spacing` pixels the turtle moves. Only the
+/// newest `count` ghosts are kept, each older one is drawn `falloff` times as
+/// opaque as the one before.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GhostTrail {
+    /// Maximum number of ghosts per turtle
+    pub count: usize,
+    /// Distance in pixels between two ghosts
+    pub spacing: f32,
+    /// Opacity of the newest ghost (0.0..=1.0)
+    pub opacity: f32,
+    /// Opacity factor from one ghost to the next older one (0.0..=1.0)
+    pub falloff: f32,
+}
+
+impl GhostTrail {
+    /// `count` ghosts every `spacing` pixels, starting at half opacity
+    #[must_use]
+    pub fn new(count: usize, spacing: f32) -> Self {
+        Self {
+            count,
+            spacing,
+            opacity: 0.5,
+            falloff: 0.7,
+        }
+    }
+
+    /// Change the opacity of the newest ghost and how fast older ones fade
+    #[must_use]
+    pub fn with_opacity(mut self, opacity: f32, falloff: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self.falloff = falloff.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Opacity of the ghost with the given age, 0 being the newest
+    #[must_use]
+    pub fn alpha(self, age: usize) -> f32 {
+        self.opacity * self.falloff.powi(i32::try_from(age).unwrap_or(i32::MAX))
+    }
+}
+
 /// The complete turtle world containing all drawing state
 pub struct TurtleWorld {
     /// All turtles in the world (indexed by turtle ID)
     pub turtles: Vec<Turtle>,
     pub camera: Camera2D,
     pub background_color: Color,
+    /// Data shared with all turtles of this world (markers, ...)
+    pub shared: SharedWorld,
+    /// Fade out old drawings (`None` keeps everything)
+    pub trail_fade: Option<TrailFade>,
+    /// Draw faded copies of the cursors along their paths
+    pub ghost_trail: Option<GhostTrail>,
+    /// Top and bottom color of a vertical gradient drawn behind everything
+    pub background_gradient: Option<(Color, Color)>,
+    /// Colors of cursors and helper marks, see `TurtleApp::set_high_contrast()`
+    pub theme: Theme,
+    /// Drawings of removed turtles, drawn below those of the living ones
+    pub layer: Vec<DrawCommand>,
 }
 
 impl TurtleWorld {
     #[must_use]
     pub fn new() -> Self {
         Self {
-            turtles: vec![], // Start with no turtles
             camera: Camera2D {
                 zoom: vec2(1.0 / screen_width() * 2.0, 1.0 / screen_height() * 2.0),
                 target: vec2(0.0, 0.0),
                 ..Default::default()
             },
+            ..Self::headless()
+        }
+    }
+
+    /// A world that does not query the window size, for use without a
+    /// macroquad window (see the `headless` module)
+    #[must_use]
+    pub fn headless() -> Self {
+        Self {
+            turtles: vec![], // Start with no turtles
+            camera: Camera2D::default(),
             background_color: WHITE,
+            shared: SharedWorld::default(),
+            trail_fade: None,
+            ghost_trail: None,
+            background_gradient: None,
+            theme: Theme::standard(),
+            layer: Vec::new(),
+        }
+    }
+
+    /// Fill the background with a vertical gradient from `top` to `bottom`
+    ///
+    /// The gradient covers the whole window behind all drawings, no matter
+    /// how the view is panned or zoomed. It is also part of SVG and headless
+    /// exports.
+    pub fn set_background_gradient(&mut self, top: Color, bottom: Color) {
+        self.background_gradient = Some((top, bottom));
+    }
+
+    /// Go back to the plain background color
+    pub fn clear_background_gradient(&mut self) {
+        self.background_gradient = None;
+    }
+
+    /// Commands executed per type, drawn length, fills, turtles used and
+    /// elapsed animation time of the whole world
+    #[must_use]
+    pub fn summary(&self) -> crate::stats::WorldSummary {
+        let mut summary = crate::stats::WorldSummary::default();
+        for turtle in &self.turtles {
+            for (name, count) in &turtle.executed {
+                *summary.commands.entry(name).or_default() += count;
+            }
+            if !turtle.executed.is_empty() {
+                summary.turtles += 1;
+            }
+            let stats = crate::stats::DrawingStats::from_commands(&turtle.commands);
+            summary.drawn_length += stats.path_length;
+            summary.fills += stats.fills;
+            summary.elapsed = summary.elapsed.max(turtle.tween_controller.now());
         }
+        summary
     }
 
     /// Add a new turtle and return its ID
@@ -334,6 +1281,7 @@ impl TurtleWorld {
         let turtle_id = self.turtles.len();
         let new_turtle = Turtle {
             turtle_id,
+            shared: Arc::clone(&self.shared),
             ..Default::default()
         };
         self.turtles.push(new_turtle);
@@ -359,8 +1307,247 @@ impl TurtleWorld {
         }
     }
 
+    /// How absolute positions and headings are interpreted
+    #[must_use]
+    pub fn coordinate_mode(&self) -> CoordinateMode {
+        self.shared_data().coordinate_mode
+    }
+
+    /// Change how absolute positions and headings are interpreted
+    ///
+    /// Affects commands executed after the change; existing drawings stay put.
+    pub fn set_coordinate_mode(&mut self, mode: CoordinateMode) {
+        self.shared_data().coordinate_mode = mode;
+    }
+
+    /// Get a marker recorded with `TurtlePlan::mark()`, in the world's `CoordinateMode`
+    #[must_use]
+    pub fn marker(&self, name: &str) -> Option<Marker> {
+        let shared = self.shared_data();
+        let mode = shared.coordinate_mode;
+        shared
+            .markers
+            .get(name)
+            .map(|marker| marker.from_screen(mode))
+    }
+
+    /// All recorded markers by name, in the world's `CoordinateMode`
+    #[must_use]
+    pub fn markers(&self) -> HashMap<String, Marker> {
+        let shared = self.shared_data();
+        let mode = shared.coordinate_mode;
+        shared
+            .markers
+            .iter()
+            .map(|(name, marker)| (name.clone(), marker.from_screen(mode)))
+            .collect()
+    }
+
+    /// Remove all recorded markers
+    pub fn clear_markers(&mut self) {
+        self.shared_data().markers.clear();
+    }
+
+    /// Take the unread messages sent to turtle `id`, oldest first
+    pub fn read_messages(&mut self, id: usize) -> Vec<Message> {
+        self.shared_data().mailboxes.remove(&id).unwrap_or_default()
+    }
+
+    /// Register a filled polygon shape that all turtles can select with
+    /// `TurtlePlan::shape_named()`, see `ShapeRegistry`
+    pub fn register_shape(&mut self, name: impl Into<String>, vertices: Vec<Vec2>) {
+        self.shared_data().shapes.register_shape(name, vertices);
+    }
+
+    /// Lock the data shared with all turtles of this world
+    pub fn shared_data(&self) -> MutexGuard<'_, SharedWorldData> {
+        self.shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Color of the topmost drawing at `point`, if anything was drawn there
+    ///
+    /// Tests the retained mesh triangles of all turtles in render order, so the
+    /// result matches what is visible on screen. `point` is in the world's
+    /// `CoordinateMode`. Text is not considered.
+    #[must_use]
+    pub fn color_at(&self, point: Vec2) -> Option<Color> {
+        let point = self.coordinate_mode().to_screen(point);
+        self.turtles.iter().rev().find_map(|turtle| {
+            turtle.commands.iter().rev().find_map(|cmd| match cmd {
+                DrawCommand::Mesh { data, .. } => data.color_at(point),
+                DrawCommand::Text { .. } => None,
+            })
+        })
+    }
+
+    /// Check whether any turtle has drawn at `point`
+    ///
+    /// Useful for game logic like "did the turtle cross an existing line?".
+    #[must_use]
+    pub fn is_drawn_at(&self, point: Vec2) -> bool {
+        self.color_at(point).is_some()
+    }
+
+    /// Clear the drawings of all turtles, optionally moving them back home
+    ///
+    /// The turtle given as `except` is skipped (it already cleared itself while
+    /// executing a `ClearScreen` command). Re-homed turtles drop their in-flight
+    /// tween so the animation does not continue from the old position; queued
+    /// commands are kept.
+    pub fn clear_screen(&mut self, except: Option<usize>, rehome: bool) {
+        self.layer.clear();
+        for turtle in &mut self.turtles {
+            if Some(turtle.turtle_id) == except {
+                continue;
+            }
+            turtle.clear_drawings(rehome);
+            if rehome {
+                turtle.tween_controller.cancel_current_tween();
+            }
+        }
+    }
+
+    /// Remove the stamp with the given ID, whichever turtle left it
+    pub fn clear_stamp(&mut self, id: StampId) -> bool {
+        let mut found = false;
+        for turtle in &mut self.turtles {
+            found |= turtle.clear_stamp(id);
+        }
+        found
+    }
+
+    /// ID of the topmost visible turtle whose cursor contains `point`
+    ///
+    /// `point` is in screen space (see `CoordinateMode::to_screen()`).
+    /// Turtles added later are drawn on top and win.
+    #[must_use]
+    pub fn turtle_at(&self, point: Vec2) -> Option<usize> {
+        self.turtles
+            .iter()
+            .rev()
+            .filter(|turtle| turtle.params.visible)
+            .find(|turtle| {
+                crate::collision::point_in_polygon(point, &turtle.params.cursor_vertices())
+            })
+            .map(|turtle| turtle.turtle_id)
+    }
+
+    /// Leave a ghost behind turtles that moved far enough since their last one
+    pub fn record_ghosts(&mut self) {
+        let Some(trail) = self.ghost_trail else {
+            for turtle in &mut self.turtles {
+                turtle.ghosts.clear();
+            }
+            return;
+        };
+        for turtle in &mut self.turtles {
+            let moved = match turtle.ghosts.back() {
+                Some(ghost) => ghost.position.distance(turtle.params.position) >= trail.spacing,
+                None => true,
+            };
+            if turtle.params.visible && moved {
+                turtle.ghosts.push_back(turtle.params.clone());
+            }
+            while turtle.ghosts.len() > trail.count {
+                turtle.ghosts.pop_front();
+            }
+        }
+    }
+
+    /// Opacity factor of a turtle's drawing under the current `trail_fade`
+    #[must_use]
+    pub fn trail_alpha(&self, turtle: &Turtle, cmd: &DrawCommand) -> f32 {
+        self.trail_fade.map_or(1.0, |fade| {
+            fade.alpha(turtle.tween_controller.now() - cmd.source().created_at)
+        })
+    }
+
+    /// Remove drawings that have completely faded out under `trail_fade`
+    pub fn prune_faded(&mut self) {
+        let Some(fade) = self.trail_fade else {
+            return;
+        };
+        for turtle in &mut self.turtles {
+            let now = turtle.tween_controller.now();
+            turtle
+                .commands
+                .retain(|cmd| fade.alpha(now - cmd.source().created_at) > 0.0);
+        }
+    }
+
+    /// Let turtles waiting at a barrier continue once no other turtle has
+    /// the barrier ahead
+    pub fn release_barriers(&mut self) {
+        let mut tags: Vec<String> = self
+            .turtles
+            .iter()
+            .filter_map(|turtle| turtle.tween_controller.waiting_at().map(str::to_string))
+            .collect();
+        tags.sort();
+        tags.dedup();
+        for tag in tags {
+            let pending = self.turtles.iter().any(|turtle| {
+                turtle.tween_controller.waiting_at() != Some(tag.as_str())
+                    && turtle.tween_controller.has_barrier_ahead(&tag)
+            });
+            if pending {
+                continue;
+            }
+            for turtle in &mut self.turtles {
+                if turtle.tween_controller.waiting_at() == Some(tag.as_str()) {
+                    turtle.tween_controller.release();
+                }
+            }
+        }
+    }
+
+    /// Apply all pending `WorldRequest`s raised by the turtles
+    pub fn apply_world_requests(&mut self) {
+        for index in 0..self.turtles.len() {
+            let source = self.turtles[index].turtle_id;
+            let requests = std::mem::take(&mut self.turtles[index].world_requests);
+            for request in requests {
+                match request {
+                    WorldRequest::ClearScreen { rehome } => self.clear_screen(Some(source), rehome),
+                    WorldRequest::Spawn { params, plan } => {
+                        let id = self.add_turtle();
+                        let turtle = &mut self.turtles[id];
+                        turtle.tween_controller.set_speed(params.speed);
+                        turtle.params = *params;
+                        let mut queue = CommandQueue::new();
+                        queue.extend(plan);
+                        turtle.tween_controller.append_commands(queue);
+                    }
+                    WorldRequest::Die { keep_drawing } => self.remove_turtle(source, keep_drawing),
+                }
+            }
+        }
+    }
+
+    /// Remove turtle `id`, moving its drawings to `layer` if `keep_drawing`
+    ///
+    /// IDs of the other turtles stay the same: the slot is left with a
+    /// hidden turtle without drawings or commands, which can be reused by
+    /// giving it new commands.
+    pub fn remove_turtle(&mut self, id: usize, keep_drawing: bool) {
+        let Some(turtle) = self.turtles.get_mut(id) else {
+            return;
+        };
+        let mut empty = Turtle {
+            turtle_id: id,
+            shared: Arc::clone(&self.shared),
+            ..Turtle::default()
+        };
+        empty.params.visible = false;
+        let removed = std::mem::replace(turtle, empty);
+        if keep_drawing {
+            self.layer.extend(removed.commands);
+        }
+    }
+
     /// Clear all drawings and reset all turtle states
     pub fn clear(&mut self) {
+        self.layer.clear();
         for (id, turtle) in self.turtles.iter_mut().enumerate() {
             turtle.reset();
             turtle.turtle_id = id; // Preserve turtle_id after reset
@@ -373,3 +1560,234 @@ impl Default for TurtleWorld {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_4;
+
+    #[test]
+    fn geometry_queries() {
+        let params = TurtleParams {
+            heading: FRAC_PI_4,
+            ..TurtleParams::default()
+        };
+        // Screen space: y grows downwards and positive angles turn right
+        let below = vec2(0.0, 10.0);
+        assert_eq!(params.distance_to(below), 10.0);
+        assert!((params.angle_to(below).value() - 45.0).abs() < 1e-4);
+        assert!((params.angle_to(vec2(0.0, -10.0)).value() + 135.0).abs() < 1e-4);
+        assert!(params.is_facing(vec2(10.0, 9.0), 5.0));
+        assert!(params.point_ahead(2.0_f32.sqrt()).distance(vec2(1.0, 1.0)) < 1e-5);
+
+        // Bouncing off a floor flips the vertical direction
+        assert!((params.reflect_heading(vec2(1.0, 0.0)) + FRAC_PI_4).abs() < 1e-5);
+        assert_eq!(params.reflect_heading(Vec2::ZERO), FRAC_PI_4);
+    }
+
+    #[test]
+    fn appending_stops_at_the_index_limit() {
+        let mesh = |vertices: usize| MeshData {
+            vertices: vec![Vertex::new(0.0, 0.0, 0.0, 0.0, 0.0, BLACK); vertices],
+            indices: vec![0, 1, u16::try_from(vertices - 1).unwrap()],
+        };
+        let mut full = mesh(MeshData::MAX_VERTICES - 3);
+        full.append(mesh(3)).unwrap();
+        assert_eq!(full.vertices.len(), MeshData::MAX_VERTICES);
+        assert_eq!(full.indices.last(), Some(&u16::MAX));
+
+        // One more vertex doesn't fit, nothing changes
+        assert!(full.append(mesh(1)).is_err());
+        assert_eq!(full.vertices.len(), MeshData::MAX_VERTICES);
+        assert_eq!(full.indices.len(), 6);
+    }
+
+    #[test]
+    fn pop_state_returns_to_the_fork() {
+        let mut turtle = Turtle::default();
+        turtle.params.pen_width = 5.0;
+        for command in [
+            TurtleCommand::PushState,
+            TurtleCommand::Move(50.0),
+            TurtleCommand::Turn(90.0),
+            TurtleCommand::SetPenWidth(1.0),
+            TurtleCommand::SetColor(RED),
+            TurtleCommand::PopState,
+            TurtleCommand::PopState,
+        ] {
+            crate::execution::execute_command(&command, &mut turtle);
+        }
+
+        assert_eq!(turtle.params.position, Vec2::ZERO);
+        assert_eq!(turtle.params.heading, 0.0);
+        assert_eq!(turtle.params.pen_width, 5.0);
+        assert_eq!(turtle.params.color, TurtleParams::default().color);
+        // Jumping back doesn't draw, popping too often is reported
+        assert_eq!(turtle.commands.len(), 1);
+        assert_eq!(turtle.errors, vec![TurtleError::PopWithoutPush]);
+    }
+
+    #[test]
+    fn spawned_turtles_start_beside_their_parent() {
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        let spawn = TurtleCommand::Spawn {
+            plan: vec![TurtleCommand::Move(5.0)],
+            offset: vec2(0.0, 10.0),
+        };
+        crate::execution::execute_command_with_id(&TurtleCommand::Move(10.0), 0, &mut world);
+        crate::execution::execute_command_with_id(&spawn, 0, &mut world);
+
+        assert_eq!(world.turtles.len(), 2);
+        let child = &world.turtles[1];
+        assert_eq!(child.turtle_id, 1);
+        // Left of an eastward turtle is up on screen
+        assert!(child.params.position.distance(vec2(10.0, -10.0)) < 1e-4);
+        assert!(child.params.heading.abs() < 1e-6);
+        assert!(!child.tween_controller.is_complete());
+    }
+
+    #[test]
+    fn dead_turtles_leave_their_drawings_behind() {
+        let mut world = TurtleWorld::headless();
+        for keep_drawing in [true, false] {
+            let id = world.add_turtle();
+            for command in [
+                TurtleCommand::Move(10.0),
+                TurtleCommand::Die { keep_drawing },
+            ] {
+                crate::execution::execute_command_with_id(&command, id, &mut world);
+            }
+        }
+
+        assert_eq!(world.turtles.len(), 2);
+        for (id, turtle) in world.turtles.iter().enumerate() {
+            assert_eq!(turtle.turtle_id, id);
+            assert!(turtle.commands.is_empty());
+            assert!(!turtle.params.visible);
+        }
+        // Only the first turtle's line was kept
+        assert_eq!(world.layer.len(), 1);
+    }
+
+    #[test]
+    fn messages_wait_in_the_mailbox_until_read() {
+        let mut world = TurtleWorld::headless();
+        world.add_turtle();
+        world.add_turtle();
+        for payload in ["hello", "again"] {
+            let command = TurtleCommand::SendMessage {
+                to: 1,
+                payload: payload.to_string(),
+            };
+            crate::execution::execute_command(&command, &mut world.turtles[0]);
+        }
+
+        let messages = world.read_messages(1);
+        let payloads: Vec<_> = messages.iter().map(|m| m.payload.as_str()).collect();
+        assert_eq!(payloads, ["hello", "again"]);
+        assert!(messages.iter().all(|message| message.from == 0));
+        assert!(world.read_messages(1).is_empty());
+        assert!(world.read_messages(0).is_empty());
+    }
+
+    /// Execute `plan` instantly on turtle `id` of `world`
+    fn run(world: &mut TurtleWorld, id: usize, plan: &crate::builders::TurtlePlan) {
+        for command in plan.clone().build().commands() {
+            crate::execution::execute_command_with_id(command, id, world);
+        }
+    }
+
+    #[test]
+    fn clear_screen_wipes_every_turtle() {
+        use crate::builders::{DirectionalMovement, Turnable, TurtlePlan};
+
+        let mut world = TurtleWorld::headless();
+        for _ in 0..2 {
+            let id = world.add_turtle();
+            let mut plan = TurtlePlan::new();
+            plan.right(90.0 * id as f32).forward(50.0);
+            run(&mut world, id, &plan);
+        }
+        assert!(world
+            .turtles
+            .iter()
+            .all(|turtle| !turtle.commands.is_empty()));
+
+        // Keeping the positions
+        let mut plan = TurtlePlan::new();
+        plan.clear_screen(false);
+        run(&mut world, 1, &plan);
+        assert!(world
+            .turtles
+            .iter()
+            .all(|turtle| turtle.commands.is_empty()));
+        assert_eq!(world.turtles[0].params.position, vec2(50.0, 0.0));
+
+        // And going back home
+        let mut plan = TurtlePlan::new();
+        plan.forward(10.0).clear_screen(true);
+        run(&mut world, 0, &plan);
+        for turtle in &world.turtles {
+            assert!(turtle.commands.is_empty());
+            assert_eq!(turtle.params.position, Vec2::ZERO);
+        }
+    }
+
+    #[test]
+    fn point_queries_hit_drawn_lines() {
+        use crate::builders::{DirectionalMovement, TurtlePlan};
+
+        let mut world = TurtleWorld::headless();
+        let id = world.add_turtle();
+        // Exactly representable as bytes, colors are stored per vertex
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        let mut plan = TurtlePlan::new();
+        plan.set_pen_color(red).set_pen_width(10.0).forward(100.0);
+        run(&mut world, id, &plan);
+
+        let mode = world.coordinate_mode();
+        let on_line = mode.from_screen(vec2(50.0, 3.0));
+        assert_eq!(world.color_at(on_line), Some(red));
+        assert!(world.is_drawn_at(on_line));
+        let beside = mode.from_screen(vec2(50.0, 20.0));
+        assert_eq!(world.color_at(beside), None);
+        assert!(!world.is_drawn_at(beside));
+    }
+
+    #[test]
+    fn barriers_wait_for_every_participant() {
+        use crate::builders::{DirectionalMovement, TurtlePlan};
+        use crate::tweening::TweenController;
+
+        let mut world = TurtleWorld::headless();
+        for detour in [0.0, 10.0] {
+            let id = world.add_turtle();
+            let mut plan = TurtlePlan::new();
+            plan.forward(detour).wait_for_group("dance").forward(5.0);
+            let controller = &mut world.turtles[id].tween_controller;
+            controller.set_speed(AnimationSpeed::Instant(100));
+            controller.append_commands(plan.build());
+        }
+        let x = |world: &TurtleWorld, id: usize| world.turtles[id].params.position.x;
+
+        // The first turtle arrives, the second still has the barrier ahead
+        TweenController::update(&mut world.turtles[0]);
+        world.release_barriers();
+        TweenController::update(&mut world.turtles[0]);
+        assert_eq!(
+            world.turtles[0].tween_controller.waiting_at(),
+            Some("dance")
+        );
+        assert_eq!(x(&world, 0), 0.0);
+
+        TweenController::update(&mut world.turtles[1]);
+        world.release_barriers();
+        for id in 0..2 {
+            assert_eq!(world.turtles[id].tween_controller.waiting_at(), None);
+            TweenController::update(&mut world.turtles[id]);
+        }
+        assert_eq!(x(&world, 0), 5.0);
+        assert_eq!(x(&world, 1), 15.0);
+    }
+}
@@ -0,0 +1,282 @@
+//! Drawing statistics computed from the retained `TurtleSource` data
+//!
+//! Useful for auto-grading exercises ("draw at least 400px of lines") or for
+//! showing a short summary of a drawing.
+
+use crate::circle_geometry::{CircleDirection, CircleGeometry};
+use crate::commands::TurtleCommand;
+use crate::general::{Bounds, Coordinate};
+use crate::state::{DrawCommand, TurtleSource};
+use std::collections::BTreeMap;
+
+/// Summary of what has been drawn
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawingStats {
+    /// Total length of all drawn lines and arcs (pixels)
+    pub path_length: f32,
+    /// Number of drawn line and arc segments
+    pub segments: usize,
+    /// Number of heading changes between consecutive drawn segments
+    pub turns: usize,
+    /// Number of completed fills
+    pub fills: usize,
+    /// Total filled area in square pixels (holes subtracted)
+    pub fill_area: f32,
+    /// Number of text labels
+    pub texts: usize,
+    /// Bounding box of all drawn geometry, `None` if nothing was drawn
+    pub bounds: Option<Bounds>,
+}
+
+impl DrawingStats {
+    /// Compute statistics from a list of draw commands
+    #[must_use]
+    pub fn from_commands(commands: &[DrawCommand]) -> Self {
+        let mut stats = Self::default();
+        let mut last_heading: Option<f32> = None;
+
+        for cmd in commands {
+            match cmd {
+                DrawCommand::Mesh { source, .. } => match &source.command {
+                    TurtleCommand::Move(_) | TurtleCommand::Goto(_) => {
+                        stats.path_length += source.start_position.distance(source.end_position);
+                        stats.segments += 1;
+                        Bounds::extend(&mut stats.bounds, source.start_position);
+                        Bounds::extend(&mut stats.bounds, source.end_position);
+                        stats.count_turn(&mut last_heading, source.start_heading);
+                        last_heading = Some(source.start_heading);
+                    }
+                    TurtleCommand::Circle {
+                        radius,
+                        angle,
+                        steps,
+                        direction,
+                    } => {
+                        stats.path_length += radius.abs() * angle.to_radians().abs();
+                        stats.segments += 1;
+                        for point in arc_points(source, *radius, *angle, *steps, *direction) {
+                            Bounds::extend(&mut stats.bounds, point);
+                        }
+                        stats.count_turn(&mut last_heading, source.start_heading);
+                        last_heading = Some(match direction {
+                            CircleDirection::Left => source.start_heading - angle.to_radians(),
+                            CircleDirection::Right => source.start_heading + angle.to_radians(),
+                        });
+                    }
+                    TurtleCommand::Together(_) | TurtleCommand::OffsetLastPath(_) => {
+                        // The sampled path of the group is kept as a single contour
+                        let points = source.contours.iter().flatten().flatten();
+                        let mut last: Option<Coordinate> = None;
+                        for point in points {
+                            if let Some(last) = last {
+                                stats.path_length += last.distance(*point);
+                            }
+                            Bounds::extend(&mut stats.bounds, *point);
+                            last = Some(*point);
+                        }
+                        stats.segments += 1;
+                        stats.count_turn(&mut last_heading, source.start_heading);
+                        last_heading = None;
+                    }
+                    TurtleCommand::EndFill => {
+                        stats.fills += 1;
+                        if let Some(contours) = &source.contours {
+                            stats.fill_area += fill_area(contours);
+                            for point in contours.iter().flatten() {
+                                Bounds::extend(&mut stats.bounds, *point);
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+                DrawCommand::Text { position, .. } => {
+                    stats.texts += 1;
+                    Bounds::extend(&mut stats.bounds, *position);
+                }
+            }
+        }
+
+        stats
+    }
+
+    /// Count a turn if `heading` differs from the end heading of the previous segment
+    fn count_turn(&mut self, last_heading: &mut Option<f32>, heading: f32) {
+        use std::f32::consts::TAU;
+        if let Some(last) = last_heading.take() {
+            let diff = (heading - last).rem_euclid(TAU);
+            if diff > 1e-4 && diff < TAU - 1e-4 {
+                self.turns += 1;
+            }
+        }
+    }
+}
+
+/// Report about a whole world, see `TurtleWorld::summary()`
+///
+/// The `Display` output lists everything on a few lines, for printing at
+/// exit or storing next to an exported drawing.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorldSummary {
+    /// Commands executed by all turtles, per `TurtleCommand::name()`
+    pub commands: BTreeMap<&'static str, usize>,
+    /// Total length of all drawn lines and arcs (pixels)
+    pub drawn_length: f32,
+    /// Number of completed fills
+    pub fills: usize,
+    /// Number of turtles that executed at least one command
+    pub turtles: usize,
+    /// Animation time of the turtle that got furthest, in seconds (see
+    /// `TweenController::now()`)
+    pub elapsed: f64,
+}
+
+impl WorldSummary {
+    /// Total number of executed commands
+    #[must_use]
+    pub fn total_commands(&self) -> usize {
+        self.commands.values().sum()
+    }
+}
+
+impl std::fmt::Display for WorldSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Turtles used: {}", self.turtles)?;
+        writeln!(f, "Commands executed: {}", self.total_commands())?;
+        for (name, count) in &self.commands {
+            writeln!(f, "  {name}: {count}")?;
+        }
+        writeln!(f, "Drawn length: {:.1} px", self.drawn_length)?;
+        writeln!(f, "Fills: {}", self.fills)?;
+        write!(f, "Elapsed time: {:.2} s", self.elapsed)
+    }
+}
+
+/// Points along an arc described by a `Circle` source (start point included)
+pub(crate) fn arc_points(
+    source: &TurtleSource,
+    radius: f32,
+    angle: f32,
+    steps: usize,
+    direction: CircleDirection,
+) -> impl Iterator<Item = Coordinate> {
+    let geom = CircleGeometry::new(
+        source.start_position,
+        source.start_heading,
+        radius,
+        direction,
+    );
+    let steps = steps.max(1);
+    (0..=steps).map(move |i| geom.position_at_progress(angle.to_radians(), i as f32 / steps as f32))
+}
+
+/// Signed area of a closed polygon (shoelace formula)
+#[must_use]
+pub fn polygon_area(points: &[Coordinate]) -> f32 {
+    if points.len() < 3 {
+        return 0.0;
+    }
+    let mut twice_area = 0.0;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        twice_area += a.x * b.y - b.x * a.y;
+    }
+    twice_area / 2.0
+}
+
+/// Area of a multi-contour fill
+///
+/// Follows the `FillState` convention: the first contour is the outer
+/// boundary, subsequent contours are holes.
+#[must_use]
+pub fn fill_area(contours: &[Vec<Coordinate>]) -> f32 {
+    let Some((outer, holes)) = contours.split_first() else {
+        return 0.0;
+    };
+    let holes: f32 = holes.iter().map(|c| polygon_area(c).abs()).sum();
+    (polygon_area(outer).abs() - holes).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{BlendMode, MeshData};
+    use macroquad::prelude::*;
+
+    fn line(start: Vec2, end: Vec2, heading: f32) -> DrawCommand {
+        DrawCommand::Mesh {
+            data: MeshData {
+                vertices: Vec::new(),
+                indices: Vec::new(),
+            },
+            source: TurtleSource {
+                command: TurtleCommand::Move(start.distance(end)),
+                color: BLACK,
+                fill_color: BLACK,
+                pen_width: 1.0,
+                start_position: start,
+                end_position: end,
+                start_heading: heading,
+                contours: None,
+                created_at: 0.0,
+                blend_mode: BlendMode::Normal,
+                annotation: None,
+            },
+        }
+    }
+
+    #[test]
+    fn square_area_with_hole() {
+        let outer = vec![
+            vec2(0.0, 0.0),
+            vec2(10.0, 0.0),
+            vec2(10.0, 10.0),
+            vec2(0.0, 10.0),
+        ];
+        let hole = vec![
+            vec2(2.0, 2.0),
+            vec2(4.0, 2.0),
+            vec2(4.0, 4.0),
+            vec2(2.0, 4.0),
+        ];
+        assert!((polygon_area(&outer) - 100.0).abs() < 0.001);
+        assert!((fill_area(&[outer, hole]) - 96.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn lines_length_turns_and_bounds() {
+        use std::f32::consts::FRAC_PI_2;
+        let commands = vec![
+            line(vec2(0.0, 0.0), vec2(100.0, 0.0), 0.0),
+            line(vec2(100.0, 0.0), vec2(100.0, 50.0), FRAC_PI_2),
+            line(vec2(100.0, 50.0), vec2(100.0, 80.0), FRAC_PI_2),
+        ];
+        let stats = DrawingStats::from_commands(&commands);
+        assert!((stats.path_length - 180.0).abs() < 0.001);
+        assert_eq!(stats.segments, 3);
+        assert_eq!(stats.turns, 1);
+        let bounds = stats.bounds.unwrap();
+        assert_eq!(bounds.min, vec2(0.0, 0.0));
+        assert_eq!(bounds.max, vec2(100.0, 80.0));
+    }
+
+    #[test]
+    fn world_summary_counts_commands_of_all_turtles() {
+        use crate::builders::{DirectionalMovement, Turnable, TurtlePlan};
+        use crate::state::TurtleWorld;
+
+        let mut world = TurtleWorld::headless();
+        let mut plan = TurtlePlan::new();
+        plan.forward(10.0).right(90.0).forward(10.0);
+        crate::headless::execute(&mut world, 0, &plan.build());
+        let mut plan = TurtlePlan::new();
+        plan.forward(5.0);
+        crate::headless::execute(&mut world, 2, &plan.build());
+
+        let summary = world.summary();
+        assert_eq!(summary.turtles, 2);
+        assert_eq!(summary.commands.get("Move"), Some(&3));
+        assert_eq!(summary.total_commands(), 4);
+        assert!((summary.drawn_length - 25.0).abs() < 0.001);
+        assert!(summary.to_string().contains("  Turn: 1"));
+    }
+}
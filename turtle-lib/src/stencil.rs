@@ -0,0 +1,237 @@
+//! Stencils: drawings recorded while they run and replayed elsewhere
+//!
+//! Everything a turtle does between `TurtlePlan::begin_stencil()` and
+//! `end_stencil()` is recorded relative to where it started, including
+//! commands that are only known at execution time like `go_to_mark()`.
+//! `stamp_stencil()` replays the recording from the turtle's current position
+//! and heading, optionally scaled or mirrored:
+//!
+//! ```
+//! use turtle_lib::*;
+//!
+//! let mut turtle = create_turtle_plan();
+//! turtle.begin_stencil();
+//! turtle.forward(40.0).left(60.0).forward(20.0);
+//! let leaf = turtle.end_stencil();
+//!
+//! turtle.pen_up().go_to(vec2(0.0, 100.0)).pen_down();
+//! turtle.stamp_stencil(&leaf, StencilTransform::new().scaled(2.0));
+//! turtle.stamp_stencil(&leaf, StencilTransform::new().mirrored());
+//! ```
+//!
+//! Stencils are stored in the world, so every turtle can stamp them once the
+//! recording turtle got to `end_stencil()`. A stencil stamped before that is
+//! skipped, see `TurtleError::UnknownStencil`.
+
+use crate::circle_geometry::CircleDirection;
+use crate::commands::TurtleCommand;
+use crate::general::{CoordinateMode, Precision};
+use crate::state::TurtleParams;
+use macroquad::prelude::Vec2;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Identifies a stencil, returned by `TurtlePlan::end_stencil()`
+///
+/// IDs are unique within the program. Recording the same stencil again, e.g.
+/// in a looping queue, replaces the earlier recording.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stencil(u64);
+
+impl Stencil {
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// How `TurtlePlan::stamp_stencil()` fits a stencil to the turtle
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StencilTransform {
+    /// Factor for distances and radii, pen widths stay the same
+    pub scale: Precision,
+    /// Swap left and right turns, mirroring the drawing along the heading
+    pub mirror: bool,
+}
+
+impl StencilTransform {
+    /// Replay the stencil as recorded
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            scale: 1.0,
+            mirror: false,
+        }
+    }
+
+    #[must_use]
+    pub fn scaled(mut self, scale: Precision) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    #[must_use]
+    pub fn mirrored(mut self) -> Self {
+        self.mirror = !self.mirror;
+        self
+    }
+
+    /// Apply to a position relative to the stencil's start (heading along x)
+    fn point(self, point: Vec2) -> Vec2 {
+        let point = if self.mirror {
+            Vec2::new(point.x, -point.y)
+        } else {
+            point
+        };
+        point * self.scale
+    }
+
+    /// Apply to an angle relative to the stencil's start heading
+    fn angle(self, angle: Precision) -> Precision {
+        if self.mirror {
+            -angle
+        } else {
+            angle
+        }
+    }
+}
+
+impl Default for StencilTransform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A stencil a turtle is recording, see `Turtle::stencil_recordings`
+#[derive(Clone, Debug)]
+pub struct StencilRecording {
+    pub stencil: Stencil,
+    // Screen space pose at `begin_stencil()`
+    origin: Vec2,
+    heading: f32,
+    // Resolved commands with absolute values relative to the origin pose
+    commands: Vec<TurtleCommand>,
+}
+
+impl StencilRecording {
+    pub(crate) fn new(stencil: Stencil, params: &TurtleParams) -> Self {
+        Self {
+            stencil,
+            origin: params.position,
+            heading: params.heading,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Record a resolved (screen space) command
+    pub(crate) fn record(&mut self, command: &TurtleCommand) {
+        let command = self.localize(command.clone());
+        self.commands.push(command);
+    }
+
+    fn localize(&self, command: TurtleCommand) -> TurtleCommand {
+        match command {
+            TurtleCommand::Goto(point) => {
+                TurtleCommand::Goto(Vec2::from_angle(-self.heading).rotate(point - self.origin))
+            }
+            TurtleCommand::SetHeading(heading) => TurtleCommand::SetHeading(heading - self.heading),
+            TurtleCommand::Together(members) => TurtleCommand::Together(
+                members
+                    .into_iter()
+                    .map(|member| self.localize(member))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    pub(crate) fn into_commands(self) -> Vec<TurtleCommand> {
+        self.commands
+    }
+}
+
+/// Commands that replay a recorded stencil from the pose in `params`, with
+/// absolute values in `mode` like commands of a plan
+pub(crate) fn place(
+    commands: &[TurtleCommand],
+    params: &TurtleParams,
+    transform: StencilTransform,
+    mode: CoordinateMode,
+) -> Vec<TurtleCommand> {
+    commands
+        .iter()
+        .map(|command| place_command(command.clone(), params, transform, mode))
+        .collect()
+}
+
+fn place_command(
+    command: TurtleCommand,
+    params: &TurtleParams,
+    transform: StencilTransform,
+    mode: CoordinateMode,
+) -> TurtleCommand {
+    match command {
+        TurtleCommand::Move(distance) => TurtleCommand::Move(distance * transform.scale),
+        TurtleCommand::Turn(degrees) => TurtleCommand::Turn(transform.angle(degrees)),
+        TurtleCommand::Circle {
+            radius,
+            angle,
+            steps,
+            direction,
+        } => TurtleCommand::Circle {
+            radius: radius * transform.scale,
+            angle,
+            steps,
+            direction: match (transform.mirror, direction) {
+                (false, direction) => direction,
+                (true, CircleDirection::Left) => CircleDirection::Right,
+                (true, CircleDirection::Right) => CircleDirection::Left,
+            },
+        },
+        TurtleCommand::Goto(local) => {
+            let point =
+                params.position + Vec2::from_angle(params.heading).rotate(transform.point(local));
+            TurtleCommand::Goto(mode.from_screen(point))
+        }
+        TurtleCommand::SetHeading(local) => {
+            let heading = params.heading + transform.angle(local);
+            TurtleCommand::SetHeading(mode.heading_from_screen(heading))
+        }
+        TurtleCommand::Together(members) => TurtleCommand::Together(
+            members
+                .into_iter()
+                .map(|member| place_command(member, params, transform, mode))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assertions::expect_position;
+    use crate::builders::{DirectionalMovement, Turnable, TurtlePlan};
+
+    #[test]
+    fn stamps_relative_to_the_current_pose() {
+        let mut plan = TurtlePlan::new();
+        plan.begin_stencil();
+        plan.forward(40.0).left(90.0).forward(20.0);
+        let corner = plan.end_stencil();
+        expect_position(&plan, (40.0, 20.0), 0.01);
+
+        // Facing up now: twice as long, turning right instead of left
+        plan.stamp_stencil(&corner, StencilTransform::new().scaled(2.0).mirrored());
+        expect_position(&plan, (80.0, 100.0), 0.01);
+
+        // Absolute moves are recorded relative to the start too
+        plan.begin_stencil();
+        plan.go_to((90.0, 100.0));
+        let step = plan.end_stencil();
+        plan.left(90.0)
+            .stamp_stencil(&step, StencilTransform::new());
+        expect_position(&plan, (90.0, 110.0), 0.01);
+    }
+}
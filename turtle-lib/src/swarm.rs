@@ -0,0 +1,487 @@
+//! Swarms: thousands of simple turtles for simulations
+//!
+//! A full turtle has its own queue, tweens and meshes per stroke, which is
+//! too much for flocks, ants or particles. A `Swarm` keeps only the position,
+//! heading and color of its members in flat lists, moves all of them with one
+//! call and draws their cursors and trails in a few batched meshes:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//! use turtle_lib::swarm::Swarm;
+//!
+//! #[macroquad::main("Swarm")]
+//! async fn main() {
+//!     let mut ants = Swarm::new(2000).with_pen(1.0).with_speed(60.0);
+//!     for (index, heading) in ants.headings_mut().iter_mut().enumerate() {
+//!         *heading = index as f32 * 0.18;
+//!     }
+//!     // One shared queue: every ant walks the same zigzag from its heading
+//!     for _ in 0..10 {
+//!         ants.forward(20.0).turn(60.0).forward(20.0).turn(-60.0);
+//!     }
+//!
+//!     let mut app = TurtleApp::new();
+//!     app.add_swarm(ants);
+//!     app.run(RunConfig::new()).await;
+//! }
+//! ```
+//!
+//! Rules that differ per member, like steering towards neighbours, go
+//! through `steer()` from a frame hook. Positions and headings (in degrees)
+//! are in the world's `CoordinateMode`, like `go_to()` and `set_heading()`.
+//!
+//! Moves that continue a member's trail in a straight line stretch its last
+//! trail piece instead of adding one. Members that turn every frame still add
+//! a piece per frame, `with_trail_limit()` keeps those trails from growing
+//! forever.
+
+use crate::general::{Color, Coordinate, CoordinateMode, Precision};
+use crate::shapes::TurtleShape;
+use crate::state::{MeshData, DEFAULT_CURSOR_COLOR};
+use crate::tessellation;
+use macroquad::prelude::{get_internal_gl, vec2, Camera2D, DrawMode, Vertex};
+use std::collections::VecDeque;
+
+/// Speed of queued moves in pixels per second unless configured otherwise
+pub const DEFAULT_SWARM_SPEED: Precision = 100.0;
+
+// macroquad draws at most 5000 indices per call, larger meshes are clipped
+const MAX_BATCH_INDICES: usize = 4800;
+
+/// A step of the shared queue, done by all members at once
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SwarmStep {
+    Forward(Precision),
+    Turn(Precision),
+}
+
+/// The latest trail piece of a member, stretched while it keeps going straight
+#[derive(Clone, Copy, Debug)]
+struct TrailEnd {
+    // Counting every batch ever added, including dropped ones
+    batch: usize,
+    // First of the piece's four vertices
+    vertex: usize,
+    heading: Precision,
+    forward: bool,
+    color: Color,
+    end: Coordinate,
+}
+
+/// Many turtles stored as a structure of arrays, see the module documentation
+#[derive(Clone, Debug)]
+pub struct Swarm {
+    positions: Vec<Coordinate>,
+    headings: Vec<Precision>,
+    colors: Vec<Color>,
+    shape: TurtleShape,
+    // Trail width, `None` moves without drawing
+    pen_width: Option<Precision>,
+    speed: Precision,
+    queue: VecDeque<SwarmStep>,
+    // Distance of the first queued `Forward` already walked
+    walked: Precision,
+    // Trail quads in world coordinates, each batch small enough for one draw call
+    trails: Vec<MeshData>,
+    trail_ends: Vec<Option<TrailEnd>>,
+    trail_quads: usize,
+    // Oldest batches removed by the trail limit
+    dropped_batches: usize,
+    trail_limit: Option<usize>,
+}
+
+impl Swarm {
+    /// `count` members at the origin, facing east
+    #[must_use]
+    pub fn new(count: usize) -> Self {
+        Self {
+            positions: vec![Coordinate::ZERO; count],
+            headings: vec![0.0; count],
+            colors: vec![DEFAULT_CURSOR_COLOR; count],
+            shape: TurtleShape::turtle(),
+            pen_width: None,
+            speed: DEFAULT_SWARM_SPEED,
+            queue: VecDeque::new(),
+            walked: 0.0,
+            trails: Vec::new(),
+            trail_ends: vec![None; count],
+            trail_quads: 0,
+            dropped_batches: 0,
+            trail_limit: None,
+        }
+    }
+
+    /// Cursor shape shared by all members
+    #[must_use]
+    pub fn with_shape(mut self, shape: TurtleShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Draw trails `width` pixels wide in the members' colors
+    #[must_use]
+    pub fn with_pen(mut self, width: Precision) -> Self {
+        self.pen_width = Some(width);
+        self
+    }
+
+    /// Keep at most about `quads` trail pieces, dropping the oldest
+    ///
+    /// Trails are dropped a draw call batch (800 pieces) at a time, the
+    /// newest batch is always kept. Without a limit trails are kept until
+    /// `clear_trails()`.
+    #[must_use]
+    pub fn with_trail_limit(mut self, quads: usize) -> Self {
+        self.trail_limit = Some(quads);
+        self
+    }
+
+    /// Number of trail pieces currently drawn
+    #[must_use]
+    pub fn trail_len(&self) -> usize {
+        self.trail_quads
+    }
+
+    /// Speed of queued moves in pixels per second
+    #[must_use]
+    pub fn with_speed(mut self, speed: Precision) -> Self {
+        self.speed = speed.max(0.0);
+        self
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    #[must_use]
+    pub fn positions(&self) -> &[Coordinate] {
+        &self.positions
+    }
+
+    /// Place members directly, without drawing
+    pub fn positions_mut(&mut self) -> &mut [Coordinate] {
+        &mut self.positions
+    }
+
+    /// Headings in degrees
+    #[must_use]
+    pub fn headings(&self) -> &[Precision] {
+        &self.headings
+    }
+
+    pub fn headings_mut(&mut self) -> &mut [Precision] {
+        &mut self.headings
+    }
+
+    #[must_use]
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    /// Cursor and trail colors
+    pub fn colors_mut(&mut self) -> &mut [Color] {
+        &mut self.colors
+    }
+
+    /// Queue a move of `distance` pixels for all members
+    pub fn forward(&mut self, distance: Precision) -> &mut Self {
+        self.queue.push_back(SwarmStep::Forward(distance));
+        self
+    }
+
+    /// Queue a turn of all members, positive degrees turn left
+    pub fn turn(&mut self, degrees: Precision) -> &mut Self {
+        self.queue.push_back(SwarmStep::Turn(degrees));
+        self
+    }
+
+    /// Whether the shared queue is done
+    #[must_use]
+    pub fn is_idle(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Turn and move every member right away
+    ///
+    /// `rule` gets the index, position and heading of a member and returns
+    /// its turn in degrees (positive turns left) and the distance to move
+    /// after turning.
+    pub fn steer(
+        &mut self,
+        mut rule: impl FnMut(usize, Coordinate, Precision) -> (Precision, Precision),
+    ) {
+        for index in 0..self.len() {
+            let (turn, distance) = rule(index, self.positions[index], self.headings[index]);
+            self.headings[index] += turn;
+            self.advance(index, distance);
+        }
+    }
+
+    /// Work through the shared queue for `dt` seconds
+    ///
+    /// Turns take no time, moves take their distance divided by the speed.
+    pub fn update(&mut self, dt: Precision) {
+        let mut budget = dt.max(0.0) * self.speed;
+        while let Some(&step) = self.queue.front() {
+            match step {
+                SwarmStep::Turn(degrees) => {
+                    for heading in &mut self.headings {
+                        *heading += degrees;
+                    }
+                }
+                SwarmStep::Forward(distance) => {
+                    let left = distance.abs() - self.walked;
+                    let part = left.min(budget);
+                    for index in 0..self.len() {
+                        self.advance(index, part.copysign(distance));
+                    }
+                    budget -= part;
+                    if part < left {
+                        self.walked += part;
+                        return;
+                    }
+                    self.walked = 0.0;
+                }
+            }
+            self.queue.pop_front();
+        }
+    }
+
+    /// Move member `index` along its heading, drawing its trail
+    fn advance(&mut self, index: usize, distance: Precision) {
+        if distance == 0.0 {
+            return;
+        }
+        let from = self.positions[index];
+        let to = from + Coordinate::from_angle(self.headings[index].to_radians()) * distance;
+        self.positions[index] = to;
+        let Some(width) = self.pen_width else {
+            return;
+        };
+        let normal = (to - from).normalize_or_zero().perp() * (width / 2.0);
+        let heading = self.headings[index];
+        let forward = distance > 0.0;
+        let color = self.colors[index];
+
+        // Stretch the last piece if this move continues it
+        let dropped = self.dropped_batches;
+        if let Some(end) = self.trail_ends[index].as_mut().filter(|end| {
+            end.end == from
+                && end.heading == heading
+                && end.forward == forward
+                && end.color == color
+        }) {
+            if let Some(batch) = end
+                .batch
+                .checked_sub(dropped)
+                .and_then(|batch| self.trails.get_mut(batch))
+            {
+                for (vertex, corner) in [(1, to + normal), (2, to - normal)] {
+                    batch.vertices[end.vertex + vertex] =
+                        Vertex::new(corner.x, corner.y, 0.0, 0.0, 0.0, color);
+                }
+                end.end = to;
+                return;
+            }
+        }
+
+        let corners = [from + normal, to + normal, to - normal, from - normal];
+        let (batch, vertex) = push_quad(&mut self.trails, corners, color);
+        self.trail_quads += 1;
+        self.trail_ends[index] = Some(TrailEnd {
+            batch: dropped + batch,
+            vertex,
+            heading,
+            forward,
+            color,
+            end: to,
+        });
+        self.trim_trails();
+    }
+
+    /// Drop the oldest batches while the trails are over the limit
+    fn trim_trails(&mut self) {
+        let Some(limit) = self.trail_limit else {
+            return;
+        };
+        let mut excess = 0;
+        let mut quads = self.trail_quads;
+        for batch in &self.trails[..self.trails.len().saturating_sub(1)] {
+            if quads <= limit {
+                break;
+            }
+            quads -= batch.indices.len() / 6;
+            excess += 1;
+        }
+        self.trails.drain(..excess);
+        self.trail_quads = quads;
+        self.dropped_batches += excess;
+    }
+
+    /// Remove all trails, the members stay where they are
+    pub fn clear_trails(&mut self) {
+        self.trails.clear();
+        self.trail_ends.fill(None);
+        self.trail_quads = 0;
+        self.dropped_batches = 0;
+    }
+
+    /// Meshes with the cursors of all members, in world coordinates
+    fn cursor_batches(&self) -> Vec<MeshData> {
+        let pivoted: Vec<_> = self
+            .shape
+            .vertices
+            .iter()
+            .map(|vertex| *vertex - self.shape.pivot)
+            .collect();
+        let Ok(template) = tessellation::tessellate_polygon(&pivoted, DEFAULT_CURSOR_COLOR) else {
+            return Vec::new();
+        };
+        let mut batches: Vec<MeshData> = Vec::new();
+        for index in 0..self.len() {
+            let batch = batch_for(&mut batches, template.indices.len());
+            let offset = batch.vertices.len() as u16;
+            let rotation = Coordinate::from_angle(self.headings[index].to_radians());
+            let position = self.positions[index];
+            batch
+                .vertices
+                .extend(template.vertices.iter().map(|vertex| {
+                    let point =
+                        position + rotation.rotate(vec2(vertex.position.x, vertex.position.y));
+                    Vertex::new(point.x, point.y, 0.0, 0.0, 0.0, self.colors[index])
+                }));
+            batch
+                .indices
+                .extend(template.indices.iter().map(|i| i + offset));
+        }
+        batches
+    }
+
+    /// Draw trails and cursors with the world's camera
+    pub(crate) fn draw(&self, mode: CoordinateMode, camera: &Camera2D) {
+        // Vertices are in `mode`, flip the camera instead of every vertex
+        let flip = mode == CoordinateMode::MathYUp;
+        let camera = Camera2D {
+            zoom: if flip {
+                vec2(camera.zoom.x, -camera.zoom.y)
+            } else {
+                camera.zoom
+            },
+            target: if flip {
+                mode.from_screen(camera.target)
+            } else {
+                camera.target
+            },
+            rotation: camera.rotation,
+            offset: camera.offset,
+            render_target: camera.render_target.clone(),
+            viewport: camera.viewport,
+        };
+        macroquad::prelude::set_camera(&camera);
+        // SAFETY: used like `draw_mesh()` does, which would need owned copies
+        // of the buffers
+        let gl = unsafe { get_internal_gl() }.quad_gl;
+        gl.texture(None);
+        gl.draw_mode(DrawMode::Triangles);
+        for batch in self.trails.iter().chain(&self.cursor_batches()) {
+            gl.geometry(&batch.vertices, &batch.indices);
+        }
+        macroquad::prelude::set_default_camera();
+    }
+}
+
+/// Last batch of `batches` if `indices` more fit into it, a new one otherwise
+fn batch_for(batches: &mut Vec<MeshData>, indices: usize) -> &mut MeshData {
+    if batches
+        .last()
+        .is_none_or(|batch| batch.indices.len() + indices > MAX_BATCH_INDICES)
+    {
+        batches.push(MeshData {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        });
+    }
+    batches.last_mut().expect("batch was just added")
+}
+
+/// Add a quad, returns the index of its batch and of its first vertex
+fn push_quad(
+    batches: &mut Vec<MeshData>,
+    corners: [Coordinate; 4],
+    color: Color,
+) -> (usize, usize) {
+    let batch = batch_for(batches, 6);
+    let vertex = batch.vertices.len();
+    let offset = vertex as u16;
+    batch.vertices.extend(
+        corners
+            .iter()
+            .map(|corner| Vertex::new(corner.x, corner.y, 0.0, 0.0, 0.0, color)),
+    );
+    batch
+        .indices
+        .extend([0, 1, 2, 0, 2, 3].map(|index| index + offset));
+    (batches.len() - 1, vertex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_queue_moves_every_member() {
+        let mut swarm = Swarm::new(1000).with_pen(2.0).with_speed(10.0);
+        swarm.headings_mut()[1] = 90.0;
+        swarm.forward(10.0).turn(90.0).forward(10.0);
+
+        // Half of the first move, then the rest and the turn
+        swarm.update(0.5);
+        assert!(swarm.positions()[0].distance(vec2(5.0, 0.0)) < 1e-4);
+        assert!(swarm.positions()[1].distance(vec2(0.0, 5.0)) < 1e-4);
+        swarm.update(1.0);
+        assert!(swarm.positions()[0].distance(vec2(10.0, 5.0)) < 1e-4);
+        assert_eq!(swarm.headings()[1], 180.0);
+        swarm.update(1.0);
+        assert!(swarm.is_idle());
+
+        // A trail piece per member and straight line, the second half of a
+        // move stretches the first, in batches one draw call can take
+        let quads: usize = swarm.trails.iter().map(|b| b.indices.len() / 6).sum();
+        assert_eq!(quads, 2000);
+        assert_eq!(swarm.trail_len(), 2000);
+        let end = swarm.trails[0].vertices[1].position;
+        assert!((end.x - 10.0).abs() < 1e-4);
+        assert!(swarm
+            .trails
+            .iter()
+            .chain(&swarm.cursor_batches())
+            .all(|batch| batch.indices.len() <= MAX_BATCH_INDICES));
+    }
+
+    #[test]
+    fn trail_limit_drops_the_oldest_batches() {
+        let mut swarm = Swarm::new(1000).with_pen(2.0).with_trail_limit(1000);
+        for _ in 0..3 {
+            swarm.steer(|_, _, _| (10.0, 1.0));
+        }
+        assert!(swarm.trail_len() <= 1000);
+        assert_eq!(
+            swarm.trail_len(),
+            swarm
+                .trails
+                .iter()
+                .map(|b| b.indices.len() / 6)
+                .sum::<usize>()
+        );
+
+        // Pieces in dropped batches are started anew
+        swarm.steer(|_, _, _| (0.0, 1.0));
+        assert!(swarm.trail_len() <= 1000);
+    }
+}
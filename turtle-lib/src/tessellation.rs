@@ -2,6 +2,8 @@
 //!
 //! This module provides helper functions to tessellate paths using Lyon,
 //! which replaces the manual triangulation with GPU-optimized tessellation.
+//! The entry points take a `Color` or a per-vertex color callback, see
+//! `VertexColor`.
 
 use crate::state::MeshData;
 use lyon::math::{point, Point};
@@ -29,23 +31,79 @@ pub fn to_macroquad_vec2(p: Point) -> Vec2 {
 #[derive(Copy, Clone, Debug)]
 pub struct SimpleVertex {
     pub position: [f32; 2],
+    /// Distance along the path for stroke vertices, 0 for fills
+    pub advancement: f32,
+}
+
+/// Color of each tessellated vertex
+///
+/// Implemented for `Color` (the whole mesh in one color) and for closures
+/// `Fn(&SimpleVertex) -> Color`, which can color vertices by position or by
+/// the distance along a stroke, e.g. `linear_gradient()`.
+pub trait VertexColor {
+    fn color_at(&self, vertex: &SimpleVertex) -> Color;
+}
+
+impl VertexColor for Color {
+    fn color_at(&self, _vertex: &SimpleVertex) -> Color {
+        *self
+    }
+}
+
+impl<F: Fn(&SimpleVertex) -> Color> VertexColor for F {
+    fn color_at(&self, vertex: &SimpleVertex) -> Color {
+        self(vertex)
+    }
+}
+
+/// Colors fading from `start` to `end` over the first `length` pixels of a
+/// stroke
+///
+/// ```
+/// # use turtle_lib::*;
+/// # use turtle_lib::tessellation::*;
+/// let points = [vec2(0.0, 0.0), vec2(100.0, 0.0)];
+/// let mesh = tessellate_stroke(&points, linear_gradient(RED, BLUE, 100.0), 4.0, false).unwrap();
+/// assert!(mesh.vertices.iter().any(|v| v.color[2] > 200));
+/// ```
+pub fn linear_gradient(start: Color, end: Color, length: f32) -> impl Fn(&SimpleVertex) -> Color {
+    move |vertex| {
+        let t = if length > 0.0 {
+            (vertex.advancement / length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        Color::new(
+            start.r + (end.r - start.r) * t,
+            start.g + (end.g - start.g) * t,
+            start.b + (end.b - start.b) * t,
+            start.a + (end.a - start.a) * t,
+        )
+    }
 }
 
 /// Build mesh data from Lyon tessellation
 #[must_use]
-pub fn build_mesh_data(vertices: &[SimpleVertex], indices: &[u16], color: Color) -> MeshData {
+pub fn build_mesh_data(
+    vertices: &[SimpleVertex],
+    indices: &[u16],
+    color: impl VertexColor,
+) -> MeshData {
     let verts: Vec<Vertex> = vertices
         .iter()
-        .map(|v| Vertex {
-            position: Vec3::new(v.position[0], v.position[1], 0.0),
-            uv: Vec2::ZERO,
-            color: [
-                (color.r * 255.0) as u8,
-                (color.g * 255.0) as u8,
-                (color.b * 255.0) as u8,
-                (color.a * 255.0) as u8,
-            ],
-            normal: Vec4::ZERO,
+        .map(|v| {
+            let color = color.color_at(v);
+            Vertex {
+                position: Vec3::new(v.position[0], v.position[1], 0.0),
+                uv: Vec2::ZERO,
+                color: [
+                    (color.r * 255.0) as u8,
+                    (color.g * 255.0) as u8,
+                    (color.b * 255.0) as u8,
+                    (color.a * 255.0) as u8,
+                ],
+                normal: Vec4::ZERO,
+            }
         })
         .collect();
 
@@ -64,7 +122,7 @@ pub fn build_mesh_data(vertices: &[SimpleVertex], indices: &[u16], color: Color)
 /// Returns an error if no vertices are provided or if tessellation fails.
 pub fn tessellate_polygon(
     vertices: &[Vec2],
-    color: Color,
+    color: impl VertexColor,
 ) -> Result<MeshData, Box<dyn std::error::Error>> {
     if vertices.is_empty() {
         return Err("No vertices provided".into());
@@ -89,6 +147,7 @@ pub fn tessellate_polygon(
         &FillOptions::default().with_fill_rule(FillRule::EvenOdd),
         &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| SimpleVertex {
             position: vertex.position().to_array(),
+            advancement: 0.0,
         }),
     )?;
 
@@ -109,7 +168,7 @@ pub fn tessellate_polygon(
 /// Returns an error if no contours are provided or if tessellation fails.
 pub fn tessellate_multi_contour(
     contours: &[Vec<Vec2>],
-    color: Color,
+    color: impl VertexColor,
 ) -> Result<MeshData, Box<dyn std::error::Error>> {
     if contours.is_empty() {
         return Err("No contours provided".into());
@@ -175,6 +234,7 @@ pub fn tessellate_multi_contour(
         &FillOptions::default().with_fill_rule(FillRule::EvenOdd),
         &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| SimpleVertex {
             position: vertex.position().to_array(),
+            advancement: 0.0,
         }),
     ) {
         Ok(()) => {
@@ -205,7 +265,7 @@ pub fn tessellate_multi_contour(
 /// Returns an error if no vertices are provided or if tessellation fails.
 pub fn tessellate_stroke(
     vertices: &[Vec2],
-    color: Color,
+    color: impl VertexColor,
     width: f32,
     closed: bool,
 ) -> Result<MeshData, Box<dyn std::error::Error>> {
@@ -234,6 +294,7 @@ pub fn tessellate_stroke(
             .with_line_join(LineJoin::Round),
         &mut BuffersBuilder::new(&mut geometry, |vertex: StrokeVertex| SimpleVertex {
             position: vertex.position().to_array(),
+            advancement: vertex.advancement(),
         }),
     )?;
 
@@ -252,7 +313,7 @@ pub fn tessellate_stroke(
 pub fn tessellate_circle(
     center: Vec2,
     radius: f32,
-    color: Color,
+    color: impl VertexColor,
     filled: bool,
     stroke_width: f32,
 ) -> Result<MeshData, Box<dyn std::error::Error>> {
@@ -269,6 +330,7 @@ pub fn tessellate_circle(
             &FillOptions::default(),
             &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| SimpleVertex {
                 position: vertex.position().to_array(),
+                advancement: 0.0,
             }),
         )?;
     } else {
@@ -278,6 +340,7 @@ pub fn tessellate_circle(
             &StrokeOptions::default().with_line_width(stroke_width),
             &mut BuffersBuilder::new(&mut geometry, |vertex: StrokeVertex| SimpleVertex {
                 position: vertex.position().to_array(),
+                advancement: vertex.advancement(),
             }),
         )?;
     }
@@ -300,7 +363,7 @@ pub fn tessellate_arc(
     radius: f32,
     start_angle_degrees: f32,
     arc_angle_degrees: f32,
-    color: Color,
+    color: impl VertexColor,
     stroke_width: f32,
     segments: usize,
     direction: crate::circle_geometry::CircleDirection,
@@ -353,6 +416,7 @@ pub fn tessellate_arc(
             .with_line_join(lyon::tessellation::LineJoin::Round),
         &mut BuffersBuilder::new(&mut geometry, |vertex: StrokeVertex| SimpleVertex {
             position: vertex.position().to_array(),
+            advancement: vertex.advancement(),
         }),
     )?;
 
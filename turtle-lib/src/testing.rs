@@ -0,0 +1,249 @@
+//! Golden-image tests
+//!
+//! Renders a plan headlessly at a fixed resolution and compares the result
+//! with a reference PNG, so changes to tessellation, line caps or fills
+//! can't slip through unnoticed. Declare tests with `golden_test!`:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//!
+//! golden_test!(square, "tests/golden/square.png", |turtle| {
+//!     for _ in 0..4 {
+//!         turtle.forward(100.0).right(90.0);
+//!     }
+//! });
+//! ```
+//!
+//! A missing reference is created on the first run. Set the environment
+//! variable `TURTLE_UPDATE_GOLDEN=1` to overwrite all references after an
+//! intended change. On a mismatch the rendered image is saved next to the
+//! reference as `<name>.actual.png`.
+
+use crate::commands::CommandQueue;
+use crate::headless;
+use crate::state::TurtleWorld;
+use macroquad::prelude::*;
+
+/// Width and height of golden images in pixels
+pub const GOLDEN_SIZE: u16 = 256;
+
+/// Environment variable that makes `assert_golden()` overwrite references
+pub const UPDATE_VARIABLE: &str = "TURTLE_UPDATE_GOLDEN";
+
+/// How much a rendering may differ from its reference
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+    /// Largest difference of a color channel (0.0 to 1.0) that still counts
+    /// as the same pixel
+    pub channel: f32,
+    /// Fraction of pixels (0.0 to 1.0) that may differ
+    pub pixels: f32,
+}
+
+impl Tolerance {
+    /// Every pixel has to match exactly
+    #[must_use]
+    pub fn exact() -> Self {
+        Self {
+            channel: 0.0,
+            pixels: 0.0,
+        }
+    }
+}
+
+impl Default for Tolerance {
+    /// Small rounding differences in 0.1% of the pixels, e.g. along edges
+    fn default() -> Self {
+        Self {
+            channel: 2.0 / 255.0,
+            pixels: 0.001,
+        }
+    }
+}
+
+/// Why a rendering does not match its reference
+#[derive(Clone, Debug, PartialEq)]
+pub enum GoldenError {
+    /// The images have different sizes
+    Size {
+        actual: (u16, u16),
+        expected: (u16, u16),
+    },
+    /// More pixels differ than the tolerance allows
+    Mismatch {
+        differing_pixels: usize,
+        total_pixels: usize,
+    },
+    /// The reference could not be read
+    Reference(String),
+}
+
+impl std::fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Size { actual, expected } => write!(
+                f,
+                "image is {}x{}, reference is {}x{}",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+            Self::Mismatch {
+                differing_pixels,
+                total_pixels,
+            } => write!(f, "{differing_pixels} of {total_pixels} pixels differ"),
+            Self::Reference(message) => write!(f, "cannot read reference: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {}
+
+/// Render `queue` on a single turtle onto a white `GOLDEN_SIZE` square,
+/// with the origin in the center
+#[must_use]
+pub fn render_plan(queue: &CommandQueue) -> Image {
+    let mut world = TurtleWorld::headless();
+    headless::execute(&mut world, 0, queue);
+    headless::render_image(&world, GOLDEN_SIZE, GOLDEN_SIZE)
+}
+
+/// Compare two images pixel by pixel
+///
+/// # Errors
+///
+/// Returns an error if the sizes differ or more pixels differ than
+/// `tolerance` allows
+pub fn compare_images(
+    actual: &Image,
+    expected: &Image,
+    tolerance: Tolerance,
+) -> Result<(), GoldenError> {
+    if (actual.width, actual.height) != (expected.width, expected.height) {
+        return Err(GoldenError::Size {
+            actual: (actual.width, actual.height),
+            expected: (expected.width, expected.height),
+        });
+    }
+    let limit = (tolerance.channel * 255.0).round() as u8;
+    let differing_pixels = actual
+        .bytes
+        .chunks_exact(4)
+        .zip(expected.bytes.chunks_exact(4))
+        .filter(|(a, e)| a.iter().zip(*e).any(|(a, e)| a.abs_diff(*e) > limit))
+        .count();
+    let total_pixels = usize::from(actual.width) * usize::from(actual.height);
+    if differing_pixels as f32 > tolerance.pixels * total_pixels as f32 {
+        return Err(GoldenError::Mismatch {
+            differing_pixels,
+            total_pixels,
+        });
+    }
+    Ok(())
+}
+
+/// Render `queue` and compare it with the PNG at `reference`
+///
+/// Relative paths start at the crate root, where cargo runs tests. The
+/// reference is written instead if it doesn't exist yet or
+/// `TURTLE_UPDATE_GOLDEN` is set.
+///
+/// # Panics
+///
+/// Panics if the rendering doesn't match the reference
+pub fn assert_golden(reference: &str, queue: &CommandQueue, tolerance: Tolerance) {
+    let actual = render_plan(queue);
+    let update = std::env::var_os(UPDATE_VARIABLE).is_some();
+    if update || !std::path::Path::new(reference).exists() {
+        if let Some(parent) = std::path::Path::new(reference).parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Err(error) = headless::write_png(&actual, reference) {
+            panic!("cannot write golden image {reference}: {error:?}");
+        }
+        tracing::warn!(reference, "Golden image written");
+        return;
+    }
+
+    let result = load_reference(reference)
+        .and_then(|expected| compare_images(&actual, &expected, tolerance));
+    if let Err(error) = result {
+        let actual_path = match reference.strip_suffix(".png") {
+            Some(stem) => format!("{stem}.actual.png"),
+            None => format!("{reference}.actual.png"),
+        };
+        let saved = match headless::write_png(&actual, &actual_path) {
+            Ok(()) => format!("rendering saved to {actual_path}"),
+            Err(write_error) => format!("cannot save rendering: {write_error:?}"),
+        };
+        panic!("golden image {reference} does not match: {error} ({saved})");
+    }
+}
+
+fn load_reference(reference: &str) -> Result<Image, GoldenError> {
+    let bytes =
+        std::fs::read(reference).map_err(|error| GoldenError::Reference(error.to_string()))?;
+    Image::from_file_with_format(&bytes, Some(ImageFormat::Png))
+        .map_err(|error| GoldenError::Reference(error.to_string()))
+}
+
+/// Declare a `#[test]` that compares a drawing with a golden image
+///
+/// The closure-like body gets a `&mut TurtlePlan`. An optional tolerance
+/// goes between the reference path and the body, see `testing::Tolerance`.
+///
+/// ```no_run
+/// use turtle_lib::*;
+/// use turtle_lib::testing::Tolerance;
+///
+/// golden_test!(thick_line, "tests/golden/thick_line.png", Tolerance::exact(), |turtle| {
+///     turtle.set_pen_width(12.0).forward(80.0);
+/// });
+/// ```
+#[macro_export]
+macro_rules! golden_test {
+    ($name:ident, $reference:expr, |$turtle:ident| $body:block) => {
+        $crate::golden_test!(
+            $name,
+            $reference,
+            $crate::testing::Tolerance::default(),
+            |$turtle| $body
+        );
+    };
+    ($name:ident, $reference:expr, $tolerance:expr, |$turtle:ident| $body:block) => {
+        #[test]
+        fn $name() {
+            let mut plan = $crate::TurtlePlan::new();
+            {
+                let $turtle = &mut plan;
+                $body
+            }
+            $crate::testing::assert_golden($reference, &plan.build(), $tolerance);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tolerates_few_small_differences() {
+        let expected = Image::gen_image_color(10, 10, WHITE);
+        let mut actual = expected.clone();
+        actual.set_pixel(3, 3, Color::new(0.995, 1.0, 1.0, 1.0));
+        assert!(compare_images(&actual, &expected, Tolerance::default()).is_ok());
+
+        actual.set_pixel(4, 4, BLACK);
+        assert_eq!(
+            compare_images(&actual, &expected, Tolerance::default()),
+            Err(GoldenError::Mismatch {
+                differing_pixels: 1,
+                total_pixels: 100
+            })
+        );
+        let loose = Tolerance {
+            pixels: 0.01,
+            ..Tolerance::default()
+        };
+        assert!(compare_images(&actual, &expected, loose).is_ok());
+    }
+}
@@ -0,0 +1,75 @@
+//! Colors of everything the library draws on its own
+//!
+//! The theme covers the overlay text, turtle cursors that keep their default
+//! color, selection and highlight outlines, helper marks like the center of
+//! an animated circle, and a palette suggested for drawings. Drawings keep
+//! the colors they were made with.
+//!
+//! Switch to the high-contrast theme with `TurtleApp::set_high_contrast()`.
+
+use crate::general::Color;
+use crate::palettes::Palette;
+use crate::state::DEFAULT_CURSOR_COLOR;
+use macroquad::prelude::{BLACK, DARKGRAY, GRAY, ORANGE, WHITE};
+
+/// Set of colors used by `TurtleApp` and the renderer
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Title, help and status line
+    pub text: Color,
+    /// Box drawn behind the overlay text, `None` draws the text directly on
+    /// the drawing
+    pub text_backdrop: Option<Color>,
+    /// Cursor colors of turtles without `TurtlePlan::set_turtle_color()`
+    pub cursor_fill: Color,
+    pub cursor_outline: Color,
+    /// Default outline of the selected turtle
+    pub selection: Color,
+    /// Outline pulsed by `TurtleApp::highlight_turtle()`
+    pub highlight: Color,
+    /// Helper marks, e.g. the center of a circle while it is drawn
+    pub guides: Color,
+    /// Pen colors that go well with this theme, for
+    /// `TurtlePlan::set_pen_palette()`
+    pub palette: Palette,
+}
+
+impl Theme {
+    /// The regular colors
+    #[must_use]
+    pub fn standard() -> Self {
+        Self {
+            text: DARKGRAY,
+            text_backdrop: None,
+            cursor_fill: DEFAULT_CURSOR_COLOR,
+            cursor_outline: DEFAULT_CURSOR_COLOR,
+            selection: ORANGE,
+            highlight: Color::new(1.0, 0.1, 0.1, 1.0),
+            guides: GRAY,
+            palette: Palette::rainbow(8),
+        }
+    }
+
+    /// Black text on white boxes, dark cursors with a strong outline and
+    /// Okabe-Ito colors, which stay distinguishable with the common kinds
+    /// of color blindness
+    #[must_use]
+    pub fn high_contrast() -> Self {
+        Self {
+            text: BLACK,
+            text_backdrop: Some(WHITE),
+            cursor_fill: Color::from_hex(0x00_72_b2),
+            cursor_outline: BLACK,
+            selection: Color::from_hex(0xe6_9f_00),
+            highlight: Color::from_hex(0xd5_5e_00),
+            guides: BLACK,
+            palette: Palette::classroom(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
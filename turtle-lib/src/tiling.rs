@@ -0,0 +1,275 @@
+//! Wallpaper patterns: a small drawing repeated across a rectangle
+//!
+//! `tile_region()` runs a plan once to find the lines it draws, then repeats
+//! them on a grid across a region and cuts off whatever sticks out:
+//!
+//! ```
+//! use turtle_lib::*;
+//! use turtle_lib::tiling::tile_region;
+//!
+//! let mut motif = create_turtle_plan();
+//! motif.set_pen_color(BLUE).circle_left(10.0, 360.0, 24);
+//!
+//! let mut turtle = create_turtle_plan();
+//! let region = Bounds::new(vec2(-200.0, -150.0), vec2(200.0, 150.0));
+//! tile_region(region, &motif, vec2(40.0, 35.0))
+//!     .with_row_offset(0.5)
+//!     .draw(&mut turtle);
+//! ```
+//!
+//! Only lines are tiled: fills, text and stamps of the tile are left out and
+//! arcs become short straight segments. Pen colors and widths set by the tile
+//! are kept, other lines use the pen of the drawing turtle.
+
+use crate::builders::TurtlePlan;
+use crate::commands::TurtleCommand;
+use crate::execution;
+use crate::general::{Bounds, Color, Coordinate, Precision};
+use crate::headless::trace_command;
+use crate::state::TurtleWorld;
+use macroquad::prelude::vec2;
+
+/// Smallest distance between tiles in pixels, smaller spacings are raised
+pub const MIN_SPACING: Precision = 1.0;
+
+/// A line of the tile with the pen it was drawn with, `None` where the tile
+/// didn't set one
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Segment {
+    from: Coordinate,
+    to: Coordinate,
+    color: Option<Color>,
+    width: Option<Precision>,
+}
+
+/// A tiled region, created with `tile_region()`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tiling {
+    bounds: Bounds,
+    spacing: Coordinate,
+    row_offset: Precision,
+    segments: Vec<Segment>,
+}
+
+/// Repeat the lines of `tile` every `spacing` pixels across `bounds`
+///
+/// The tile's origin is placed at the corner `bounds.min` and from there
+/// every `spacing.x` horizontally and `spacing.y` vertically. Tiles that
+/// stick out of `bounds` are cut off at its edges.
+#[must_use]
+pub fn tile_region(bounds: Bounds, tile: &TurtlePlan, spacing: impl Into<Coordinate>) -> Tiling {
+    Tiling {
+        bounds,
+        spacing: spacing.into().max(vec2(MIN_SPACING, MIN_SPACING)),
+        row_offset: 0.0,
+        segments: segments(tile),
+    }
+}
+
+/// Lines drawn by `tile`, in the default `CoordinateMode`
+fn segments(tile: &TurtlePlan) -> Vec<Segment> {
+    let mut world = TurtleWorld::headless();
+    world.add_turtle();
+    world.turtles[0].tween_controller.set_fixed_timestep(true);
+    let mode = world.coordinate_mode();
+
+    let (mut color, mut width) = (None, None);
+    let mut segments = Vec::new();
+    for command in tile
+        .clone()
+        .build()
+        .commands()
+        .iter()
+        .filter(|command| command.validate().is_ok())
+    {
+        match command {
+            TurtleCommand::SetColor(new_color) => color = Some(*new_color),
+            TurtleCommand::SetPenWidth(new_width) => width = Some(*new_width),
+            _ => {}
+        }
+        let before = world.turtles[0].params.clone();
+        execution::execute_command_with_id(command, 0, &mut world);
+        let draws = matches!(
+            command,
+            TurtleCommand::Move(_)
+                | TurtleCommand::Circle { .. }
+                | TurtleCommand::Goto(_)
+                | TurtleCommand::GoToMark(_)
+                | TurtleCommand::Together(_)
+        );
+        if !draws || !before.pen_down {
+            continue;
+        }
+        let mut trace = vec![before.position];
+        trace_command(
+            &mut trace,
+            command,
+            &before,
+            world.turtles[0].params.position,
+        );
+        segments.extend(trace.windows(2).map(|points| Segment {
+            from: mode.from_screen(points[0]),
+            to: mode.from_screen(points[1]),
+            color,
+            width,
+        }));
+    }
+    segments
+}
+
+impl Tiling {
+    /// Shift every other row by `fraction` of the horizontal spacing, `0.5`
+    /// gives a brick pattern
+    #[must_use]
+    pub fn with_row_offset(mut self, fraction: Precision) -> Self {
+        self.row_offset = fraction;
+        self
+    }
+
+    /// Positions of the tile origins whose lines reach into the region
+    fn origins(&self) -> Vec<Coordinate> {
+        let Some(extent) = Bounds::from_points(
+            self.segments
+                .iter()
+                .flat_map(|segment| [segment.from, segment.to]),
+        ) else {
+            return Vec::new();
+        };
+        let size = self.bounds.max - self.bounds.min;
+        let spacing = self.spacing;
+        let first_row = (-extent.max.y / spacing.y).ceil() as i64;
+        let last_row = ((size.y - extent.min.y) / spacing.y).floor() as i64;
+
+        let mut origins = Vec::new();
+        for row in first_row..=last_row {
+            let shift = if row.rem_euclid(2) == 1 {
+                (self.row_offset * spacing.x).rem_euclid(spacing.x)
+            } else {
+                0.0
+            };
+            let first_column = ((-extent.max.x - shift) / spacing.x).ceil() as i64;
+            let last_column = ((size.x - extent.min.x - shift) / spacing.x).floor() as i64;
+            origins.extend((first_column..=last_column).map(|column| {
+                self.bounds.min
+                    + vec2(
+                        shift + column as Precision * spacing.x,
+                        row as Precision * spacing.y,
+                    )
+            }));
+        }
+        origins
+    }
+
+    /// Every line of every tile cut to the region, in drawing order
+    fn pieces(&self) -> Vec<(Segment, Coordinate, Coordinate)> {
+        self.origins()
+            .into_iter()
+            .flat_map(|origin| {
+                self.segments.iter().filter_map(move |segment| {
+                    let (from, to) = (segment.from + origin, segment.to + origin);
+                    let (from, to) = clip(from, to, self.bounds)?;
+                    Some((*segment, from, to))
+                })
+            })
+            .collect()
+    }
+
+    /// Draw all tiles, moving with the pen up between separate lines
+    ///
+    /// The turtle returns to where it started with its pen color and width,
+    /// and the pen down.
+    pub fn draw(&self, plan: &mut TurtlePlan) {
+        let pieces = self.pieces();
+        if pieces.is_empty() {
+            return;
+        }
+        plan.push_state().pen_up();
+        let mut at = None;
+        let (mut color, mut width) = (None, None);
+        for (segment, from, to) in pieces {
+            // Back to the turtle's own pen for lines the tile drew with it
+            if (segment.color.is_none() && color.is_some())
+                || (segment.width.is_none() && width.is_some())
+            {
+                plan.pen_up().pop_state().push_state();
+                (color, width, at) = (None, None, None);
+            }
+            if let Some(new_color) = segment.color.filter(|_| segment.color != color) {
+                plan.set_pen_color(new_color);
+                color = segment.color;
+            }
+            if let Some(new_width) = segment.width.filter(|_| segment.width != width) {
+                plan.set_pen_width(new_width);
+                width = segment.width;
+            }
+            if at != Some(from) {
+                plan.pen_up().go_to(from).pen_down();
+            }
+            plan.go_to(to);
+            at = Some(to);
+        }
+        plan.pen_up().pop_state().pen_down();
+    }
+}
+
+/// Part of the line from `from` to `to` inside `bounds` (Liang-Barsky),
+/// `None` if nothing of it is
+fn clip(from: Coordinate, to: Coordinate, bounds: Bounds) -> Option<(Coordinate, Coordinate)> {
+    let delta = to - from;
+    let (mut enter, mut leave) = (0.0, 1.0);
+    for (direction, distance) in [
+        (-delta.x, from.x - bounds.min.x),
+        (delta.x, bounds.max.x - from.x),
+        (-delta.y, from.y - bounds.min.y),
+        (delta.y, bounds.max.y - from.y),
+    ] {
+        if direction == 0.0 {
+            // Parallel to this edge: entirely inside or outside of it
+            if distance < 0.0 {
+                return None;
+            }
+        } else if direction < 0.0 {
+            enter = Precision::max(enter, distance / direction);
+        } else {
+            leave = Precision::min(leave, distance / direction);
+        }
+    }
+    (enter < leave).then(|| (from + delta * enter, from + delta * leave))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builders::DirectionalMovement;
+
+    #[test]
+    fn tiles_are_cut_to_the_region() {
+        let mut dash = TurtlePlan::new();
+        dash.forward(30.0);
+        let region = Bounds::new(vec2(0.0, 0.0), vec2(100.0, 50.0));
+        let length = |tiling: &Tiling| -> Precision {
+            tiling
+                .pieces()
+                .iter()
+                .map(|(_, from, to)| from.distance(*to))
+                .sum()
+        };
+
+        // Three rows of 30 + 30 + 20 (cut at the right edge)
+        let grid = tile_region(region, &dash, vec2(40.0, 20.0));
+        assert!((length(&grid) - 240.0).abs() < 1e-3);
+
+        // The middle row starts at -20 (cut to 10) and adds 30 + 30
+        let bricks = grid.with_row_offset(0.5);
+        assert!((length(&bricks) - 230.0).abs() < 1e-3);
+        assert!(bricks.pieces().iter().all(|(_, from, to)| {
+            [from, to]
+                .iter()
+                .all(|point| point.x >= 0.0 && point.x <= 100.0)
+        }));
+
+        assert!(tile_region(region, &TurtlePlan::new(), vec2(10.0, 10.0))
+            .pieces()
+            .is_empty());
+    }
+}
@@ -0,0 +1,375 @@
+//! Turn images into turtle strokes
+//!
+//! An `ImageTracer` converts a macroquad `Image` (see `load_image()`) into
+//! strokes the turtle can draw, either as hatching that gets denser in dark
+//! areas or as the outlines found by edge detection:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//! use turtle_lib::trace::{draw_strokes, ImageTracer};
+//!
+//! #[macroquad::main("Trace")]
+//! async fn main() {
+//!     let image = macroquad::texture::load_image("photo.png").await.unwrap();
+//!     let tracer = ImageTracer::new().with_scale(2.0).with_spacing(5.0);
+//!     let mut turtle = create_turtle_plan();
+//!     turtle.set_speed(900.0);
+//!     draw_strokes(&mut turtle, &tracer.hatch(&image));
+//!
+//!     let mut app = TurtleApp::new().with_commands(turtle.build());
+//!     app.run(RunConfig::new()).await;
+//! }
+//! ```
+//!
+//! Strokes are lists of turtle positions with the image centered on the
+//! turtle origin. Transparent pixels count as white. Needs the `trace`
+//! feature.
+
+use crate::builders::TurtlePlan;
+use crate::general::{Coordinate, Precision};
+use macroquad::prelude::vec2;
+use macroquad::texture::Image;
+
+/// A polyline drawn with the pen down
+pub type Stroke = Vec<Coordinate>;
+
+// Hatching directions in degrees, the first layers cover the most
+const HATCH_ANGLES: [Precision; 4] = [45.0, 135.0, 0.0, 90.0];
+// Edge traces with fewer pixels are noise
+const MIN_EDGE_PIXELS: usize = 4;
+
+/// Settings for converting images into strokes, see the module documentation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageTracer {
+    scale: Precision,
+    center: Coordinate,
+    spacing: Precision,
+    layers: usize,
+    edge_threshold: f32,
+    simplify: Precision,
+}
+
+impl ImageTracer {
+    /// One turtle pixel per image pixel, hatch lines 4 pixels apart in 3
+    /// layers
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            scale: 1.0,
+            center: Coordinate::ZERO,
+            spacing: 4.0,
+            layers: 3,
+            edge_threshold: 0.3,
+            simplify: 1.0,
+        }
+    }
+
+    /// Turtle pixels per image pixel
+    #[must_use]
+    pub fn with_scale(mut self, scale: Precision) -> Self {
+        self.scale = scale.max(f32::EPSILON);
+        self
+    }
+
+    /// Turtle position of the middle of the image
+    #[must_use]
+    pub fn with_center(mut self, center: impl Into<Coordinate>) -> Self {
+        self.center = center.into();
+        self
+    }
+
+    /// Distance between hatch lines in turtle pixels
+    #[must_use]
+    pub fn with_spacing(mut self, spacing: Precision) -> Self {
+        self.spacing = spacing.max(0.5);
+        self
+    }
+
+    /// Number of hatching layers (1 to 4), each in another direction
+    ///
+    /// Layer `i` of `n` covers pixels darker than `(n - i) / (n + 1)`, so the
+    /// darkest areas are crossed by all layers.
+    #[must_use]
+    pub fn with_layers(mut self, layers: usize) -> Self {
+        self.layers = layers.clamp(1, HATCH_ANGLES.len());
+        self
+    }
+
+    /// Minimal brightness change (0.0 to 1.0) between neighbouring pixels
+    /// that counts as an edge
+    #[must_use]
+    pub fn with_edge_threshold(mut self, threshold: f32) -> Self {
+        self.edge_threshold = threshold;
+        self
+    }
+
+    /// How far in turtle pixels simplified outlines may stray from the
+    /// traced edge pixels
+    #[must_use]
+    pub fn with_simplify(mut self, tolerance: Precision) -> Self {
+        self.simplify = tolerance.max(0.0);
+        self
+    }
+
+    fn to_turtle(self, image: &Image, x: Precision, y: Precision) -> Coordinate {
+        let size = vec2(Precision::from(image.width), Precision::from(image.height));
+        self.center + vec2(x - size.x / 2.0, size.y / 2.0 - y) * self.scale
+    }
+
+    /// Parallel lines over the dark areas, in up to four crossing layers
+    #[must_use]
+    pub fn hatch(&self, image: &Image) -> Vec<Stroke> {
+        let pixels = Brightness::new(image);
+        let size = vec2(pixels.width as Precision, pixels.height as Precision);
+        let middle = size / 2.0;
+        let reach = size.length() / 2.0;
+        let spacing = self.spacing / self.scale;
+        let mut strokes = Vec::new();
+
+        for (layer, angle) in HATCH_ANGLES[..self.layers].iter().enumerate() {
+            let threshold = (self.layers - layer) as f32 / (self.layers + 1) as f32;
+            // In image space y points down, the angles are turned to match
+            let along = Coordinate::from_angle(-angle.to_radians());
+            let across = along.perp();
+            let lines = (reach / spacing).ceil() as i32;
+            for line in -lines..=lines {
+                let offset = middle + across * line as Precision * spacing;
+                let mut samples: Vec<Precision> = (-reach.ceil() as i32..=reach.ceil() as i32)
+                    .map(|s| s as Precision)
+                    .collect();
+                // Every other line runs backwards so the pen moves less
+                if line % 2 != 0 {
+                    samples.reverse();
+                }
+                let mut run: Option<(Coordinate, Coordinate)> = None;
+                for s in samples {
+                    let point = offset + along * s;
+                    if pixels.at(point).is_some_and(|value| value < threshold) {
+                        run = Some((run.map_or(point, |(first, _)| first), point));
+                        continue;
+                    }
+                    if let Some((first, last)) = run.take() {
+                        if first != last {
+                            strokes.push(vec![
+                                self.to_turtle(image, first.x, first.y),
+                                self.to_turtle(image, last.x, last.y),
+                            ]);
+                        }
+                    }
+                }
+            }
+        }
+        strokes
+    }
+
+    /// Outlines between light and dark areas (Sobel edge detection), traced
+    /// into simplified polylines
+    #[must_use]
+    pub fn edges(&self, image: &Image) -> Vec<Stroke> {
+        let pixels = Brightness::new(image);
+        let (width, height) = (pixels.width, pixels.height);
+        let mut edge = vec![false; width * height];
+        let gradients: Vec<Coordinate> = (0..width * height)
+            .map(|index| pixels.gradient(index % width, index / width))
+            .collect();
+        for y in 1..height.saturating_sub(1) {
+            for x in 1..width.saturating_sub(1) {
+                let gradient = gradients[y * width + x];
+                let strength = gradient.length();
+                if strength < self.edge_threshold {
+                    continue;
+                }
+                // Keep only the strongest pixel across the edge (non-maximum
+                // suppression), so every edge is one pixel wide
+                let step = gradient / strength;
+                let (dx, dy) = (step.x.round() as isize, step.y.round() as isize);
+                let neighbor = |sign: isize| {
+                    let nx = x.wrapping_add_signed(sign * dx);
+                    let ny = y.wrapping_add_signed(sign * dy);
+                    gradients[ny * width + nx].length()
+                };
+                edge[y * width + x] = strength >= neighbor(1) && strength > neighbor(-1);
+            }
+        }
+
+        let mut strokes = Vec::new();
+        for start in 0..edge.len() {
+            if !edge[start] {
+                continue;
+            }
+            edge[start] = false;
+            let mut path = vec![(start % width, start / width)];
+            while let Some(next) = next_edge_pixel(&edge, width, height, path[path.len() - 1]) {
+                edge[next.1 * width + next.0] = false;
+                path.push(next);
+            }
+            if path.len() < MIN_EDGE_PIXELS {
+                continue;
+            }
+            let points: Vec<_> = path
+                .iter()
+                .map(|&(x, y)| self.to_turtle(image, x as Precision + 0.5, y as Precision + 0.5))
+                .collect();
+            strokes.push(simplify(&points, self.simplify));
+        }
+        strokes
+    }
+}
+
+impl Default for ImageTracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Draw every stroke, moving between them with the pen up
+///
+/// Leaves the pen down.
+pub fn draw_strokes(plan: &mut TurtlePlan, strokes: &[Stroke]) {
+    for stroke in strokes {
+        let Some((&first, rest)) = stroke.split_first() else {
+            continue;
+        };
+        plan.pen_up().go_to(first).pen_down();
+        for &point in rest {
+            plan.go_to(point);
+        }
+    }
+}
+
+/// Brightness of every pixel, 0.0 (black) to 1.0 (white)
+struct Brightness {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+impl Brightness {
+    fn new(image: &Image) -> Self {
+        let values = image
+            .get_image_data()
+            .iter()
+            .map(|&[r, g, b, a]| {
+                let luma =
+                    (0.299 * f32::from(r) + 0.587 * f32::from(g) + 0.114 * f32::from(b)) / 255.0;
+                let alpha = f32::from(a) / 255.0;
+                1.0 - alpha * (1.0 - luma)
+            })
+            .collect();
+        Self {
+            width: usize::from(image.width),
+            height: usize::from(image.height),
+            values,
+        }
+    }
+
+    /// Brightness of the pixel containing `point`, `None` outside the image
+    fn at(&self, point: Coordinate) -> Option<f32> {
+        if point.x < 0.0 || point.y < 0.0 {
+            return None;
+        }
+        let (x, y) = (point.x as usize, point.y as usize);
+        (x < self.width && y < self.height).then(|| self.values[y * self.width + x])
+    }
+
+    /// Sobel gradient at a pixel, scaled so a sharp black to white step has
+    /// a length of 1
+    fn gradient(&self, x: usize, y: usize) -> Coordinate {
+        let value = |dx: isize, dy: isize| {
+            let nx = x.saturating_add_signed(dx).min(self.width - 1);
+            let ny = y.saturating_add_signed(dy).min(self.height - 1);
+            self.values[ny * self.width + nx]
+        };
+        let gx = value(1, -1) + 2.0 * value(1, 0) + value(1, 1)
+            - value(-1, -1)
+            - 2.0 * value(-1, 0)
+            - value(-1, 1);
+        let gy = value(-1, 1) + 2.0 * value(0, 1) + value(1, 1)
+            - value(-1, -1)
+            - 2.0 * value(0, -1)
+            - value(1, -1);
+        vec2(gx, gy) / 4.0
+    }
+}
+
+/// An unvisited edge pixel touching `(x, y)`, straight neighbours first
+fn next_edge_pixel(
+    edge: &[bool],
+    width: usize,
+    height: usize,
+    (x, y): (usize, usize),
+) -> Option<(usize, usize)> {
+    const OFFSETS: [(isize, isize); 8] = [
+        (1, 0),
+        (0, 1),
+        (-1, 0),
+        (0, -1),
+        (1, 1),
+        (-1, 1),
+        (-1, -1),
+        (1, -1),
+    ];
+    OFFSETS.iter().find_map(|&(dx, dy)| {
+        let nx = x.checked_add_signed(dx)?;
+        let ny = y.checked_add_signed(dy)?;
+        (nx < width && ny < height && edge[ny * width + nx]).then_some((nx, ny))
+    })
+}
+
+/// Drop points that are within `tolerance` of the simplified line
+/// (Ramer-Douglas-Peucker)
+fn simplify(points: &[Coordinate], tolerance: Precision) -> Stroke {
+    let (Some(&first), Some(&last)) = (points.first(), points.last()) else {
+        return Vec::new();
+    };
+    let along = last - first;
+    let distance = |point: Coordinate| {
+        if along.length_squared() == 0.0 {
+            point.distance(first)
+        } else {
+            along.perp_dot(point - first).abs() / along.length()
+        }
+    };
+    let farthest = points
+        .iter()
+        .enumerate()
+        .map(|(index, &point)| (index, distance(point)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    match farthest {
+        Some((index, offset)) if offset > tolerance && index > 0 && index < points.len() - 1 => {
+            let mut stroke = simplify(&points[..=index], tolerance);
+            stroke.pop();
+            stroke.extend(simplify(&points[index..], tolerance));
+            stroke
+        }
+        _ if points.len() == 1 => vec![first],
+        _ => vec![first, last],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::prelude::{BLACK, WHITE};
+
+    #[test]
+    fn traces_dark_half_of_an_image() {
+        // 20×10 pixels, black on the left
+        let mut image = Image::gen_image_color(20, 10, WHITE);
+        for y in 0..10 {
+            for x in 0..10 {
+                image.set_pixel(x, y, BLACK);
+            }
+        }
+        let tracer = ImageTracer::new().with_layers(1);
+        let hatching = tracer.hatch(&image);
+        assert!(!hatching.is_empty());
+        assert!(hatching.iter().flatten().all(|point| point.x <= 0.0));
+
+        // One straight outline between the halves
+        let edges = tracer.edges(&image);
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].len(), 2);
+        assert!(edges[0].iter().all(|point| (point.x + 0.5).abs() <= 1.0));
+    }
+}
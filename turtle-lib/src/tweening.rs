@@ -2,10 +2,12 @@
 
 use crate::circle_geometry::{CircleDirection, CircleGeometry};
 use crate::commands::{CommandQueue, TurtleCommand};
-use crate::general::AnimationSpeed;
+use crate::general::{mix_colors, AnimationSpeed};
 use crate::state::{Turtle, TurtleParams};
 use macroquad::prelude::*;
-use tween::{CubicInOut, TweenValue, Tweener};
+use std::collections::VecDeque;
+use std::ops::RangeBounds;
+use tween::{Tween, TweenValue, Tweener};
 
 // Newtype wrapper for Vec2 to implement TweenValue
 #[derive(Debug, Clone, Copy)]
@@ -43,12 +45,121 @@ impl From<TweenVec2> for Vec2 {
     }
 }
 
+/// Easing curve used to animate commands
+///
+/// Mirrors the easing functions of the `tween` crate, see
+/// <https://easings.net/> for visual demonstrations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Easing {
+    Linear,
+    SineIn,
+    SineOut,
+    SineInOut,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    #[default]
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    QuintIn,
+    QuintOut,
+    QuintInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    CircIn,
+    CircOut,
+    CircInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+impl Easing {
+    /// Eased progress for a linear progress `percent` between `0.0` and `1.0`
+    #[must_use]
+    pub fn apply(self, percent: f32) -> f32 {
+        match self {
+            Easing::Linear => tween::Linear.tween(1.0, percent),
+            Easing::SineIn => tween::SineIn.tween(1.0, percent),
+            Easing::SineOut => tween::SineOut.tween(1.0, percent),
+            Easing::SineInOut => tween::SineInOut.tween(1.0, percent),
+            Easing::QuadIn => tween::QuadIn.tween(1.0, percent),
+            Easing::QuadOut => tween::QuadOut.tween(1.0, percent),
+            Easing::QuadInOut => tween::QuadInOut.tween(1.0, percent),
+            Easing::CubicIn => tween::CubicIn.tween(1.0, percent),
+            Easing::CubicOut => tween::CubicOut.tween(1.0, percent),
+            Easing::CubicInOut => tween::CubicInOut.tween(1.0, percent),
+            Easing::QuartIn => tween::QuartIn.tween(1.0, percent),
+            Easing::QuartOut => tween::QuartOut.tween(1.0, percent),
+            Easing::QuartInOut => tween::QuartInOut.tween(1.0, percent),
+            Easing::QuintIn => tween::QuintIn.tween(1.0, percent),
+            Easing::QuintOut => tween::QuintOut.tween(1.0, percent),
+            Easing::QuintInOut => tween::QuintInOut.tween(1.0, percent),
+            Easing::ExpoIn => tween::ExpoIn.tween(1.0, percent),
+            Easing::ExpoOut => tween::ExpoOut.tween(1.0, percent),
+            Easing::ExpoInOut => tween::ExpoInOut.tween(1.0, percent),
+            Easing::CircIn => tween::CircIn.tween(1.0, percent),
+            Easing::CircOut => tween::CircOut.tween(1.0, percent),
+            Easing::CircInOut => tween::CircInOut.tween(1.0, percent),
+            Easing::BackIn => tween::BackIn.tween(1.0, percent),
+            Easing::BackOut => tween::BackOut.tween(1.0, percent),
+            Easing::BackInOut => tween::BackInOut.tween(1.0, percent),
+            Easing::ElasticIn => tween::ElasticIn.tween(1.0, percent),
+            Easing::ElasticOut => tween::ElasticOut.tween(1.0, percent),
+            Easing::ElasticInOut => tween::ElasticInOut.tween(1.0, percent),
+            Easing::BounceIn => tween::BounceIn.tween(1.0, percent),
+            Easing::BounceOut => tween::BounceOut.tween(1.0, percent),
+            Easing::BounceInOut => tween::BounceInOut.tween(1.0, percent),
+        }
+    }
+}
+
+impl<Value: TweenValue> Tween<Value> for Easing {
+    fn tween(&mut self, value_delta: Value, percent: f32) -> Value {
+        value_delta.scale(self.apply(percent))
+    }
+}
+
 /// Controls tweening of turtle commands
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub struct TweenController {
     queue: CommandQueue,
     current_tween: Option<CommandTween>,
     speed: AnimationSpeed,
+    // Time at which the controller was paused, `None` while running
+    paused_at: Option<f64>,
+    // Commands that may still start in step mode, `None` when running freely
+    step_budget: Option<usize>,
+    // Playback speed multiplier applied to all tween durations
+    time_scale: f64,
+    // Commands finished by `update()` since the last `take_finished()`,
+    // `None` while nobody is interested in them
+    finished: Option<Vec<(TurtleCommand, TurtleParams, TurtleParams)>>,
+    // Time advanced only by `advance()`, `None` to follow the wall clock.
+    // Starts fixed, the wall clock needs a macroquad window
+    clock: Option<f64>,
+    // Commands of stamped stencils, run before the rest of the queue
+    replay: VecDeque<TurtleCommand>,
+    // Tag of the barrier the turtle waits at, see `TurtlePlan::wait_for_group()`
+    waiting: Option<String>,
+}
+
+impl Default for TweenController {
+    fn default() -> Self {
+        Self::new(CommandQueue::new(), AnimationSpeed::default())
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -57,13 +168,34 @@ pub struct CommandTween {
     pub command: TurtleCommand,
     pub start_time: f64,
     pub duration: f64,
+    /// Scaled time elapsed since `start_time` as of the last update
+    pub elapsed: f64,
     pub start_params: TurtleParams,
     pub target_params: TurtleParams,
     pub current_position: Vec2,
     pub current_heading: f32,
-    position_tweener: Tweener<TweenVec2, f64, CubicInOut>,
-    heading_tweener: Tweener<f32, f64, CubicInOut>,
-    pen_width_tweener: Tweener<f32, f64, CubicInOut>,
+    position_tweener: Tweener<TweenVec2, f64, Easing>,
+    heading_tweener: Tweener<f32, f64, Easing>,
+    pen_width_tweener: Tweener<f32, f64, Easing>,
+}
+
+impl CommandTween {
+    /// Distance in pixels the turtle has moved since the tween started
+    #[must_use]
+    pub fn distance_travelled(&self) -> f32 {
+        match &self.command {
+            TurtleCommand::Circle { radius, angle, .. } => {
+                let progress = if self.duration > 0.0 {
+                    (self.elapsed / self.duration).min(1.0) as f32
+                } else {
+                    1.0
+                };
+                let progress = self.start_params.easing.apply(progress);
+                radius.abs() * angle.to_radians().abs() * progress
+            }
+            _ => self.start_params.position.distance(self.current_position),
+        }
+    }
 }
 
 impl TweenController {
@@ -73,6 +205,13 @@ impl TweenController {
             queue,
             current_tween: None,
             speed,
+            paused_at: None,
+            step_budget: None,
+            time_scale: 1.0,
+            finished: None,
+            clock: Some(0.0),
+            replay: VecDeque::new(),
+            waiting: None,
         }
     }
 
@@ -80,9 +219,235 @@ impl TweenController {
         self.speed = speed;
     }
 
+    /// Show or hide the cursor during the running tween as well
+    pub(crate) fn set_visible(&mut self, visible: bool) {
+        if let Some(tween) = &mut self.current_tween {
+            tween.start_params.visible = visible;
+            tween.target_params.visible = visible;
+        }
+    }
+
+    /// Current animation time in seconds
+    ///
+    /// Follows the wall clock unless fixed-timestep mode is enabled. New
+    /// controllers start in fixed-timestep mode, so turtles work without a
+    /// window; `TurtleApp` switches them to the wall clock unless
+    /// `TurtleApp::set_fixed_timestep()` is enabled.
+    #[must_use]
+    pub fn now(&self) -> f64 {
+        self.clock.unwrap_or_else(get_time)
+    }
+
+    /// Advance time only with `advance()` instead of following the wall clock
+    ///
+    /// Makes animations independent of frame timing, so tests and offline
+    /// renders produce identical frames. Switching keeps a running tween at
+    /// its current progress.
+    pub fn set_fixed_timestep(&mut self, enabled: bool) {
+        if enabled == self.clock.is_some() {
+            return;
+        }
+        if self.current_tween.is_none() && self.paused_at.is_none() {
+            // Nothing depends on the old time, so the clock can start at zero
+            self.clock = enabled.then_some(0.0);
+            return;
+        }
+
+        let old_now = self.now();
+        let new_now = if enabled { old_now } else { get_time() };
+        self.clock = enabled.then_some(new_now);
+
+        // Rebase the running tween and pause onto the new time source
+        let shift = new_now - old_now;
+        if let Some(tween) = &mut self.current_tween {
+            tween.start_time += shift;
+        }
+        if let Some(paused_at) = &mut self.paused_at {
+            *paused_at += shift;
+        }
+    }
+
+    #[must_use]
+    pub fn is_fixed_timestep(&self) -> bool {
+        self.clock.is_some()
+    }
+
+    /// Move the fixed-timestep clock forward by `dt` seconds
+    ///
+    /// Has no effect unless fixed-timestep mode is enabled.
+    pub fn advance(&mut self, dt: f64) {
+        if let Some(clock) = &mut self.clock {
+            *clock += dt.max(0.0);
+        }
+    }
+
+    /// Freeze the tween in progress and stop processing the queue
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(self.now());
+        }
+    }
+
+    /// Continue a paused controller exactly where it stopped
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            let now = self.now();
+            // Shift the tween start so the paused time does not count as progress
+            if let Some(tween) = &mut self.current_tween {
+                tween.start_time += now - paused_at;
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Scale the playback speed of all tweens (`2.0` = twice as fast)
+    ///
+    /// Takes effect immediately, a running tween continues from its current
+    /// progress. Values are clamped to at least `0.01`; use `pause()` to stop.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        let scale = f64::from(scale.max(0.01));
+        let now = self.paused_at.unwrap_or_else(|| self.now());
+        if let Some(tween) = &mut self.current_tween {
+            // Rebase the start time so the scaled elapsed time stays the same
+            let elapsed = (now - tween.start_time) * self.time_scale;
+            tween.start_time = now - elapsed / scale;
+        }
+        self.time_scale = scale;
+    }
+
+    #[must_use]
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale as f32
+    }
+
+    /// Enable or disable step mode
+    ///
+    /// In step mode the controller only starts a new command after `step()`;
+    /// a command that is already running still finishes.
+    pub fn set_step_mode(&mut self, enabled: bool) {
+        self.step_budget = enabled.then_some(0);
+    }
+
+    #[must_use]
+    pub fn is_step_mode(&self) -> bool {
+        self.step_budget.is_some()
+    }
+
+    /// Allow one more command to run in step mode
+    pub fn step(&mut self) {
+        if let Some(budget) = &mut self.step_budget {
+            *budget += 1;
+        }
+    }
+
+    /// Take the next command from the queue unless step mode forbids it
+    fn next_command(&mut self) -> Option<TurtleCommand> {
+        if self.step_budget == Some(0) || self.waiting.is_some() {
+            return None;
+        }
+        let command = match self.replay.pop_front() {
+            Some(command) => command,
+            None => self.queue.next()?,
+        };
+        if let Some(budget) = &mut self.step_budget {
+            *budget -= 1;
+        }
+        Some(command)
+    }
+
+    /// Record the commands finished by `update()` for `take_finished()`
+    pub fn set_track_finished(&mut self, enabled: bool) {
+        if enabled != self.finished.is_some() {
+            self.finished = enabled.then(Vec::new);
+        }
+    }
+
+    #[must_use]
+    pub fn is_tracking_finished(&self) -> bool {
+        self.finished.is_some()
+    }
+
+    /// Take the (`command`, `start_state`, `end_state`) of every command
+    /// finished since the last call, oldest first
+    ///
+    /// Always empty unless enabled with `set_track_finished()`.
+    pub fn take_finished(&mut self) -> Vec<(TurtleCommand, TurtleParams, TurtleParams)> {
+        self.finished
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn report_finished(
+        &mut self,
+        command: &TurtleCommand,
+        start: &TurtleParams,
+        end: &TurtleParams,
+    ) {
+        if let Some(finished) = &mut self.finished {
+            finished.push((command.clone(), start.clone(), end.clone()));
+        }
+    }
+
+    /// Abort the tween in progress without applying its target state
+    pub fn cancel_current_tween(&mut self) {
+        self.current_tween = None;
+    }
+
     /// Append commands to the queue
     pub fn append_commands(&mut self, new_queue: CommandQueue) {
-        self.queue.extend(new_queue);
+        self.queue.append(new_queue);
+    }
+
+    /// Insert commands among the queued ones, see `CommandQueue::splice_pending()`
+    pub fn splice_commands(&mut self, index: usize, new_queue: CommandQueue) {
+        self.queue.splice_pending(index, new_queue);
+    }
+
+    /// Run `commands` next, before the rest of the queue and of earlier
+    /// replays
+    ///
+    /// Unlike `splice_commands()` this leaves the queue itself alone, so a
+    /// looping queue doesn't grow with every repetition.
+    pub(crate) fn replay(&mut self, commands: Vec<TurtleCommand>) {
+        for command in commands.into_iter().rev() {
+            self.replay.push_front(command);
+        }
+    }
+
+    /// Tag of the barrier the turtle waits at, `None` while it runs
+    #[must_use]
+    pub fn waiting_at(&self) -> Option<&str> {
+        self.waiting.as_deref()
+    }
+
+    /// Stop starting commands until `release()`
+    pub(crate) fn wait_at(&mut self, tag: String) {
+        self.waiting = Some(tag);
+    }
+
+    /// Continue after a barrier
+    pub(crate) fn release(&mut self) {
+        self.waiting = None;
+    }
+
+    /// Whether a barrier with `tag` is still ahead in the queue
+    pub(crate) fn has_barrier_ahead(&self, tag: &str) -> bool {
+        self.replay
+            .iter()
+            .chain(self.queue.upcoming())
+            .any(|command| matches!(command, TurtleCommand::Barrier(ahead) if ahead == tag))
+    }
+
+    /// Remove queued commands, see `CommandQueue::remove_pending()`
+    ///
+    /// The animated command isn't part of the queue and always finishes.
+    pub fn remove_pending(&mut self, range: impl RangeBounds<usize>) -> Vec<TurtleCommand> {
+        self.queue.remove_pending(range)
     }
 
     /// Update the tween, returns `Vec` of (`command`, `start_state`, `end_state`) for all completed commands this frame
@@ -90,14 +455,31 @@ impl TweenController {
     /// Each `command` has its own `start_state` and `end_state` pair
     #[allow(clippy::too_many_lines)]
     pub fn update(state: &mut Turtle) -> Vec<(TurtleCommand, TurtleParams, TurtleParams)> {
+        if state.tween_controller.is_paused() {
+            return Vec::new();
+        }
+
         // In instant mode, execute commands up to the draw calls per frame limit
         if let AnimationSpeed::Instant(max_draw_calls) = state.tween_controller.speed {
             let mut completed_commands: Vec<(TurtleCommand, TurtleParams, TurtleParams)> =
                 Vec::new();
             let mut draw_call_count = 0;
+            // Looping queues without drawings would otherwise never yield
+            let max_commands =
+                state.tween_controller.queue.len() + state.tween_controller.replay.len() + 1;
 
             // Consume commands from the real queue so the current_index advances
-            while let Some(command) = state.tween_controller.queue.next() {
+            for _ in 0..max_commands {
+                let Some(command) = state.tween_controller.next_command() else {
+                    break;
+                };
+                state.count_command(&command);
+
+                let Some(command) = state.resolve_reporting(command) else {
+                    continue;
+                };
+                state.apply_pen_palette(&command);
+
                 // Handle SetSpeed command to potentially switch modes
                 if let TurtleCommand::SetSpeed(new_speed) = &command {
                     state.params.speed = *new_speed;
@@ -108,17 +490,33 @@ impl TweenController {
                     continue;
                 }
 
+                // Drawings collected this frame would survive a clear otherwise
+                if matches!(
+                    command,
+                    TurtleCommand::Clear | TurtleCommand::ClearScreen { .. }
+                ) {
+                    completed_commands.clear();
+                }
+
+                // Save start state and compute target state
+                let start_params = state.params.clone();
+
                 // Execute side-effect-only commands using centralized helper
                 if crate::execution::execute_command_side_effects(&command, state) {
+                    let end_params = state.params.clone();
+                    state
+                        .tween_controller
+                        .report_finished(&command, &start_params, &end_params);
                     continue; // Command fully handled
                 }
 
-                // Save start state and compute target state
-                let start_params = state.params.clone();
                 let target_params = Self::calculate_target_state(&start_params, &command);
 
                 // Update state to the target (instant execution)
                 state.params = target_params.clone();
+                state
+                    .tween_controller
+                    .report_finished(&command, &start_params, &target_params);
 
                 // Record fill vertices AFTER movement
                 crate::execution::record_fill_vertices_after_movement(
@@ -141,8 +539,11 @@ impl TweenController {
         }
 
         // Process current tween
+        let time_scale = state.tween_controller.time_scale;
+        let now = state.tween_controller.now();
         if let Some(ref mut tween) = state.tween_controller.current_tween {
-            let elapsed = get_time() - tween.start_time;
+            let elapsed = (now - tween.start_time) * time_scale;
+            tween.elapsed = elapsed;
 
             // Use tweeners to calculate current values
             // For circles, calculate position along the arc instead of straight line
@@ -164,6 +565,9 @@ impl TweenController {
                         *direction,
                     )
                 }
+                TurtleCommand::Together(members) => {
+                    group_state_at(&tween.start_params, members, progress).position
+                }
                 _ => {
                     // For non-circle commands, use normal position tweening
                     tween.position_tweener.move_to(elapsed).into()
@@ -188,9 +592,14 @@ impl TweenController {
                 TurtleCommand::Turn(angle) => {
                     tween.start_params.heading + angle.to_radians() * progress
                 }
+                TurtleCommand::Together(members) => {
+                    group_state_at(&tween.start_params, members, progress).heading
+                }
                 _ => {
-                    // For other commands that change heading, lerp directly
-                    let heading_diff = tween.target_params.heading - tween.start_params.heading;
+                    // For other commands that change heading (`SetHeading`),
+                    // rotate the short way round
+                    let heading_diff =
+                        normalize_angle(tween.target_params.heading - tween.start_params.heading);
                     tween.start_params.heading + heading_diff * progress
                 }
             });
@@ -198,12 +607,30 @@ impl TweenController {
             state.params.heading = current_heading;
             tween.current_heading = current_heading;
             state.params.pen_width = tween.pen_width_tweener.move_to(elapsed);
+            state.params.color = mix_colors(
+                tween.start_params.color,
+                tween.target_params.color,
+                progress,
+            );
+            state.params.shape_size = tween
+                .start_params
+                .shape_size
+                .lerp(&tween.target_params.shape_size, progress);
+            state.params.cursor_fill = mix_colors(
+                tween.start_params.cursor_fill,
+                tween.target_params.cursor_fill,
+                progress,
+            );
+            state.params.cursor_outline = mix_colors(
+                tween.start_params.cursor_outline,
+                tween.target_params.cursor_outline,
+                progress,
+            );
 
             // Discrete properties (switch at 50% progress)
             let progress = (elapsed / tween.duration).min(1.0);
             if progress >= 0.5 {
                 state.params.pen_down = tween.target_params.pen_down;
-                state.params.color = tween.target_params.color;
                 state.params.fill_color = tween.target_params.fill_color;
                 state.params.visible = tween.target_params.visible;
                 state.params.shape = tween.target_params.shape.clone();
@@ -225,6 +652,9 @@ impl TweenController {
                 );
 
                 state.tween_controller.current_tween = None;
+                state
+                    .tween_controller
+                    .report_finished(&command, &start_params, &target_params);
 
                 // Execute side-effect-only commands using centralized helper
                 if crate::execution::execute_command_side_effects(&command, state) {
@@ -243,7 +673,12 @@ impl TweenController {
         }
 
         // Start next tween
-        if let Some(command) = state.tween_controller.queue.next() {
+        if let Some(command) = state.tween_controller.next_command() {
+            state.count_command(&command);
+            let Some(command) = state.resolve_reporting(command) else {
+                return Self::update(state);
+            };
+            state.apply_pen_palette(&command);
             let command_clone = command.clone();
 
             // Handle commands that should execute immediately (no animation)
@@ -258,7 +693,14 @@ impl TweenController {
                 }
                 _ => {
                     // Use centralized helper for side effects
+                    let start_params = state.params.clone();
                     if crate::execution::execute_command_side_effects(&command_clone, state) {
+                        let end_params = state.params.clone();
+                        state.tween_controller.report_finished(
+                            &command_clone,
+                            &start_params,
+                            &end_params,
+                        );
                         return Self::update(state);
                     }
                 }
@@ -270,31 +712,33 @@ impl TweenController {
             // Calculate target state
             let target_state = Self::calculate_target_state(&state.params, &command_clone);
 
-            // Create tweeners for smooth animation
+            // Create tweeners with the easing active when the command starts
+            let easing = state.params.easing;
             let position_tweener = Tweener::new(
                 TweenVec2::from(state.params.position),
                 TweenVec2::from(target_state.position),
                 duration,
-                CubicInOut,
+                easing,
             );
 
             let heading_tweener = Tweener::new(
                 0.0, // We'll handle angle wrapping separately
-                1.0, duration, CubicInOut,
+                1.0, duration, easing,
             );
 
             let pen_width_tweener = Tweener::new(
                 state.params.pen_width,
                 target_state.pen_width,
                 duration,
-                CubicInOut,
+                easing,
             );
 
             state.tween_controller.current_tween = Some(CommandTween {
                 turtle_id: state.turtle_id,
                 command: command_clone,
-                start_time: get_time(),
+                start_time: state.tween_controller.now(),
                 duration,
+                elapsed: 0.0,
                 start_params: state.params.clone(),
                 target_params: target_state.clone(),
                 current_position: state.params.position,
@@ -308,9 +752,76 @@ impl TweenController {
         Vec::new()
     }
 
+    /// Complete the tween in progress and execute all queued commands instantly
+    ///
+    /// Ignores pause and step mode; looping queues stop at the end of the
+    /// current repetition. The resulting drawings are added to
+    /// `state.commands` directly.
+    pub fn finish(state: &mut Turtle) {
+        state.tween_controller.release();
+        if let Some(tween) = state.tween_controller.current_tween.take() {
+            state.params = tween.target_params.clone();
+            crate::execution::record_fill_vertices_after_movement(
+                &tween.command,
+                &tween.start_params,
+                state,
+            );
+            if !crate::execution::execute_command_side_effects(&tween.command, state) {
+                let mut end_state = tween.target_params;
+                match crate::execution::add_draw_for_completed_tween(
+                    &tween.command,
+                    &tween.start_params,
+                    &mut end_state,
+                    state.tween_controller.now(),
+                ) {
+                    Ok(draw_command) => state.commands.extend(draw_command),
+                    Err(error) => state.report_error(error),
+                }
+            }
+        }
+
+        while let Some(command) = state.tween_controller.replay.pop_front() {
+            crate::execution::execute_command(&command, state);
+        }
+        // A looping queue finishes its current repetition
+        for _ in 0..state.tween_controller.queue.remaining() {
+            if let Some(command) = state.tween_controller.queue.next() {
+                crate::execution::execute_command(&command, state);
+            }
+        }
+        // Keep the controller in sync with `SetSpeed` commands executed above
+        state.tween_controller.speed = state.params.speed;
+    }
+
+    /// Number of commands not yet finished, including the running one
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.replay.len() + self.queue.remaining() + usize::from(self.current_tween.is_some())
+    }
+
     #[must_use]
     pub fn is_complete(&self) -> bool {
-        self.current_tween.is_none() && self.queue.is_complete()
+        self.current_tween.is_none() && self.replay.is_empty() && self.queue.is_complete()
+    }
+
+    /// The animated command and how much of it is done (0.0 to 1.0)
+    #[must_use]
+    pub fn current_command(&self) -> Option<(&TurtleCommand, f32)> {
+        self.current_tween.as_ref().map(|tween| {
+            let progress = if tween.duration > 0.0 {
+                (tween.elapsed / tween.duration).clamp(0.0, 1.0) as f32
+            } else {
+                1.0
+            };
+            (&tween.command, progress)
+        })
+    }
+
+    /// Commands waiting in the queue after the animated one (the replay of a
+    /// stamped stencil runs before them)
+    #[must_use]
+    pub fn upcoming(&self) -> &[TurtleCommand] {
+        self.queue.upcoming()
     }
 
     /// Get the current active tween if one is in progress
@@ -321,15 +832,66 @@ impl TweenController {
     fn command_creates_drawing(command: &TurtleCommand) -> bool {
         matches!(
             command,
-            TurtleCommand::Move(_) | TurtleCommand::Circle { .. } | TurtleCommand::Goto(_)
+            TurtleCommand::Move(_)
+                | TurtleCommand::Circle { .. }
+                | TurtleCommand::Goto(_)
+                | TurtleCommand::Together(_)
         )
     }
 
+    /// Seconds the animation of `command` takes when it starts from `current`
+    ///
+    /// Commands that are executed without a tween (speed changes, fills,
+    /// pen changes, stamps, text, ...) and everything in instant mode take no
+    /// time. The time scale is not applied.
+    pub(crate) fn animated_duration(command: &TurtleCommand, current: &Turtle) -> f64 {
+        let speed = current.params.speed;
+        let untweened = matches!(
+            command,
+            TurtleCommand::SetSpeed(_)
+                | TurtleCommand::BeginFill
+                | TurtleCommand::EndFill
+                | TurtleCommand::PenUp
+                | TurtleCommand::PenDown
+                | TurtleCommand::Reset
+                | TurtleCommand::Clear
+                | TurtleCommand::ClearScreen { .. }
+                | TurtleCommand::Mark(_)
+                | TurtleCommand::Stamp(_)
+                | TurtleCommand::ClearStamp(_)
+                | TurtleCommand::ClearStamps(_)
+                | TurtleCommand::WriteText { .. }
+                | TurtleCommand::BeginStencil(_)
+                | TurtleCommand::EndStencil(_)
+                | TurtleCommand::StampStencil { .. }
+                | TurtleCommand::PushState
+                | TurtleCommand::PopState
+                | TurtleCommand::OffsetLastPath(_)
+                | TurtleCommand::Barrier(_)
+                | TurtleCommand::SendMessage { .. }
+                | TurtleCommand::Spawn { .. }
+                | TurtleCommand::Die { .. }
+        );
+        if untweened || !speed.is_animating() {
+            0.0
+        } else {
+            Self::calculate_duration_with_state(command, current, speed)
+        }
+    }
+
     fn calculate_duration_with_state(
         command: &TurtleCommand,
         current: &Turtle,
         speed: AnimationSpeed,
     ) -> f64 {
+        // A group takes as long as its slowest member
+        if let TurtleCommand::Together(members) = command {
+            return members
+                .iter()
+                .map(|member| Self::calculate_duration_with_state(member, current, speed))
+                .fold(0.01, f64::max);
+        }
+
         let mut speed = speed.value();
 
         // For high speeds, make animation even faster by scaling speed exponentially
@@ -337,11 +899,22 @@ impl TweenController {
             speed *= speed / 100.0;
         }
 
+        // Rotation speed in degrees per second, derived from the movement speed
+        // unless set explicitly: 180 degrees per second at speed 100
+        let turn_speed = current
+            .params
+            .turn_speed
+            .unwrap_or(speed * 1.8)
+            .max(f32::EPSILON);
+
         let base_time = match command {
             TurtleCommand::Move(dist) => dist.abs() / speed,
-            TurtleCommand::Turn(angle) => {
-                // Rotation speed: assume 180 degrees per second at speed 100
-                angle.abs() / (speed * 1.8)
+            TurtleCommand::Turn(angle) => angle.abs() / turn_speed,
+            TurtleCommand::SetHeading(heading) => {
+                normalize_angle(heading - current.params.heading)
+                    .to_degrees()
+                    .abs()
+                    / turn_speed
             }
             TurtleCommand::Circle { radius, angle, .. } => {
                 let arc_length = radius * angle.to_radians().abs();
@@ -354,6 +927,11 @@ impl TweenController {
                 let distance = (dx * dx + dy * dy).sqrt();
                 distance / speed
             }
+            // Style changes take a fixed time, independent of the speed
+            TurtleCommand::SetColor(_)
+            | TurtleCommand::SetPenWidth(_)
+            | TurtleCommand::SetShapeSize(_)
+            | TurtleCommand::SetTurtleColor { .. } => current.params.style_transition,
             _ => 0.0, // Instant commands
         };
         f64::from(base_time.max(0.01)) // Minimum duration
@@ -363,13 +941,12 @@ impl TweenController {
         let mut target = current.clone();
 
         match command {
+            // Computed from the exact pose, so many small steps don't drift
             TurtleCommand::Move(dist) => {
-                let dx = dist * current.heading.cos();
-                let dy = dist * current.heading.sin();
-                target.position = vec2(current.position.x + dx, current.position.y + dy);
+                target.set_exact_pose(current.exact_pose().moved(*dist));
             }
             TurtleCommand::Turn(angle) => {
-                target.heading = normalize_angle(current.heading + angle.to_radians());
+                target.set_exact_pose(current.exact_pose().turned(angle.to_radians()).normalized());
             }
             TurtleCommand::Circle {
                 radius,
@@ -377,28 +954,31 @@ impl TweenController {
                 direction,
                 ..
             } => {
-                // Use helper function to calculate final position
-                target.position = calculate_circle_position(
-                    current.position,
-                    current.heading,
-                    *radius,
-                    angle.to_radians(),
-                    *direction,
-                );
-                target.heading = normalize_angle(match direction {
-                    CircleDirection::Left => current.heading - angle.to_radians(),
-                    CircleDirection::Right => current.heading + angle.to_radians(),
-                });
+                let pose =
+                    current
+                        .exact_pose()
+                        .along_circle(*radius, angle.to_radians(), *direction);
+                target.set_exact_pose(pose.normalized());
             }
             TurtleCommand::Goto(coord) => {
-                // Flip Y coordinate: turtle graphics uses Y+ = up, but Macroquad uses Y+ = down
-                target.position = vec2(coord.x, -coord.y);
+                // Already in screen space, see `Turtle::resolve_command()`
+                target.position = *coord;
             }
             TurtleCommand::SetHeading(heading) => {
                 target.heading = normalize_angle(*heading);
             }
             TurtleCommand::SetColor(color) => {
                 target.color = *color;
+                target.pen_palette = None;
+            }
+            TurtleCommand::SetPenPalette(palette) => {
+                target.pen_palette = palette.clone().map(|palette| (palette, 0));
+            }
+            TurtleCommand::SetBlendMode(mode) => {
+                target.blend_mode = *mode;
+            }
+            TurtleCommand::Annotate(label) => {
+                target.annotation = Some(label.clone());
             }
             TurtleCommand::SetPenWidth(width) => {
                 target.pen_width = *width;
@@ -409,6 +989,32 @@ impl TweenController {
             TurtleCommand::SetShape(shape) => {
                 target.shape = shape.clone();
             }
+            TurtleCommand::SetEasing(easing) => {
+                target.easing = *easing;
+            }
+            TurtleCommand::SetTurnSpeed(turn_speed) => {
+                target.turn_speed = *turn_speed;
+            }
+            TurtleCommand::SetStyleTransition(seconds) => {
+                target.style_transition = seconds.max(0.0);
+            }
+            TurtleCommand::SetShapeSize(size) => {
+                target.shape_size = *size;
+            }
+            TurtleCommand::Tilt(angle) => {
+                target.tilt = normalize_angle(current.tilt + angle.to_radians());
+            }
+            TurtleCommand::SetTiltAngle(angle) => {
+                target.tilt = normalize_angle(*angle);
+            }
+            TurtleCommand::SetTurtleColor { fill, outline } => {
+                target.cursor_fill = *fill;
+                target.cursor_outline = *outline;
+            }
+            TurtleCommand::Together(members) => {
+                target = group_state_at(current, members, 1.0);
+                target.heading = normalize_angle(target.heading);
+            }
             TurtleCommand::PenUp => {
                 target.pen_down = false;
             }
@@ -424,7 +1030,27 @@ impl TweenController {
             TurtleCommand::SetFillColor(color) => {
                 target.fill_color = *color;
             }
-            TurtleCommand::BeginFill | TurtleCommand::EndFill | TurtleCommand::WriteText { .. } => {
+            TurtleCommand::BeginFill
+            | TurtleCommand::EndFill
+            | TurtleCommand::WriteText { .. }
+            | TurtleCommand::ClearScreen { .. }
+            | TurtleCommand::Clear
+            | TurtleCommand::Mark(_)
+            | TurtleCommand::GoToMark(_)
+            | TurtleCommand::SetShapeNamed(_)
+            | TurtleCommand::Stamp(_)
+            | TurtleCommand::ClearStamp(_)
+            | TurtleCommand::ClearStamps(_)
+            | TurtleCommand::BeginStencil(_)
+            | TurtleCommand::EndStencil(_)
+            | TurtleCommand::StampStencil { .. }
+            | TurtleCommand::PushState
+            | TurtleCommand::PopState
+            | TurtleCommand::OffsetLastPath(_)
+            | TurtleCommand::Barrier(_)
+            | TurtleCommand::SendMessage { .. }
+            | TurtleCommand::Spawn { .. }
+            | TurtleCommand::Die { .. } => {
                 // Fill and text commands don't change turtle state for tweening purposes
                 // They're handled directly in execution
             }
@@ -434,6 +1060,11 @@ impl TweenController {
             }
         }
 
+        // The first stroke drawn after `annotate()` takes the label
+        if current.pen_down && Self::command_creates_drawing(command) {
+            target.annotation = None;
+        }
+
         target
     }
 }
@@ -450,8 +1081,102 @@ fn calculate_circle_position(
     geom.position_at_angle(angle_traveled)
 }
 
+/// Minimum number of samples used for the path of a `Together` group
+const GROUP_PATH_SAMPLES: usize = 32;
+
+/// Combined state of a `Together` group at `progress` (`0.0` to `1.0`)
+///
+/// Every member starts from `start`; continuous changes (position, heading,
+/// pen width) add up, colors blend and discrete settings are applied in
+/// order. The heading is not normalized.
+pub(crate) fn group_state_at(
+    start: &TurtleParams,
+    members: &[TurtleCommand],
+    progress: f32,
+) -> TurtleParams {
+    let mut state = start.clone();
+    for member in members {
+        match member {
+            TurtleCommand::Move(dist) => {
+                state.position += vec2(start.heading.cos(), start.heading.sin()) * *dist * progress;
+            }
+            TurtleCommand::Goto(target) => {
+                state.position += (*target - start.position) * progress;
+            }
+            TurtleCommand::Circle {
+                radius,
+                angle,
+                direction,
+                ..
+            } => {
+                let angle_traveled = angle.to_radians() * progress;
+                state.position += calculate_circle_position(
+                    start.position,
+                    start.heading,
+                    *radius,
+                    angle_traveled,
+                    *direction,
+                ) - start.position;
+                state.heading += match direction {
+                    CircleDirection::Left => -angle_traveled,
+                    CircleDirection::Right => angle_traveled,
+                };
+            }
+            TurtleCommand::Turn(angle) => {
+                state.heading += angle.to_radians() * progress;
+            }
+            TurtleCommand::SetHeading(heading) => {
+                state.heading += normalize_angle(heading - start.heading) * progress;
+            }
+            TurtleCommand::SetPenWidth(width) => {
+                state.pen_width += (width - start.pen_width) * progress;
+            }
+            TurtleCommand::SetColor(color) => {
+                state.color = mix_colors(state.color, *color, progress);
+            }
+            other => {
+                // Discrete settings don't touch position, heading or pen width
+                state = TweenController::calculate_target_state(&state, other);
+            }
+        }
+    }
+    state
+}
+
+/// Positions, pen widths and colors along the path of a `Together` group,
+/// from the start up to `until` (`0.0` to `1.0`)
+pub(crate) fn group_path(
+    start: &TurtleParams,
+    members: &[TurtleCommand],
+    until: f32,
+) -> Vec<(Vec2, f32, Color)> {
+    let samples = members
+        .iter()
+        .filter_map(|member| match member {
+            TurtleCommand::Circle { steps, .. } => Some(*steps),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+        .max(GROUP_PATH_SAMPLES);
+
+    let mut path: Vec<(Vec2, f32, Color)> = (0..=samples)
+        .map(|i| i as f32 / samples as f32)
+        .take_while(|progress| *progress <= until)
+        .map(|progress| {
+            let state = group_state_at(start, members, progress);
+            (state.position, state.pen_width, state.color)
+        })
+        .collect();
+    if until < 1.0 {
+        let state = group_state_at(start, members, until);
+        path.push((state.position, state.pen_width, state.color));
+    }
+    path
+}
+
 /// Normalize angle to range [-PI, PI] to prevent floating-point drift
-fn normalize_angle(angle: f32) -> f32 {
+pub(crate) fn normalize_angle(angle: f32) -> f32 {
     let two_pi = std::f32::consts::PI * 2.0;
     let mut normalized = angle % two_pi;
 
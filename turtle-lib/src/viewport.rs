@@ -0,0 +1,134 @@
+//! Split screen: the world rendered several times, each with its own camera
+//!
+//! Once a viewport is added with `TurtleApp::add_viewport()`, `render()` draws
+//! the world into every viewport rectangle instead of the whole window. Each
+//! viewport looks at the world through its `CameraMode`, e.g. one camera per
+//! turtle in a race:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//! use turtle_lib::viewport::CameraMode;
+//! use macroquad::prelude::{screen_height, screen_width, Rect};
+//!
+//! #[macroquad::main("Split Screen")]
+//! async fn main() {
+//!     let mut app = TurtleApp::new();
+//!     let (width, height) = (screen_width() / 2.0, screen_height());
+//!     for id in 0..2 {
+//!         let mut plan = create_turtle_plan();
+//!         plan.right(id as f32 * 180.0);
+//!         for _ in 0..50 {
+//!             plan.forward(40.0).right(35.0 + id as f32 * 10.0);
+//!         }
+//!         app.append_to_queue(id, plan);
+//!         let rect = Rect::new(id as f32 * width, 0.0, width, height);
+//!         app.add_viewport(rect, CameraMode::Follow { turtle_id: id, zoom: 1.5 });
+//!     }
+//!     app.run(RunConfig::new()).await;
+//! }
+//! ```
+//!
+//! Rectangles are in window pixels from the top left corner. Panning and
+//! zooming with the mouse only affect `CameraMode::World` viewports.
+//!
+//! # Several apps in one window
+//!
+//! Viewports show the same world. For independent worlds, give each
+//! `TurtleApp` its own canvas with `with_canvas()` and update and render all
+//! of them every frame. Each app keeps its own camera, zoom and turtles,
+//! and mouse input (panning, zoom, click selection) as well as the playback
+//! keys of `handle_keys()` go to the app under the mouse only:
+//!
+//! ```no_run
+//! use turtle_lib::*;
+//! use macroquad::prelude::{clear_background, next_frame, screen_height, screen_width, Rect};
+//!
+//! #[macroquad::main("Two Worlds")]
+//! async fn main() {
+//!     let (width, height) = (screen_width() / 2.0, screen_height());
+//!     let mut apps: Vec<TurtleApp> = (0..2)
+//!         .map(|side| {
+//!             let mut plan = create_turtle_plan();
+//!             plan.set_pen_color(if side == 0 { RED } else { BLUE });
+//!             plan.circle_left(80.0, 360.0, 36);
+//!             TurtleApp::new()
+//!                 .with_canvas(Rect::new(side as f32 * width, 0.0, width, height))
+//!                 .with_commands(plan.build())
+//!         })
+//!         .collect();
+//!
+//!     loop {
+//!         clear_background(WHITE);
+//!         for app in &mut apps {
+//!             app.handle_keys();
+//!             app.update();
+//!             app.render();
+//!         }
+//!         next_frame().await;
+//!     }
+//! }
+//! ```
+//!
+//! Update the canvases with `TurtleApp::set_canvas()` when the window is
+//! resized.
+
+use crate::general::Coordinate;
+use crate::state::TurtleWorld;
+use macroquad::prelude::{screen_height, vec2, Camera2D, Rect};
+
+/// What a viewport looks at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraMode {
+    /// The same part of the world as the main view, including panning and
+    /// zoom
+    World,
+    /// Centered on a turtle, `zoom` times enlarged
+    Follow { turtle_id: usize, zoom: f32 },
+    /// Centered on a point in the world's `CoordinateMode`, `zoom` times
+    /// enlarged
+    Fixed { center: Coordinate, zoom: f32 },
+}
+
+/// A rectangle of the window with its camera, see `TurtleApp::add_viewport()`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    /// Window pixels, from the top left corner
+    pub rect: Rect,
+    pub mode: CameraMode,
+}
+
+impl Viewport {
+    #[must_use]
+    pub fn new(rect: Rect, mode: CameraMode) -> Self {
+        Self { rect, mode }
+    }
+
+    /// Camera rendering into this viewport, `zoom_level` is the zoom of the
+    /// main view (smaller values zoom in)
+    #[must_use]
+    pub fn camera(&self, world: &TurtleWorld, zoom_level: f32) -> Camera2D {
+        let (target, scale) = match self.mode {
+            CameraMode::World => (world.camera.target, 1.0 / zoom_level),
+            CameraMode::Follow { turtle_id, zoom } => {
+                let target = world
+                    .get_turtle(turtle_id)
+                    .map_or(world.camera.target, |turtle| turtle.params.position);
+                (target, zoom)
+            }
+            CameraMode::Fixed { center, zoom } => (world.coordinate_mode().to_screen(center), zoom),
+        };
+        let Rect { x, y, w, h } = self.rect;
+        Camera2D {
+            zoom: vec2(2.0 / w.max(1.0), 2.0 / h.max(1.0)) * scale,
+            target,
+            // OpenGL counts viewport rows from the bottom of the window
+            viewport: Some((
+                x as i32,
+                (screen_height() - y - h) as i32,
+                w as i32,
+                h as i32,
+            )),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Live coding: re-run a script file whenever it changes
+//!
+//! Polls the modification time of a script file (see `script`), so no
+//! platform-specific file notification is needed. Enabled with the `watch`
+//! feature, used through `TurtleApp::watch_script()`.
+
+use crate::builders::TurtlePlan;
+use crate::script::parse_script;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Seconds between two checks of the file's modification time
+const POLL_INTERVAL: f64 = 0.25;
+
+/// A watched script file
+#[derive(Clone, Debug)]
+pub struct ScriptWatch {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Option<f64>,
+}
+
+impl ScriptWatch {
+    /// Watch `path`; the first `poll()` loads the file
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            modified: None,
+            last_check: None,
+        }
+    }
+
+    /// The watched file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check the file at time `now` (in seconds) and parse it if it changed
+    ///
+    /// Returns `None` if the file is unchanged, was checked less than a
+    /// quarter second ago, or could not be read or parsed. Errors are logged
+    /// and retried on the next change, so a typo keeps the previous drawing.
+    pub fn poll(&mut self, now: f64) -> Option<TurtlePlan> {
+        if self
+            .last_check
+            .is_some_and(|last| now - last < POLL_INTERVAL)
+        {
+            return None;
+        }
+        self.last_check = Some(now);
+
+        let modified = match std::fs::metadata(&self.path).and_then(|meta| meta.modified()) {
+            Ok(modified) => modified,
+            Err(error) => {
+                tracing::warn!(path = %self.path.display(), %error, "Cannot watch script");
+                return None;
+            }
+        };
+        if self.modified == Some(modified) {
+            return None;
+        }
+        self.modified = Some(modified);
+
+        let source = match std::fs::read_to_string(&self.path) {
+            Ok(source) => source,
+            Err(error) => {
+                tracing::warn!(path = %self.path.display(), %error, "Cannot read script");
+                return None;
+            }
+        };
+        match parse_script(&source) {
+            Ok(plan) => {
+                tracing::info!(path = %self.path.display(), "Script reloaded");
+                Some(plan)
+            }
+            Err(error) => {
+                tracing::warn!(path = %self.path.display(), %error, "Script has errors");
+                None
+            }
+        }
+    }
+}
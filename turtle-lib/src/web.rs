@@ -0,0 +1,48 @@
+//! Browser support for wasm32 builds
+//!
+//! macroquad programs run in the browser when built for
+//! `wasm32-unknown-unknown` and loaded with macroquad's `mq_js_bundle.js`.
+//! Browsers have no file system, so `TurtleApp::export_drawing()` offers the
+//! drawing as a download instead of writing a file. That needs the small
+//! plugin in `turtle-lib/js/turtle_web.js`, loaded after the bundle:
+//!
+//! ```html
+//! <script src="mq_js_bundle.js"></script>
+//! <script src="turtle_web.js"></script>
+//! <script>load("my_turtle_program.wasm");</script>
+//! ```
+//!
+//! Threads don't exist in the browser either: send commands from a frame
+//! hook (see `RunConfig`) instead of a game thread. `TurtleCommandSender`
+//! works the same, except that a full channel makes `send()` fail instead of
+//! waiting.
+
+extern "C" {
+    // Provided by `js/turtle_web.js`
+    fn turtle_download(
+        name: *const u8,
+        name_len: usize,
+        mime: *const u8,
+        mime_len: usize,
+        data: *const u8,
+        data_len: usize,
+    );
+}
+
+/// Let the browser download `data` as a file called `filename`
+pub fn download(filename: &str, mime: &str, data: &[u8]) {
+    // Only the file name, paths mean nothing to the browser
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    // SAFETY: the pointers are valid for the given lengths during the call,
+    // the plugin copies the bytes before returning
+    unsafe {
+        turtle_download(
+            name.as_ptr(),
+            name.len(),
+            mime.as_ptr(),
+            mime.len(),
+            data.as_ptr(),
+            data.len(),
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Versioned serde format for commands, plans and turtle state (`serde` feature)
+//!
+//! `TurtleCommand`, `CommandQueue`, `TurtlePlan`, `TurtleShape`,
+//! `TurtleParams`, `MeshData` and `AnimationSpeed` implement `Serialize` and
+//! `Deserialize`. Wrap them in `Versioned` when they leave the program, so
+//! network peers, replay files and plan loaders can reject data written by an
+//! incompatible version of the library:
+//!
+//! ```ignore
+//! use turtle_lib::wire::Versioned;
+//!
+//! let json = serde_json::to_string(&Versioned::new(plan.build()))?;
+//! let queue = serde_json::from_str::<Versioned<CommandQueue>>(&json)?.into_data()?;
+//! ```
+//!
+//! Colors are stored as `[r, g, b, a]` and points as `[x, y]`. `Color` is
+//! macroquad's type, so it can't implement serde itself; fields of your own
+//! types use the helper modules of this module instead:
+//!
+//! ```ignore
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Player {
+//!     #[serde(with = "turtle_lib::wire::color")]
+//!     color: Color,
+//!     #[serde(with = "turtle_lib::wire::vec2")]
+//!     start: Vec2,
+//! }
+//! ```
+//!
+//! Stamp and stencil IDs are only unique within the program that created
+//! them.
+
+use serde::{Deserialize, Serialize};
+
+/// Version of the schema, increased on every incompatible change
+pub const WIRE_FORMAT_VERSION: u32 = 1;
+
+/// Data tagged with the schema version it was written with
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub version: u32,
+    pub data: T,
+}
+
+impl<T> Versioned<T> {
+    /// Tag `data` with the current `WIRE_FORMAT_VERSION`
+    pub fn new(data: T) -> Self {
+        Self {
+            version: WIRE_FORMAT_VERSION,
+            data,
+        }
+    }
+
+    /// The data, if it was written with the current schema
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the version differs from `WIRE_FORMAT_VERSION`
+    pub fn into_data(self) -> Result<T, WireError> {
+        if self.version == WIRE_FORMAT_VERSION {
+            Ok(self.data)
+        } else {
+            Err(WireError::UnsupportedVersion {
+                found: self.version,
+                supported: WIRE_FORMAT_VERSION,
+            })
+        }
+    }
+}
+
+/// Error while reading versioned data
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WireError {
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+impl std::fmt::Display for WireError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedVersion { found, supported } => write!(
+                f,
+                "wire format version {found} is not supported (expected {supported})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+// `serde(with = ...)` helpers for macroquad types, which don't implement serde
+/// `Color` as `[r, g, b, a]`
+pub mod color {
+    use crate::general::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        [color.r, color.g, color.b, color.a].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let [r, g, b, a] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(Color::new(r, g, b, a))
+    }
+}
+
+/// `Option<Color>` as `[r, g, b, a]` or nothing
+pub mod option_color {
+    use crate::general::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        color: &Option<Color>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        color
+            .map(|color| [color.r, color.g, color.b, color.a])
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Color>, D::Error> {
+        let color = Option::<[f32; 4]>::deserialize(deserializer)?;
+        Ok(color.map(|[r, g, b, a]| Color::new(r, g, b, a)))
+    }
+}
+
+/// `Vec<Color>` as a list of `[r, g, b, a]`
+pub mod color_list {
+    use crate::general::Color;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(colors: &[Color], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(colors.iter().map(|c| [c.r, c.g, c.b, c.a]))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Color>, D::Error> {
+        let colors = Vec::<[f32; 4]>::deserialize(deserializer)?;
+        Ok(colors
+            .into_iter()
+            .map(|[r, g, b, a]| Color::new(r, g, b, a))
+            .collect())
+    }
+}
+
+/// `Vec2` as `[x, y]`
+pub mod vec2 {
+    use macroquad::prelude::{vec2, Vec2};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(point: &Vec2, serializer: S) -> Result<S::Ok, S::Error> {
+        [point.x, point.y].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec2, D::Error> {
+        let [x, y] = <[f32; 2]>::deserialize(deserializer)?;
+        Ok(vec2(x, y))
+    }
+}
+
+/// `Vec<Vec2>` as a list of `[x, y]`
+pub mod vec2_list {
+    use macroquad::prelude::{vec2, Vec2};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(points: &[Vec2], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(points.iter().map(|p| [p.x, p.y]))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec2>, D::Error> {
+        let points = Vec::<[f32; 2]>::deserialize(deserializer)?;
+        Ok(points.into_iter().map(|[x, y]| vec2(x, y)).collect())
+    }
+}
+
+/// Lists of points, e.g. the contours of a shape
+pub mod vec2_lists {
+    use macroquad::prelude::{vec2, Vec2};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(lists: &[Vec<Vec2>], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            lists
+                .iter()
+                .map(|points| points.iter().map(|p| [p.x, p.y]).collect::<Vec<_>>()),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<Vec2>>, D::Error> {
+        let lists = Vec::<Vec<[f32; 2]>>::deserialize(deserializer)?;
+        Ok(lists
+            .into_iter()
+            .map(|points| points.into_iter().map(|[x, y]| vec2(x, y)).collect())
+            .collect())
+    }
+}
+
+/// Vertices as position `[x, y, z]`, texture coordinates `[u, v]` and color
+/// `[r, g, b, a]` bytes
+pub mod vertex_list {
+    use macroquad::prelude::{Color, Vertex};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    type Fields = ([f32; 3], [f32; 2], [u8; 4]);
+
+    pub fn serialize<S: Serializer>(vertices: &[Vertex], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(vertices.iter().map(|vertex| {
+            let (position, uv) = (vertex.position, vertex.uv);
+            (
+                [position.x, position.y, position.z],
+                [uv.x, uv.y],
+                vertex.color,
+            )
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vertex>, D::Error> {
+        let vertices = Vec::<Fields>::deserialize(deserializer)?;
+        Ok(vertices
+            .into_iter()
+            .map(|([x, y, z], [u, v], [r, g, b, a])| {
+                Vertex::new(x, y, z, u, v, Color::from_rgba(r, g, b, a))
+            })
+            .collect())
+    }
+}